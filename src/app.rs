@@ -1,7 +1,19 @@
+pub mod bindings_panel;
+pub mod commands;
 pub mod config;
+pub mod convert;
+pub mod gizmo;
+pub mod history;
+pub mod history_panel;
+pub mod inspector;
 pub mod left_panel;
+pub mod notifications;
 pub mod scene_display;
+pub mod scene_panel;
 pub mod scene_viewer_app;
+pub mod timeline;
+pub mod view_cube;
+pub mod viewport;
 
 pub use config::load_default_config;
 pub use scene_viewer_app::SceneViewerAppFactory;
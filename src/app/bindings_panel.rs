@@ -0,0 +1,167 @@
+use crate::core::input::{ActionMap, Binding, Input, Trigger};
+use winit::keyboard::KeyCode;
+
+/// Window listing every action with its bindings. Bindings can be removed or
+/// captured from the next key press, click or scroll over the viewport;
+/// bindings shared by several actions are shown in red.
+pub struct BindingsPanel {
+    open: bool,
+    /// Action waiting for its new binding.
+    capturing: Option<String>,
+    defaults: ActionMap,
+}
+
+const CONFLICT_COLOR: egui::Color32 = egui::Color32::from_rgb(230, 90, 70);
+
+impl BindingsPanel {
+    /// `defaults` is what "Reset" restores, normally the configured map.
+    pub fn new(defaults: ActionMap) -> Self {
+        Self {
+            open: false,
+            capturing: None,
+            defaults,
+        }
+    }
+
+    pub fn open_mut(&mut self) -> &mut bool {
+        &mut self.open
+    }
+
+    /// While capturing, shortcuts should not also trigger their actions.
+    pub fn is_capturing(&self) -> bool {
+        self.capturing.is_some()
+    }
+
+    pub fn ui(&mut self, egui_ctx: &egui::Context, input: &mut Input) {
+        if !self.open {
+            self.capturing = None;
+            return;
+        }
+        self.capture(input);
+
+        let mut open = self.open;
+        egui::Window::new("Key bindings")
+            .open(&mut open)
+            .default_width(360.0)
+            .show(egui_ctx, |ui| {
+                let conflicts = input.actions().conflicts().len();
+                if conflicts > 0 {
+                    ui.colored_label(
+                        CONFLICT_COLOR,
+                        format!("{conflicts} binding(s) used by more than one action"),
+                    );
+                }
+                egui::ScrollArea::vertical()
+                    .max_height(400.0)
+                    .show(ui, |ui| self.actions_ui(ui, input));
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("Reset to defaults").clicked() {
+                        *input.actions_mut() = self.defaults.clone();
+                        self.capturing = None;
+                    }
+                    if ui
+                        .button("Copy as TOML")
+                        .on_hover_text("Copy a [bindings] table for app_config.toml")
+                        .clicked()
+                    {
+                        ui.ctx().copy_text(bindings_toml(input.actions()));
+                    }
+                });
+            });
+        self.open = open;
+    }
+
+    fn actions_ui(&mut self, ui: &mut egui::Ui, input: &mut Input) {
+        let mut removed: Option<(String, Binding)> = None;
+        egui::Grid::new("bindings_grid")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                let actions = input.actions();
+                for (action, bindings) in actions.actions() {
+                    ui.label(action);
+                    ui.horizontal_wrapped(|ui| {
+                        for &binding in bindings {
+                            let mut text = egui::RichText::new(binding.to_string());
+                            let conflicting = actions.is_conflicting(binding);
+                            if conflicting {
+                                text = text.color(CONFLICT_COLOR);
+                            }
+                            let mut response = ui.button(text).on_hover_text("Click to remove");
+                            if conflicting {
+                                let others: Vec<&str> = actions
+                                    .actions()
+                                    .filter(|(a, b)| *a != action && b.contains(&binding))
+                                    .map(|(a, _)| a)
+                                    .collect();
+                                response = response
+                                    .on_hover_text(format!("Also bound to {}", others.join(", ")));
+                            }
+                            if response.clicked() {
+                                removed = Some((action.to_owned(), binding));
+                            }
+                        }
+                        if self.capturing.as_deref() == Some(action) {
+                            ui.label("Press a key, or click or scroll in the viewport…");
+                            if ui.button("Cancel").clicked() {
+                                self.capturing = None;
+                            }
+                        } else if ui.button("+").on_hover_text("Add a binding").clicked() {
+                            self.capturing = Some(action.to_owned());
+                        }
+                    });
+                    ui.end_row();
+                }
+            });
+        if let Some((action, binding)) = removed {
+            input.actions_mut().unbind(&action, binding);
+        }
+    }
+
+    /// Binds the first trigger pressed this frame to the capturing action.
+    /// Modifier keys alone are not bindings; they combine with what follows.
+    /// Keys without a name in bindings files are ignored, so a copied
+    /// configuration always loads again.
+    fn capture(&mut self, input: &mut Input) {
+        let Some(action) = &self.capturing else {
+            return;
+        };
+        let state = input.state();
+        let Some(trigger) = state
+            .pressed_triggers()
+            .find(|trigger| !is_modifier(*trigger) && trigger.has_name())
+        else {
+            return;
+        };
+        let binding = Binding::new(state.modifiers(), trigger);
+        let action = action.clone();
+        input.actions_mut().bind(&action, binding);
+        self.capturing = None;
+    }
+}
+
+fn is_modifier(trigger: Trigger) -> bool {
+    matches!(
+        trigger,
+        Trigger::Key(
+            KeyCode::ShiftLeft
+                | KeyCode::ShiftRight
+                | KeyCode::ControlLeft
+                | KeyCode::ControlRight
+                | KeyCode::AltLeft
+                | KeyCode::AltRight
+                | KeyCode::SuperLeft
+                | KeyCode::SuperRight
+        )
+    )
+}
+
+fn bindings_toml(actions: &ActionMap) -> String {
+    let mut text = String::from("[bindings]\n");
+    for (action, bindings) in actions.to_config() {
+        let bindings: Vec<String> = bindings.iter().map(|b| format!("\"{b}\"")).collect();
+        text.push_str(&format!("\"{action}\" = [{}]\n", bindings.join(", ")));
+    }
+    text
+}
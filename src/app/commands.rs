@@ -0,0 +1,323 @@
+use std::any::Any;
+use std::mem::{size_of, size_of_val};
+
+use crate::app::history::Command;
+use crate::core::{
+    Handle, Scene, Transform,
+    asset_manager::Mesh,
+    scene::{Node, NodeId, Subtree},
+};
+
+#[derive(Clone, Copy, Debug)]
+pub struct TransformChange {
+    pub node: NodeId,
+    pub before: Transform,
+    pub after: Transform,
+}
+
+/// Sets the local transforms of one or more nodes, e.g. after a gizmo drag.
+pub struct SetTransforms {
+    label: String,
+    changes: Vec<TransformChange>,
+    continuous: bool,
+}
+
+impl SetTransforms {
+    pub fn new(label: impl Into<String>, changes: Vec<TransformChange>) -> Self {
+        Self {
+            label: label.into(),
+            changes,
+            continuous: false,
+        }
+    }
+
+    /// Marks the edit as one step of a drag, so it merges with the next step
+    /// on the same nodes.
+    pub fn continuous(mut self) -> Self {
+        self.continuous = true;
+        self
+    }
+
+    fn set(scene: &mut Scene, changes: &[TransformChange], after: bool) {
+        for change in changes {
+            if let Some(node) = scene.node_mut(change.node) {
+                node.transform = if after { change.after } else { change.before };
+            }
+        }
+    }
+}
+
+impl Command for SetTransforms {
+    fn label(&self) -> String {
+        self.label.clone()
+    }
+
+    fn apply(&mut self, scene: &mut Scene) {
+        Self::set(scene, &self.changes, true);
+    }
+
+    fn revert(&mut self, scene: &mut Scene) {
+        Self::set(scene, &self.changes, false);
+    }
+
+    fn merge(&mut self, next: &dyn Command) -> bool {
+        let Some(next) = next.as_any().downcast_ref::<Self>() else {
+            return false;
+        };
+        let same_nodes = self.changes.len() == next.changes.len()
+            && self
+                .changes
+                .iter()
+                .zip(&next.changes)
+                .all(|(a, b)| a.node == b.node);
+        if !(self.continuous && next.continuous && self.label == next.label && same_nodes) {
+            return false;
+        }
+        for (change, next) in self.changes.iter_mut().zip(&next.changes) {
+            change.after = next.after;
+        }
+        true
+    }
+
+    fn memory_size(&self) -> usize {
+        size_of::<Self>() + self.label.len() + self.changes.len() * size_of::<TransformChange>()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Sets a node's morph target weights from the inspector. Consecutive edits
+/// of the same node merge.
+pub struct SetMorphWeights {
+    node: NodeId,
+    before: Vec<f32>,
+    after: Vec<f32>,
+}
+
+impl SetMorphWeights {
+    pub fn new(node: NodeId, before: Vec<f32>, after: Vec<f32>) -> Self {
+        Self {
+            node,
+            before,
+            after,
+        }
+    }
+}
+
+impl Command for SetMorphWeights {
+    fn label(&self) -> String {
+        "Edit morph weights".to_owned()
+    }
+
+    fn apply(&mut self, scene: &mut Scene) {
+        if let Some(node) = scene.node_mut(self.node) {
+            node.morph_weights.clone_from(&self.after);
+        }
+    }
+
+    fn revert(&mut self, scene: &mut Scene) {
+        if let Some(node) = scene.node_mut(self.node) {
+            node.morph_weights.clone_from(&self.before);
+        }
+    }
+
+    fn merge(&mut self, next: &dyn Command) -> bool {
+        match next.as_any().downcast_ref::<Self>() {
+            Some(next) if next.node == self.node => {
+                self.after.clone_from(&next.after);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn memory_size(&self) -> usize {
+        size_of::<Self>() + (self.before.len() + self.after.len()) * size_of::<f32>()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Swaps the mesh a node draws, e.g. for a processed copy from the mesh
+/// tools. Both meshes stay loaded while the command is in the history.
+pub struct SetMesh {
+    label: String,
+    node: NodeId,
+    before: Option<Handle<Mesh>>,
+    after: Option<Handle<Mesh>>,
+    /// CPU size of the new mesh, which only this command keeps alive.
+    mesh_bytes: usize,
+}
+
+impl SetMesh {
+    pub fn new(
+        label: impl Into<String>,
+        node: NodeId,
+        before: Option<Handle<Mesh>>,
+        after: Option<Handle<Mesh>>,
+        mesh_bytes: usize,
+    ) -> Self {
+        Self {
+            label: label.into(),
+            node,
+            before,
+            after,
+            mesh_bytes,
+        }
+    }
+}
+
+impl Command for SetMesh {
+    fn label(&self) -> String {
+        self.label.clone()
+    }
+
+    fn apply(&mut self, scene: &mut Scene) {
+        if let Some(node) = scene.node_mut(self.node) {
+            node.mesh.clone_from(&self.after);
+        }
+    }
+
+    fn revert(&mut self, scene: &mut Scene) {
+        if let Some(node) = scene.node_mut(self.node) {
+            node.mesh.clone_from(&self.before);
+        }
+    }
+
+    fn memory_size(&self) -> usize {
+        size_of::<Self>() + self.label.len() + self.mesh_bytes
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Whole subtrees going in or out of the scene: imports and deletes. Taken
+/// subtrees keep their ids, so later commands still find the nodes after an
+/// undo brings them back.
+struct Subtrees {
+    roots: Vec<NodeId>,
+    taken: Vec<Subtree>,
+}
+
+impl Subtrees {
+    fn take(&mut self, scene: &mut Scene) {
+        self.taken = self
+            .roots
+            .iter()
+            .filter_map(|&id| scene.take_subtree(id))
+            .collect();
+    }
+
+    /// Restores in reverse, so siblings get their old positions back.
+    fn restore(&mut self, scene: &mut Scene) {
+        for subtree in self.taken.drain(..).rev() {
+            scene.restore_subtree(subtree);
+        }
+    }
+
+    fn memory_size(&self) -> usize {
+        let node_size = |node: &Node| {
+            size_of::<(NodeId, Node)>()
+                + node.name.len()
+                + node.morph_weights.len() * size_of::<f32>()
+                + size_of_val(node.children())
+        };
+        self.roots.len() * size_of::<NodeId>()
+            + self
+                .taken
+                .iter()
+                .flat_map(Subtree::nodes)
+                .map(node_size)
+                .sum::<usize>()
+    }
+}
+
+/// Records nodes that were just added, e.g. a spawned model.
+pub struct AddNodes {
+    label: String,
+    nodes: Subtrees,
+}
+
+impl AddNodes {
+    pub fn new(label: impl Into<String>, roots: Vec<NodeId>) -> Self {
+        Self {
+            label: label.into(),
+            nodes: Subtrees {
+                roots,
+                taken: Vec::new(),
+            },
+        }
+    }
+}
+
+impl Command for AddNodes {
+    fn label(&self) -> String {
+        self.label.clone()
+    }
+
+    fn apply(&mut self, scene: &mut Scene) {
+        self.nodes.restore(scene);
+    }
+
+    fn revert(&mut self, scene: &mut Scene) {
+        self.nodes.take(scene);
+    }
+
+    fn memory_size(&self) -> usize {
+        size_of::<Self>() + self.label.len() + self.nodes.memory_size()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Deletes nodes with their subtrees.
+pub struct RemoveNodes {
+    nodes: Subtrees,
+}
+
+impl RemoveNodes {
+    /// Removes `ids` from the scene and returns the command that did it, or
+    /// `None` if none of them exist.
+    pub fn execute(scene: &mut Scene, ids: &[NodeId]) -> Option<Self> {
+        let mut nodes = Subtrees {
+            roots: ids.to_vec(),
+            taken: Vec::new(),
+        };
+        nodes.take(scene);
+        nodes.roots = nodes.taken.iter().map(Subtree::root).collect();
+        (!nodes.roots.is_empty()).then_some(Self { nodes })
+    }
+}
+
+impl Command for RemoveNodes {
+    fn label(&self) -> String {
+        match self.nodes.roots.len() {
+            1 => "Delete node".to_owned(),
+            n => format!("Delete {n} nodes"),
+        }
+    }
+
+    fn apply(&mut self, scene: &mut Scene) {
+        self.nodes.take(scene);
+    }
+
+    fn revert(&mut self, scene: &mut Scene) {
+        self.nodes.restore(scene);
+    }
+
+    fn memory_size(&self) -> usize {
+        size_of::<Self>() + self.nodes.memory_size()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
@@ -1,5 +1,10 @@
+use std::collections::BTreeMap;
+
 use serde::Deserialize;
 
+use crate::app::history::History;
+use crate::core::time::Time;
+
 #[derive(Deserialize, Clone)]
 pub struct AppConfig {
     pub title: String,
@@ -7,6 +12,48 @@ pub struct AppConfig {
     pub height: u32,
     pub min_width: u32,
     pub min_height: u32,
+    /// Watch loaded files and shaders and reload them when they change.
+    #[serde(default)]
+    pub hot_reload: bool,
+    /// Directories and zip archives mounted into the asset file system.
+    #[serde(default)]
+    pub mounts: Vec<MountConfig>,
+    /// Fixed simulation rate.
+    #[serde(default = "default_simulation_hz")]
+    pub simulation_hz: f64,
+    /// Most simulation steps run in one frame when catching up.
+    #[serde(default = "default_max_catch_up_steps")]
+    pub max_catch_up_steps: u32,
+    /// Draw only when something changes instead of continuously.
+    #[serde(default)]
+    pub reactive_redraw: bool,
+    /// Frame-rate cap; zero draws as fast as possible.
+    #[serde(default)]
+    pub max_fps: f64,
+    /// Memory the undo history may hold before dropping its oldest edits.
+    #[serde(default = "default_history_memory_mb")]
+    pub history_memory_mb: usize,
+    /// Action name to bindings such as `"Ctrl+Z"` or `"Shift+MouseLeft"`.
+    #[serde(default)]
+    pub bindings: BTreeMap<String, Vec<String>>,
+}
+
+fn default_simulation_hz() -> f64 {
+    Time::DEFAULT_HZ
+}
+
+fn default_max_catch_up_steps() -> u32 {
+    Time::DEFAULT_MAX_STEPS
+}
+
+fn default_history_memory_mb() -> usize {
+    History::DEFAULT_MAX_BYTES >> 20
+}
+
+#[derive(Deserialize, Clone)]
+pub struct MountConfig {
+    pub prefix: String,
+    pub path: std::path::PathBuf,
 }
 
 const DEFAULT_CONFIG: &str = include_str!("app_config.toml");
@@ -27,5 +74,8 @@ mod tests {
         assert!(cfg.min_height > 0);
         assert!(cfg.width >= cfg.min_width);
         assert!(cfg.height >= cfg.min_height);
+        let (bindings, errors) = crate::core::input::ActionMap::from_config(&cfg.bindings);
+        assert!(errors.is_empty());
+        assert!(bindings.conflicts().is_empty());
     }
 }
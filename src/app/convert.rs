@@ -0,0 +1,609 @@
+//! Headless `convert` mode: loads a model through the `AssetManager`,
+//! optionally cleans it up and writes it as glTF, GLB or OBJ.
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+use anyhow::{Context, anyhow, bail};
+use glam::{EulerRot, Mat4, Quat, Vec3};
+
+use crate::core::asset_manager::{AssetManager, AssetState, Handle, Model, exporters};
+use crate::core::mesh_processing::{self, MeshOperation};
+use crate::core::scene::{Node, NodeId, Scene};
+use crate::core::transform::Transform;
+
+pub const USAGE: &str = "\
+usage: convert <input> <output> [options]
+
+Reads OBJ, PLY, STL, glTF or GLB and writes .gltf, .glb or .obj.
+
+options:
+  --normals              recompute normals
+  --crease-angle <deg>   faces meeting at a sharper angle keep hard edges
+                         when recomputing normals (default 60)
+  --tangents             recompute tangents from normals and UVs
+  --weld <epsilon>       merge vertices closer than epsilon
+  --remove-degenerate    drop zero-area triangles and unused vertices
+  --simplify <ratio>     reduce each mesh to this fraction of its triangles
+  --optimize             reorder triangles and vertices for the GPU caches
+  --scale <factor>       uniform scale, e.g. 0.01 for centimetres to metres
+  --translate <x,y,z>    offset applied after scaling and rotating
+  --rotate <x,y,z>       rotation in degrees about X, then Y, then Z
+  --up <z-to-y|y-to-z>   convert between Z-up and Y-up
+  --merge-by-material    merge static meshes that share a material
+  --embed-textures       store .gltf images as data URIs";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UpAxisConversion {
+    ZToY,
+    YToZ,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ConvertOptions {
+    pub input: PathBuf,
+    pub output: PathBuf,
+    pub recompute_normals: bool,
+    pub crease_angle_degrees: f32,
+    pub recompute_tangents: bool,
+    pub weld_epsilon: Option<f32>,
+    pub remove_degenerate: bool,
+    /// Fraction of each mesh's triangles to keep.
+    pub simplify_ratio: Option<f32>,
+    pub optimize_vertex_cache: bool,
+    pub scale: f32,
+    pub translation: Vec3,
+    pub rotation_degrees: Vec3,
+    pub up: Option<UpAxisConversion>,
+    pub merge_by_material: bool,
+    pub embed_textures: bool,
+}
+
+impl ConvertOptions {
+    pub fn new(input: impl Into<PathBuf>, output: impl Into<PathBuf>) -> Self {
+        Self {
+            input: input.into(),
+            output: output.into(),
+            recompute_normals: false,
+            crease_angle_degrees: 60.0,
+            recompute_tangents: false,
+            weld_epsilon: None,
+            remove_degenerate: false,
+            simplify_ratio: None,
+            optimize_vertex_cache: false,
+            scale: 1.0,
+            translation: Vec3::ZERO,
+            rotation_degrees: Vec3::ZERO,
+            up: None,
+            merge_by_material: false,
+            embed_textures: false,
+        }
+    }
+
+    /// Parses the arguments following `convert`.
+    pub fn parse(args: &[String]) -> anyhow::Result<Self> {
+        let mut paths = Vec::new();
+        let mut options = Self::new("", "");
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .map(String::as_str)
+                    .ok_or_else(|| anyhow!("{arg} needs a value"))
+            };
+            match arg.as_str() {
+                "--normals" => options.recompute_normals = true,
+                "--crease-angle" => options.crease_angle_degrees = number(arg, value()?)?,
+                "--tangents" => options.recompute_tangents = true,
+                "--weld" => options.weld_epsilon = Some(number(arg, value()?)?),
+                "--remove-degenerate" => options.remove_degenerate = true,
+                "--simplify" => options.simplify_ratio = Some(number(arg, value()?)?),
+                "--optimize" => options.optimize_vertex_cache = true,
+                "--scale" => options.scale = number(arg, value()?)?,
+                "--translate" => options.translation = vector(arg, value()?)?,
+                "--rotate" => options.rotation_degrees = vector(arg, value()?)?,
+                "--up" => {
+                    options.up = Some(match value()? {
+                        "z-to-y" => UpAxisConversion::ZToY,
+                        "y-to-z" => UpAxisConversion::YToZ,
+                        other => bail!("--up: expected z-to-y or y-to-z, got `{other}`"),
+                    })
+                }
+                "--merge-by-material" => options.merge_by_material = true,
+                "--embed-textures" => options.embed_textures = true,
+                flag if flag.starts_with("--") => bail!("unknown option {flag}"),
+                path => paths.push(PathBuf::from(path)),
+            }
+        }
+        let [input, output] = <[PathBuf; 2]>::try_from(paths)
+            .map_err(|_| anyhow!("expected an input and an output file"))?;
+        if !(options.scale.is_finite() && options.scale > 0.0) {
+            bail!("--scale must be positive");
+        }
+        if options
+            .simplify_ratio
+            .is_some_and(|ratio| !(ratio > 0.0 && ratio <= 1.0))
+        {
+            bail!("--simplify must be a fraction between 0 and 1");
+        }
+        options.input = input;
+        options.output = output;
+        Ok(options)
+    }
+
+    /// The processing steps for a mesh of `triangles` triangles, in the
+    /// order they run: cleanup first, so simplification and normals see
+    /// connected surfaces, and reordering last.
+    pub fn mesh_operations(&self, triangles: usize) -> Vec<MeshOperation> {
+        let mut operations = Vec::new();
+        if let Some(epsilon) = self.weld_epsilon {
+            operations.push(MeshOperation::Weld { epsilon });
+        }
+        if self.remove_degenerate {
+            operations.push(MeshOperation::RemoveDegenerateTriangles);
+        }
+        if let Some(ratio) = self.simplify_ratio {
+            operations.push(MeshOperation::Simplify {
+                target_triangles: (triangles as f32 * ratio).ceil() as usize,
+            });
+        }
+        if self.recompute_normals {
+            operations.push(MeshOperation::SmoothNormals {
+                crease_angle: self.crease_angle_degrees.to_radians(),
+            });
+        }
+        if self.recompute_tangents {
+            operations.push(MeshOperation::Tangents);
+        }
+        if self.optimize_vertex_cache {
+            operations.push(MeshOperation::OptimizeVertexCache);
+        }
+        operations
+    }
+
+    /// Scale, then rotation, then translation, after the up-axis change.
+    pub fn transform(&self) -> Mat4 {
+        let up = match self.up {
+            Some(UpAxisConversion::ZToY) => Quat::from_rotation_x(-90f32.to_radians()),
+            Some(UpAxisConversion::YToZ) => Quat::from_rotation_x(90f32.to_radians()),
+            None => Quat::IDENTITY,
+        };
+        let rotation = Quat::from_euler(
+            EulerRot::ZYX,
+            self.rotation_degrees.z.to_radians(),
+            self.rotation_degrees.y.to_radians(),
+            self.rotation_degrees.x.to_radians(),
+        );
+        Mat4::from_scale_rotation_translation(Vec3::splat(self.scale), rotation, self.translation)
+            * Mat4::from_quat(up)
+    }
+}
+
+fn number(arg: &str, value: &str) -> anyhow::Result<f32> {
+    value
+        .parse()
+        .map_err(|_| anyhow!("{arg}: expected a number, got `{value}`"))
+}
+
+fn vector(arg: &str, value: &str) -> anyhow::Result<Vec3> {
+    let components = value
+        .split(',')
+        .map(|c| number(arg, c.trim()))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    match components[..] {
+        [x, y, z] => Ok(Vec3::new(x, y, z)),
+        _ => bail!("{arg}: expected x,y,z, got `{value}`"),
+    }
+}
+
+pub fn run(options: &ConvertOptions) -> anyhow::Result<()> {
+    let extension = options
+        .output
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    if !matches!(extension.as_str(), "gltf" | "glb" | "obj") {
+        bail!(
+            "unsupported output format: {} (expected .gltf, .glb or .obj)",
+            options.output.display()
+        );
+    }
+
+    let mut assets = AssetManager::new();
+    let model: Handle<Model> = assets.load_blocking(&options.input);
+    if let AssetState::Failed(err) = assets.state(&model) {
+        bail!("failed to load {}: {err}", options.input.display());
+    }
+    let mut scene = Scene::new();
+    scene.spawn_model(assets.get(&model).unwrap(), None);
+
+    let transform = options.transform();
+    if transform != Mat4::IDENTITY {
+        let name = options
+            .input
+            .file_stem()
+            .map_or_else(|| "root".to_owned(), |s| s.to_string_lossy().into_owned());
+        wrap_roots(&mut scene, &transform, name);
+    }
+    if options.merge_by_material {
+        let merged = merge_by_material(&mut scene, &mut assets);
+        log::info!("merged {merged} meshes by material");
+    }
+    process_meshes(&mut scene, &mut assets, options);
+
+    match extension.as_str() {
+        "obj" => exporters::obj::export(&options.output, &scene, &assets)?,
+        _ => {
+            let gltf_options = exporters::gltf::GltfExportOptions {
+                embed_textures: options.embed_textures,
+                ..Default::default()
+            };
+            exporters::gltf::export(&options.output, &scene, &assets, &gltf_options)?
+        }
+    }
+    log::info!("wrote {} ({} nodes)", options.output.display(), scene.len());
+    Ok(())
+}
+
+/// Moves the roots under a new node carrying `transform`, so that it also
+/// applies to roots whose transform an animation drives. The transform
+/// stays exact because the scale is uniform.
+fn wrap_roots(scene: &mut Scene, transform: &Mat4, name: String) -> NodeId {
+    let roots = scene.roots().to_vec();
+    let mut node = Node::new(name);
+    node.transform = Transform::from_matrix(*transform);
+    let parent = scene.add_node(node, None);
+    for id in roots {
+        scene.set_parent(id, Some(parent));
+    }
+    parent
+}
+
+fn animated_nodes(scene: &Scene) -> HashSet<NodeId> {
+    scene
+        .animators()
+        .iter()
+        .flat_map(|animator| {
+            animator.clips().iter().flat_map(|clip| {
+                clip.channels
+                    .iter()
+                    .filter_map(|channel| animator.targets().get(channel.node).copied())
+            })
+        })
+        .collect()
+}
+
+/// Animated nodes and everything below them, whose world transforms change
+/// while playing.
+fn moving_nodes(scene: &Scene, animated: &HashSet<NodeId>) -> HashSet<NodeId> {
+    let mut moving = HashSet::new();
+    let mut stack: Vec<_> = scene.roots().iter().map(|&id| (id, false)).collect();
+    while let Some((id, parent_moves)) = stack.pop() {
+        let moves = parent_moves || animated.contains(&id);
+        if moves {
+            moving.insert(id);
+        }
+        let children = scene.node(id).unwrap().children();
+        stack.extend(children.iter().map(|&child| (child, moves)));
+    }
+    moving
+}
+
+/// Local-to-world matrices from the node transforms alone, without running
+/// animations.
+fn world_matrices(scene: &Scene) -> HashMap<NodeId, Mat4> {
+    let mut worlds = HashMap::with_capacity(scene.len());
+    let mut stack: Vec<_> = scene
+        .roots()
+        .iter()
+        .map(|&id| (id, Mat4::IDENTITY))
+        .collect();
+    while let Some((id, parent_world)) = stack.pop() {
+        let node = scene.node(id).unwrap();
+        let world = parent_world * node.transform.to_matrix();
+        stack.extend(node.children().iter().map(|&child| (child, world)));
+        worlds.insert(id, world);
+    }
+    worlds
+}
+
+/// Replaces static meshes that share a material with one world-space mesh
+/// per material, then drops the nodes left without content. Skinned and
+/// morphing meshes, and meshes on or under animated nodes, are kept as they
+/// are. Returns the number of meshes merged away.
+fn merge_by_material(scene: &mut Scene, assets: &mut AssetManager) -> usize {
+    let animated = animated_nodes(scene);
+    let moving = moving_nodes(scene, &animated);
+    let joints: HashSet<NodeId> = scene
+        .iter()
+        .filter_map(|(_, node)| node.skin.as_ref())
+        .flat_map(|skin| skin.joints.iter().copied())
+        .collect();
+    let worlds = world_matrices(scene);
+
+    let mut groups: Vec<(Option<_>, Vec<NodeId>)> = Vec::new();
+    for (id, node) in scene.iter() {
+        let Some(mesh) = node.mesh.as_ref().and_then(|mesh| assets.get(mesh)) else {
+            continue;
+        };
+        if mesh.is_skinned() || mesh.has_morph_targets() || moving.contains(&id) {
+            continue;
+        }
+        let material = node.material.as_ref().map(|m| m.id());
+        match groups.iter_mut().find(|(key, _)| *key == material) {
+            Some((_, ids)) => ids.push(id),
+            None => groups.push((material, vec![id])),
+        }
+    }
+
+    let mut merged = 0;
+    for (_, ids) in groups.into_iter().filter(|(_, ids)| ids.len() > 1) {
+        let parts: Vec<_> = ids
+            .iter()
+            .map(|id| {
+                let node = scene.node(*id).unwrap();
+                let mut mesh = assets.get(node.mesh.as_ref().unwrap()).unwrap().clone();
+                mesh_processing::transform(&mut mesh, &worlds[id]);
+                mesh
+            })
+            .collect();
+        let material = scene.node(ids[0]).unwrap().material.clone();
+        let name = material
+            .as_ref()
+            .and_then(|material| assets.get(material))
+            .map_or_else(|| "merged".to_owned(), |material| material.name.clone());
+        let mesh = mesh_processing::merge(name.clone(), &parts.iter().collect::<Vec<_>>());
+        for id in &ids {
+            let node = scene.node_mut(*id).unwrap();
+            node.mesh = None;
+            node.material = None;
+        }
+        let mut node = Node::new(name);
+        node.mesh = Some(assets.add(mesh));
+        node.material = material;
+        scene.add_node(node, None);
+        merged += ids.len();
+    }
+
+    // Drop subtrees that no longer hold anything worth keeping.
+    let mut keep = HashSet::new();
+    for (id, node) in scene.iter() {
+        if node.mesh.is_some()
+            || node.point_cloud.is_some()
+            || node.skin.is_some()
+            || joints.contains(&id)
+            || animated.contains(&id)
+        {
+            let mut current = Some(id);
+            while let Some(id) = current {
+                if !keep.insert(id) {
+                    break;
+                }
+                current = scene.node(id).unwrap().parent();
+            }
+        }
+    }
+    let empty: Vec<_> = scene
+        .iter()
+        .map(|(id, _)| id)
+        .filter(|id| !keep.contains(id))
+        .collect();
+    for id in empty {
+        scene.remove_node(id);
+    }
+    merged
+}
+
+/// Runs the requested mesh operations on a copy of every mesh in the
+/// scene, keeping meshes that are shared between nodes shared.
+fn process_meshes(scene: &mut Scene, assets: &mut AssetManager, options: &ConvertOptions) {
+    let mut processed = HashMap::new();
+    let ids: Vec<_> = scene.iter().map(|(id, _)| id).collect();
+    for id in ids {
+        let Some(handle) = scene.node(id).unwrap().mesh.clone() else {
+            continue;
+        };
+        let new_handle = match processed.get(&handle.id()) {
+            Some(new_handle) => Handle::clone(new_handle),
+            None => {
+                let Some(mesh) = assets.get(&handle) else {
+                    continue;
+                };
+                let operations = options.mesh_operations(mesh.triangle_count());
+                if operations.is_empty() {
+                    continue;
+                }
+                let mut mesh = mesh.clone();
+                if options.recompute_normals {
+                    // Old normals and tangents would stop the weld from
+                    // joining vertices across hard edges.
+                    mesh.normals.clear();
+                    mesh.tangents.clear();
+                }
+                for operation in operations {
+                    if let Err(err) = operation.apply(&mut mesh) {
+                        log::warn!("mesh \"{}\": {}: {err}", mesh.name, operation.label());
+                    }
+                }
+                let new_handle = assets.add(mesh);
+                processed.insert(handle.id(), new_handle.clone());
+                new_handle
+            }
+        };
+        scene.node_mut(id).unwrap().mesh = Some(new_handle);
+    }
+}
+
+/// Parses the arguments following `convert` and runs the conversion.
+/// Argument errors carry the usage text.
+pub fn run_from_args(args: &[String]) -> anyhow::Result<()> {
+    let options = ConvertOptions::parse(args).with_context(|| format!("\n{USAGE}"))?;
+    run(&options)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::core::animation::{AnimationClip, Channel, ChannelTarget, Interpolation};
+    use crate::core::asset_manager::{Material, Mesh, model::ModelNode};
+
+    /// An animated `arm` with a static `hand` mesh below it, next to two
+    /// static boxes; every mesh uses the same material.
+    fn animated_scene(assets: &mut AssetManager) -> Scene {
+        let mut mesh = Mesh::new("triangle");
+        mesh.positions = vec![Vec3::ZERO, Vec3::X, Vec3::Y];
+        mesh.indices = vec![0, 1, 2];
+        let clip = AnimationClip::new(
+            "swing",
+            vec![Channel {
+                node: 0,
+                target: ChannelTarget::Translation,
+                interpolation: Interpolation::Linear,
+                times: vec![0.0, 1.0],
+                values: vec![0.0, 0.0, 0.0, 0.0, 1.0, 0.0],
+            }],
+        );
+        let node = |name: &str, parent, mesh| ModelNode {
+            name: name.to_owned(),
+            parent,
+            transform: Transform::default(),
+            mesh,
+            material: mesh.map(|_| 0),
+            point_cloud: None,
+            skin: None,
+            morph_weights: Vec::new(),
+        };
+        let model = Model {
+            nodes: vec![
+                node("arm", None, None),
+                node("hand", Some(0), Some(0)),
+                node("box", None, Some(0)),
+                node("other box", None, Some(0)),
+            ],
+            meshes: vec![assets.add(mesh)],
+            materials: vec![assets.add(Material::default())],
+            textures: Vec::new(),
+            point_clouds: Vec::new(),
+            skins: Vec::new(),
+            animations: Arc::from([clip]),
+        };
+        let mut scene = Scene::new();
+        scene.spawn_model(&model, None);
+        scene
+    }
+
+    fn find(scene: &Scene, name: &str) -> Option<NodeId> {
+        scene
+            .iter()
+            .find(|(_, node)| node.name == name)
+            .map(|(id, _)| id)
+    }
+
+    #[test]
+    fn conversion_applies_above_animated_roots() {
+        let mut assets = AssetManager::new();
+        let mut scene = animated_scene(&mut assets);
+        let transform = Mat4::from_scale(Vec3::splat(0.01));
+        let parent = wrap_roots(&mut scene, &transform, "in".to_owned());
+
+        assert_eq!(scene.roots(), [parent]);
+        let arm = scene.node(find(&scene, "arm").unwrap()).unwrap();
+        assert_eq!(arm.parent(), Some(parent));
+        assert_eq!(arm.transform, Transform::default());
+        let wrapper = scene.node(parent).unwrap();
+        assert!(wrapper.transform.to_matrix().abs_diff_eq(transform, 1e-6));
+    }
+
+    #[test]
+    fn merging_skips_meshes_under_animated_nodes() {
+        let mut assets = AssetManager::new();
+        let mut scene = animated_scene(&mut assets);
+        assert_eq!(merge_by_material(&mut scene, &mut assets), 2);
+
+        let hand = scene.node(find(&scene, "hand").unwrap()).unwrap();
+        assert!(hand.mesh.is_some());
+        assert_eq!(hand.parent(), find(&scene, "arm"));
+        assert!(find(&scene, "box").is_none());
+    }
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_owned).collect()
+    }
+
+    #[test]
+    fn parses_options() {
+        let options = ConvertOptions::parse(&args(
+            "in.obj out.glb --normals --weld 0.001 --scale 0.01 --up z-to-y --translate 1,2,3",
+        ))
+        .unwrap();
+        assert_eq!(options.input, PathBuf::from("in.obj"));
+        assert_eq!(options.output, PathBuf::from("out.glb"));
+        assert!(options.recompute_normals);
+        assert_eq!(options.weld_epsilon, Some(0.001));
+        assert_eq!(options.translation, Vec3::new(1.0, 2.0, 3.0));
+        let up = options.transform().transform_vector3(Vec3::Z);
+        assert!(up.abs_diff_eq(Vec3::Y * 0.01, 1e-6));
+
+        let err = ConvertOptions::parse(&args("in.obj")).unwrap_err();
+        assert_eq!(err.to_string(), "expected an input and an output file");
+        let err = ConvertOptions::parse(&args("a b --up x")).unwrap_err();
+        assert_eq!(err.to_string(), "--up: expected z-to-y or y-to-z, got `x`");
+        let err = ConvertOptions::parse(&args("a b --weld")).unwrap_err();
+        assert_eq!(err.to_string(), "--weld needs a value");
+
+        let options =
+            ConvertOptions::parse(&args("a b --optimize --simplify 0.25 --weld 0.5")).unwrap();
+        assert_eq!(
+            options.mesh_operations(10),
+            [
+                MeshOperation::Weld { epsilon: 0.5 },
+                MeshOperation::Simplify {
+                    target_triangles: 3
+                },
+                MeshOperation::OptimizeVertexCache,
+            ]
+        );
+        let err = ConvertOptions::parse(&args("a b --simplify 2")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "--simplify must be a fraction between 0 and 1"
+        );
+    }
+
+    #[test]
+    fn converts_and_merges_obj_to_glb() {
+        let dir = std::env::temp_dir().join(format!("convert-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        // Two objects sharing a material, Z-up, in centimetres.
+        std::fs::write(dir.join("in.mtl"), "newmtl paint\nKd 1 0 0\n").unwrap();
+        std::fs::write(
+            dir.join("in.obj"),
+            "mtllib in.mtl\n\
+             o a\nv 0 0 0\nv 100 0 0\nv 0 100 0\nusemtl paint\nf 1 2 3\n\
+             o b\nv 0 0 100\nv 100 0 100\nv 0 100 100\nusemtl paint\nf 4 5 6\n",
+        )
+        .unwrap();
+
+        let mut options = ConvertOptions::new(dir.join("in.obj"), dir.join("out.glb"));
+        options.scale = 0.01;
+        options.up = Some(UpAxisConversion::ZToY);
+        options.merge_by_material = true;
+        options.recompute_normals = true;
+        run(&options).unwrap();
+
+        let mut assets = AssetManager::new();
+        let model: Handle<Model> = assets.load_blocking(dir.join("out.glb"));
+        let model = assets.get(&model).unwrap();
+        assert_eq!(model.nodes.len(), 1);
+        assert_eq!(model.nodes[0].name, "paint");
+        let mesh = assets.get(model.mesh(&model.nodes[0]).unwrap()).unwrap();
+        assert_eq!(mesh.triangle_count(), 2);
+        assert_eq!(mesh.normals.len(), mesh.vertex_count());
+        // The second triangle sat at z = 100 cm, now one metre up.
+        let top = mesh.positions.iter().map(|p| p.y).fold(f32::MIN, f32::max);
+        assert!((top - 1.0).abs() < 1e-5);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,942 @@
+use glam::{Mat3, Mat4, Quat, Vec2, Vec3};
+
+use crate::app::commands::{SetTransforms, TransformChange};
+use crate::core::{
+    Camera, Input, Scene, Transform, bounds::Ray, camera::Projection, scene::NodeId,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GizmoMode {
+    Translate,
+    Rotate,
+    Scale,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GizmoSpace {
+    World,
+    /// Axes of the active (last selected) node.
+    Local,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PivotMode {
+    /// Rotate and scale around the average origin of the selection.
+    Median,
+    /// Rotate and scale every node around its own origin.
+    Individual,
+}
+
+/// Increments drags snap to while `enabled`.
+#[derive(Clone, Copy, Debug)]
+pub struct Snapping {
+    pub enabled: bool,
+    pub translate: f32,
+    pub rotate_degrees: f32,
+    pub scale: f32,
+}
+
+impl Snapping {
+    pub fn distance(&self, value: f32) -> f32 {
+        snap(value, self.translate, self.enabled)
+    }
+
+    pub fn angle(&self, radians: f32) -> f32 {
+        snap(radians, self.rotate_degrees.to_radians(), self.enabled)
+    }
+
+    /// Snaps how far a factor is from 1, so factors step 1.0, 1.1, 1.2...
+    pub fn factor(&self, factor: f32) -> f32 {
+        1.0 + snap(factor - 1.0, self.scale, self.enabled)
+    }
+}
+
+impl Default for Snapping {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            translate: 0.25,
+            rotate_degrees: 15.0,
+            scale: 0.1,
+        }
+    }
+}
+
+fn snap(value: f32, increment: f32, enabled: bool) -> f32 {
+    if enabled && increment > 0.0 {
+        (value / increment).round() * increment
+    } else {
+        value
+    }
+}
+
+/// Part of the gizmo under the pointer or being dragged. Indices pick an
+/// axis; a plane handle is named by its normal.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Handle {
+    Axis(usize),
+    Plane(usize),
+    Ring(usize),
+    /// Free move in the view plane, or uniform scale.
+    Center,
+}
+
+/// Length of the axis handles in points, independent of zoom.
+const SIZE: f32 = 90.0;
+const PICK_DISTANCE: f32 = 6.0;
+const PLANE_RANGE: (f32, f32) = (0.25, 0.45);
+const RING_SEGMENTS: usize = 64;
+const AXIS_COLORS: [egui::Color32; 3] = [
+    egui::Color32::from_rgb(220, 70, 70),
+    egui::Color32::from_rgb(110, 200, 90),
+    egui::Color32::from_rgb(70, 120, 230),
+];
+const ACTIVE_COLOR: egui::Color32 = egui::Color32::from_rgb(240, 200, 60);
+
+/// Camera and viewport rectangle, for going between world and screen.
+struct View<'a> {
+    camera: &'a Camera,
+    view_proj: Mat4,
+    rect: egui::Rect,
+}
+
+impl<'a> View<'a> {
+    fn new(camera: &'a Camera, rect: egui::Rect) -> Self {
+        let aspect = rect.width().max(1.0) / rect.height().max(1.0);
+        Self {
+            camera,
+            view_proj: camera.projection(aspect) * camera.view(),
+            rect,
+        }
+    }
+
+    fn project(&self, point: Vec3) -> Option<egui::Pos2> {
+        let clip = self.view_proj * point.extend(1.0);
+        if clip.w <= 1e-6 {
+            return None;
+        }
+        let ndc = clip.truncate() / clip.w;
+        Some(egui::pos2(
+            self.rect.min.x + (ndc.x + 1.0) * 0.5 * self.rect.width(),
+            self.rect.min.y + (1.0 - ndc.y) * 0.5 * self.rect.height(),
+        ))
+    }
+
+    fn ray(&self, pos: egui::Pos2) -> Ray {
+        let uv = (pos - self.rect.min) / self.rect.size();
+        let ndc = Vec2::new(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0);
+        let aspect = self.rect.width().max(1.0) / self.rect.height().max(1.0);
+        self.camera.ray(ndc, aspect)
+    }
+
+    fn forward(&self) -> Vec3 {
+        (self.camera.target - self.camera.position()).normalize_or_zero()
+    }
+
+    /// World length of one point at the depth of `point`.
+    fn world_per_point(&self, point: Vec3) -> f32 {
+        let height = match self.camera.projection {
+            Projection::Perspective { fov_y } => {
+                let depth = (point - self.camera.position())
+                    .dot(self.forward())
+                    .max(1e-3);
+                2.0 * depth * (fov_y * 0.5).tan()
+            }
+            Projection::Orthographic { height } => height,
+        };
+        height / self.rect.height().max(1.0)
+    }
+}
+
+/// A selected node as it was when the drag started.
+struct Target {
+    node: NodeId,
+    start: Transform,
+    world: Mat4,
+    parent_inverse: Mat4,
+    axes: [Vec3; 3],
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Delta {
+    Translate(Vec3),
+    Rotate(usize, f32),
+    Scale(Vec3),
+}
+
+struct Drag {
+    handle: Handle,
+    mode: GizmoMode,
+    targets: Vec<Target>,
+    pivot: Vec3,
+    axes: [Vec3; 3],
+    size: f32,
+    /// Where the pointer first hit the handle's line, plane or ring.
+    start_param: f32,
+    start_point: Vec3,
+    last_angle: f32,
+    total_angle: f32,
+    start_distance: f32,
+    delta: Delta,
+}
+
+/// Translate, rotate and scale handles over the selection. Drags edit the
+/// node transforms live and end in a `SetTransforms` command.
+pub struct Gizmo {
+    pub mode: GizmoMode,
+    pub space: GizmoSpace,
+    pub pivot: PivotMode,
+    pub snapping: Snapping,
+    hovered: Option<Handle>,
+    drag: Option<Drag>,
+}
+
+impl Gizmo {
+    pub fn new() -> Self {
+        Self {
+            mode: GizmoMode::Translate,
+            space: GizmoSpace::World,
+            pivot: PivotMode::Median,
+            snapping: Snapping::default(),
+            hovered: None,
+            drag: None,
+        }
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+
+    /// Whether a press now would grab a handle rather than the scene.
+    pub fn wants_pointer(&self) -> bool {
+        self.drag.is_some() || self.hovered.is_some()
+    }
+
+    /// Mode and space shortcuts: `gizmo.translate`, `gizmo.rotate`,
+    /// `gizmo.scale` and `gizmo.toggle_space`.
+    pub fn handle_actions(&mut self, input: &Input) {
+        if self.drag.is_some() {
+            return;
+        }
+        for (action, mode) in [
+            ("gizmo.translate", GizmoMode::Translate),
+            ("gizmo.rotate", GizmoMode::Rotate),
+            ("gizmo.scale", GizmoMode::Scale),
+        ] {
+            if input.pressed(action) {
+                self.mode = mode;
+            }
+        }
+        if input.pressed("gizmo.toggle_space") {
+            self.space = match self.space {
+                GizmoSpace::World => GizmoSpace::Local,
+                GizmoSpace::Local => GizmoSpace::World,
+            };
+        }
+    }
+
+    /// Puts the dragged nodes back where they started.
+    pub fn cancel(&mut self, scene: &mut Scene) {
+        if let Some(drag) = self.drag.take() {
+            for target in &drag.targets {
+                if let Some(node) = scene.node_mut(target.node) {
+                    node.transform = target.start;
+                }
+            }
+        }
+    }
+
+    /// Hit-tests, drags and draws the gizmo for `selection`, whose last
+    /// entry is the active node. Returns the finished edit on release.
+    pub fn ui(
+        &mut self,
+        ui: &egui::Ui,
+        response: &egui::Response,
+        camera: &Camera,
+        selection: &[NodeId],
+        scene: &mut Scene,
+    ) -> Option<SetTransforms> {
+        let view = View::new(camera, response.rect);
+        let (pointer, pressed, down) = ui.input(|i| {
+            (
+                i.pointer.hover_pos(),
+                i.pointer.primary_pressed(),
+                i.pointer.primary_down(),
+            )
+        });
+
+        let mut finished = None;
+        if let Some(drag) = &mut self.drag
+            && down
+        {
+            if let Some(pos) = pointer {
+                drag.update(&view, pos, &self.snapping);
+                drag.apply(self.space, self.pivot, scene);
+            }
+        } else if let Some(drag) = self.drag.take() {
+            finished = drag.finish(self.space, self.pivot);
+        }
+
+        let Some(targets) = self.targets(selection, scene) else {
+            self.hovered = None;
+            return finished;
+        };
+        let (pivot, axes) = match &self.drag {
+            Some(drag) => (drag.pivot, drag.axes),
+            None => self.frame(&targets),
+        };
+        let size = view.world_per_point(pivot) * SIZE;
+
+        if self.drag.is_none() {
+            self.hovered = pointer
+                .filter(|_| response.hovered())
+                .and_then(|pos| self.hit_test(&view, pivot, axes, size, pos));
+            if pressed
+                && let Some(handle) = self.hovered
+                && let Some(pos) = pointer
+            {
+                self.drag = Some(Drag::begin(
+                    &view, handle, self.mode, targets, pivot, axes, size, pos,
+                ));
+            }
+        }
+
+        self.draw(ui, &view, pivot, axes, size);
+        finished
+    }
+
+    /// Selected nodes without a selected ancestor, which would move them
+    /// twice. `None` when nothing is selected.
+    fn targets(&self, selection: &[NodeId], scene: &Scene) -> Option<Vec<Target>> {
+        let targets: Vec<Target> = selection
+            .iter()
+            .filter(|&&id| {
+                !selection
+                    .iter()
+                    .any(|&other| other != id && scene.is_ancestor(other, id))
+            })
+            .filter_map(|&id| {
+                let node = scene.node(id)?;
+                let world = node.world_matrix();
+                let parent = node
+                    .parent()
+                    .and_then(|parent| scene.node(parent))
+                    .map_or(Mat4::IDENTITY, |parent| parent.world_matrix());
+                Some(Target {
+                    node: id,
+                    start: node.transform,
+                    world,
+                    parent_inverse: parent.inverse(),
+                    axes: rotation_axes(&world),
+                })
+            })
+            .collect();
+        (!targets.is_empty()).then_some(targets)
+    }
+
+    /// Pivot and axes the handles are drawn at.
+    fn frame(&self, targets: &[Target]) -> (Vec3, [Vec3; 3]) {
+        let active = targets.last().unwrap();
+        let pivot = match self.pivot {
+            PivotMode::Median => {
+                targets
+                    .iter()
+                    .map(|t| t.world.w_axis.truncate())
+                    .sum::<Vec3>()
+                    / targets.len() as f32
+            }
+            PivotMode::Individual => active.world.w_axis.truncate(),
+        };
+        let axes = match self.space {
+            GizmoSpace::World => [Vec3::X, Vec3::Y, Vec3::Z],
+            GizmoSpace::Local => active.axes,
+        };
+        (pivot, axes)
+    }
+
+    fn handles(&self) -> Vec<Handle> {
+        match self.mode {
+            GizmoMode::Translate => vec![
+                Handle::Center,
+                Handle::Plane(0),
+                Handle::Plane(1),
+                Handle::Plane(2),
+                Handle::Axis(0),
+                Handle::Axis(1),
+                Handle::Axis(2),
+            ],
+            GizmoMode::Rotate => vec![Handle::Ring(0), Handle::Ring(1), Handle::Ring(2)],
+            GizmoMode::Scale => vec![
+                Handle::Center,
+                Handle::Axis(0),
+                Handle::Axis(1),
+                Handle::Axis(2),
+            ],
+        }
+    }
+
+    /// First handle in priority order within picking distance; rings pick
+    /// the nearest.
+    fn hit_test(
+        &self,
+        view: &View,
+        pivot: Vec3,
+        axes: [Vec3; 3],
+        size: f32,
+        pos: egui::Pos2,
+    ) -> Option<Handle> {
+        let center = view.project(pivot)?;
+        let mut best: Option<(Handle, f32)> = None;
+        for handle in self.handles() {
+            let distance = match handle {
+                Handle::Center => center.distance(pos) - PICK_DISTANCE,
+                Handle::Axis(i) => {
+                    let Some(end) = view.project(pivot + axes[i] * size) else {
+                        continue;
+                    };
+                    segment_distance(pos, center, end)
+                }
+                Handle::Plane(i) => {
+                    let corners = plane_corners(pivot, axes, i, size);
+                    let Some(points) = project_all(view, &corners) else {
+                        continue;
+                    };
+                    if inside_quad(pos, &points) {
+                        0.0
+                    } else {
+                        f32::MAX
+                    }
+                }
+                Handle::Ring(i) => {
+                    let Some(points) = project_all(view, &ring_points(pivot, axes, i, size)) else {
+                        continue;
+                    };
+                    points
+                        .windows(2)
+                        .map(|w| segment_distance(pos, w[0], w[1]))
+                        .fold(f32::MAX, f32::min)
+                }
+            };
+            if distance > PICK_DISTANCE {
+                continue;
+            }
+            if !matches!(handle, Handle::Ring(_)) {
+                return Some(handle);
+            }
+            if best.is_none_or(|(_, d)| distance < d) {
+                best = Some((handle, distance));
+            }
+        }
+        best.map(|(handle, _)| handle)
+    }
+
+    fn draw(&self, ui: &egui::Ui, view: &View, pivot: Vec3, axes: [Vec3; 3], size: f32) {
+        let painter = ui.painter_at(view.rect);
+        let active = self.drag.as_ref().map(|d| d.handle).or(self.hovered);
+        let color = |handle: Handle, base: egui::Color32| {
+            if active == Some(handle) {
+                ACTIVE_COLOR
+            } else {
+                base
+            }
+        };
+        // A translate drag moves the handles along with the nodes.
+        let pivot = match self.drag.as_ref().map(|d| d.delta) {
+            Some(Delta::Translate(delta)) => pivot + delta,
+            _ => pivot,
+        };
+        let Some(center) = view.project(pivot) else {
+            return;
+        };
+
+        for handle in self.handles() {
+            match handle {
+                Handle::Axis(i) => {
+                    let scale = match self.drag.as_ref().map(|d| d.delta) {
+                        Some(Delta::Scale(s)) => s[i],
+                        _ => 1.0,
+                    };
+                    let Some(end) = view.project(pivot + axes[i] * size * scale) else {
+                        continue;
+                    };
+                    let color = color(handle, AXIS_COLORS[i]);
+                    painter.line_segment([center, end], egui::Stroke::new(2.5, color));
+                    if self.mode == GizmoMode::Scale {
+                        painter.rect_filled(
+                            egui::Rect::from_center_size(end, egui::vec2(9.0, 9.0)),
+                            1.0,
+                            color,
+                        );
+                    } else {
+                        painter.circle_filled(end, 5.0, color);
+                    }
+                }
+                Handle::Plane(i) => {
+                    let corners = plane_corners(pivot, axes, i, size);
+                    if let Some(points) = project_all(view, &corners) {
+                        let color = color(handle, AXIS_COLORS[i]);
+                        painter.add(egui::Shape::convex_polygon(
+                            points,
+                            color.gamma_multiply(0.35),
+                            egui::Stroke::new(1.0, color),
+                        ));
+                    }
+                }
+                Handle::Ring(i) => {
+                    if let Some(points) = project_all(view, &ring_points(pivot, axes, i, size)) {
+                        painter.add(egui::Shape::line(
+                            points,
+                            egui::Stroke::new(2.5, color(handle, AXIS_COLORS[i])),
+                        ));
+                    }
+                }
+                Handle::Center => {
+                    let color = color(handle, egui::Color32::from_white_alpha(220));
+                    if self.mode == GizmoMode::Scale {
+                        painter.rect_stroke(
+                            egui::Rect::from_center_size(center, egui::vec2(12.0, 12.0)),
+                            1.0,
+                            egui::Stroke::new(2.0, color),
+                            egui::StrokeKind::Middle,
+                        );
+                    } else {
+                        painter.circle_stroke(center, 6.0, egui::Stroke::new(2.0, color));
+                    }
+                }
+            }
+        }
+
+        if let Some(drag) = &self.drag
+            && let Some(pos) = ui.input(|i| i.pointer.hover_pos())
+        {
+            let text = match drag.delta {
+                Delta::Translate(d) => format!("{:.3}, {:.3}, {:.3}", d.x, d.y, d.z),
+                Delta::Rotate(_, angle) => format!("{:.1}°", angle.to_degrees()),
+                Delta::Scale(s) => format!("×{:.3}, {:.3}, {:.3}", s.x, s.y, s.z),
+            };
+            painter.text(
+                pos + egui::vec2(14.0, 14.0),
+                egui::Align2::LEFT_TOP,
+                text,
+                egui::FontId::monospace(12.0),
+                egui::Color32::WHITE,
+            );
+        }
+    }
+
+    /// Mode, space, pivot and snapping controls along the top of `rect`.
+    pub fn toolbar(&mut self, ui: &egui::Ui, rect: egui::Rect) {
+        egui::Area::new(egui::Id::new("gizmo_toolbar"))
+            .fixed_pos(rect.center_top() + egui::vec2(-160.0, 8.0))
+            .show(ui.ctx(), |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        for (mode, label) in [
+                            (GizmoMode::Translate, "Move"),
+                            (GizmoMode::Rotate, "Rotate"),
+                            (GizmoMode::Scale, "Scale"),
+                        ] {
+                            ui.selectable_value(&mut self.mode, mode, label);
+                        }
+                        ui.separator();
+                        egui::ComboBox::from_id_salt("gizmo_space")
+                            .width(60.0)
+                            .selected_text(match self.space {
+                                GizmoSpace::World => "World",
+                                GizmoSpace::Local => "Local",
+                            })
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut self.space, GizmoSpace::World, "World");
+                                ui.selectable_value(&mut self.space, GizmoSpace::Local, "Local");
+                            });
+                        egui::ComboBox::from_id_salt("gizmo_pivot")
+                            .width(80.0)
+                            .selected_text(match self.pivot {
+                                PivotMode::Median => "Median",
+                                PivotMode::Individual => "Individual",
+                            })
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut self.pivot, PivotMode::Median, "Median");
+                                ui.selectable_value(
+                                    &mut self.pivot,
+                                    PivotMode::Individual,
+                                    "Individual",
+                                );
+                            });
+                        ui.separator();
+                        ui.checkbox(&mut self.snapping.enabled, "Snap");
+                        ui.menu_button("⚙", |ui| {
+                            let snapping = &mut self.snapping;
+                            ui.add(
+                                egui::DragValue::new(&mut snapping.translate)
+                                    .range(0.001..=100.0)
+                                    .speed(0.01)
+                                    .prefix("Move "),
+                            );
+                            ui.add(
+                                egui::DragValue::new(&mut snapping.rotate_degrees)
+                                    .range(0.1..=180.0)
+                                    .suffix("°")
+                                    .prefix("Rotate "),
+                            );
+                            ui.add(
+                                egui::DragValue::new(&mut snapping.scale)
+                                    .range(0.001..=10.0)
+                                    .speed(0.01)
+                                    .prefix("Scale "),
+                            );
+                        });
+                    });
+                });
+            });
+    }
+}
+
+impl Default for Gizmo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drag {
+    #[allow(clippy::too_many_arguments)]
+    fn begin(
+        view: &View,
+        handle: Handle,
+        mode: GizmoMode,
+        targets: Vec<Target>,
+        pivot: Vec3,
+        axes: [Vec3; 3],
+        size: f32,
+        pos: egui::Pos2,
+    ) -> Self {
+        let mut drag = Self {
+            handle,
+            mode,
+            targets,
+            pivot,
+            axes,
+            size,
+            start_param: 0.0,
+            start_point: pivot,
+            last_angle: 0.0,
+            total_angle: 0.0,
+            start_distance: 1.0,
+            delta: match mode {
+                GizmoMode::Translate => Delta::Translate(Vec3::ZERO),
+                GizmoMode::Rotate => Delta::Rotate(0, 0.0),
+                GizmoMode::Scale => Delta::Scale(Vec3::ONE),
+            },
+        };
+        let ray = view.ray(pos);
+        match handle {
+            Handle::Axis(i) => {
+                drag.start_param = line_param(&ray, pivot, axes[i]).unwrap_or(0.0);
+            }
+            Handle::Plane(i) => {
+                drag.start_point = plane_hit(&ray, pivot, axes[i]).unwrap_or(pivot);
+            }
+            Handle::Ring(i) => {
+                drag.last_angle = ring_angle(&ray, pivot, axes, i).unwrap_or(0.0);
+                drag.delta = Delta::Rotate(i, 0.0);
+            }
+            Handle::Center => {
+                drag.start_point = plane_hit(&ray, pivot, view.forward()).unwrap_or(pivot);
+                let center = view.project(pivot).unwrap_or(pos);
+                drag.start_distance = center.distance(pos).max(1.0);
+            }
+        }
+        drag
+    }
+
+    fn update(&mut self, view: &View, pos: egui::Pos2, snapping: &Snapping) {
+        let ray = view.ray(pos);
+        let axes = self.axes;
+        // Snaps a free offset along each gizmo axis.
+        let snap_vector =
+            |v: Vec3| -> Vec3 { axes.iter().map(|&a| a * snapping.distance(v.dot(a))).sum() };
+        self.delta = match (self.mode, self.handle) {
+            (GizmoMode::Translate, Handle::Axis(i)) => {
+                let Some(t) = line_param(&ray, self.pivot, axes[i]) else {
+                    return;
+                };
+                Delta::Translate(axes[i] * snapping.distance(t - self.start_param))
+            }
+            (GizmoMode::Translate, Handle::Plane(i)) => {
+                let Some(hit) = plane_hit(&ray, self.pivot, axes[i]) else {
+                    return;
+                };
+                let offset = hit - self.start_point;
+                Delta::Translate(snap_vector(offset - axes[i] * offset.dot(axes[i])))
+            }
+            (GizmoMode::Translate, Handle::Center) => {
+                let Some(hit) = plane_hit(&ray, self.pivot, view.forward()) else {
+                    return;
+                };
+                Delta::Translate(snap_vector(hit - self.start_point))
+            }
+            (_, Handle::Ring(i)) => {
+                let Some(angle) = ring_angle(&ray, self.pivot, axes, i) else {
+                    return;
+                };
+                let step = angle - self.last_angle;
+                self.total_angle += (step + std::f32::consts::PI).rem_euclid(std::f32::consts::TAU)
+                    - std::f32::consts::PI;
+                self.last_angle = angle;
+                Delta::Rotate(i, snapping.angle(self.total_angle))
+            }
+            (GizmoMode::Scale, Handle::Axis(i)) => {
+                let Some(t) = line_param(&ray, self.pivot, axes[i]) else {
+                    return;
+                };
+                let mut scale = Vec3::ONE;
+                scale[i] = snapping
+                    .factor(1.0 + (t - self.start_param) / self.size)
+                    .max(0.01);
+                Delta::Scale(scale)
+            }
+            (GizmoMode::Scale, Handle::Center) => {
+                let center = view.project(self.pivot).unwrap_or(pos);
+                let factor = center.distance(pos) / self.start_distance;
+                Delta::Scale(Vec3::splat(snapping.factor(factor).max(0.01)))
+            }
+            _ => return,
+        };
+    }
+
+    /// Writes the transforms for the current delta to the scene.
+    fn apply(&self, space: GizmoSpace, pivot: PivotMode, scene: &mut Scene) {
+        for target in &self.targets {
+            let transform = self.transform(target, space, pivot);
+            if let Some(node) = scene.node_mut(target.node) {
+                node.transform = transform;
+            }
+        }
+    }
+
+    fn transform(&self, target: &Target, space: GizmoSpace, pivot: PivotMode) -> Transform {
+        let origin = target.world.w_axis.truncate();
+        let center = match pivot {
+            PivotMode::Median => self.pivot,
+            PivotMode::Individual => origin,
+        };
+        let axes = match (space, pivot) {
+            (GizmoSpace::Local, PivotMode::Individual) => target.axes,
+            _ => self.axes,
+        };
+        let start = target.start;
+        match self.delta {
+            Delta::Translate(delta) => Transform {
+                translation: start.translation + target.parent_inverse.transform_vector3(delta),
+                ..start
+            },
+            Delta::Rotate(i, angle) => {
+                let rotation = Mat4::from_translation(center)
+                    * Mat4::from_quat(Quat::from_axis_angle(axes[i], angle))
+                    * Mat4::from_translation(-center);
+                let local = Transform::from_matrix(target.parent_inverse * rotation * target.world);
+                Transform {
+                    translation: local.translation,
+                    rotation: local.rotation.normalize(),
+                    scale: start.scale,
+                }
+            }
+            Delta::Scale(scale) => {
+                let basis = Mat3::from_cols(axes[0], axes[1], axes[2]);
+                let world_scale = basis * Mat3::from_diagonal(scale) * basis.transpose();
+                let new_origin = center + world_scale * (origin - center);
+                let translation = target.parent_inverse.transform_point3(new_origin);
+                match space {
+                    GizmoSpace::Local => Transform {
+                        translation,
+                        rotation: start.rotation,
+                        scale: start.scale * scale,
+                    },
+                    GizmoSpace::World => {
+                        let linear = Mat4::from_mat3(world_scale)
+                            * Mat4::from_mat3(Mat3::from_mat4(target.world));
+                        let local = Transform::from_matrix(
+                            target.parent_inverse * Mat4::from_translation(new_origin) * linear,
+                        );
+                        Transform {
+                            translation,
+                            ..local
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// The command for a drag that changed something.
+    fn finish(self, space: GizmoSpace, pivot: PivotMode) -> Option<SetTransforms> {
+        let changed = match self.delta {
+            Delta::Translate(delta) => delta != Vec3::ZERO,
+            Delta::Rotate(_, angle) => angle != 0.0,
+            Delta::Scale(scale) => scale != Vec3::ONE,
+        };
+        if !changed {
+            return None;
+        }
+        let label = match self.delta {
+            Delta::Translate(_) => "Move",
+            Delta::Rotate(..) => "Rotate",
+            Delta::Scale(_) => "Scale",
+        };
+        let changes = self
+            .targets
+            .iter()
+            .map(|target| TransformChange {
+                node: target.node,
+                before: target.start,
+                after: self.transform(target, space, pivot),
+            })
+            .collect();
+        Some(SetTransforms::new(label, changes))
+    }
+}
+
+fn rotation_axes(world: &Mat4) -> [Vec3; 3] {
+    let (_, rotation, _) = world.to_scale_rotation_translation();
+    [rotation * Vec3::X, rotation * Vec3::Y, rotation * Vec3::Z]
+}
+
+/// Parameter along the line `origin + t * axis` closest to the ray, or
+/// `None` when they are nearly parallel.
+fn line_param(ray: &Ray, origin: Vec3, axis: Vec3) -> Option<f32> {
+    let dir = ray.direction.normalize_or_zero();
+    let b = axis.dot(dir);
+    let denom = 1.0 - b * b;
+    if denom < 1e-4 {
+        return None;
+    }
+    let w = ray.origin - origin;
+    Some((axis.dot(w) - b * dir.dot(w)) / denom)
+}
+
+fn plane_hit(ray: &Ray, origin: Vec3, normal: Vec3) -> Option<Vec3> {
+    let denom = ray.direction.dot(normal);
+    if denom.abs() < 1e-6 {
+        return None;
+    }
+    let t = (origin - ray.origin).dot(normal) / denom;
+    (t >= 0.0).then(|| ray.at(t))
+}
+
+/// Angle of the ray's hit on the plane of ring `i`, measured from the next
+/// axis around.
+fn ring_angle(ray: &Ray, pivot: Vec3, axes: [Vec3; 3], i: usize) -> Option<f32> {
+    let hit = plane_hit(ray, pivot, axes[i])? - pivot;
+    let (u, v) = (axes[(i + 1) % 3], axes[(i + 2) % 3]);
+    Some(hit.dot(v).atan2(hit.dot(u)))
+}
+
+fn ring_points(pivot: Vec3, axes: [Vec3; 3], i: usize, size: f32) -> Vec<Vec3> {
+    let (u, v) = (axes[(i + 1) % 3], axes[(i + 2) % 3]);
+    (0..=RING_SEGMENTS)
+        .map(|s| {
+            let angle = s as f32 / RING_SEGMENTS as f32 * std::f32::consts::TAU;
+            pivot + (u * angle.cos() + v * angle.sin()) * size
+        })
+        .collect()
+}
+
+fn plane_corners(pivot: Vec3, axes: [Vec3; 3], normal: usize, size: f32) -> [Vec3; 4] {
+    let (u, v) = (axes[(normal + 1) % 3], axes[(normal + 2) % 3]);
+    let (near, far) = (PLANE_RANGE.0 * size, PLANE_RANGE.1 * size);
+    [
+        pivot + u * near + v * near,
+        pivot + u * far + v * near,
+        pivot + u * far + v * far,
+        pivot + u * near + v * far,
+    ]
+}
+
+fn project_all(view: &View, points: &[Vec3]) -> Option<Vec<egui::Pos2>> {
+    points.iter().map(|&p| view.project(p)).collect()
+}
+
+fn segment_distance(p: egui::Pos2, a: egui::Pos2, b: egui::Pos2) -> f32 {
+    let ab = b - a;
+    let t = ((p - a).dot(ab) / ab.length_sq().max(1e-6)).clamp(0.0, 1.0);
+    p.distance(a + ab * t)
+}
+
+fn inside_quad(p: egui::Pos2, quad: &[egui::Pos2]) -> bool {
+    let side = |a: egui::Pos2, b: egui::Pos2| (b - a).x * (p - a).y - (b - a).y * (p - a).x;
+    let signs: Vec<f32> = (0..quad.len())
+        .map(|i| side(quad[i], quad[(i + 1) % quad.len()]))
+        .collect();
+    signs.iter().all(|&s| s >= 0.0) || signs.iter().all(|&s| s <= 0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snaps_to_increments() {
+        let mut snapping = Snapping {
+            enabled: true,
+            ..Snapping::default()
+        };
+        assert_eq!(snapping.distance(0.4), 0.5);
+        assert!((snapping.angle(20f32.to_radians()) - 15f32.to_radians()).abs() < 1e-6);
+        assert!((snapping.factor(1.26) - 1.3).abs() < 1e-6);
+        snapping.enabled = false;
+        assert_eq!(snapping.distance(0.4), 0.4);
+    }
+
+    #[test]
+    fn rotates_and_scales_around_the_median_pivot() {
+        let target = |x: f32| {
+            let world = Mat4::from_translation(Vec3::new(x, 0.0, 0.0));
+            Target {
+                node: Scene::new().add_node(crate::core::scene::Node::new("n"), None),
+                start: Transform::from_matrix(world),
+                world,
+                parent_inverse: Mat4::IDENTITY,
+                axes: [Vec3::X, Vec3::Y, Vec3::Z],
+            }
+        };
+        let mut drag = Drag {
+            handle: Handle::Ring(1),
+            mode: GizmoMode::Rotate,
+            targets: vec![target(1.0), target(3.0)],
+            pivot: Vec3::new(2.0, 0.0, 0.0),
+            axes: [Vec3::X, Vec3::Y, Vec3::Z],
+            size: 1.0,
+            start_param: 0.0,
+            start_point: Vec3::ZERO,
+            last_angle: 0.0,
+            total_angle: 0.0,
+            start_distance: 1.0,
+            delta: Delta::Rotate(1, std::f32::consts::PI),
+        };
+        let median = drag.transform(&drag.targets[0], GizmoSpace::World, PivotMode::Median);
+        assert!(
+            median
+                .translation
+                .abs_diff_eq(Vec3::new(3.0, 0.0, 0.0), 1e-5)
+        );
+        let own = drag.transform(&drag.targets[0], GizmoSpace::World, PivotMode::Individual);
+        assert!(own.translation.abs_diff_eq(Vec3::new(1.0, 0.0, 0.0), 1e-5));
+
+        drag.delta = Delta::Scale(Vec3::new(2.0, 1.0, 1.0));
+        let scaled = drag.transform(&drag.targets[1], GizmoSpace::Local, PivotMode::Median);
+        assert!(
+            scaled
+                .translation
+                .abs_diff_eq(Vec3::new(4.0, 0.0, 0.0), 1e-5)
+        );
+        assert_eq!(scaled.scale, Vec3::new(2.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn closest_point_on_axis_to_ray() {
+        let ray = Ray::new(Vec3::new(2.0, 5.0, 0.0), Vec3::NEG_Y);
+        let t = line_param(&ray, Vec3::ZERO, Vec3::X).unwrap();
+        assert!((t - 2.0).abs() < 1e-5);
+        assert!(line_param(&ray, Vec3::ZERO, Vec3::Y).is_none());
+    }
+}
@@ -0,0 +1,289 @@
+use std::any::Any;
+use std::time::{Duration, Instant};
+
+use crate::core::Scene;
+
+/// A reversible scene edit. Commands are recorded after they have been
+/// applied, so `apply` only runs again on redo.
+pub trait Command {
+    fn label(&self) -> String;
+    fn apply(&mut self, scene: &mut Scene);
+    fn revert(&mut self, scene: &mut Scene);
+
+    /// Folds `next`, recorded right after this command, into it when both
+    /// are steps of one continuous edit such as a slider drag.
+    fn merge(&mut self, _next: &dyn Command) -> bool {
+        false
+    }
+
+    /// Approximate bytes held, for the history's memory bound.
+    fn memory_size(&self) -> usize;
+
+    fn as_any(&self) -> &dyn Any;
+}
+
+struct Entry {
+    command: Box<dyn Command>,
+    size: usize,
+}
+
+/// Undo and redo stacks of applied commands. Commands recorded within
+/// `MERGE_WINDOW` of each other may merge, and the oldest entries are
+/// dropped once the stacks hold more than `max_bytes`.
+pub struct History {
+    done: Vec<Entry>,
+    undone: Vec<Entry>,
+    bytes: usize,
+    max_bytes: usize,
+    dropped: usize,
+    last_push: Option<Instant>,
+}
+
+impl History {
+    pub const MERGE_WINDOW: Duration = Duration::from_millis(750);
+    pub const DEFAULT_MAX_BYTES: usize = 64 << 20;
+
+    pub fn new() -> Self {
+        Self {
+            done: Vec::new(),
+            undone: Vec::new(),
+            bytes: 0,
+            max_bytes: Self::DEFAULT_MAX_BYTES,
+            dropped: 0,
+            last_push: None,
+        }
+    }
+
+    pub fn max_bytes(&self) -> usize {
+        self.max_bytes
+    }
+
+    pub fn set_max_bytes(&mut self, max_bytes: usize) {
+        self.max_bytes = max_bytes;
+        self.trim();
+    }
+
+    /// Bytes currently held by recorded commands.
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// Records a command that has already been applied. Anything undone
+    /// before can no longer be redone.
+    pub fn push(&mut self, command: Box<dyn Command>) {
+        self.push_at(command, Instant::now());
+    }
+
+    fn push_at(&mut self, command: Box<dyn Command>, now: Instant) {
+        for entry in self.undone.drain(..) {
+            self.bytes -= entry.size;
+        }
+        let recent = self
+            .last_push
+            .is_some_and(|last| now.duration_since(last) <= Self::MERGE_WINDOW);
+        self.last_push = Some(now);
+        if recent
+            && let Some(top) = self.done.last_mut()
+            && top.command.merge(command.as_ref())
+        {
+            let size = top.command.memory_size();
+            self.bytes = self.bytes - top.size + size;
+            top.size = size;
+        } else {
+            let size = command.memory_size();
+            self.bytes += size;
+            self.done.push(Entry { command, size });
+        }
+        self.trim();
+    }
+
+    /// Drops the oldest entries until the bound is met, always keeping the
+    /// newest one.
+    fn trim(&mut self) {
+        let excess = self.done.len().saturating_sub(1);
+        let mut count = 0;
+        while count < excess && self.bytes > self.max_bytes {
+            self.bytes -= self.done[count].size;
+            count += 1;
+        }
+        self.done.drain(..count);
+        self.dropped += count;
+    }
+
+    /// Forgets every entry, e.g. when another scene is opened.
+    pub fn clear(&mut self) {
+        self.done.clear();
+        self.undone.clear();
+        self.bytes = 0;
+        self.dropped = 0;
+        self.last_push = None;
+    }
+
+    /// Ends merging, so the next command starts a new entry.
+    pub fn seal(&mut self) {
+        self.last_push = None;
+    }
+
+    pub fn undo(&mut self, scene: &mut Scene) -> bool {
+        let Some(mut entry) = self.done.pop() else {
+            return false;
+        };
+        entry.command.revert(scene);
+        self.resize(&mut entry);
+        self.undone.push(entry);
+        self.seal();
+        true
+    }
+
+    pub fn redo(&mut self, scene: &mut Scene) -> bool {
+        let Some(mut entry) = self.undone.pop() else {
+            return false;
+        };
+        entry.command.apply(scene);
+        self.resize(&mut entry);
+        self.done.push(entry);
+        self.seal();
+        true
+    }
+
+    /// Re-measures a command whose held state changed, e.g. a delete that
+    /// now holds the removed nodes.
+    fn resize(&mut self, entry: &mut Entry) {
+        let size = entry.command.memory_size();
+        self.bytes = self.bytes - entry.size + size;
+        entry.size = size;
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.done.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// Labels of all entries, oldest first; the first `position` are done.
+    pub fn labels(&self) -> impl Iterator<Item = String> + '_ {
+        self.done
+            .iter()
+            .chain(self.undone.iter().rev())
+            .map(|entry| entry.command.label())
+    }
+
+    /// Number of entries currently applied.
+    pub fn position(&self) -> usize {
+        self.done.len()
+    }
+
+    /// Entries dropped from the front to stay within the memory bound.
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// Undoes or redoes until exactly `position` entries are applied.
+    pub fn jump_to(&mut self, position: usize, scene: &mut Scene) {
+        while self.position() > position && self.undo(scene) {}
+        while self.position() < position && self.redo(scene) {}
+    }
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::commands::{RemoveNodes, SetTransforms, TransformChange};
+    use crate::core::{Transform, scene::Node};
+    use glam::Vec3;
+
+    fn move_to(node: crate::core::scene::NodeId, from: f32, to: f32) -> Box<SetTransforms> {
+        Box::new(
+            SetTransforms::new(
+                "Edit transform",
+                vec![TransformChange {
+                    node,
+                    before: Transform::from_translation(Vec3::X * from),
+                    after: Transform::from_translation(Vec3::X * to),
+                }],
+            )
+            .continuous(),
+        )
+    }
+
+    #[test]
+    fn undo_and_redo_transform_edits() {
+        let mut scene = Scene::new();
+        let node = scene.add_node(Node::new("node"), None);
+        let moved = Transform::from_translation(Vec3::X);
+        scene.node_mut(node).unwrap().transform = moved;
+
+        let mut history = History::new();
+        history.push(Box::new(SetTransforms::new(
+            "Move",
+            vec![TransformChange {
+                node,
+                before: Transform::IDENTITY,
+                after: moved,
+            }],
+        )));
+        assert!(history.undo(&mut scene));
+        assert_eq!(scene.node(node).unwrap().transform, Transform::IDENTITY);
+        assert!(!history.undo(&mut scene));
+        assert!(history.redo(&mut scene));
+        assert_eq!(scene.node(node).unwrap().transform, moved);
+
+        history.undo(&mut scene);
+        history.push(Box::new(SetTransforms::new("Empty", Vec::new())));
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn merges_continuous_edits_within_the_window() {
+        let mut scene = Scene::new();
+        let node = scene.add_node(Node::new("node"), None);
+        let mut history = History::new();
+        let start = Instant::now();
+        history.push_at(move_to(node, 0.0, 1.0), start);
+        history.push_at(move_to(node, 1.0, 2.0), start + Duration::from_millis(100));
+        assert_eq!(history.position(), 1);
+        history.push_at(move_to(node, 2.0, 3.0), start + Duration::from_secs(5));
+        assert_eq!(history.position(), 2);
+
+        scene.node_mut(node).unwrap().transform = Transform::from_translation(Vec3::X * 3.0);
+        history.jump_to(0, &mut scene);
+        assert_eq!(scene.node(node).unwrap().transform, Transform::IDENTITY);
+        history.jump_to(1, &mut scene);
+        assert_eq!(
+            scene.node(node).unwrap().transform.translation,
+            Vec3::X * 2.0
+        );
+    }
+
+    #[test]
+    fn undoes_deletes_and_bounds_memory() {
+        let mut scene = Scene::new();
+        let parent = scene.add_node(Node::new("parent"), None);
+        let child = scene.add_node(Node::new("child"), Some(parent));
+
+        let mut history = History::new();
+        let delete = RemoveNodes::execute(&mut scene, &[child, parent]).unwrap();
+        history.push(Box::new(delete));
+        assert!(scene.is_empty());
+        history.undo(&mut scene);
+        assert_eq!(scene.node(child).unwrap().parent(), Some(parent));
+        history.redo(&mut scene);
+        assert!(scene.is_empty());
+
+        history.set_max_bytes(1);
+        let start = Instant::now();
+        for i in 0..3 {
+            history.push_at(move_to(parent, 0.0, 1.0), start + Duration::from_secs(i));
+        }
+        assert_eq!(history.position(), 1);
+        assert_eq!(history.dropped(), 3);
+    }
+}
@@ -0,0 +1,77 @@
+use crate::app::history::History;
+use crate::core::Scene;
+
+/// Window listing the edit history. Clicking an entry undoes or redoes up to
+/// it; entries past the current state are greyed out.
+pub struct HistoryPanel {
+    open: bool,
+}
+
+impl HistoryPanel {
+    pub fn new() -> Self {
+        Self { open: false }
+    }
+
+    pub fn open_mut(&mut self) -> &mut bool {
+        &mut self.open
+    }
+
+    pub fn ui(&mut self, egui_ctx: &egui::Context, history: &mut History, scene: &mut Scene) {
+        let mut target = None;
+        egui::Window::new("History")
+            .open(&mut self.open)
+            .default_width(240.0)
+            .show(egui_ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(history.can_undo(), egui::Button::new("⟲ Undo"))
+                        .clicked()
+                    {
+                        target = Some(history.position() - 1);
+                    }
+                    if ui
+                        .add_enabled(history.can_redo(), egui::Button::new("⟳ Redo"))
+                        .clicked()
+                    {
+                        target = Some(history.position() + 1);
+                    }
+                });
+                ui.label(format!(
+                    "{:.1} of {:.0} MiB",
+                    history.bytes() as f64 / (1 << 20) as f64,
+                    history.max_bytes() as f64 / (1 << 20) as f64
+                ));
+                ui.separator();
+                egui::ScrollArea::vertical()
+                    .max_height(360.0)
+                    .stick_to_bottom(true)
+                    .show(ui, |ui| {
+                        let position = history.position();
+                        let first = if history.dropped() > 0 {
+                            format!("({} older edits dropped)", history.dropped())
+                        } else {
+                            "Initial state".to_owned()
+                        };
+                        let labels = std::iter::once(first).chain(history.labels());
+                        for (i, label) in labels.enumerate() {
+                            let mut text = egui::RichText::new(label);
+                            if i > position {
+                                text = text.weak();
+                            }
+                            if ui.selectable_label(i == position, text).clicked() {
+                                target = Some(i);
+                            }
+                        }
+                    });
+            });
+        if let Some(target) = target {
+            history.jump_to(target, scene);
+        }
+    }
+}
+
+impl Default for HistoryPanel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
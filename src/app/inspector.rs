@@ -0,0 +1,224 @@
+use glam::{EulerRot, Quat, Vec3};
+
+use crate::app::commands::{SetMorphWeights, SetTransforms, TransformChange};
+use crate::app::history::History;
+use crate::core::asset_manager::Mesh;
+use crate::core::mesh_processing::MeshOperation;
+use crate::core::{AssetManager, Transform, scene::NodeId, scene::Scene};
+
+/// Right panel with details of the selected node.
+pub struct Inspector {
+    crease_angle_degrees: f32,
+    weld_epsilon: f32,
+    simplify_ratio: f32,
+}
+
+impl Inspector {
+    pub fn new() -> Self {
+        Self {
+            crease_angle_degrees: 60.0,
+            weld_epsilon: 1e-5,
+            simplify_ratio: 0.5,
+        }
+    }
+
+    /// Returns a mesh tool the user picked for the selected node's mesh.
+    pub fn ui(
+        &mut self,
+        egui_ctx: &egui::Context,
+        selected: Option<NodeId>,
+        scene: &mut Scene,
+        assets: &AssetManager,
+        history: &mut History,
+    ) -> Option<MeshOperation> {
+        let (id, node) = selected.and_then(|id| Some((id, scene.node_mut(id)?)))?;
+        let mut operation = None;
+        egui::SidePanel::right("inspector")
+            .resizable(true)
+            .default_width(240.0)
+            .show(egui_ctx, |ui| {
+                ui.heading(&node.name);
+                ui.separator();
+                egui::CollapsingHeader::new("Transform")
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        if let Some(after) = Self::transform_ui(ui, &node.transform) {
+                            let change = TransformChange {
+                                node: id,
+                                before: node.transform,
+                                after,
+                            };
+                            node.transform = after;
+                            history.push(Box::new(
+                                SetTransforms::new("Edit transform", vec![change]).continuous(),
+                            ));
+                        }
+                    });
+                let Some(mesh) = node.mesh.as_ref().and_then(|handle| assets.get(handle)) else {
+                    return;
+                };
+                ui.label(format!(
+                    "{}: {} vertices, {} triangles",
+                    mesh.name,
+                    mesh.vertex_count(),
+                    mesh.triangle_count()
+                ));
+                for (i, lod) in mesh.lods.iter().enumerate() {
+                    let triangles = assets.get(&lod.mesh).map_or(0, |m| m.triangle_count());
+                    ui.weak(format!(
+                        "LOD {}: {} triangles below {:.0}% of the view",
+                        i + 1,
+                        triangles,
+                        lod.screen_coverage * 100.0
+                    ));
+                }
+                egui::CollapsingHeader::new("Mesh tools").show(ui, |ui| {
+                    operation = self.mesh_tools_ui(ui, mesh);
+                });
+                if !mesh.has_morph_targets() {
+                    return;
+                }
+                egui::CollapsingHeader::new("Morph targets")
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        // Editing starts from the mesh defaults; the node
+                        // then keeps its own copy.
+                        let mut weights = if node.morph_weights.is_empty() {
+                            mesh.morph_weights.clone()
+                        } else {
+                            node.morph_weights.clone()
+                        };
+                        weights.resize(mesh.morph_targets.len(), 0.0);
+                        let mut changed = false;
+                        for (target, weight) in mesh.morph_targets.iter().zip(&mut weights) {
+                            changed |= ui
+                                .add(egui::Slider::new(weight, 0.0..=1.0).text(&target.name))
+                                .changed();
+                        }
+                        let after = if ui.button("Reset").clicked() {
+                            Vec::new()
+                        } else if changed {
+                            weights
+                        } else {
+                            return;
+                        };
+                        let before = std::mem::replace(&mut node.morph_weights, after.clone());
+                        history.push(Box::new(SetMorphWeights::new(id, before, after)));
+                    });
+            });
+        operation
+    }
+
+    /// Buttons for the processing steps, with their settings next to them.
+    /// The tools edit a copy, so other nodes sharing the mesh keep theirs.
+    fn mesh_tools_ui(&mut self, ui: &mut egui::Ui, mesh: &Mesh) -> Option<MeshOperation> {
+        let mut operation = None;
+        let has_uvs = mesh.tex_coords.len() == mesh.vertex_count();
+        egui::Grid::new("inspector_mesh_tools")
+            .num_columns(2)
+            .show(ui, |ui| {
+                if ui.button("Smooth normals").clicked() {
+                    operation = Some(MeshOperation::SmoothNormals {
+                        crease_angle: self.crease_angle_degrees.to_radians(),
+                    });
+                }
+                ui.add(
+                    egui::DragValue::new(&mut self.crease_angle_degrees)
+                        .range(0.0..=180.0)
+                        .suffix("°"),
+                )
+                .on_hover_text("Faces meeting at a sharper angle keep a hard edge");
+                ui.end_row();
+                if ui.button("Flat normals").clicked() {
+                    operation = Some(MeshOperation::FlatNormals);
+                }
+                ui.end_row();
+                if ui
+                    .add_enabled(has_uvs, egui::Button::new("Tangents"))
+                    .on_disabled_hover_text("The mesh has no texture coordinates")
+                    .clicked()
+                {
+                    operation = Some(MeshOperation::Tangents);
+                }
+                ui.end_row();
+                if ui.button("Weld").clicked() {
+                    operation = Some(MeshOperation::Weld {
+                        epsilon: self.weld_epsilon,
+                    });
+                }
+                ui.add(
+                    egui::DragValue::new(&mut self.weld_epsilon)
+                        .range(0.0..=1.0)
+                        .speed(1e-5)
+                        .max_decimals(6),
+                )
+                .on_hover_text("Vertices closer than this merge");
+                ui.end_row();
+                if ui.button("Remove degenerates").clicked() {
+                    operation = Some(MeshOperation::RemoveDegenerateTriangles);
+                }
+                ui.end_row();
+                if ui.button("Optimize vertex cache").clicked() {
+                    operation = Some(MeshOperation::OptimizeVertexCache);
+                }
+                ui.end_row();
+                let triangle_count = mesh.triangle_count();
+                if ui.button("Simplify").clicked() {
+                    let target_triangles =
+                        (triangle_count as f64 * f64::from(self.simplify_ratio)).ceil() as usize;
+                    operation = Some(MeshOperation::Simplify { target_triangles });
+                }
+                ui.add(
+                    egui::Slider::new(&mut self.simplify_ratio, 0.01..=1.0).custom_formatter(
+                        move |ratio, _| {
+                            let target = (triangle_count as f64 * ratio).ceil() as usize;
+                            format!("{target} tris")
+                        },
+                    ),
+                );
+                ui.end_row();
+            });
+        operation
+    }
+
+    /// Position, rotation as XYZ Euler angles in degrees, and scale. Returns
+    /// the edited transform if anything changed.
+    fn transform_ui(ui: &mut egui::Ui, transform: &Transform) -> Option<Transform> {
+        let mut translation = transform.translation;
+        let (x, y, z) = transform.rotation.to_euler(EulerRot::XYZ);
+        let mut degrees = Vec3::new(x, y, z) * 180.0 / std::f32::consts::PI;
+        let mut scale = transform.scale;
+        let mut changed = false;
+        egui::Grid::new("inspector_transform")
+            .num_columns(4)
+            .show(ui, |ui| {
+                for (label, value, speed) in [
+                    ("Position", &mut translation, 0.01),
+                    ("Rotation", &mut degrees, 0.5),
+                    ("Scale", &mut scale, 0.01),
+                ] {
+                    ui.label(label);
+                    for component in value.as_mut() {
+                        changed |= ui
+                            .add(egui::DragValue::new(component).speed(speed).max_decimals(3))
+                            .changed();
+                    }
+                    ui.end_row();
+                }
+            });
+        changed.then(|| {
+            let radians = degrees * std::f32::consts::PI / 180.0;
+            Transform {
+                translation,
+                rotation: Quat::from_euler(EulerRot::XYZ, radians.x, radians.y, radians.z),
+                scale,
+            }
+        })
+    }
+}
+
+impl Default for Inspector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
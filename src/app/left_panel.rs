@@ -1,11 +1,42 @@
-pub struct LeftPanel;
+use std::f32::consts::PI;
+
+use glam::Vec3;
+
+use crate::core::{
+    AssetManager,
+    asset_manager::{AssetState, Environment},
+    mesh_processing::LodGeneration,
+    redraw::{RedrawMode, RedrawScheduler},
+    renderer::{Background, PointColorMode, PointSizeMode, Renderer},
+    time::Time,
+};
+
+pub struct LeftPanel {
+    lod_generation: LodGeneration,
+    /// Outcome of the last "Generate LODs" click.
+    lod_report: Option<String>,
+    /// Path typed into the environment map field.
+    environment_path: String,
+}
 
 impl LeftPanel {
     pub fn new() -> Self {
-        Self
+        Self {
+            lod_generation: LodGeneration::new(),
+            lod_report: None,
+            environment_path: String::new(),
+        }
     }
 
-    pub fn ui(&mut self, egui_ctx: &egui::Context) {
+    pub fn ui(
+        &mut self,
+        egui_ctx: &egui::Context,
+        assets: &mut AssetManager,
+        renderer: &mut Renderer,
+        time: &mut Time,
+        redraw: &mut RedrawScheduler,
+        windows: &mut [(&str, &mut bool)],
+    ) {
         egui::SidePanel::left("left_panel")
             .resizable(true)
             .min_width(200.0)
@@ -14,6 +45,341 @@ impl LeftPanel {
             .show(egui_ctx, |ui| {
                 ui.heading("Panel");
                 ui.separator();
+                Self::loading_ui(ui, assets);
+                ui.separator();
+                egui::CollapsingHeader::new("Simulation").show(ui, |ui| {
+                    Self::simulation_ui(ui, time);
+                });
+                egui::CollapsingHeader::new("Redraw").show(ui, |ui| {
+                    Self::redraw_ui(ui, redraw);
+                });
+                egui::CollapsingHeader::new("Environment").show(ui, |ui| {
+                    self.environment_ui(ui, assets, renderer);
+                });
+                egui::CollapsingHeader::new("Point clouds").show(ui, |ui| {
+                    Self::point_cloud_ui(ui, renderer);
+                });
+                egui::CollapsingHeader::new("Level of detail").show(ui, |ui| {
+                    self.lod_ui(ui, assets, renderer);
+                });
+                ui.separator();
+                ui.horizontal_wrapped(|ui| {
+                    for (label, open) in windows.iter_mut() {
+                        ui.toggle_value(open, *label);
+                    }
+                });
+            });
+    }
+
+    fn simulation_ui(ui: &mut egui::Ui, time: &mut Time) {
+        ui.horizontal(|ui| {
+            let mut paused = time.is_paused();
+            if ui.toggle_value(&mut paused, "⏸ Pause").changed() {
+                time.set_paused(paused);
+            }
+            if ui
+                .add_enabled(paused, egui::Button::new("Step"))
+                .on_hover_text("Advance one simulation step")
+                .clicked()
+            {
+                time.step();
+            }
+        });
+        let mut scale = time.scale();
+        if ui
+            .add(
+                egui::Slider::new(&mut scale, 0.05..=4.0)
+                    .logarithmic(true)
+                    .text("Time scale"),
+            )
+            .changed()
+        {
+            time.set_scale(scale);
+        }
+        let mut hz = time.hz().round();
+        if ui
+            .add(
+                egui::Slider::new(&mut hz, 10.0..=240.0)
+                    .suffix(" Hz")
+                    .text("Rate"),
+            )
+            .changed()
+        {
+            time.set_hz(hz);
+        }
+        ui.weak(format!(
+            "{:.2} s simulated, {} steps this frame",
+            time.simulated().as_secs_f64(),
+            time.steps()
+        ));
+    }
+
+    fn redraw_ui(ui: &mut egui::Ui, redraw: &mut RedrawScheduler) {
+        let mut mode = redraw.mode();
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut mode, RedrawMode::Reactive, "On change")
+                .on_hover_text("Draw only after input, loads or while animating");
+            ui.selectable_value(&mut mode, RedrawMode::Continuous, "Continuous");
+        });
+        redraw.set_mode(mode);
+
+        let mut capped = redraw.fps_cap().is_some();
+        let mut fps = redraw.fps_cap().unwrap_or(60.0);
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut capped, "FPS cap");
+            ui.add_enabled(capped, egui::Slider::new(&mut fps, 10.0..=240.0));
+        });
+        redraw.set_fps_cap(capped.then_some(fps));
+    }
+
+    fn environment_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        assets: &mut AssetManager,
+        renderer: &mut Renderer,
+    ) {
+        let settings = renderer.environment_settings_mut();
+        ui.horizontal(|ui| {
+            ui.label("Background");
+            for background in [
+                Background::Solid,
+                Background::Gradient,
+                Background::Environment,
+            ] {
+                ui.selectable_value(&mut settings.background, background, background.label());
+            }
+        });
+        match settings.background {
+            Background::Solid => {
+                ui.horizontal(|ui| {
+                    color_edit(ui, &mut settings.solid_color);
+                    ui.label("Color");
+                });
+            }
+            Background::Gradient => {
+                ui.horizontal(|ui| {
+                    color_edit(ui, &mut settings.gradient_top);
+                    ui.label("Top");
+                    color_edit(ui, &mut settings.gradient_bottom);
+                    ui.label("Bottom");
+                });
+            }
+            Background::Environment => {}
+        }
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.environment_path)
+                    .hint_text(".hdr or .exr path")
+                    .desired_width(140.0),
+            );
+            let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if (ui.button("Load").clicked() || submitted) && !self.environment_path.is_empty() {
+                settings.map = Some(assets.load::<Environment>(self.environment_path.trim()));
+                settings.background = Background::Environment;
+            }
+        });
+        if let Some(map) = &settings.map {
+            let name = assets
+                .path(map)
+                .and_then(|path| path.file_name())
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let mut remove = false;
+            ui.horizontal(|ui| {
+                match assets.state(map) {
+                    AssetState::Loading => {
+                        ui.spinner();
+                        ui.label(name);
+                    }
+                    AssetState::Loaded => {
+                        ui.label(name);
+                    }
+                    AssetState::Failed(error) => {
+                        ui.colored_label(ui.visuals().error_fg_color, name)
+                            .on_hover_text(error);
+                    }
+                    AssetState::Unloaded => {
+                        ui.weak(name);
+                    }
+                }
+                remove = ui.small_button("✖").on_hover_text("Remove").clicked();
+            });
+            if remove {
+                settings.map = None;
+            }
+        } else {
+            ui.weak("Drop an .hdr or .exr file onto the window to use it.");
+        }
+        ui.add_enabled_ui(settings.map.is_some(), |ui| {
+            ui.checkbox(&mut settings.lighting, "Image-based lighting");
+            ui.add(egui::Slider::new(&mut settings.rotation, -PI..=PI).text("Rotation"))
+                .on_hover_text("Turn around the up axis");
+            ui.add(
+                egui::Slider::new(&mut settings.intensity, 0.05..=8.0)
+                    .logarithmic(true)
+                    .text("Intensity"),
+            );
+        });
+    }
+
+    fn point_cloud_ui(ui: &mut egui::Ui, renderer: &mut Renderer) {
+        let stats = renderer.point_cloud_stats();
+        let settings = renderer.point_cloud_settings_mut();
+
+        ui.horizontal(|ui| {
+            ui.label("Size");
+            ui.selectable_value(&mut settings.size_mode, PointSizeMode::Screen, "Pixels");
+            ui.selectable_value(&mut settings.size_mode, PointSizeMode::World, "World");
+        });
+        match settings.size_mode {
+            PointSizeMode::Screen => {
+                ui.add(egui::Slider::new(&mut settings.point_size, 1.0..=16.0).text("Point size"))
+            }
+            PointSizeMode::World => ui.add(
+                egui::Slider::new(&mut settings.world_point_size, 0.0001..=1.0)
+                    .logarithmic(true)
+                    .text("Point size"),
+            ),
+        };
+        ui.horizontal(|ui| {
+            ui.label("Color");
+            ui.selectable_value(&mut settings.color_mode, PointColorMode::Rgb, "RGB");
+            ui.selectable_value(
+                &mut settings.color_mode,
+                PointColorMode::Intensity,
+                "Intensity",
+            );
+        });
+        ui.checkbox(&mut settings.round_points, "Round splats");
+        ui.checkbox(&mut settings.eye_dome_lighting, "Eye-dome lighting");
+        ui.add_enabled_ui(settings.eye_dome_lighting, |ui| {
+            ui.add(egui::Slider::new(&mut settings.edl_strength, 0.1..=5.0).text("Strength"));
+            ui.add(egui::Slider::new(&mut settings.edl_radius, 1.0..=4.0).text("Radius"));
+        });
+
+        let mut point_budget = settings.point_budget as f64 / 1e6;
+        if ui
+            .add(
+                egui::Slider::new(&mut point_budget, 0.1..=50.0)
+                    .logarithmic(true)
+                    .suffix(" M")
+                    .text("Point budget"),
+            )
+            .changed()
+        {
+            settings.point_budget = (point_budget * 1e6) as usize;
+        }
+        let mut gpu_budget = settings.gpu_budget >> 20;
+        if ui
+            .add(
+                egui::Slider::new(&mut gpu_budget, 64..=8192)
+                    .logarithmic(true)
+                    .suffix(" MiB")
+                    .text("GPU budget"),
+            )
+            .changed()
+        {
+            settings.gpu_budget = gpu_budget << 20;
+        }
+
+        ui.weak(format!(
+            "{} points in {} of {} nodes",
+            stats.drawn_points, stats.drawn_nodes, stats.selected_nodes
+        ));
+        ui.weak(format!(
+            "{} chunks resident, {:.1} MiB",
+            stats.resident_chunks,
+            stats.resident_bytes as f64 / (1 << 20) as f64
+        ));
+        if stats.pending_nodes > 0 {
+            ui.weak(format!("Streaming {} nodes…", stats.pending_nodes));
+            ui.ctx().request_repaint();
+        }
+    }
+
+    fn lod_ui(&mut self, ui: &mut egui::Ui, assets: &mut AssetManager, renderer: &mut Renderer) {
+        let settings = renderer.lod_settings_mut();
+        ui.checkbox(&mut settings.enabled, "Use levels of detail");
+        ui.add_enabled_ui(settings.enabled, |ui| {
+            ui.add(egui::Slider::new(&mut settings.bias, -3.0..=3.0).text("Bias"))
+                .on_hover_text("Positive values switch to coarser levels sooner");
+            ui.add(egui::Slider::new(&mut settings.hysteresis, 0.0..=0.5).text("Hysteresis"))
+                .on_hover_text("Band around each switch distance that keeps the current level");
+        });
+
+        ui.separator();
+        let generation = &mut self.lod_generation;
+        ui.add(egui::Slider::new(&mut generation.levels, 1..=6).text("Levels"));
+        ui.add(egui::Slider::new(&mut generation.reduction, 0.1..=0.9).text("Reduction"))
+            .on_hover_text("Triangles kept from one level to the next");
+        ui.add(
+            egui::Slider::new(&mut generation.min_triangles, 100..=100_000)
+                .logarithmic(true)
+                .text("Min triangles"),
+        );
+        if ui
+            .button("Generate LODs")
+            .on_hover_text("Simplify every loaded mesh that has no levels yet")
+            .clicked()
+        {
+            let meshes = assets.generate_all_lods(generation);
+            self.lod_report = Some(format!("Generated levels for {meshes} meshes"));
+        }
+        if let Some(report) = &self.lod_report {
+            ui.weak(report);
+        }
+    }
+
+    fn loading_ui(ui: &mut egui::Ui, assets: &mut AssetManager) {
+        let mut loads: Vec<_> = assets.loads().cloned().collect();
+        let pending_uploads = assets.pending_uploads();
+        if loads.is_empty() && pending_uploads == 0 {
+            ui.weak("Drop a model, scene or environment map onto the window to load it.");
+            return;
+        }
+
+        loads.sort_by_key(|task| task.id);
+        let mut cancelled = Vec::new();
+        for task in &loads {
+            let name = task
+                .path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| task.path.display().to_string());
+            ui.horizontal(|ui| {
+                if ui.small_button("✖").on_hover_text("Cancel").clicked() {
+                    cancelled.push(task.id);
+                }
+                ui.add(
+                    egui::ProgressBar::new(task.progress.fraction())
+                        .text(name)
+                        .animate(true),
+                );
             });
+        }
+        if pending_uploads > 0 {
+            ui.label(format!("Uploading {pending_uploads} assets to the GPU…"));
+        }
+
+        for id in cancelled {
+            assets.cancel_load(id);
+        }
+        ui.ctx().request_repaint();
+    }
+}
+
+/// Color picker for a display-space RGB value.
+fn color_edit(ui: &mut egui::Ui, color: &mut Vec3) {
+    let mut rgb = color.to_array();
+    if ui.color_edit_button_rgb(&mut rgb).changed() {
+        *color = Vec3::from_array(rgb);
+    }
+}
+
+impl Default for LeftPanel {
+    fn default() -> Self {
+        Self::new()
     }
 }
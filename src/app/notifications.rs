@@ -0,0 +1,101 @@
+use std::time::{Duration, Instant};
+
+const INFO_LIFETIME: Duration = Duration::from_secs(3);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Severity {
+    Info,
+    Error,
+}
+
+struct Notification {
+    severity: Severity,
+    title: String,
+    detail: Option<String>,
+    created: Instant,
+}
+
+/// Toasts stacked in the bottom-right corner. Info messages fade out on their
+/// own, errors stay until dismissed.
+pub struct Notifications {
+    items: Vec<Notification>,
+}
+
+impl Notifications {
+    pub fn new() -> Self {
+        Self { items: Vec::new() }
+    }
+
+    pub fn info(&mut self, title: impl Into<String>) {
+        self.push(Severity::Info, title.into(), None);
+    }
+
+    pub fn error(&mut self, title: impl Into<String>, detail: impl Into<String>) {
+        self.push(Severity::Error, title.into(), Some(detail.into()));
+    }
+
+    fn push(&mut self, severity: Severity, title: String, detail: Option<String>) {
+        self.items.push(Notification {
+            severity,
+            title,
+            detail,
+            created: Instant::now(),
+        });
+    }
+
+    pub fn ui(&mut self, egui_ctx: &egui::Context) {
+        let now = Instant::now();
+        self.items.retain(|n| {
+            n.severity == Severity::Error || now.duration_since(n.created) < INFO_LIFETIME
+        });
+        if self.items.is_empty() {
+            return;
+        }
+
+        let mut dismissed = Vec::new();
+        egui::Area::new(egui::Id::new("notifications"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-12.0, -12.0))
+            .order(egui::Order::Foreground)
+            .show(egui_ctx, |ui| {
+                ui.set_max_width(420.0);
+                for (i, n) in self.items.iter().enumerate() {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            let title = match n.severity {
+                                Severity::Info => egui::RichText::new(&n.title),
+                                Severity::Error => {
+                                    egui::RichText::new(&n.title).color(ui.visuals().error_fg_color)
+                                }
+                            };
+                            ui.label(title.strong());
+                            if n.severity == Severity::Error && ui.small_button("✖").clicked() {
+                                dismissed.push(i);
+                            }
+                        });
+                        if let Some(detail) = &n.detail {
+                            egui::ScrollArea::vertical()
+                                .id_salt(("notification", i))
+                                .max_height(160.0)
+                                .show(ui, |ui| {
+                                    ui.label(egui::RichText::new(detail).monospace().small());
+                                });
+                        }
+                    });
+                }
+            });
+        for i in dismissed.into_iter().rev() {
+            self.items.remove(i);
+        }
+
+        // Keep repainting while an info message is counting down.
+        if self.items.iter().any(|n| n.severity == Severity::Info) {
+            egui_ctx.request_repaint_after(Duration::from_millis(250));
+        }
+    }
+}
+
+impl Default for Notifications {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,64 +1,439 @@
 use std::sync::Arc;
 
-use crate::core::{RenderTarget, Renderer};
-use anyhow::Context;
+use crate::app::commands::RemoveNodes;
+use crate::app::gizmo::Gizmo;
+use crate::app::history::History;
+use crate::app::view_cube;
+use crate::app::viewport::{Viewport, ViewportLayout};
+use crate::core::{
+    AssetManager, Camera, Input, Renderer, Scene,
+    bounds::Aabb,
+    camera::{AxisView, UpAxis},
+    frame_stats::{FrameStats, Phase},
+    renderer::ViewMode,
+    scene::NodeId,
+};
 use egui_glow::Painter;
+use glam::Vec2;
 use winit::dpi::PhysicalSize;
 
+/// Points between neighbouring viewports.
+const VIEWPORT_GAP: f32 = 2.0;
+
+/// The central area: one, two or four viewports onto the shared scene, a
+/// selection they have in common and the gizmo, which lives in the active
+/// viewport.
 pub struct SceneDisplay {
-    render_target: RenderTarget,
-    texture_id: egui::TextureId,
+    /// Perspective, top, front and right, in layout order; layouts with
+    /// fewer viewports show the first ones.
+    viewports: Vec<Viewport>,
+    layout: ViewportLayout,
+    /// The viewport that last took a click and gets the camera input.
+    active: usize,
+    /// Up axis shared by every viewport's camera.
+    up: UpAxis,
+    /// Selected nodes; the last one is the active node.
+    selection: Vec<NodeId>,
+    gizmo: Gizmo,
 }
 
 impl SceneDisplay {
     pub fn new(painter: &mut Painter, gl: Arc<glow::Context>) -> anyhow::Result<Self> {
-        let render_target =
-            RenderTarget::new(gl).context("failed to create RenderTarget for scene display")?;
-        let texture_id = painter.register_native_texture(render_target.color_texture());
+        let cameras = [
+            Camera::new(),
+            Viewport::axis_camera(AxisView::Top),
+            Viewport::axis_camera(AxisView::Front),
+            Viewport::axis_camera(AxisView::Right),
+        ];
+        let viewports = cameras
+            .into_iter()
+            .map(|camera| Viewport::new(painter, gl.clone(), camera))
+            .collect::<anyhow::Result<_>>()?;
         Ok(Self {
-            render_target,
-            texture_id,
+            viewports,
+            layout: ViewportLayout::Single,
+            active: 0,
+            up: UpAxis::Y,
+            selection: Vec::new(),
+            gizmo: Gizmo::new(),
         })
     }
 
-    pub fn ui(&mut self, egui_ctx: &egui::Context) {
+    pub fn ui(
+        &mut self,
+        egui_ctx: &egui::Context,
+        frame: &FrameStats,
+        scene: &mut Scene,
+        input: &Input,
+        history: &mut History,
+    ) {
+        self.selection.retain(|&id| scene.contains(id));
+        self.sync_up_axis();
         egui::CentralPanel::default()
             .frame(egui::Frame::NONE.inner_margin(egui::Margin::ZERO))
             .show(egui_ctx, |ui| {
+                self.layout_bar(ui);
                 ui.spacing_mut().item_spacing = egui::Vec2::ZERO;
 
                 let pixels_per_point = egui_ctx.pixels_per_point();
-                let available_points = ui.available_size();
-
-                let texture_pixels = self.render_target.size();
-                let texture_points = egui::Vec2::new(
-                    texture_pixels.width as f32 / pixels_per_point,
-                    texture_pixels.height as f32 / pixels_per_point,
-                );
-
-                let image = egui::Image::from_texture(egui::load::SizedTexture::new(
-                    self.texture_id,
-                    texture_points,
-                ))
-                .fit_to_exact_size(available_points)
-                .maintain_aspect_ratio(false);
-
-                let response = ui.add_sized(available_points, image);
-                let allocated_points = response.rect.size();
-
-                let desired_pixels = PhysicalSize::new(
-                    (allocated_points.x * pixels_per_point).max(1.0) as u32,
-                    (allocated_points.y * pixels_per_point).max(1.0) as u32,
-                );
-
-                if desired_pixels != self.render_target.size() {
-                    self.render_target
-                        .resize(desired_pixels)
-                        .unwrap_or_else(|err| {
-                            log::error!("render target resize failed: {:#}", err);
-                        });
+                let display = ui.available_rect_before_wrap();
+                let pressed = ui.input(|i| i.pointer.any_pressed());
+                let count = self.layout.count();
+                let mut responses = Vec::with_capacity(count);
+                for (i, rect) in self
+                    .layout
+                    .rects(display, VIEWPORT_GAP)
+                    .into_iter()
+                    .enumerate()
+                {
+                    let response = self.viewports[i].image(ui, rect);
+                    if pressed && response.hovered() && !self.gizmo.is_dragging() {
+                        self.active = i;
+                    }
+                    responses.push(response);
+                }
+                for viewport in &mut self.viewports[count..] {
+                    viewport.hovered = false;
+                }
+                if self.active >= count {
+                    self.active = 0;
+                }
+
+                for (i, response) in responses.iter().enumerate() {
+                    let active = i == self.active;
+                    let viewport = &mut self.viewports[i];
+                    if active
+                        && let Some(command) =
+                            self.gizmo
+                                .ui(ui, response, &viewport.camera, &self.selection, scene)
+                    {
+                        history.push(Box::new(command));
+                    }
+                    if !self.gizmo.is_dragging() {
+                        Self::handle_camera_input(
+                            viewport,
+                            response,
+                            input,
+                            pixels_per_point,
+                            active,
+                        );
+                    }
+                    if !(active && self.gizmo.wants_pointer()) {
+                        Self::handle_pick_input(viewport, ui, response);
+                    }
+                    let id = egui::Id::new("viewport").with(i);
+                    if let Some(side) =
+                        view_cube::ui(ui, id.with("view_cube"), response.rect, &viewport.camera)
+                    {
+                        viewport.camera.snap_to(side);
+                    }
+                    Self::view_mode_ui(ui, id.with("view_mode"), response.rect, viewport);
+
+                    let viewport = &self.viewports[i];
+                    let title = (count > 1).then(|| viewport.label());
+                    self.stats_overlay(ui, response.rect, viewport, title, scene);
+                    if count > 1 && active {
+                        ui.painter().rect_stroke(
+                            response.rect.shrink(1.0),
+                            0.0,
+                            ui.visuals().selection.stroke,
+                            egui::StrokeKind::Inside,
+                        );
+                    }
                 }
+                Self::frame_overlay(ui, display, frame);
+                self.gizmo.toolbar(ui, responses[self.active].rect);
             });
+        self.sync_up_axis();
+    }
+
+    /// Layout choice in a strip above the viewports.
+    fn layout_bar(&mut self, ui: &mut egui::Ui) {
+        egui::Frame::NONE
+            .inner_margin(egui::Margin::symmetric(8, 4))
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Layout");
+                    for layout in ViewportLayout::ALL {
+                        ui.selectable_value(&mut self.layout, layout, layout.label());
+                    }
+                });
+            });
+    }
+
+    /// Gives every camera the up axis one of them was switched to, so the
+    /// views keep agreeing on what is up.
+    fn sync_up_axis(&mut self) {
+        let changed = self
+            .viewports
+            .iter()
+            .map(|viewport| viewport.camera.up)
+            .find(|&up| up != self.up);
+        if let Some(up) = changed {
+            self.up = up;
+            for viewport in &mut self.viewports {
+                viewport.camera.up = up;
+            }
+        }
+    }
+
+    /// Drives the camera from the `camera.*` actions. Presses outside the
+    /// viewports never reach `input`, so drags only start over the scene,
+    /// and a press makes its viewport the active one: orbiting and panning
+    /// move the active camera, scrolling zooms the one under the pointer.
+    fn handle_camera_input(
+        viewport: &mut Viewport,
+        response: &egui::Response,
+        input: &Input,
+        pixels_per_point: f32,
+        active: bool,
+    ) {
+        let state = input.state();
+        let delta = state.cursor_delta() / pixels_per_point;
+        if active && input.held("camera.orbit") {
+            viewport.camera.orbit(-delta.x * 0.01, delta.y * 0.01);
+        }
+        if active && input.held("camera.pan") {
+            let delta = delta / response.rect.height().max(1.0);
+            viewport.camera.pan(delta.x, delta.y);
+        }
+        if viewport.hovered && input.held("camera.zoom") {
+            viewport.camera.zoom((-state.scroll().y * 0.1).exp());
+        }
+    }
+
+    /// Selection, view and gizmo shortcuts: `selection.delete`,
+    /// `selection.clear`, which cancels a gizmo drag first, and
+    /// `view.frame_selected`, which frames the whole scene when nothing is
+    /// selected.
+    pub fn handle_actions(&mut self, input: &Input, scene: &mut Scene, history: &mut History) {
+        self.gizmo.handle_actions(input);
+        if input.pressed("selection.clear") {
+            if self.gizmo.is_dragging() {
+                self.gizmo.cancel(scene);
+            } else {
+                self.selection.clear();
+            }
+        }
+        if self.gizmo.is_dragging() {
+            return;
+        }
+        if input.pressed("selection.delete")
+            && let Some(command) = RemoveNodes::execute(scene, &self.selection)
+        {
+            history.push(Box::new(command));
+            self.selection.clear();
+        }
+        if input.pressed("view.frame_selected") {
+            let ids = if self.selection.is_empty() {
+                scene.roots()
+            } else {
+                &self.selection
+            };
+            let bounds = ids
+                .iter()
+                .filter_map(|&id| scene.node(id))
+                .fold(Aabb::EMPTY, |bounds, node| {
+                    bounds.union(&node.subtree_bounds())
+                });
+            self.viewports[self.active].camera.frame(&bounds);
+        }
+    }
+
+    /// Whether the pointer was over one of the viewports last frame, which
+    /// decides if mouse presses go to the scene or to the UI.
+    pub fn is_hovered(&self) -> bool {
+        self.viewports.iter().any(|viewport| viewport.hovered)
+    }
+
+    /// A click selects the node under the pointer; with Ctrl or Shift it
+    /// adds or removes the node instead.
+    fn handle_pick_input(viewport: &mut Viewport, ui: &egui::Ui, response: &egui::Response) {
+        if !response.clicked_by(egui::PointerButton::Primary) {
+            return;
+        }
+        let toggle = ui.input(|i| i.modifiers.ctrl || i.modifiers.shift);
+        if let Some(pos) = response.interact_pointer_pos() {
+            let rect = response.rect;
+            let uv = (pos - rect.min) / rect.size();
+            let ndc = Vec2::new(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0);
+            viewport.pending_pick = Some((ndc, toggle));
+        }
+    }
+
+    /// View mode, overlay toggles, projection and up axis in the bottom
+    /// left corner.
+    fn view_mode_ui(ui: &egui::Ui, id: egui::Id, rect: egui::Rect, viewport: &mut Viewport) {
+        egui::Area::new(id)
+            .fixed_pos(rect.left_bottom() + egui::vec2(8.0, -36.0))
+            .show(ui.ctx(), |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        egui::ComboBox::from_id_salt("view_mode_combo")
+                            .selected_text(viewport.view.mode.label())
+                            .show_ui(ui, |ui| {
+                                for mode in ViewMode::ALL {
+                                    ui.selectable_value(
+                                        &mut viewport.view.mode,
+                                        mode,
+                                        mode.label(),
+                                    );
+                                }
+                            });
+                        let overlays = &mut viewport.view.overlays;
+                        ui.toggle_value(&mut overlays.grid, "Grid");
+                        ui.toggle_value(&mut overlays.normals, "Normals");
+                        ui.toggle_value(&mut overlays.bounds, "Bounds");
+                        ui.toggle_value(&mut overlays.skeleton, "Skeleton");
+                        let camera = &mut viewport.camera;
+                        if ui
+                            .selectable_label(camera.is_orthographic(), "Ortho")
+                            .clicked()
+                        {
+                            camera.toggle_projection();
+                        }
+                        egui::ComboBox::from_id_salt("up_axis_combo")
+                            .selected_text(camera.up.label())
+                            .show_ui(ui, |ui| {
+                                for up in [UpAxis::Y, UpAxis::Z] {
+                                    ui.selectable_value(&mut camera.up, up, up.label());
+                                }
+                            });
+                    });
+                });
+            });
+    }
+
+    /// Counts of the viewport's last pass and the selection in its top left
+    /// corner, under `title` when there is one.
+    fn stats_overlay(
+        &self,
+        ui: &egui::Ui,
+        rect: egui::Rect,
+        viewport: &Viewport,
+        title: Option<String>,
+        scene: &Scene,
+    ) {
+        let stats = viewport.stats();
+        let mut text = title.map(|title| title + "\n").unwrap_or_default();
+        text += &format!(
+            "{} visible, {} culled\n{} draw calls ({} without batching)\n{} triangles",
+            stats.visible_objects,
+            stats.culled_objects,
+            stats.draw_calls,
+            stats.instances,
+            stats.triangles
+        );
+        if let Some(node) = self.selected().and_then(|id| scene.node(id)) {
+            text.push_str(&format!("\nSelected: {}", node.name));
+            if self.selection.len() > 1 {
+                text.push_str(&format!(" (+{} more)", self.selection.len() - 1));
+            }
+        }
+        ui.painter().text(
+            rect.min + egui::vec2(8.0, 8.0),
+            egui::Align2::LEFT_TOP,
+            text,
+            egui::FontId::monospace(12.0),
+            egui::Color32::from_white_alpha(200),
+        );
+    }
+
+    /// Frame-time graph with a summary line, CPU phases and GPU time in the
+    /// top right corner. The guide line marks 60 FPS.
+    fn frame_overlay(ui: &egui::Ui, rect: egui::Rect, frame: &FrameStats) {
+        const SIZE: egui::Vec2 = egui::vec2(240.0, 64.0);
+        const TARGET_MS: f32 = 1000.0 / 60.0;
+        let ms = |d: std::time::Duration| d.as_secs_f32() * 1000.0;
+
+        let painter = ui.painter();
+        let graph = egui::Rect::from_min_size(
+            egui::pos2(rect.max.x - SIZE.x - 8.0, rect.min.y + 8.0),
+            SIZE,
+        );
+        painter.rect_filled(graph, 2.0, egui::Color32::from_black_alpha(160));
+
+        let scale_ms = ms(frame.max()).max(2.0 * TARGET_MS);
+        let bar_width = SIZE.x / FrameStats::DEFAULT_CAPACITY as f32;
+        let start = FrameStats::DEFAULT_CAPACITY.saturating_sub(frame.frame_times().len());
+        for (i, time) in frame.frame_times().enumerate() {
+            let t = ms(time);
+            let x = graph.min.x + (start + i) as f32 * bar_width;
+            let top = graph.max.y - SIZE.y * (t / scale_ms).min(1.0);
+            let color = if t > 2.0 * TARGET_MS {
+                egui::Color32::from_rgb(230, 90, 70)
+            } else if t > TARGET_MS * 1.05 {
+                egui::Color32::from_rgb(230, 190, 70)
+            } else {
+                egui::Color32::from_rgb(110, 200, 120)
+            };
+            painter.rect_filled(
+                egui::Rect::from_min_max(
+                    egui::pos2(x, top),
+                    egui::pos2(x + bar_width, graph.max.y),
+                ),
+                0.0,
+                color,
+            );
+        }
+        let target_y = graph.max.y - SIZE.y * TARGET_MS / scale_ms;
+        painter.hline(
+            graph.x_range(),
+            target_y,
+            egui::Stroke::new(1.0, egui::Color32::from_white_alpha(90)),
+        );
+
+        let mut text = format!(
+            "{:.0} FPS  avg {:.1}  p95 {:.1}  p99 {:.1}\nmin {:.1}  max {:.1} ms",
+            frame.fps(),
+            ms(frame.average()),
+            ms(frame.percentile(95.0)),
+            ms(frame.percentile(99.0)),
+            ms(frame.min()),
+            ms(frame.max()),
+        );
+        for (i, phase) in Phase::ALL.into_iter().enumerate() {
+            text.push_str(if i % 3 == 0 { "\n" } else { "  " });
+            text.push_str(&format!("{} {:.2}", phase.label(), ms(frame.phase(phase))));
+        }
+        if let Some(gpu) = frame.gpu_time() {
+            text.push_str(&format!("  gpu {:.2}", ms(gpu)));
+        }
+        painter.text(
+            graph.left_bottom() + egui::vec2(0.0, 4.0),
+            egui::Align2::LEFT_TOP,
+            text,
+            egui::FontId::monospace(11.0),
+            egui::Color32::from_white_alpha(200),
+        );
+    }
+
+    /// Whether a gizmo drag is editing the scene right now.
+    pub fn is_dragging(&self) -> bool {
+        self.gizmo.is_dragging()
+    }
+
+    /// The active node, i.e. the last one selected.
+    pub fn selected(&self) -> Option<NodeId> {
+        self.selection.last().copied()
+    }
+
+    pub fn selection(&self) -> &[NodeId] {
+        &self.selection
+    }
+
+    /// Deselects everything, e.g. before ids of another scene appear.
+    pub fn clear_selection(&mut self) {
+        self.selection.clear();
+    }
+
+    /// Camera of the active viewport.
+    pub fn camera(&self) -> &Camera {
+        &self.viewports[self.active].camera
+    }
+
+    pub fn camera_mut(&mut self) -> &mut Camera {
+        &mut self.viewports[self.active].camera
     }
 
     pub fn points_to_pixels(
@@ -70,17 +445,45 @@ impl SceneDisplay {
         PhysicalSize::new(w, h)
     }
 
-    pub fn render_to_target(&mut self, renderer: &mut Renderer) {
-        self.render_target.bind();
-        renderer.render_scene_pass();
-        self.render_target.unbind();
+    /// Width over height of the active viewport.
+    pub fn aspect(&self) -> f32 {
+        self.viewports[self.active].aspect()
     }
 
-    pub fn texture_id(&self) -> egui::TextureId {
-        self.texture_id
+    /// Resolves pending picks and renders every viewport of the layout,
+    /// timing them together on the GPU.
+    pub fn render_viewports(
+        &mut self,
+        renderer: &mut Renderer,
+        scene: &Scene,
+        assets: &AssetManager,
+    ) {
+        renderer.begin_frame();
+        for viewport in &mut self.viewports[..self.layout.count()] {
+            if let Some((ndc, toggle)) = viewport.pending_pick.take() {
+                let ray = viewport.camera.ray(ndc, viewport.aspect());
+                let hit = scene.raycast(&ray, assets).map(|hit| hit.node);
+                match (hit, toggle) {
+                    (Some(id), true) => {
+                        if let Some(i) = self.selection.iter().position(|&s| s == id) {
+                            self.selection.remove(i);
+                        } else {
+                            self.selection.push(id);
+                        }
+                    }
+                    (Some(id), false) => self.selection = vec![id],
+                    (None, true) => {}
+                    (None, false) => self.selection.clear(),
+                }
+            }
+            viewport.render(renderer, scene, assets);
+        }
+        renderer.end_frame();
     }
 
     pub fn shutdown(&mut self, painter: &mut Painter) {
-        painter.free_texture(self.texture_id);
+        for viewport in &mut self.viewports {
+            viewport.shutdown(painter);
+        }
     }
 }
@@ -0,0 +1,141 @@
+use std::path::PathBuf;
+
+use crate::core::Camera;
+use crate::core::scene::document::{BINARY_EXTENSION, CameraBookmark, CameraState};
+
+/// What the user asked the scene window to do this frame.
+pub enum SceneFileRequest {
+    Save(PathBuf),
+    Open(PathBuf),
+    /// glTF or GLB, picked by the extension.
+    Export(PathBuf),
+}
+
+/// Window for saving and opening scene files and for camera bookmarks.
+/// Bookmarks belong to the scene and are saved with it.
+pub struct ScenePanel {
+    open: bool,
+    path: String,
+    export_path: String,
+    bookmarks: Vec<CameraBookmark>,
+    new_bookmark: String,
+}
+
+impl ScenePanel {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            path: "scene.toml".to_owned(),
+            export_path: "scene.glb".to_owned(),
+            bookmarks: Vec::new(),
+            new_bookmark: String::new(),
+        }
+    }
+
+    pub fn open_mut(&mut self) -> &mut bool {
+        &mut self.open
+    }
+
+    /// File the scene was last saved to or opened from.
+    pub fn path(&self) -> PathBuf {
+        PathBuf::from(&self.path)
+    }
+
+    pub fn set_path(&mut self, path: PathBuf) {
+        self.path = path.display().to_string();
+    }
+
+    pub fn bookmarks(&self) -> &[CameraBookmark] {
+        &self.bookmarks
+    }
+
+    pub fn set_bookmarks(&mut self, bookmarks: Vec<CameraBookmark>) {
+        self.bookmarks = bookmarks;
+    }
+
+    pub fn ui(
+        &mut self,
+        egui_ctx: &egui::Context,
+        camera: &mut Camera,
+    ) -> Option<SceneFileRequest> {
+        let mut request = None;
+        let mut removed = None;
+        egui::Window::new("Scene")
+            .open(&mut self.open)
+            .default_width(260.0)
+            .show(egui_ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("File");
+                    ui.text_edit_singleline(&mut self.path)
+                        .on_hover_text(format!(
+                            "TOML text, or binary for .{BINARY_EXTENSION} files"
+                        ));
+                });
+                ui.horizontal(|ui| {
+                    let valid = !self.path.trim().is_empty();
+                    if ui
+                        .add_enabled(valid, egui::Button::new("💾 Save"))
+                        .clicked()
+                    {
+                        request = Some(SceneFileRequest::Save(PathBuf::from(self.path.trim())));
+                    }
+                    if ui
+                        .add_enabled(valid, egui::Button::new("📂 Open"))
+                        .clicked()
+                    {
+                        request = Some(SceneFileRequest::Open(PathBuf::from(self.path.trim())));
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Export");
+                    ui.text_edit_singleline(&mut self.export_path)
+                        .on_hover_text("glTF with its files alongside, or a single .glb");
+                });
+                let valid = !self.export_path.trim().is_empty();
+                if ui
+                    .add_enabled(valid, egui::Button::new("📤 Export glTF"))
+                    .clicked()
+                {
+                    request = Some(SceneFileRequest::Export(PathBuf::from(
+                        self.export_path.trim(),
+                    )));
+                }
+                ui.separator();
+                ui.label("Camera bookmarks");
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut self.new_bookmark);
+                    if ui.button("Add view").clicked() {
+                        let name = match self.new_bookmark.trim() {
+                            "" => format!("View {}", self.bookmarks.len() + 1),
+                            name => name.to_owned(),
+                        };
+                        self.bookmarks.push(CameraBookmark {
+                            name,
+                            camera: CameraState::from(&*camera),
+                        });
+                        self.new_bookmark.clear();
+                    }
+                });
+                for (i, bookmark) in self.bookmarks.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        if ui.button(&bookmark.name).clicked() {
+                            *camera = Camera::from(&bookmark.camera);
+                        }
+                        if ui.small_button("✖").on_hover_text("Remove").clicked() {
+                            removed = Some(i);
+                        }
+                    });
+                }
+            });
+        if let Some(i) = removed {
+            self.bookmarks.remove(i);
+        }
+        request
+    }
+}
+
+impl Default for ScenePanel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,9 +1,36 @@
-use crate::app::config::AppConfig;
-use crate::core::{AppClient, AppContext, AppFactory, GlWindow};
+use crate::app::config::{AppConfig, MountConfig};
+use crate::core::asset_manager::{
+    AssetEvent, AssetState, Environment, Model,
+    exporters::{
+        self,
+        gltf::{ExportCamera, ExportLight, GltfExportOptions, LightKind},
+    },
+    vfs::{DirectoryFiles, ZipArchiveFiles},
+};
+use crate::core::{
+    AppClient, AppContext, AppFactory, Camera, GlWindow, Handle, Scene,
+    frame_stats::Phase,
+    input::ActionMap,
+    mesh_processing::MeshOperation,
+    redraw::RedrawMode,
+    renderer::{Background, LIGHT_DIRECTION},
+    scene::document::{BINARY_EXTENSION, CameraState, RenderSettings, SceneDocument},
+};
 
+use crate::app::bindings_panel::BindingsPanel;
+use crate::app::commands::{AddNodes, SetMesh};
+use crate::app::history::History;
+use crate::app::history_panel::HistoryPanel;
+use crate::app::inspector::Inspector;
 use crate::app::left_panel::LeftPanel;
+use crate::app::notifications::Notifications;
 use crate::app::scene_display::SceneDisplay;
+use crate::app::scene_panel::{SceneFileRequest, ScenePanel};
+use crate::app::timeline::Timeline;
 use anyhow::Context;
+use glam::Vec3;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 use winit::{
     dpi::LogicalSize, event::WindowEvent, event_loop::ActiveEventLoop, window::WindowAttributes,
 };
@@ -18,7 +45,31 @@ pub struct SceneViewerApp {
     painter: egui_glow::Painter,
 
     left_panel: LeftPanel,
+    bindings_panel: BindingsPanel,
+    inspector: Inspector,
     scene_display: SceneDisplay,
+    timeline: Timeline,
+    notifications: Notifications,
+    history: History,
+    history_panel: HistoryPanel,
+    scene_panel: ScenePanel,
+
+    hot_reload: bool,
+    mounts: Vec<MountConfig>,
+    simulation_hz: f64,
+    max_catch_up_steps: u32,
+    reactive_redraw: bool,
+    max_fps: f64,
+    bindings: ActionMap,
+    pending_models: Vec<Handle<Model>>,
+    pending_scene: Option<PendingScene>,
+}
+
+/// A scene file read from disk, waiting for the models it references.
+struct PendingScene {
+    path: PathBuf,
+    document: SceneDocument,
+    models: Vec<Handle<Model>>,
 }
 
 impl SceneViewerAppFactory {
@@ -47,12 +98,36 @@ impl AppFactory for SceneViewerAppFactory {
         let scene_display = SceneDisplay::new(&mut painter, window.gl_cloned())
             .context("failed to create SceneDisplay")?;
 
+        let (bindings, errors) = ActionMap::from_config(&self.config.bindings);
+        for err in errors {
+            log::error!("{err:#}");
+        }
+
+        let mut history = History::new();
+        history.set_max_bytes(self.config.history_memory_mb << 20);
+
         Ok(Box::new(SceneViewerApp {
             egui_ctx,
             egui_state,
             painter,
             left_panel: LeftPanel::new(),
+            bindings_panel: BindingsPanel::new(bindings.clone()),
+            inspector: Inspector::new(),
             scene_display,
+            timeline: Timeline::new(),
+            notifications: Notifications::new(),
+            history,
+            history_panel: HistoryPanel::new(),
+            scene_panel: ScenePanel::new(),
+            hot_reload: self.config.hot_reload,
+            mounts: self.config.mounts.clone(),
+            simulation_hz: self.config.simulation_hz,
+            max_catch_up_steps: self.config.max_catch_up_steps,
+            reactive_redraw: self.config.reactive_redraw,
+            max_fps: self.config.max_fps,
+            bindings,
+            pending_models: Vec::new(),
+            pending_scene: None,
         }))
     }
 
@@ -69,13 +144,257 @@ impl AppFactory for SceneViewerAppFactory {
     }
 }
 
+impl SceneViewerApp {
+    /// Adds models dropped onto the window to the scene once their background
+    /// load has finished.
+    fn spawn_loaded_models(&mut self, ctx: &mut AppContext) {
+        self.pending_models
+            .retain(|handle| match ctx.assets.state(handle) {
+                AssetState::Loading => true,
+                AssetState::Loaded => {
+                    if let Some(model) = ctx.assets.get(handle) {
+                        let roots = ctx.scene.spawn_model(model, None);
+                        ctx.scene.keep_model(handle.clone());
+                        let name = ctx.assets.path(handle).map(file_name).unwrap_or_default();
+                        self.history
+                            .push(Box::new(AddNodes::new(format!("Import {name}"), roots)));
+                    }
+                    false
+                }
+                AssetState::Failed(_) | AssetState::Unloaded => false,
+            });
+    }
+
+    /// `edit.undo` and `edit.redo`; ignored in the middle of a gizmo drag.
+    fn handle_edit_actions(&mut self, ctx: &mut AppContext) {
+        if self.scene_display.is_dragging() {
+            return;
+        }
+        if ctx.input.pressed("edit.undo") {
+            self.history.undo(ctx.scene);
+        }
+        if ctx.input.pressed("edit.redo") {
+            self.history.redo(ctx.scene);
+        }
+    }
+
+    fn save_scene(&mut self, path: PathBuf, ctx: &mut AppContext) {
+        let mut document = SceneDocument::capture(ctx.scene, ctx.assets);
+        document.camera = Some(CameraState::from(self.scene_display.camera()));
+        document.bookmarks = self.scene_panel.bookmarks().to_vec();
+        document.render = Some(RenderSettings::from(ctx.renderer.point_cloud_settings()));
+        match document.save(&path) {
+            Ok(()) => {
+                self.notifications
+                    .info(format!("Saved scene {}", file_name(&path)));
+                self.scene_panel.set_path(path);
+            }
+            Err(err) => self.notifications.error(
+                format!("Failed to save scene {}", file_name(&path)),
+                format!("{err:#}"),
+            ),
+        }
+    }
+
+    /// Runs a mesh tool on a copy of the selected node's mesh and swaps the
+    /// copy in as one undoable step.
+    fn apply_mesh_operation(&mut self, operation: MeshOperation, ctx: &mut AppContext) {
+        let Some(id) = self.scene_display.selected() else {
+            return;
+        };
+        let Some(before) = ctx.scene.node(id).and_then(|node| node.mesh.clone()) else {
+            return;
+        };
+        let Some(mut mesh) = ctx.assets.get(&before).cloned() else {
+            return;
+        };
+        let triangles = mesh.triangle_count();
+        if let Err(err) = operation.apply(&mut mesh) {
+            self.notifications
+                .error(format!("{} failed", operation.label()), format!("{err:#}"));
+            return;
+        }
+        self.notifications.info(format!(
+            "{}: {} → {} triangles, {} vertices",
+            operation.label(),
+            triangles,
+            mesh.triangle_count(),
+            mesh.vertex_count()
+        ));
+        let mesh_bytes = mesh.cpu_bytes();
+        let after = ctx.assets.add(mesh);
+        ctx.scene.node_mut(id).unwrap().mesh = Some(after.clone());
+        self.history.push(Box::new(SetMesh::new(
+            operation.label(),
+            id,
+            Some(before),
+            Some(after),
+            mesh_bytes,
+        )));
+    }
+
+    /// Writes the scene as glTF with the current view as its camera and the
+    /// renderer's light as a directional light.
+    fn export_scene(&mut self, path: PathBuf, ctx: &mut AppContext) {
+        let options = GltfExportOptions {
+            embed_textures: false,
+            cameras: vec![ExportCamera {
+                name: "Camera".to_owned(),
+                camera: self.scene_display.camera().clone(),
+                aspect: self.scene_display.aspect(),
+            }],
+            lights: vec![ExportLight {
+                name: "Light".to_owned(),
+                kind: LightKind::Directional,
+                color: Vec3::ONE,
+                intensity: 1.0,
+                range: None,
+                position: Vec3::ZERO,
+                direction: LIGHT_DIRECTION,
+            }],
+        };
+        match exporters::gltf::export(&path, ctx.scene, ctx.assets, &options) {
+            Ok(()) => self
+                .notifications
+                .info(format!("Exported {}", file_name(&path))),
+            Err(err) => self.notifications.error(
+                format!("Failed to export {}", file_name(&path)),
+                format!("{err:#}"),
+            ),
+        }
+    }
+
+    /// Reads a scene file and starts loading the models it references; the
+    /// current scene is replaced once they are in.
+    fn open_scene(&mut self, path: PathBuf, ctx: &mut AppContext) {
+        match SceneDocument::load(&path) {
+            Ok(document) => {
+                let models = document
+                    .model_paths()
+                    .into_iter()
+                    .map(|model| ctx.assets.load(model))
+                    .collect();
+                self.pending_scene = Some(PendingScene {
+                    path,
+                    document,
+                    models,
+                });
+            }
+            Err(err) => self.notifications.error(
+                format!("Failed to open scene {}", file_name(&path)),
+                format!("{err:#}"),
+            ),
+        }
+    }
+
+    fn finish_opening_scene(&mut self, ctx: &mut AppContext) {
+        let Some(pending) = &self.pending_scene else {
+            return;
+        };
+        if pending
+            .models
+            .iter()
+            .any(|model| ctx.assets.state(model) == AssetState::Loading)
+        {
+            return;
+        }
+        let PendingScene { path, document, .. } = self.pending_scene.take().unwrap();
+        let mut scene = Scene::new();
+        if let Err(err) = document.instantiate(&mut scene, ctx.assets) {
+            self.notifications.error(
+                format!("Failed to open scene {}", file_name(&path)),
+                format!("{err:#}"),
+            );
+            return;
+        }
+        *ctx.scene = scene;
+        self.history.clear();
+        self.scene_display.clear_selection();
+        if let Some(camera) = &document.camera {
+            *self.scene_display.camera_mut() = Camera::from(camera);
+        }
+        if let Some(render) = &document.render {
+            render.apply(ctx.renderer.point_cloud_settings_mut());
+        }
+        self.scene_panel.set_bookmarks(document.bookmarks);
+        self.notifications
+            .info(format!("Opened scene {}", file_name(&path)));
+        self.scene_panel.set_path(path);
+    }
+
+    fn show_asset_events(&mut self, ctx: &mut AppContext) {
+        for event in ctx.assets.drain_events() {
+            match event {
+                AssetEvent::Reloaded { kind, path } => {
+                    self.notifications
+                        .info(format!("Reloaded {kind} {}", file_name(&path)));
+                }
+                AssetEvent::ReloadFailed { kind, path, error } => {
+                    self.notifications.error(
+                        format!("Failed to reload {kind} {}", file_name(&path)),
+                        error,
+                    );
+                }
+                AssetEvent::ShaderFailed { name, error } => {
+                    self.notifications
+                        .error(format!("Failed to compile shader {name}"), error);
+                }
+            }
+        }
+    }
+}
+
+/// Scene files are TOML or binary scene documents; anything else dropped
+/// on the window is imported as a model.
+fn is_scene_file(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| {
+        extension.eq_ignore_ascii_case("toml") || extension.eq_ignore_ascii_case(BINARY_EXTENSION)
+    })
+}
+
+fn is_environment_file(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| {
+        extension.eq_ignore_ascii_case("hdr") || extension.eq_ignore_ascii_case("exr")
+    })
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
 impl AppClient for SceneViewerApp {
+    fn init(&mut self, ctx: &mut AppContext) {
+        ctx.assets.set_hot_reload(self.hot_reload);
+        ctx.time.set_hz(self.simulation_hz);
+        ctx.time.set_max_steps(self.max_catch_up_steps);
+        ctx.redraw.set_mode(if self.reactive_redraw {
+            RedrawMode::Reactive
+        } else {
+            RedrawMode::Continuous
+        });
+        ctx.redraw.set_fps_cap(Some(self.max_fps));
+        *ctx.input.actions_mut() = self.bindings.clone();
+        for mount in &self.mounts {
+            let vfs = ctx.assets.vfs();
+            if mount.path.is_dir() {
+                vfs.mount(&mount.prefix, DirectoryFiles::new(&mount.path));
+                continue;
+            }
+            match ZipArchiveFiles::open(&mount.path) {
+                Ok(archive) => vfs.mount(&mount.prefix, archive),
+                Err(err) => log::error!("cannot mount {}: {:#}", mount.path.display(), err),
+            }
+        }
+    }
+
     fn on_window_event(
         &mut self,
         event_loop: &ActiveEventLoop,
         ctx: &mut AppContext,
         event: &WindowEvent,
-    ) {
+    ) -> bool {
         let response = self
             .egui_state
             .on_window_event(ctx.window.raw_handle(), event);
@@ -84,24 +403,114 @@ impl AppClient for SceneViewerApp {
             ctx.window.request_redraw();
         }
 
-        if matches!(event, WindowEvent::CloseRequested) {
-            event_loop.exit();
+        match event {
+            WindowEvent::CloseRequested => event_loop.exit(),
+            WindowEvent::DroppedFile(path) if is_scene_file(path) => {
+                self.open_scene(path.clone(), ctx);
+            }
+            WindowEvent::DroppedFile(path) if is_environment_file(path) => {
+                log::info!("loading environment {}", path.display());
+                let settings = ctx.renderer.environment_settings_mut();
+                settings.map = Some(ctx.assets.load::<Environment>(path));
+                settings.background = Background::Environment;
+            }
+            WindowEvent::DroppedFile(path) => {
+                log::info!("loading {}", path.display());
+                self.pending_models.push(ctx.assets.load(path));
+            }
+            _ => {}
+        }
+
+        // The UI gets input first: key presses while it has keyboard focus
+        // and clicks or scrolling outside the viewport are its alone.
+        // Releases and cursor motion always pass so held actions end cleanly.
+        match event {
+            WindowEvent::KeyboardInput { event, .. } if event.state.is_pressed() => {
+                self.egui_ctx.wants_keyboard_input()
+            }
+            WindowEvent::MouseInput { state, .. } if state.is_pressed() => {
+                !self.scene_display.is_hovered()
+            }
+            WindowEvent::MouseWheel { .. } => !self.scene_display.is_hovered(),
+            _ => false,
         }
     }
 
     fn render(&mut self, ctx: &mut AppContext) {
+        self.spawn_loaded_models(ctx);
+        self.finish_opening_scene(ctx);
+        self.show_asset_events(ctx);
+
+        if !self.bindings_panel.is_capturing() {
+            if ctx.input.pressed("file.save") {
+                self.save_scene(self.scene_panel.path(), ctx);
+            }
+            self.handle_edit_actions(ctx);
+            self.scene_display
+                .handle_actions(ctx.input, ctx.scene, &mut self.history);
+        }
+
         let window = ctx.window.raw_handle();
         let raw_input = self.egui_state.take_egui_input(window);
 
+        let ui_start = Instant::now();
+        let mut scene_request = None;
+        let mut mesh_operation = None;
         let full_output = self.egui_ctx.run(raw_input, |egui_ctx| {
-            self.left_panel.ui(egui_ctx);
-            self.scene_display.ui(egui_ctx);
+            self.left_panel.ui(
+                egui_ctx,
+                ctx.assets,
+                ctx.renderer,
+                ctx.time,
+                ctx.redraw,
+                &mut [
+                    ("⌨ Key bindings", self.bindings_panel.open_mut()),
+                    ("🕘 History", self.history_panel.open_mut()),
+                    ("💾 Scene", self.scene_panel.open_mut()),
+                ],
+            );
+            scene_request = self
+                .scene_panel
+                .ui(egui_ctx, self.scene_display.camera_mut());
+            self.bindings_panel.ui(egui_ctx, ctx.input);
+            self.history_panel
+                .ui(egui_ctx, &mut self.history, ctx.scene);
+            mesh_operation = self.inspector.ui(
+                egui_ctx,
+                self.scene_display.selected(),
+                ctx.scene,
+                ctx.assets,
+                &mut self.history,
+            );
+            self.timeline.ui(egui_ctx, ctx.scene);
+            self.scene_display.ui(
+                egui_ctx,
+                ctx.time.stats(),
+                ctx.scene,
+                ctx.input,
+                &mut self.history,
+            );
+            self.notifications.ui(egui_ctx);
         });
 
-        self.scene_display.render_to_target(ctx.renderer);
+        let stats = ctx.time.stats_mut();
+        stats.record_phase(Phase::UiBuild, ui_start.elapsed());
+
+        let render_start = Instant::now();
+        self.scene_display
+            .render_viewports(ctx.renderer, ctx.scene, ctx.assets);
+        let stats = ctx.time.stats_mut();
+        stats.record_phase(Phase::SceneRender, render_start.elapsed());
+        stats.set_gpu_time(ctx.renderer.gpu_time());
+
+        let paint_start = Instant::now();
 
         self.egui_state
             .handle_platform_output(window, full_output.platform_output);
+        // egui asks for follow-up frames, e.g. for animations or tooltips.
+        if let Some(viewport) = full_output.viewport_output.get(&egui::ViewportId::ROOT) {
+            ctx.redraw.request_redraw_after(viewport.repaint_delay);
+        }
 
         let window_size = ctx.window.inner_size();
         let clipped = self
@@ -114,6 +523,19 @@ impl AppClient for SceneViewerApp {
             &clipped,
             &full_output.textures_delta,
         );
+        ctx.time
+            .stats_mut()
+            .record_phase(Phase::EguiPaint, paint_start.elapsed());
+
+        match scene_request {
+            Some(SceneFileRequest::Save(path)) => self.save_scene(path, ctx),
+            Some(SceneFileRequest::Open(path)) => self.open_scene(path, ctx),
+            Some(SceneFileRequest::Export(path)) => self.export_scene(path, ctx),
+            None => {}
+        }
+        if let Some(operation) = mesh_operation {
+            self.apply_mesh_operation(operation, ctx);
+        }
     }
 
     fn shutdown(&mut self, _ctx: &mut AppContext) {
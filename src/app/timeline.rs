@@ -0,0 +1,77 @@
+use crate::core::scene::Scene;
+
+/// Bottom panel with playback controls for the animated models in the scene.
+pub struct Timeline {
+    selected: usize,
+}
+
+impl Timeline {
+    pub fn new() -> Self {
+        Self { selected: 0 }
+    }
+
+    pub fn ui(&mut self, egui_ctx: &egui::Context, scene: &mut Scene) {
+        if scene.animators().is_empty() {
+            return;
+        }
+        self.selected = self.selected.min(scene.animators().len() - 1);
+
+        egui::TopBottomPanel::bottom("timeline").show(egui_ctx, |ui| {
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_salt("timeline_animator")
+                    .selected_text(&scene.animators()[self.selected].name)
+                    .show_ui(ui, |ui| {
+                        for (i, animator) in scene.animators().iter().enumerate() {
+                            ui.selectable_value(&mut self.selected, i, &animator.name);
+                        }
+                    });
+
+                let animator = &mut scene.animators_mut()[self.selected];
+                let mut clip = animator.player.clip;
+                egui::ComboBox::from_id_salt("timeline_clip")
+                    .selected_text(animator.clip().map_or("", |c| c.name.as_str()))
+                    .show_ui(ui, |ui| {
+                        for (i, c) in animator.clips().iter().enumerate() {
+                            ui.selectable_value(&mut clip, i, &c.name);
+                        }
+                    });
+                if clip != animator.player.clip {
+                    animator.player.select(clip);
+                }
+
+                let player = &mut animator.player;
+                if ui.button(if player.playing { "⏸" } else { "▶" }).clicked() {
+                    if player.playing {
+                        player.pause();
+                    } else {
+                        player.play();
+                    }
+                }
+                ui.checkbox(&mut player.looping, "Loop");
+                ui.add(
+                    egui::Slider::new(&mut player.speed, -2.0..=2.0)
+                        .step_by(0.05)
+                        .text("Speed"),
+                );
+            });
+
+            let animator = &mut scene.animators_mut()[self.selected];
+            let duration = animator.clip().map_or(0.0, |c| c.duration());
+            let mut time = animator.player.time;
+            ui.spacing_mut().slider_width = ui.available_width() - 120.0;
+            let response = ui.add(
+                egui::Slider::new(&mut time, 0.0..=duration.max(f32::EPSILON))
+                    .custom_formatter(|t, _| format!("{t:.2} / {duration:.2} s")),
+            );
+            if response.changed() {
+                animator.player.seek(time, duration);
+            }
+        });
+    }
+}
+
+impl Default for Timeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,178 @@
+use glam::{Mat3, Vec3};
+
+use crate::core::{Camera, camera::AxisView};
+
+/// Edge length of the widget in points.
+const SIZE: f32 = 96.0;
+/// Cube half-size in widget radii; the axes reach past the cube.
+const CUBE_SCALE: f32 = 0.28;
+const AXIS_LENGTH: f32 = 1.6;
+
+/// Orientation cube in the bottom right corner of `rect`, turning with the
+/// camera. `id` tells the cubes of several viewports apart. Returns the side
+/// whose face was clicked so the caller can snap the view to it.
+pub fn ui(ui: &egui::Ui, id: egui::Id, rect: egui::Rect, camera: &Camera) -> Option<AxisView> {
+    let pos = rect.right_bottom() - egui::vec2(SIZE + 8.0, SIZE + 8.0);
+    egui::Area::new(id)
+        .fixed_pos(pos)
+        .show(ui.ctx(), |ui| {
+            let (rect, response) =
+                ui.allocate_exact_size(egui::vec2(SIZE, SIZE), egui::Sense::click());
+            let rotation = Mat3::from_mat4(camera.view());
+            let center = rect.center();
+            let scale = SIZE * CUBE_SCALE;
+            let project = |world: Vec3| {
+                let v = rotation * world;
+                (center + egui::vec2(v.x, -v.y) * scale, v.z)
+            };
+
+            let faces: Vec<_> = AxisView::ALL
+                .into_iter()
+                .filter_map(|view| {
+                    let normal = view.direction(camera.up);
+                    let facing = (rotation * normal).z;
+                    (facing > 1e-3).then(|| {
+                        let corners = face_corners(normal).map(|c| project(c).0);
+                        (view, facing, corners)
+                    })
+                })
+                .collect();
+            let hovered = response.hover_pos().and_then(|pointer| {
+                faces
+                    .iter()
+                    .find(|(_, _, corners)| contains(corners, pointer))
+                    .map(|&(view, ..)| view)
+            });
+
+            let painter = ui.painter_at(rect);
+            // Axes pointing away from the viewer go behind the cube.
+            for (direction, color, label) in AXES {
+                if (rotation * direction).z < 0.0 {
+                    draw_axis(&painter, project, direction, color, label);
+                }
+            }
+            for (view, facing, corners) in &faces {
+                let fill = if Some(*view) == hovered {
+                    egui::Color32::from_rgb(90, 140, 210)
+                } else {
+                    egui::Color32::from_gray((70.0 + 80.0 * facing) as u8)
+                };
+                painter.add(egui::Shape::convex_polygon(
+                    corners.to_vec(),
+                    fill,
+                    egui::Stroke::new(1.0, egui::Color32::from_gray(30)),
+                ));
+                if *facing > 0.4 {
+                    let middle = corners
+                        .iter()
+                        .fold(egui::Vec2::ZERO, |sum, p| sum + p.to_vec2())
+                        / 4.0;
+                    painter.text(
+                        middle.to_pos2(),
+                        egui::Align2::CENTER_CENTER,
+                        view.label(),
+                        egui::FontId::proportional(10.0),
+                        egui::Color32::from_white_alpha((255.0 * facing) as u8),
+                    );
+                }
+            }
+            for (direction, color, label) in AXES {
+                if (rotation * direction).z >= 0.0 {
+                    draw_axis(&painter, project, direction, color, label);
+                }
+            }
+
+            let response = match hovered {
+                Some(view) => response.on_hover_text(format!("{} view", view.label())),
+                None => response,
+            };
+            if response.clicked() { hovered } else { None }
+        })
+        .inner
+}
+
+/// World axes with their colors, matching the ground grid.
+const AXES: [(Vec3, egui::Color32, &str); 3] = [
+    (Vec3::X, egui::Color32::from_rgb(230, 60, 60), "X"),
+    (Vec3::Y, egui::Color32::from_rgb(90, 200, 60), "Y"),
+    (Vec3::Z, egui::Color32::from_rgb(60, 110, 240), "Z"),
+];
+
+fn draw_axis(
+    painter: &egui::Painter,
+    project: impl Fn(Vec3) -> (egui::Pos2, f32),
+    direction: Vec3,
+    color: egui::Color32,
+    label: &str,
+) {
+    let (origin, _) = project(Vec3::ZERO);
+    let (tip, _) = project(direction * AXIS_LENGTH);
+    painter.line_segment([origin, tip], egui::Stroke::new(2.0, color));
+    painter.text(
+        tip,
+        egui::Align2::CENTER_CENTER,
+        label,
+        egui::FontId::monospace(11.0),
+        color,
+    );
+}
+
+/// Corners of the unit cube's face along `normal`, in winding order.
+fn face_corners(normal: Vec3) -> [Vec3; 4] {
+    let u = normal.any_orthonormal_vector();
+    let v = normal.cross(u);
+    [
+        normal - u - v,
+        normal + u - v,
+        normal + u + v,
+        normal - u + v,
+    ]
+}
+
+/// Whether `point` lies inside the convex polygon `corners`, in either
+/// winding.
+fn contains(corners: &[egui::Pos2; 4], point: egui::Pos2) -> bool {
+    let sides = corners
+        .iter()
+        .zip(corners.iter().cycle().skip(1))
+        .map(|(a, b)| {
+            let edge = *b - *a;
+            let to_point = point - *a;
+            edge.x * to_point.y - edge.y * to_point.x
+        });
+    let (mut positive, mut negative) = (false, false);
+    for side in sides {
+        positive |= side > 0.0;
+        negative |= side < 0.0;
+    }
+    !(positive && negative)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::camera::UpAxis;
+
+    #[test]
+    fn face_corners_lie_on_the_face() {
+        for view in AxisView::ALL {
+            let normal = view.direction(UpAxis::Z);
+            for corner in face_corners(normal) {
+                assert!((corner.dot(normal) - 1.0).abs() < 1e-5);
+                assert!(corner.abs().max_element() <= 1.0 + 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn polygon_contains_its_center_only() {
+        let square = [
+            egui::pos2(0.0, 0.0),
+            egui::pos2(1.0, 0.0),
+            egui::pos2(1.0, 1.0),
+            egui::pos2(0.0, 1.0),
+        ];
+        assert!(contains(&square, egui::pos2(0.5, 0.5)));
+        assert!(!contains(&square, egui::pos2(1.5, 0.5)));
+    }
+}
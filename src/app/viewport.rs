@@ -0,0 +1,240 @@
+use std::f32::consts::TAU;
+use std::sync::Arc;
+
+use crate::core::{
+    AssetManager, Camera, RenderTarget, Renderer, Scene,
+    camera::AxisView,
+    renderer::{RenderStats, ViewState},
+};
+use anyhow::Context;
+use egui_glow::Painter;
+use glam::Vec2;
+use winit::dpi::PhysicalSize;
+
+/// How many viewports the scene display shows and how they tile.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ViewportLayout {
+    #[default]
+    Single,
+    /// Two viewports side by side.
+    Split,
+    /// Two by two viewports.
+    Quad,
+}
+
+impl ViewportLayout {
+    pub const ALL: [ViewportLayout; 3] = [
+        ViewportLayout::Single,
+        ViewportLayout::Split,
+        ViewportLayout::Quad,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ViewportLayout::Single => "Single",
+            ViewportLayout::Split => "Split",
+            ViewportLayout::Quad => "Quad",
+        }
+    }
+
+    pub fn count(self) -> usize {
+        match self {
+            ViewportLayout::Single => 1,
+            ViewportLayout::Split => 2,
+            ViewportLayout::Quad => 4,
+        }
+    }
+
+    /// Rectangles of the first `count()` viewports inside `rect`, left to
+    /// right and top to bottom, `gap` points apart.
+    pub fn rects(self, rect: egui::Rect, gap: f32) -> Vec<egui::Rect> {
+        let (columns, rows) = match self {
+            ViewportLayout::Single => (1, 1),
+            ViewportLayout::Split => (2, 1),
+            ViewportLayout::Quad => (2, 2),
+        };
+        let size = egui::vec2(
+            ((rect.width() - gap * (columns - 1) as f32) / columns as f32).max(1.0),
+            ((rect.height() - gap * (rows - 1) as f32) / rows as f32).max(1.0),
+        );
+        (0..rows)
+            .flat_map(|row| (0..columns).map(move |column| (row, column)))
+            .map(|(row, column)| {
+                let min = rect.min
+                    + egui::vec2(column as f32 * (size.x + gap), row as f32 * (size.y + gap));
+                egui::Rect::from_min_size(min, size)
+            })
+            .collect()
+    }
+}
+
+/// One view into the shared scene with its own camera, view mode and
+/// render target, shown through an egui native texture.
+pub struct Viewport {
+    render_target: RenderTarget,
+    texture_id: egui::TextureId,
+    pub camera: Camera,
+    pub view: ViewState,
+    /// Counts from this viewport's last scene pass.
+    stats: RenderStats,
+    /// Click position in normalized device coordinates, resolved against
+    /// the scene before the next render, and whether it toggles the hit
+    /// node instead of replacing the selection.
+    pub pending_pick: Option<(Vec2, bool)>,
+    /// Whether the pointer was over the viewport image last frame.
+    pub hovered: bool,
+}
+
+impl Viewport {
+    pub fn new(
+        painter: &mut Painter,
+        gl: Arc<glow::Context>,
+        camera: Camera,
+    ) -> anyhow::Result<Self> {
+        let render_target =
+            RenderTarget::new(gl).context("failed to create RenderTarget for viewport")?;
+        let texture_id = painter.register_native_texture(render_target.color_texture());
+        Ok(Self {
+            render_target,
+            texture_id,
+            camera,
+            view: ViewState::default(),
+            stats: RenderStats::default(),
+            pending_pick: None,
+            hovered: false,
+        })
+    }
+
+    /// Orthographic camera looking from `side`, framing about what the
+    /// default perspective camera shows.
+    pub fn axis_camera(side: AxisView) -> Camera {
+        let mut camera = Camera::new();
+        camera.toggle_projection();
+        camera.snap_to(side);
+        camera
+    }
+
+    /// "Top", "Front" and so on while the camera looks along an axis,
+    /// "User" otherwise, followed by the projection.
+    pub fn label(&self) -> String {
+        let projection = if self.camera.is_orthographic() {
+            "Orthographic"
+        } else {
+            "Perspective"
+        };
+        format!(
+            "{} {projection}",
+            axis_side(&self.camera).map_or("User", AxisView::label)
+        )
+    }
+
+    /// Shows the last rendered frame stretched over `rect` and resizes the
+    /// render target to match it for the next one.
+    pub fn image(&mut self, ui: &mut egui::Ui, rect: egui::Rect) -> egui::Response {
+        let pixels_per_point = ui.ctx().pixels_per_point();
+        let texture_pixels = self.render_target.size();
+        let texture_points = egui::Vec2::new(
+            texture_pixels.width as f32 / pixels_per_point,
+            texture_pixels.height as f32 / pixels_per_point,
+        );
+        let image = egui::Image::from_texture(egui::load::SizedTexture::new(
+            self.texture_id,
+            texture_points,
+        ))
+        .fit_to_exact_size(rect.size())
+        .maintain_aspect_ratio(false)
+        .sense(egui::Sense::click_and_drag());
+        let response = ui.put(rect, image);
+        self.hovered = response.hovered();
+
+        let desired_pixels = PhysicalSize::new(
+            (rect.width() * pixels_per_point).max(1.0) as u32,
+            (rect.height() * pixels_per_point).max(1.0) as u32,
+        );
+        if desired_pixels != self.render_target.size() {
+            self.render_target
+                .resize(desired_pixels)
+                .unwrap_or_else(|err| {
+                    log::error!("render target resize failed: {:#}", err);
+                });
+        }
+        response
+    }
+
+    pub fn stats(&self) -> RenderStats {
+        self.stats
+    }
+
+    /// Width over height of the rendered view.
+    pub fn aspect(&self) -> f32 {
+        let size = self.render_target.size();
+        size.width.max(1) as f32 / size.height.max(1) as f32
+    }
+
+    pub fn render(&mut self, renderer: &mut Renderer, scene: &Scene, assets: &AssetManager) {
+        self.render_target.bind();
+        renderer.render_scene_pass(
+            scene,
+            assets,
+            &self.camera,
+            self.render_target.size(),
+            &mut self.view,
+        );
+        self.render_target.unbind();
+        self.stats = renderer.stats();
+    }
+
+    pub fn texture_id(&self) -> egui::TextureId {
+        self.texture_id
+    }
+
+    pub fn shutdown(&mut self, painter: &mut Painter) {
+        painter.free_texture(self.texture_id);
+    }
+}
+
+/// The side `camera` looks from when it is snapped to an axis view.
+fn axis_side(camera: &Camera) -> Option<AxisView> {
+    let yaw = camera.yaw.rem_euclid(TAU);
+    AxisView::ALL.into_iter().find(|view| {
+        let (view_yaw, view_pitch) = view.yaw_pitch();
+        let yaw_delta = (yaw - view_yaw.rem_euclid(TAU)).abs();
+        yaw_delta.min(TAU - yaw_delta) < 1e-4 && (camera.pitch - view_pitch).abs() < 1e-4
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layouts_tile_the_display() {
+        let rect = egui::Rect::from_min_size(egui::pos2(10.0, 20.0), egui::vec2(202.0, 102.0));
+        for layout in ViewportLayout::ALL {
+            let rects = layout.rects(rect, 2.0);
+            assert_eq!(rects.len(), layout.count());
+            let area: f32 = rects.iter().map(|r| r.area()).sum();
+            let gaps = match layout {
+                ViewportLayout::Single => 0.0,
+                ViewportLayout::Split => 2.0 * 102.0,
+                ViewportLayout::Quad => 2.0 * 102.0 + 2.0 * 202.0 - 4.0,
+            };
+            assert!((area + gaps - rect.area()).abs() < 1e-2, "{layout:?}");
+            assert!(rects.iter().all(|r| rect.contains_rect(*r)));
+        }
+        let quad = ViewportLayout::Quad.rects(rect, 2.0);
+        assert_eq!(quad[1].min, egui::pos2(112.0, 20.0));
+        assert_eq!(quad[2].min, egui::pos2(10.0, 72.0));
+    }
+
+    #[test]
+    fn axis_side_follows_the_camera() {
+        let mut camera = Viewport::axis_camera(AxisView::Left);
+        assert!(camera.is_orthographic());
+        assert_eq!(axis_side(&camera), Some(AxisView::Left));
+        camera.orbit(0.3, 0.0);
+        assert_eq!(axis_side(&camera), None);
+        camera.orbit(-0.3 + TAU, 0.0);
+        assert_eq!(axis_side(&camera), Some(AxisView::Left));
+    }
+}
@@ -1,13 +1,26 @@
+pub mod animation;
 pub mod application;
 pub mod asset_manager;
+pub mod bounds;
+pub mod camera;
+pub mod frame_stats;
 pub mod gl_window;
+pub mod input;
+pub mod mesh_processing;
+pub mod redraw;
 pub mod render_target;
 pub mod renderer;
 pub mod scene;
 pub mod time;
+pub mod transform;
 
 pub use application::Application;
 pub use application::{AppClient, AppContext, AppFactory};
+pub use asset_manager::{AssetManager, Handle};
+pub use camera::Camera;
 pub use gl_window::GlWindow;
+pub use input::Input;
 pub use render_target::RenderTarget;
 pub use renderer::Renderer;
+pub use scene::Scene;
+pub use transform::Transform;
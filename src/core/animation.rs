@@ -0,0 +1,87 @@
+pub mod clip;
+pub mod player;
+pub mod skin;
+
+use std::sync::Arc;
+
+use crate::core::scene::{NodeId, Scene};
+
+pub use clip::{AnimationClip, Channel, ChannelTarget, Interpolation};
+pub use player::AnimationPlayer;
+pub use skin::{MAX_JOINT_INFLUENCES, Skin};
+
+/// Plays the clips of one spawned model. Clip channels address nodes by
+/// model node index; `targets` maps those to the spawned scene nodes.
+pub struct Animator {
+    pub name: String,
+    pub player: AnimationPlayer,
+    clips: Arc<[AnimationClip]>,
+    targets: Vec<NodeId>,
+}
+
+impl Animator {
+    pub fn new(name: impl Into<String>, clips: Arc<[AnimationClip]>, targets: Vec<NodeId>) -> Self {
+        Self {
+            name: name.into(),
+            player: AnimationPlayer::new(),
+            clips,
+            targets,
+        }
+    }
+
+    pub fn clips(&self) -> &[AnimationClip] {
+        &self.clips
+    }
+
+    pub fn clip(&self) -> Option<&AnimationClip> {
+        self.clips.get(self.player.clip)
+    }
+
+    pub fn targets(&self) -> &[NodeId] {
+        &self.targets
+    }
+
+    pub fn advance(&mut self, delta: f32) {
+        let duration = self.clip().map_or(0.0, AnimationClip::duration);
+        self.player.advance(delta, duration);
+    }
+
+    /// Writes the pose of the selected clip into the target nodes, sampled
+    /// `lead` seconds past the play head without moving it.
+    pub fn apply(&self, scene: &mut Scene, lead: f32) {
+        let Some(clip) = self.clip() else {
+            return;
+        };
+        let mut player = self.player.clone();
+        player.advance(lead, clip.duration());
+        let mut values = Vec::new();
+        for channel in &clip.channels {
+            let Some(node) = self
+                .targets
+                .get(channel.node)
+                .and_then(|&id| scene.node_mut(id))
+            else {
+                continue;
+            };
+            channel.sample(player.time, &mut values);
+            let transform = &mut node.transform;
+            match channel.target {
+                ChannelTarget::Translation => transform.translation = vec3(&values),
+                ChannelTarget::Scale => transform.scale = vec3(&values),
+                ChannelTarget::Rotation => {
+                    transform.rotation = glam::Quat::from_slice(&values[..4]).normalize();
+                }
+                ChannelTarget::Weights => node.morph_weights.clone_from(&values),
+            }
+        }
+    }
+
+    /// Whether any target node still exists.
+    pub fn is_alive(&self, scene: &Scene) -> bool {
+        self.targets.iter().any(|&id| scene.contains(id))
+    }
+}
+
+fn vec3(values: &[f32]) -> glam::Vec3 {
+    glam::Vec3::from_slice(&values[..3])
+}
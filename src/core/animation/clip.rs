@@ -0,0 +1,200 @@
+use glam::{Quat, Vec4};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Interpolation {
+    Step,
+    Linear,
+    /// Hermite spline; every key stores an in-tangent, the value and an
+    /// out-tangent.
+    CubicSpline,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ChannelTarget {
+    Translation,
+    Rotation,
+    Scale,
+    /// Morph target weights, one value per target.
+    Weights,
+}
+
+/// Keyframes for one property of one node. `values` holds
+/// `components()` floats per key, three times as many for cubic splines.
+#[derive(Clone, Debug)]
+pub struct Channel {
+    /// Model node index.
+    pub node: usize,
+    pub target: ChannelTarget,
+    pub interpolation: Interpolation,
+    pub times: Vec<f32>,
+    pub values: Vec<f32>,
+}
+
+impl Channel {
+    pub fn components(&self) -> usize {
+        let per_key = match self.interpolation {
+            Interpolation::CubicSpline => 3,
+            _ => 1,
+        };
+        match self.target {
+            ChannelTarget::Translation | ChannelTarget::Scale => 3,
+            ChannelTarget::Rotation => 4,
+            ChannelTarget::Weights => self.values.len() / (self.times.len() * per_key).max(1),
+        }
+    }
+
+    /// Evaluates the channel at `time` into `out`, clamping outside the key
+    /// range. Rotations come out normalized.
+    pub fn sample(&self, time: f32, out: &mut Vec<f32>) {
+        let n = self.components();
+        out.clear();
+        out.resize(n, 0.0);
+        let Some(&last) = self.times.last() else {
+            return;
+        };
+        let cubic = self.interpolation == Interpolation::CubicSpline;
+        let stride = if cubic { 3 * n } else { n };
+        // Value of key `k`; for splines the middle of its triplet.
+        let value = |k: usize| {
+            let start = k * stride + if cubic { n } else { 0 };
+            &self.values[start..start + n]
+        };
+
+        let next = self.times.partition_point(|&t| t <= time);
+        if next == 0 || time >= last {
+            let k = if next == 0 { 0 } else { self.times.len() - 1 };
+            out.copy_from_slice(value(k));
+            return;
+        }
+        let k = next - 1;
+        let (t0, t1) = (self.times[k], self.times[next]);
+        let dt = t1 - t0;
+        let s = if dt > 0.0 { (time - t0) / dt } else { 0.0 };
+
+        match self.interpolation {
+            Interpolation::Step => out.copy_from_slice(value(k)),
+            Interpolation::Linear if self.target == ChannelTarget::Rotation => {
+                let a = Quat::from_slice(value(k));
+                let b = Quat::from_slice(value(next));
+                out.copy_from_slice(&a.slerp(b, s).to_array());
+            }
+            Interpolation::Linear => {
+                for ((o, a), b) in out.iter_mut().zip(value(k)).zip(value(next)) {
+                    *o = a + (b - a) * s;
+                }
+            }
+            Interpolation::CubicSpline => {
+                let out_tangent = &self.values[k * stride + 2 * n..k * stride + 3 * n];
+                let in_tangent = &self.values[next * stride..next * stride + n];
+                let (s2, s3) = (s * s, s * s * s);
+                let h00 = 2.0 * s3 - 3.0 * s2 + 1.0;
+                let h10 = s3 - 2.0 * s2 + s;
+                let h01 = -2.0 * s3 + 3.0 * s2;
+                let h11 = s3 - s2;
+                for i in 0..n {
+                    out[i] = h00 * value(k)[i]
+                        + h10 * dt * out_tangent[i]
+                        + h01 * value(next)[i]
+                        + h11 * dt * in_tangent[i];
+                }
+            }
+        }
+        if self.target == ChannelTarget::Rotation {
+            let q = Vec4::from_slice(out).normalize_or_zero();
+            out.copy_from_slice(&q.to_array());
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AnimationClip {
+    pub name: String,
+    pub channels: Vec<Channel>,
+    duration: f32,
+}
+
+impl AnimationClip {
+    pub fn new(name: impl Into<String>, channels: Vec<Channel>) -> Self {
+        let duration = channels
+            .iter()
+            .filter_map(|c| c.times.last().copied())
+            .fold(0.0, f32::max);
+        Self {
+            name: name.into(),
+            channels,
+            duration,
+        }
+    }
+
+    /// Time of the last key of any channel, in seconds.
+    pub fn duration(&self) -> f32 {
+        self.duration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channel(target: ChannelTarget, interpolation: Interpolation, values: Vec<f32>) -> Channel {
+        Channel {
+            node: 0,
+            target,
+            interpolation,
+            times: vec![0.0, 1.0],
+            values,
+        }
+    }
+
+    #[test]
+    fn samples_linear_step_and_clamps() {
+        let mut out = Vec::new();
+        let linear = channel(
+            ChannelTarget::Translation,
+            Interpolation::Linear,
+            vec![0.0, 0.0, 0.0, 2.0, 4.0, 6.0],
+        );
+        linear.sample(0.25, &mut out);
+        assert_eq!(out, vec![0.5, 1.0, 1.5]);
+        linear.sample(-1.0, &mut out);
+        assert_eq!(out, vec![0.0, 0.0, 0.0]);
+        linear.sample(5.0, &mut out);
+        assert_eq!(out, vec![2.0, 4.0, 6.0]);
+
+        let step = Channel {
+            interpolation: Interpolation::Step,
+            ..linear
+        };
+        step.sample(0.99, &mut out);
+        assert_eq!(out, vec![0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn rotation_slerps_and_splines_hit_their_keys() {
+        let mut out = Vec::new();
+        let quarter = Quat::from_rotation_y(std::f32::consts::FRAC_PI_2);
+        let rotation = channel(
+            ChannelTarget::Rotation,
+            Interpolation::Linear,
+            [Quat::IDENTITY, quarter]
+                .iter()
+                .flat_map(|q| q.to_array())
+                .collect(),
+        );
+        rotation.sample(0.5, &mut out);
+        let half = Quat::from_rotation_y(std::f32::consts::FRAC_PI_4);
+        assert!(Quat::from_slice(&out).abs_diff_eq(half, 1e-5));
+
+        // Two weights with zero tangents: in, value, out per key.
+        let spline = channel(
+            ChannelTarget::Weights,
+            Interpolation::CubicSpline,
+            vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0],
+        );
+        assert_eq!(spline.components(), 2);
+        spline.sample(0.5, &mut out);
+        assert_eq!(out, vec![0.5, 0.5]);
+        spline.sample(1.0, &mut out);
+        assert_eq!(out, vec![1.0, 0.0]);
+    }
+}
@@ -0,0 +1,89 @@
+/// Playback state of one animator: which clip, where in it, and how it
+/// advances.
+#[derive(Clone, Debug)]
+pub struct AnimationPlayer {
+    pub clip: usize,
+    /// Seconds into the clip.
+    pub time: f32,
+    /// Playback rate; negative values play backwards.
+    pub speed: f32,
+    pub playing: bool,
+    pub looping: bool,
+}
+
+impl AnimationPlayer {
+    pub fn new() -> Self {
+        Self {
+            clip: 0,
+            time: 0.0,
+            speed: 1.0,
+            playing: true,
+            looping: true,
+        }
+    }
+
+    pub fn play(&mut self) {
+        self.playing = true;
+    }
+
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    /// Switches clips, starting the new one from the beginning.
+    pub fn select(&mut self, clip: usize) {
+        self.clip = clip;
+        self.time = 0.0;
+    }
+
+    pub fn seek(&mut self, time: f32, duration: f32) {
+        self.time = time.clamp(0.0, duration.max(0.0));
+    }
+
+    /// Moves the play head by `delta` seconds of simulation time. Looping clips
+    /// wrap around; others stop at either end.
+    pub fn advance(&mut self, delta: f32, duration: f32) {
+        if !self.playing {
+            return;
+        }
+        self.time += delta * self.speed;
+        if duration <= 0.0 {
+            self.time = 0.0;
+        } else if self.looping {
+            self.time = self.time.rem_euclid(duration);
+        } else if !(0.0..=duration).contains(&self.time) {
+            self.time = self.time.clamp(0.0, duration);
+            self.playing = false;
+        }
+    }
+}
+
+impl Default for AnimationPlayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loops_or_stops_at_the_end() {
+        let mut player = AnimationPlayer::new();
+        player.speed = 2.0;
+        player.advance(1.5, 2.0);
+        assert_eq!(player.time, 1.0);
+
+        player.looping = false;
+        player.advance(1.0, 2.0);
+        assert_eq!(player.time, 2.0);
+        assert!(!player.playing);
+
+        player.speed = -1.0;
+        player.play();
+        player.advance(3.0, 2.0);
+        assert_eq!(player.time, 0.0);
+        assert!(!player.playing);
+    }
+}
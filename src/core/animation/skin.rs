@@ -0,0 +1,36 @@
+use std::sync::Arc;
+
+use glam::Mat4;
+
+use crate::core::scene::NodeId;
+
+/// Joints a vertex may be bound to; matches the four-component joint and
+/// weight streams of a mesh.
+pub const MAX_JOINT_INFLUENCES: usize = 4;
+
+/// Joints deforming a skinned mesh and the matrices taking each joint from
+/// bind pose to its local space. `J` is a model node index in imported data
+/// and a `NodeId` once spawned into a scene.
+#[derive(Clone, Debug)]
+pub struct Skin<J = NodeId> {
+    pub name: String,
+    pub joints: Vec<J>,
+    pub inverse_bind_matrices: Arc<[Mat4]>,
+}
+
+impl<J> Skin<J> {
+    pub fn map_joints<K>(&self, f: impl FnMut(&J) -> K) -> Skin<K> {
+        Skin {
+            name: self.name.clone(),
+            joints: self.joints.iter().map(f).collect(),
+            inverse_bind_matrices: self.inverse_bind_matrices.clone(),
+        }
+    }
+
+    pub fn inverse_bind_matrix(&self, joint: usize) -> Mat4 {
+        self.inverse_bind_matrices
+            .get(joint)
+            .copied()
+            .unwrap_or(Mat4::IDENTITY)
+    }
+}
@@ -1,5 +1,14 @@
+use std::time::{Duration, Instant};
+
 use crate::core::{
-    asset_manager::AssetManager, gl_window::GlWindow, renderer::Renderer, scene::Scene, time::Time,
+    asset_manager::AssetManager,
+    frame_stats::Phase,
+    gl_window::GlWindow,
+    input::Input,
+    redraw::{RedrawMode, RedrawScheduler},
+    renderer::Renderer,
+    scene::Scene,
+    time::Time,
 };
 use winit::{
     application::ApplicationHandler,
@@ -9,7 +18,9 @@ use winit::{
 };
 
 pub struct AppContext<'a> {
-    pub time: &'a Time,
+    pub time: &'a mut Time,
+    pub redraw: &'a mut RedrawScheduler,
+    pub input: &'a mut Input,
     pub scene: &'a mut Scene,
     pub assets: &'a mut AssetManager,
     pub renderer: &'a mut Renderer,
@@ -17,12 +28,16 @@ pub struct AppContext<'a> {
 }
 
 pub trait AppClient {
+    /// Called once, right after the client has been created.
+    fn init(&mut self, _ctx: &mut AppContext) {}
+    /// Returns true when the client, e.g. its UI, consumed the event so it
+    /// should not reach the input actions.
     fn on_window_event(
         &mut self,
         event_loop: &ActiveEventLoop,
         ctx: &mut AppContext,
         event: &WindowEvent,
-    );
+    ) -> bool;
     fn render(&mut self, ctx: &mut AppContext);
     fn shutdown(&mut self, ctx: &mut AppContext);
 }
@@ -32,8 +47,14 @@ pub trait AppFactory {
     fn create_client(&mut self, ctx: &GlWindow) -> anyhow::Result<Box<dyn AppClient>>;
 }
 
+/// How often a reactive viewer with nothing to draw still wakes up to look
+/// for changed files.
+const HOT_RELOAD_POLL: Duration = Duration::from_millis(250);
+
 pub struct Application {
     time: Time,
+    redraw: RedrawScheduler,
+    input: Input,
     scene: Scene,
     assets: AssetManager,
     renderer: Option<Renderer>,
@@ -46,6 +67,8 @@ impl Application {
     pub fn new(app_factory: Box<dyn AppFactory>) -> Self {
         Self {
             time: Time::new(),
+            redraw: RedrawScheduler::new(RedrawMode::Continuous),
+            input: Input::default(),
             scene: Scene::new(),
             assets: AssetManager::new(),
             renderer: None,
@@ -70,7 +93,9 @@ impl Application {
         };
 
         let mut ctx = AppContext {
-            time: &self.time,
+            time: &mut self.time,
+            redraw: &mut self.redraw,
+            input: &mut self.input,
             scene: &mut self.scene,
             assets: &mut self.assets,
             renderer,
@@ -83,8 +108,6 @@ impl Application {
 
 impl ApplicationHandler for Application {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-        event_loop.set_control_flow(ControlFlow::Poll);
-
         if self.main_window.is_none() {
             let attributes = self.app_factory.window_attributes();
             self.main_window = Some(GlWindow::new(event_loop, attributes));
@@ -92,13 +115,14 @@ impl ApplicationHandler for Application {
 
         if let Some(window) = &self.main_window {
             if self.renderer.is_none() {
-                self.renderer = Some(Renderer::new(window.gl_cloned()));
+                self.renderer = Some(Renderer::new(window.gl_cloned(), &mut self.assets));
             }
 
             if self.app_client.is_none() {
                 match self.app_factory.create_client(window) {
                     Ok(client) => {
                         self.app_client = Some(client);
+                        self.with_ctx(|client, ctx| client.init(ctx));
                     }
                     Err(err) => {
                         log::error!("App client creation failed: {:#}", err);
@@ -109,6 +133,10 @@ impl ApplicationHandler for Application {
     }
 
     fn window_event(&mut self, event_loop: &ActiveEventLoop, id: WindowId, event: WindowEvent) {
+        // Input and window changes may alter what is shown.
+        if !matches!(event, WindowEvent::RedrawRequested) {
+            self.redraw.request_redraw();
+        }
         self.with_ctx(|client, ctx| {
             if id != ctx.window.id() {
                 return;
@@ -132,21 +160,60 @@ impl ApplicationHandler for Application {
                 _ => {}
             }
 
-            client.on_window_event(event_loop, ctx, &event);
+            let consumed = client.on_window_event(event_loop, ctx, &event);
+            if !consumed {
+                ctx.input.handle_event(&event);
+            }
 
             if do_render {
                 client.render(ctx);
+                let swap_start = Instant::now();
                 ctx.window.swap_buffers();
+                ctx.time
+                    .stats_mut()
+                    .record_phase(Phase::Swap, swap_start.elapsed());
+                ctx.input.end_frame();
             }
         });
     }
 
-    fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
-        self.time.update();
-        self.scene.update();
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        let Some(window) = self.main_window.as_ref() else {
+            return;
+        };
+        self.assets.update(&window.gl_cloned());
+        let streaming = self
+            .renderer
+            .as_ref()
+            .is_some_and(|renderer| renderer.point_cloud_stats().pending_nodes > 0);
+        let animating = self.scene.is_animating() && !self.time.is_paused();
+        if self.assets.is_busy() || streaming || animating {
+            self.redraw.request_redraw();
+        }
 
-        if let Some(w) = self.main_window.as_ref() {
-            w.request_redraw();
+        let now = Instant::now();
+        match self.redraw.next_frame(now) {
+            Some(at) if at <= now => {
+                self.redraw.frame_started(now);
+                self.time.update();
+                let update_start = Instant::now();
+                self.scene.update(&self.time, &self.assets);
+                self.time
+                    .stats_mut()
+                    .record_phase(Phase::SceneUpdate, update_start.elapsed());
+                window.request_redraw();
+                event_loop.set_control_flow(ControlFlow::Poll);
+            }
+            Some(at) => event_loop.set_control_flow(ControlFlow::WaitUntil(at)),
+            // No frame is scheduled, so the loop idles until an event.
+            None if self.assets.hot_reload() => {
+                self.time.set_idle();
+                event_loop.set_control_flow(ControlFlow::WaitUntil(now + HOT_RELOAD_POLL));
+            }
+            None => {
+                self.time.set_idle();
+                event_loop.set_control_flow(ControlFlow::Wait);
+            }
         }
     }
 
@@ -1,20 +1,1001 @@
+pub mod environment;
+pub mod exporters;
+pub mod handle;
+pub mod importers;
+pub mod loader;
+pub mod material;
+pub mod mesh;
+pub mod model;
+pub mod point_cloud;
+pub mod shader;
+pub mod storage;
+pub mod texture;
+pub mod vfs;
+pub mod watcher;
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    mem::size_of,
+    ops::{Add, AddAssign},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use glam::Vec3;
+
+pub use environment::Environment;
+pub use handle::{AssetId, Handle, WeakHandle};
+pub use loader::{Cancelled, LoadProgress, LoadTask};
+pub use material::Material;
+pub use mesh::{Mesh, MeshLod, MorphTarget};
+pub use model::Model;
+pub use point_cloud::PointCloud;
+pub use shader::Shader;
+pub use storage::{AssetState, AssetStorage};
+pub use texture::Texture;
+pub use vfs::Vfs;
+
+use crate::core::asset_manager::{loader::AssetLoader, watcher::AssetWatcher};
+use crate::core::mesh_processing::{self, LodGeneration};
+use crate::core::renderer::{GpuMesh, GpuTexture};
+
+/// Default amount of vertex, index and texel data uploaded to the GPU per
+/// frame, so that a large import does not stall rendering.
+pub const DEFAULT_UPLOAD_BUDGET: usize = 64 * 1024 * 1024;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum AssetKind {
+    Mesh,
+    Texture,
+    Material,
+    Model,
+    PointCloud,
+    Shader,
+    Environment,
+}
+
+impl fmt::Display for AssetKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            AssetKind::Mesh => "mesh",
+            AssetKind::Texture => "texture",
+            AssetKind::Material => "material",
+            AssetKind::Model => "model",
+            AssetKind::PointCloud => "point cloud",
+            AssetKind::Shader => "shader",
+            AssetKind::Environment => "environment",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct MemoryUsage {
+    pub cpu_bytes: usize,
+    pub gpu_bytes: usize,
+}
+
+impl Add for MemoryUsage {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            cpu_bytes: self.cpu_bytes + rhs.cpu_bytes,
+            gpu_bytes: self.gpu_bytes + rhs.gpu_bytes,
+        }
+    }
+}
+
+impl AddAssign for MemoryUsage {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+pub trait Asset: Sized + 'static {
+    const KIND: AssetKind;
+
+    fn memory_usage(&self) -> MemoryUsage;
+    fn storage(assets: &AssetManager) -> &AssetStorage<Self>;
+    fn storage_mut(assets: &mut AssetManager) -> &mut AssetStorage<Self>;
+}
+
+/// An asset that can be read from a file. Loading is split in two so that
+/// `import` can run on a worker thread without access to the `AssetManager`
+/// (or a GL context), while `finish` registers any nested assets it produced
+/// on the main thread.
+pub trait LoadableAsset: Asset {
+    type Data: Send + 'static;
+
+    fn import(path: &Path, vfs: &Vfs, progress: &LoadProgress) -> anyhow::Result<Self::Data>;
+    fn finish(data: Self::Data, assets: &mut AssetManager) -> Self;
+
+    /// Files besides the main path that the import read, e.g. glTF buffers
+    /// or OBJ material libraries. Hot reload watches them too.
+    fn dependencies(_data: &Self::Data) -> Vec<PathBuf> {
+        Vec::new()
+    }
+
+    /// Builds the replacement for `previous` after its files changed. On
+    /// error the previous version stays in use.
+    fn reload(
+        data: Self::Data,
+        _previous: &Self,
+        assets: &mut AssetManager,
+    ) -> anyhow::Result<Self> {
+        Ok(Self::finish(data, assets))
+    }
+}
+
+/// Reported by `AssetManager::drain_events` so the UI can surface hot reload
+/// results and shader compile errors.
+#[derive(Clone, Debug)]
+pub enum AssetEvent {
+    Reloaded {
+        kind: AssetKind,
+        path: PathBuf,
+    },
+    ReloadFailed {
+        kind: AssetKind,
+        path: PathBuf,
+        error: String,
+    },
+    /// A shader failed its first compile, so nothing drawn with it shows up.
+    ShaderFailed {
+        name: String,
+        error: String,
+    },
+}
+
+/// Everything needed to reload an asset that came from a file.
+struct SourceFiles {
+    files: Vec<PathBuf>,
+    reload: fn(&mut AssetManager, AssetId),
+}
+
+#[derive(Clone, Debug)]
+pub struct AssetMemory {
+    pub id: AssetId,
+    pub kind: AssetKind,
+    pub path: Option<PathBuf>,
+    pub state: AssetState,
+    pub usage: MemoryUsage,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct MemoryReport {
+    pub assets: Vec<AssetMemory>,
+    pub total: MemoryUsage,
+}
+
 pub struct AssetManager {
-    // fields go here
+    pub(crate) meshes: AssetStorage<Mesh>,
+    pub(crate) textures: AssetStorage<Texture>,
+    pub(crate) materials: AssetStorage<Material>,
+    pub(crate) models: AssetStorage<Model>,
+    pub(crate) point_clouds: AssetStorage<PointCloud>,
+    pub(crate) shaders: AssetStorage<Shader>,
+    pub(crate) environments: AssetStorage<Environment>,
+    pub(crate) gl: Option<Arc<glow::Context>>,
+    vfs: Vfs,
+    loader: AssetLoader,
+    upload_budget: usize,
+    sources: HashMap<AssetId, SourceFiles>,
+    watcher: Option<AssetWatcher>,
+    events: Vec<AssetEvent>,
 }
 
 impl AssetManager {
     pub fn new() -> Self {
-        // initialization code goes here
         Self {
-            // initialize fields
+            meshes: AssetStorage::new(),
+            textures: AssetStorage::new(),
+            materials: AssetStorage::new(),
+            models: AssetStorage::new(),
+            point_clouds: AssetStorage::new(),
+            shaders: AssetStorage::new(),
+            environments: AssetStorage::new(),
+            gl: None,
+            vfs: Vfs::new(),
+            loader: AssetLoader::new(),
+            upload_budget: DEFAULT_UPLOAD_BUDGET,
+            sources: HashMap::new(),
+            watcher: None,
+            events: Vec::new(),
+        }
+    }
+
+    /// The file system all loads go through. Mount directories, archives or
+    /// in-memory files on it before loading from their prefixes.
+    pub fn vfs(&self) -> &Vfs {
+        &self.vfs
+    }
+
+    pub fn add<T: Asset>(&mut self, asset: T) -> Handle<T> {
+        T::storage_mut(self).insert(asset)
+    }
+
+    /// Starts loading `path` on a worker thread unless an asset from the same
+    /// path is still referenced, in which case a new handle to it is returned.
+    /// The handle stays in the `Loading` state until `process_completed` picks
+    /// up the result; failures are recorded in the asset state. Loading a
+    /// path whose earlier load failed or was cancelled tries again, in place.
+    pub fn load<T: LoadableAsset>(&mut self, path: impl AsRef<Path>) -> Handle<T> {
+        let path = &vfs::normalize(path.as_ref());
+        if let Some(handle) = T::storage(self).find_by_path(path) {
+            if let Some(AssetState::Failed(_)) = T::storage(self).state(handle.id()) {
+                T::storage_mut(self).set_loading(handle.id());
+                self.spawn_import::<T>(handle.id(), path, false);
+            }
+            return handle;
+        }
+
+        let handle = T::storage_mut(self).reserve(Some(path.to_path_buf()));
+        self.spawn_import::<T>(handle.id(), path, false);
+        handle
+    }
+
+    fn spawn_import<T: LoadableAsset>(&mut self, id: AssetId, path: &Path, reload: bool) {
+        let task = LoadTask {
+            id,
+            kind: T::KIND,
+            path: path.to_path_buf(),
+            progress: Arc::new(LoadProgress::new()),
+            reload,
+        };
+        let (path, progress) = (task.path.clone(), task.progress.clone());
+        let vfs = self.vfs.clone();
+        self.loader.spawn(task, move || {
+            let result = T::import(&path, &vfs, &progress);
+            Box::new(move |assets: &mut AssetManager| {
+                if reload {
+                    assets.complete_reload::<T>(id, &path, result);
+                } else {
+                    assets.complete::<T>(id, &path, result);
+                }
+            })
+        });
+    }
+
+    /// Like `load`, but imports on the calling thread and returns once the
+    /// asset is loaded or has failed.
+    pub fn load_blocking<T: LoadableAsset>(&mut self, path: impl AsRef<Path>) -> Handle<T> {
+        let path = &vfs::normalize(path.as_ref());
+        let handle = match T::storage(self).find_by_path(path) {
+            Some(handle) => match T::storage(self).state(handle.id()) {
+                Some(AssetState::Failed(_)) => {
+                    T::storage_mut(self).set_loading(handle.id());
+                    handle
+                }
+                Some(AssetState::Loading) => T::storage_mut(self).reserve(Some(path.to_path_buf())),
+                _ => return handle,
+            },
+            None => T::storage_mut(self).reserve(Some(path.to_path_buf())),
+        };
+        let result = T::import(path, &self.vfs, &LoadProgress::new());
+        self.complete::<T>(handle.id(), path, result);
+        handle
+    }
+
+    fn complete<T: LoadableAsset>(
+        &mut self,
+        id: AssetId,
+        path: &Path,
+        result: anyhow::Result<T::Data>,
+    ) {
+        if !T::storage(self).contains(id) {
+            return;
+        }
+        match result {
+            Ok(data) => {
+                self.track_sources::<T>(id, path, T::dependencies(&data));
+                let asset = T::finish(data, self);
+                T::storage_mut(self).set_loaded(id, asset);
+            }
+            Err(err) => {
+                log::error!("failed to load {} {}: {:#}", T::KIND, path.display(), err);
+                T::storage_mut(self).set_failed(id, format!("{err:#}"));
+            }
+        }
+    }
+
+    fn track_sources<T: LoadableAsset>(&mut self, id: AssetId, path: &Path, deps: Vec<PathBuf>) {
+        // Watch the native files behind the virtual paths; several entries of
+        // one archive map to the same file.
+        let mut files = Vec::new();
+        for file in std::iter::once(path.to_path_buf()).chain(deps) {
+            if let Some(native) = self.vfs.watch_path(&file)
+                && !files.contains(&native)
+            {
+                files.push(native);
+            }
+        }
+        if let Some(watcher) = &mut self.watcher {
+            watcher.watch(id, files.clone());
+        }
+        self.sources.insert(
+            id,
+            SourceFiles {
+                files,
+                reload: Self::reload_by_id::<T>,
+            },
+        );
+    }
+
+    fn reload_by_id<T: LoadableAsset>(&mut self, id: AssetId) {
+        let Some(path) = T::storage(self).path(id).map(Path::to_path_buf) else {
+            return;
+        };
+        log::info!("reloading {} {}", T::KIND, path.display());
+        self.spawn_import::<T>(id, &path, true);
+    }
+
+    /// Re-imports a file-backed asset in the background. Handles stay valid;
+    /// once the import succeeds the asset is replaced in place.
+    pub fn reload<T: LoadableAsset>(&mut self, handle: &Handle<T>) {
+        self.reload_by_id::<T>(handle.id());
+    }
+
+    fn complete_reload<T: LoadableAsset>(
+        &mut self,
+        id: AssetId,
+        path: &Path,
+        result: anyhow::Result<T::Data>,
+    ) {
+        let Some(previous) = T::storage_mut(self).take(id) else {
+            return;
+        };
+        let result = result.and_then(|data| {
+            let deps = T::dependencies(&data);
+            T::reload(data, &previous, self).map(|asset| (asset, deps))
+        });
+        match result {
+            Ok((asset, deps)) => {
+                T::storage_mut(self).set_loaded(id, asset);
+                self.track_sources::<T>(id, path, deps);
+                self.events.push(AssetEvent::Reloaded {
+                    kind: T::KIND,
+                    path: path.to_path_buf(),
+                });
+            }
+            Err(err) => {
+                log::error!("failed to reload {} {}: {:#}", T::KIND, path.display(), err);
+                T::storage_mut(self).set_loaded(id, previous);
+                self.events.push(AssetEvent::ReloadFailed {
+                    kind: T::KIND,
+                    path: path.to_path_buf(),
+                    error: format!("{err:#}"),
+                });
+            }
+        }
+    }
+
+    /// Turns watching of asset source files on or off. Assets loaded while
+    /// it was off are picked up when it is turned on.
+    pub fn set_hot_reload(&mut self, enabled: bool) {
+        if enabled == self.watcher.is_some() {
+            return;
+        }
+        if !enabled {
+            self.watcher = None;
+            return;
+        }
+        match AssetWatcher::new() {
+            Ok(mut watcher) => {
+                for (id, source) in &self.sources {
+                    watcher.watch(*id, source.files.clone());
+                }
+                self.watcher = Some(watcher);
+            }
+            Err(err) => log::error!("hot reload unavailable: {}", err),
+        }
+    }
+
+    pub fn hot_reload(&self) -> bool {
+        self.watcher.is_some()
+    }
+
+    pub fn drain_events(&mut self) -> Vec<AssetEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    fn reload_changed(&mut self) {
+        let Some(watcher) = &mut self.watcher else {
+            return;
+        };
+        for id in watcher.poll() {
+            if let Some(reload) = self.sources.get(&id).map(|s| s.reload) {
+                reload(self, id);
+            }
+        }
+    }
+
+    /// Applies the results of finished background loads.
+    pub fn process_completed(&mut self) -> usize {
+        let completed = self.loader.take_completed();
+        let count = completed.len();
+        for (_, finish) in completed {
+            finish(self);
+        }
+        count
+    }
+
+    /// Cancels a background load. A first load ends up in the `Failed`
+    /// state, a cancelled reload keeps the current version.
+    pub fn cancel_load(&mut self, id: AssetId) -> bool {
+        let Some(task) = self.loader.task(id) else {
+            return false;
+        };
+        let (kind, reload) = (task.kind, task.reload);
+        self.loader.cancel(id);
+        if reload {
+            return true;
+        }
+        let error = loader::Cancelled.to_string();
+        match kind {
+            AssetKind::Mesh => self.meshes.set_failed(id, error),
+            AssetKind::Texture => self.textures.set_failed(id, error),
+            AssetKind::Material => self.materials.set_failed(id, error),
+            AssetKind::Model => self.models.set_failed(id, error),
+            AssetKind::PointCloud => self.point_clouds.set_failed(id, error),
+            AssetKind::Shader => self.shaders.set_failed(id, error),
+            AssetKind::Environment => self.environments.set_failed(id, error),
+        }
+        true
+    }
+
+    /// Background loads that have not finished yet.
+    pub fn loads(&self) -> impl Iterator<Item = &LoadTask> {
+        self.loader.tasks()
+    }
+
+    pub fn set_upload_budget(&mut self, bytes_per_frame: usize) {
+        self.upload_budget = bytes_per_frame.max(1);
+    }
+
+    pub fn upload_budget(&self) -> usize {
+        self.upload_budget
+    }
+
+    pub fn get<T: Asset>(&self, handle: &Handle<T>) -> Option<&T> {
+        T::storage(self).get(handle)
+    }
+
+    pub fn get_mut<T: Asset>(&mut self, handle: &Handle<T>) -> Option<&mut T> {
+        T::storage_mut(self).get_mut(handle)
+    }
+
+    pub fn state<T: Asset>(&self, handle: &Handle<T>) -> AssetState {
+        T::storage(self)
+            .state(handle.id())
+            .unwrap_or(AssetState::Unloaded)
+    }
+
+    pub fn path<T: Asset>(&self, handle: &Handle<T>) -> Option<&Path> {
+        T::storage(self).path(handle.id())
+    }
+
+    pub fn storage<T: Asset>(&self) -> &AssetStorage<T> {
+        T::storage(self)
+    }
+
+    /// Replaces the levels of detail of `mesh` with ones built by
+    /// simplification. Returns the number of levels; meshes below
+    /// `settings.min_triangles` get none.
+    pub fn generate_lods(&mut self, mesh: &Handle<Mesh>, settings: &LodGeneration) -> usize {
+        self.generate_lods_by_id(mesh.id(), settings)
+    }
+
+    /// Generates levels of detail for every loaded mesh that is large enough
+    /// and has none yet, whether imported or generated. Returns the number of
+    /// meshes that got levels.
+    pub fn generate_all_lods(&mut self, settings: &LodGeneration) -> usize {
+        let levels: HashSet<AssetId> = self
+            .meshes
+            .iter()
+            .flat_map(|(_, mesh)| mesh.lods.iter().map(|lod| lod.mesh.id()))
+            .collect();
+        let ids: Vec<AssetId> = self
+            .meshes
+            .iter()
+            .filter(|(id, mesh)| {
+                mesh.lods.is_empty()
+                    && !levels.contains(id)
+                    && mesh.triangle_count() >= settings.min_triangles
+            })
+            .map(|(id, _)| id)
+            .collect();
+        ids.into_iter()
+            .filter(|&id| self.generate_lods_by_id(id, settings) > 0)
+            .count()
+    }
+
+    fn generate_lods_by_id(&mut self, id: AssetId, settings: &LodGeneration) -> usize {
+        let Some(mesh) = self.meshes.get_by_id(id) else {
+            return 0;
+        };
+        let lods: Vec<MeshLod> = mesh_processing::build_lods(mesh, settings)
+            .into_iter()
+            .map(|(mesh, screen_coverage)| MeshLod {
+                mesh: self.add(mesh),
+                screen_coverage,
+            })
+            .collect();
+        let count = lods.len();
+        if let Some(mesh) = self.meshes.get_by_id_mut(id) {
+            mesh.lods = lods;
+        }
+        count
+    }
+
+    /// Per-frame housekeeping: applies finished loads, starts reloads of
+    /// changed files, unloads unreferenced assets and uploads pending GPU
+    /// data within the frame budget.
+    pub fn update(&mut self, gl: &Arc<glow::Context>) {
+        if self.gl.is_none() {
+            self.gl = Some(gl.clone());
+        }
+        self.process_completed();
+        self.reload_changed();
+        self.collect_unused();
+        self.upload_pending(gl);
+    }
+
+    /// Unloads every asset that is no longer referenced and cancels loads
+    /// nobody is waiting for anymore. Models release their materials and
+    /// meshes, and materials their textures, so storages are drained in
+    /// dependency order.
+    pub fn collect_unused(&mut self) -> usize {
+        let mut removed = self.models.collect_unused();
+        removed.extend(self.materials.collect_unused());
+        removed.extend(self.meshes.collect_unused());
+        removed.extend(self.point_clouds.collect_unused());
+        removed.extend(self.textures.collect_unused());
+        removed.extend(self.shaders.collect_unused());
+        removed.extend(self.environments.collect_unused());
+        for id in &removed {
+            self.loader.cancel(*id);
+            self.sources.remove(id);
+            if let Some(watcher) = &mut self.watcher {
+                watcher.unwatch(*id);
+            }
+        }
+        if !removed.is_empty() {
+            log::debug!("unloaded {} unused assets", removed.len());
+        }
+        removed.len()
+    }
+
+    /// Creates GPU resources for loaded meshes, textures and environments
+    /// that do not have them yet, stopping once the per-frame upload budget
+    /// is spent. Must be called on the thread owning the GL context.
+    pub fn upload_pending(&mut self, gl: &Arc<glow::Context>) {
+        for (_, shader) in self.shaders.iter_mut() {
+            if shader.program.is_none()
+                && shader.compile_error.is_none()
+                && let Err(err) = shader.compile(gl)
+            {
+                log::error!("{:#}", err);
+                self.events.push(AssetEvent::ShaderFailed {
+                    name: shader.name.clone(),
+                    error: format!("{err:#}"),
+                });
+            }
         }
+
+        // A failed upload is recorded on the asset, so it is logged once and
+        // does not eat into the budget of every later frame, while the CPU
+        // data stays available.
+        let mut spent = 0;
+        for (id, mesh) in self.meshes.iter_mut().filter(|(_, m)| m.needs_upload()) {
+            if spent >= self.upload_budget {
+                return;
+            }
+            spent += mesh.cpu_bytes();
+            match GpuMesh::upload(gl.clone(), mesh) {
+                Ok(gpu) => mesh.gpu = Some(gpu),
+                Err(err) => {
+                    log::error!("mesh {id} upload failed: {:#}", err);
+                    mesh.upload_error = Some(format!("{err:#}"));
+                }
+            }
+        }
+        for (id, texture) in self.textures.iter_mut().filter(|(_, t)| t.needs_upload()) {
+            if spent >= self.upload_budget {
+                return;
+            }
+            spent += texture.pixels.len();
+            match GpuTexture::upload(gl.clone(), texture) {
+                Ok(gpu) => texture.gpu = Some(gpu),
+                Err(err) => {
+                    log::error!("texture {id} upload failed: {:#}", err);
+                    texture.upload_error = Some(format!("{err:#}"));
+                }
+            }
+        }
+        for (id, environment) in self
+            .environments
+            .iter_mut()
+            .filter(|(_, e)| e.needs_upload())
+        {
+            if spent >= self.upload_budget {
+                return;
+            }
+            spent += environment.pixels.len() * size_of::<Vec3>();
+            match GpuTexture::upload_hdr(
+                gl.clone(),
+                environment.width,
+                environment.height,
+                &environment.pixels,
+            ) {
+                Ok(gpu) => environment.gpu = Some(gpu),
+                Err(err) => {
+                    log::error!("environment {id} upload failed: {:#}", err);
+                    environment.upload_error = Some(format!("{err:#}"));
+                }
+            }
+        }
+    }
+
+    /// Whether loads are in flight, uploads are pending or events have not
+    /// been drained yet; the viewer keeps drawing frames until this clears.
+    pub fn is_busy(&self) -> bool {
+        self.loader.tasks().next().is_some()
+            || !self.events.is_empty()
+            || self.pending_uploads() > 0
+    }
+
+    /// Number of loaded meshes, textures and environments still waiting for
+    /// GPU upload.
+    pub fn pending_uploads(&self) -> usize {
+        let meshes = self.meshes.iter().filter(|(_, m)| m.needs_upload()).count();
+        let textures = self
+            .textures
+            .iter()
+            .filter(|(_, t)| t.needs_upload())
+            .count();
+        let environments = self
+            .environments
+            .iter()
+            .filter(|(_, e)| e.needs_upload())
+            .count();
+        meshes + textures + environments
+    }
+
+    pub fn memory_report(&self) -> MemoryReport {
+        let mut report = MemoryReport::default();
+        self.report_storage(&self.meshes, &mut report);
+        self.report_storage(&self.textures, &mut report);
+        self.report_storage(&self.materials, &mut report);
+        self.report_storage(&self.models, &mut report);
+        self.report_storage(&self.point_clouds, &mut report);
+        self.report_storage(&self.shaders, &mut report);
+        self.report_storage(&self.environments, &mut report);
+        report
+    }
+
+    fn report_storage<T: Asset>(&self, storage: &AssetStorage<T>, report: &mut MemoryReport) {
+        for id in storage.ids() {
+            let usage = storage
+                .get_by_id(id)
+                .map(Asset::memory_usage)
+                .unwrap_or_default();
+            report.total += usage;
+            report.assets.push(AssetMemory {
+                id,
+                kind: T::KIND,
+                path: storage.path(id).map(Path::to_path_buf),
+                state: storage.state(id).unwrap_or(AssetState::Loading),
+                usage,
+            });
+        }
+    }
+}
+
+impl Default for AssetManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Vec3;
+
+    fn triangle() -> Mesh {
+        let mut mesh = Mesh::new("triangle");
+        mesh.positions = vec![Vec3::ZERO, Vec3::X, Vec3::Y];
+        mesh.indices = vec![0, 1, 2];
+        mesh
+    }
+
+    #[test]
+    fn dropping_last_handle_unloads() {
+        let mut assets = AssetManager::new();
+        let handle = assets.add(triangle());
+        let second = handle.clone();
+        let id = handle.id();
+
+        drop(handle);
+        assert_eq!(assets.collect_unused(), 0);
+        assert!(assets.storage::<Mesh>().contains(id));
+
+        drop(second);
+        assert_eq!(assets.collect_unused(), 1);
+        assert!(!assets.storage::<Mesh>().contains(id));
+    }
+
+    #[test]
+    fn weak_handle_does_not_keep_asset_alive() {
+        let mut assets = AssetManager::new();
+        let handle = assets.add(triangle());
+        let weak = handle.downgrade();
+        assert!(weak.upgrade().is_some());
+
+        drop(handle);
+        assert!(weak.upgrade().is_none());
+        assets.collect_unused();
+        assert!(!assets.storage::<Mesh>().contains(weak.id()));
+    }
+
+    #[test]
+    fn material_keeps_texture_loaded() {
+        let mut assets = AssetManager::new();
+        let texture = assets.add(Texture::from_rgba8(
+            "white",
+            1,
+            1,
+            vec![255; 4],
+            texture::ColorSpace::Srgb,
+        ));
+        let texture_id = texture.id();
+        let mut material = Material::default();
+        material.textures.base_color = Some(texture);
+        let material = assets.add(material);
+
+        assets.collect_unused();
+        assert!(assets.storage::<Texture>().contains(texture_id));
+
+        drop(material);
+        assert_eq!(assets.collect_unused(), 2);
+        assert!(assets.storage::<Texture>().is_empty());
+    }
+
+    #[test]
+    fn missing_file_reports_failed_state() {
+        let mut assets = AssetManager::new();
+        let handle: Handle<Model> = assets.load_blocking("does/not/exist.gltf");
+        assert!(matches!(assets.state(&handle), AssetState::Failed(_)));
+        assert!(assets.get(&handle).is_none());
+    }
+
+    #[test]
+    fn equivalent_paths_share_one_asset() {
+        let mut assets = AssetManager::new();
+        assets.vfs().mount(
+            "mem",
+            vfs::MemoryFiles::new().with_file("tri.obj", "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n"),
+        );
+        let model: Handle<Model> = assets.load_blocking("mem/tri.obj");
+        let again: Handle<Model> = assets.load("./mem/models/../tri.obj");
+        assert_eq!(again, model);
+        assert_eq!(assets.storage::<Model>().len(), 1);
+        assert_eq!(assets.path(&model), Some(Path::new("mem/tri.obj")));
+    }
+
+    #[test]
+    fn unknown_ids_report_unloaded() {
+        let assets = AssetManager::new();
+        let mut other = AssetManager::new();
+        let foreign = other.add(triangle());
+        assert_eq!(assets.state(&foreign), AssetState::Unloaded);
+        assert_eq!(other.state(&foreign), AssetState::Loaded);
+    }
+
+    #[test]
+    fn background_load_completes_on_main_thread() {
+        let mut assets = AssetManager::new();
+        let handle: Handle<Model> = assets.load("does/not/exist.obj");
+        assert_eq!(assets.loads().count(), 1);
+
+        let start = std::time::Instant::now();
+        while assets.state(&handle) == AssetState::Loading {
+            assert!(start.elapsed() < std::time::Duration::from_secs(5));
+            assets.process_completed();
+            std::thread::yield_now();
+        }
+        assert!(matches!(assets.state(&handle), AssetState::Failed(_)));
+        assert_eq!(assets.loads().count(), 0);
+    }
+
+    fn wait_for_loads(assets: &mut AssetManager) {
+        let start = std::time::Instant::now();
+        while assets.loads().count() > 0 {
+            assert!(start.elapsed() < std::time::Duration::from_secs(5));
+            assets.process_completed();
+            std::thread::yield_now();
+        }
+    }
+
+    fn temp_file(name: &str, contents: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("asset-manager-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn reload_replaces_model_in_place() {
+        let path = temp_file("reload.obj", "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n");
+        let mut assets = AssetManager::new();
+        let model: Handle<Model> = assets.load_blocking(&path);
+        let mesh = assets.get(&model).unwrap().meshes[0].clone();
+        assert_eq!(assets.get(&mesh).unwrap().vertex_count(), 3);
+
+        std::fs::write(&path, "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\nf 1 2 3 4\n").unwrap();
+        assets.reload(&model);
+        wait_for_loads(&mut assets);
+
+        assert_eq!(assets.get(&model).unwrap().meshes[0], mesh);
+        assert_eq!(assets.get(&mesh).unwrap().vertex_count(), 4);
+        assert!(matches!(
+            assets.drain_events()[..],
+            [AssetEvent::Reloaded {
+                kind: AssetKind::Model,
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn back_to_back_reloads_keep_the_newest_version() {
+        let path = temp_file("twice.obj", "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n");
+        let mut assets = AssetManager::new();
+        let model: Handle<Model> = assets.load_blocking(&path);
+
+        std::fs::write(&path, "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\nf 1 2 3 4\n").unwrap();
+        assets.reload(&model);
+        std::fs::write(
+            &path,
+            "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\nv 2 1 0\nf 1 2 3 4 5\n",
+        )
+        .unwrap();
+        assets.reload(&model);
+        wait_for_loads(&mut assets);
+        // A late result of the first reload must not win either.
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assets.process_completed();
+
+        let mesh = &assets.get(&model).unwrap().meshes[0];
+        assert_eq!(assets.get(mesh).unwrap().vertex_count(), 5);
+    }
+
+    #[test]
+    fn models_kept_by_a_scene_stay_watched_and_reload() {
+        let path = temp_file("spawned.obj", "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n");
+        let mut assets = AssetManager::new();
+        let mut scene = crate::core::Scene::new();
+        let model: Handle<Model> = assets.load_blocking(&path);
+        scene.spawn_model(assets.get(&model).unwrap(), None);
+        scene.keep_model(model.clone());
+        let id = model.id();
+        drop(model);
+        assets.collect_unused();
+        assert!(assets.sources.contains_key(&id));
+
+        std::fs::write(&path, "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\nf 1 2 3 4\n").unwrap();
+        assets.reload(&scene.models()[0]);
+        wait_for_loads(&mut assets);
+
+        let mesh = scene
+            .iter()
+            .find_map(|(_, node)| node.mesh.clone())
+            .unwrap();
+        assert_eq!(assets.get(&mesh).unwrap().vertex_count(), 4);
+    }
+
+    #[test]
+    fn failed_reload_keeps_previous_version() {
+        let path = temp_file("broken.obj", "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n");
+        let mut assets = AssetManager::new();
+        let model: Handle<Model> = assets.load_blocking(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assets.reload(&model);
+        wait_for_loads(&mut assets);
+
+        assert_eq!(assets.state(&model), AssetState::Loaded);
+        assert_eq!(assets.get(&model).unwrap().meshes.len(), 1);
+        assert!(matches!(
+            assets.drain_events()[..],
+            [AssetEvent::ReloadFailed {
+                kind: AssetKind::Model,
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn loads_model_and_material_from_memory_mount() {
+        let mut assets = AssetManager::new();
+        assets.vfs().mount(
+            "mem",
+            vfs::MemoryFiles::new()
+                .with_file(
+                    "models/tri.obj",
+                    "mtllib ../materials/tri.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl red\nf 1 2 3\n",
+                )
+                .with_file("materials/tri.mtl", "newmtl red\nKd 1 0 0\n"),
+        );
+
+        let model: Handle<Model> = assets.load_blocking("mem/models/tri.obj");
+        assert_eq!(assets.state(&model), AssetState::Loaded);
+        let model = assets.get(&model).unwrap();
+        let material = assets.get(&model.materials[0]).unwrap();
+        assert_eq!(material.base_color.x, 1.0);
+        assert_eq!(material.base_color.y, 0.0);
+    }
+
+    #[test]
+    fn cancelled_load_ignores_result() {
+        let mut assets = AssetManager::new();
+        let handle: Handle<Model> = assets.load("does/not/exist.gltf");
+        assert!(assets.cancel_load(handle.id()));
+        assert_eq!(
+            assets.state(&handle),
+            AssetState::Failed(Cancelled.to_string())
+        );
+
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assets.process_completed();
+        assert_eq!(
+            assets.state(&handle),
+            AssetState::Failed(Cancelled.to_string())
+        );
+    }
+
+    #[test]
+    fn loading_a_failed_path_again_retries() {
+        let path = temp_file("retry.obj", "");
+        std::fs::remove_file(&path).unwrap();
+        let mut assets = AssetManager::new();
+        let model: Handle<Model> = assets.load(&path);
+        wait_for_loads(&mut assets);
+        assert!(matches!(assets.state(&model), AssetState::Failed(_)));
+
+        std::fs::write(&path, "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n").unwrap();
+        let again: Handle<Model> = assets.load(&path);
+        assert_eq!(again, model);
+        assert_eq!(assets.state(&model), AssetState::Loading);
+        wait_for_loads(&mut assets);
+        assert_eq!(assets.state(&model), AssetState::Loaded);
     }
 
-    pub fn load_asset(&self) {
-        // code to load an asset from the given path
+    #[test]
+    fn cancelled_load_can_be_started_again() {
+        let mut assets = AssetManager::new();
+        assets.vfs().mount(
+            "mem",
+            vfs::MemoryFiles::new().with_file("tri.obj", "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n"),
+        );
+        let model: Handle<Model> = assets.load("mem/tri.obj");
+        assets.cancel_load(model.id());
+        let again: Handle<Model> = assets.load_blocking("mem/tri.obj");
+        assert_eq!(again, model);
+        assert_eq!(assets.state(&model), AssetState::Loaded);
     }
 
-    pub fn get_asset(&self) {
-        // code to retrieve an asset by name
+    #[test]
+    fn memory_report_counts_cpu_bytes() {
+        let mut assets = AssetManager::new();
+        let _handle = assets.add(triangle());
+        let report = assets.memory_report();
+        assert_eq!(report.assets.len(), 1);
+        assert_eq!(report.assets[0].kind, AssetKind::Mesh);
+        assert_eq!(report.total.cpu_bytes, 3 * 12 + 3 * 4);
+        assert_eq!(report.total.gpu_bytes, 0);
     }
 }
@@ -0,0 +1,259 @@
+use std::{
+    f32::consts::PI,
+    mem::size_of,
+    path::Path,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use anyhow::{Context, bail};
+use glam::Vec3;
+
+use crate::core::asset_manager::{
+    Asset, AssetKind, AssetManager, AssetStorage, LoadProgress, LoadableAsset, MemoryUsage, Vfs,
+};
+use crate::core::renderer::GpuTexture;
+
+static NEXT_REVISION: AtomicU64 = AtomicU64::new(1);
+
+/// Samples along the longer side used for the irradiance projection; the
+/// result is smooth, so large maps are strided.
+const IRRADIANCE_SAMPLES: usize = 512;
+
+/// Equirectangular HDR environment map for the background and image-based
+/// lighting. Directions are in a Y-up frame: the top row is +Y and the
+/// middle column looks along -Z.
+pub struct Environment {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    /// Linear RGB radiance, top row first.
+    pub pixels: Vec<Vec3>,
+    /// Diffuse irradiance as nine spherical harmonics coefficients, already
+    /// convolved with the cosine lobe and divided by pi: evaluated at a
+    /// normal they give the light a white Lambertian surface reflects.
+    pub irradiance: [Vec3; 9],
+    revision: u64,
+    pub(crate) gpu: Option<GpuTexture>,
+    pub(crate) upload_error: Option<String>,
+}
+
+impl Environment {
+    pub fn new(name: impl Into<String>, width: u32, height: u32, pixels: Vec<Vec3>) -> Self {
+        debug_assert_eq!(pixels.len(), (width * height) as usize);
+        let irradiance = project_irradiance(width as usize, height as usize, &pixels);
+        Self {
+            name: name.into(),
+            width,
+            height,
+            pixels,
+            irradiance,
+            revision: NEXT_REVISION.fetch_add(1, Ordering::Relaxed),
+            gpu: None,
+            upload_error: None,
+        }
+    }
+
+    /// Decodes a Radiance `.hdr` or OpenEXR `.exr` file, telling them apart
+    /// by their magic numbers.
+    pub fn decode(name: impl Into<String>, bytes: &[u8]) -> anyhow::Result<Self> {
+        let (format, description) = if bytes.starts_with(&[0x76, 0x2f, 0x31, 0x01]) {
+            (image::ImageFormat::OpenExr, "OpenEXR")
+        } else if bytes.starts_with(b"#?") {
+            (image::ImageFormat::Hdr, "Radiance HDR")
+        } else {
+            bail!("not a Radiance HDR or OpenEXR image");
+        };
+        let image = image::load_from_memory_with_format(bytes, format)
+            .with_context(|| format!("failed to decode {description} image"))?
+            .to_rgb32f();
+        let (width, height) = image.dimensions();
+        let pixels = image.pixels().map(|p| Vec3::from_array(p.0)).collect();
+        Ok(Self::new(name, width, height, pixels))
+    }
+
+    pub fn gpu(&self) -> Option<&GpuTexture> {
+        self.gpu.as_ref()
+    }
+
+    /// Why the GPU upload failed; it is not retried for this version of the
+    /// map.
+    pub fn upload_error(&self) -> Option<&str> {
+        self.upload_error.as_deref()
+    }
+
+    pub(crate) fn needs_upload(&self) -> bool {
+        self.gpu.is_none() && self.upload_error.is_none()
+    }
+
+    /// Differs between every version of every environment, so GPU data
+    /// derived from the map can tell when it went stale.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Diffuse light arriving at a surface facing `normal`, from the
+    /// spherical harmonics in `irradiance`.
+    pub fn irradiance_at(&self, normal: Vec3) -> Vec3 {
+        sh_basis(normal)
+            .iter()
+            .zip(&self.irradiance)
+            .map(|(&basis, &coefficient)| coefficient * basis)
+            .sum()
+    }
+}
+
+/// Direction through the center of pixel `(x, y)`.
+pub fn equirect_direction(x: f32, y: f32, width: usize, height: usize) -> Vec3 {
+    let phi = ((x + 0.5) / width as f32 - 0.5) * 2.0 * PI;
+    let theta = (y + 0.5) / height as f32 * PI;
+    let (sin_theta, cos_theta) = theta.sin_cos();
+    Vec3::new(sin_theta * phi.sin(), cos_theta, -sin_theta * phi.cos())
+}
+
+/// The first nine real spherical harmonics at unit direction `d`.
+fn sh_basis(d: Vec3) -> [f32; 9] {
+    [
+        0.282_095,
+        0.488_603 * d.y,
+        0.488_603 * d.z,
+        0.488_603 * d.x,
+        1.092_548 * d.x * d.y,
+        1.092_548 * d.y * d.z,
+        0.315_392 * (3.0 * d.z * d.z - 1.0),
+        1.092_548 * d.x * d.z,
+        0.546_274 * (d.x * d.x - d.y * d.y),
+    ]
+}
+
+/// Projects the map onto the spherical harmonics basis, weighting each
+/// sample by its solid angle, and applies the cosine convolution.
+fn project_irradiance(width: usize, height: usize, pixels: &[Vec3]) -> [Vec3; 9] {
+    let mut coefficients = [Vec3::ZERO; 9];
+    if width == 0 || height == 0 {
+        return coefficients;
+    }
+    let stride = width.max(height).div_ceil(IRRADIANCE_SAMPLES).max(1);
+    let texel_angle = (2.0 * PI / width as f32) * (PI / height as f32);
+    for y in (0..height).step_by(stride) {
+        for x in (0..width).step_by(stride) {
+            let direction = equirect_direction(x as f32, y as f32, width, height);
+            let sin_theta = (1.0 - direction.y * direction.y).max(0.0).sqrt();
+            let weight = texel_angle * sin_theta * (stride * stride) as f32;
+            let radiance = pixels[y * width + x];
+            for (coefficient, basis) in coefficients.iter_mut().zip(sh_basis(direction)) {
+                *coefficient += radiance * basis * weight;
+            }
+        }
+    }
+    // Cosine lobe per band (pi, 2pi/3, pi/4), then divided by pi.
+    for (i, coefficient) in coefficients.iter_mut().enumerate() {
+        *coefficient *= match i {
+            0 => 1.0,
+            1..=3 => 2.0 / 3.0,
+            _ => 0.25,
+        };
+    }
+    coefficients
+}
+
+impl Asset for Environment {
+    const KIND: AssetKind = AssetKind::Environment;
+
+    fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage {
+            cpu_bytes: self.pixels.len() * size_of::<Vec3>(),
+            gpu_bytes: self.gpu.as_ref().map_or(0, GpuTexture::gpu_bytes),
+        }
+    }
+
+    fn storage(assets: &AssetManager) -> &AssetStorage<Self> {
+        &assets.environments
+    }
+
+    fn storage_mut(assets: &mut AssetManager) -> &mut AssetStorage<Self> {
+        &mut assets.environments
+    }
+}
+
+impl LoadableAsset for Environment {
+    type Data = Environment;
+
+    fn import(path: &Path, vfs: &Vfs, progress: &LoadProgress) -> anyhow::Result<Self::Data> {
+        let bytes = vfs.read(path)?;
+        progress.check_cancelled()?;
+        progress.set_fraction(0.3);
+        let name = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Environment::decode(name, &bytes)
+    }
+
+    fn finish(data: Self::Data, _assets: &mut AssetManager) -> Self {
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uniform_sky_gives_uniform_irradiance() {
+        let (width, height) = (64, 32);
+        let environment = Environment::new("sky", width, height, vec![Vec3::splat(2.0); 64 * 32]);
+        for normal in [
+            Vec3::X,
+            Vec3::Y,
+            Vec3::NEG_Y,
+            Vec3::new(1.0, 1.0, 1.0).normalize(),
+        ] {
+            let irradiance = environment.irradiance_at(normal);
+            assert!(
+                irradiance.abs_diff_eq(Vec3::splat(2.0), 0.02),
+                "{irradiance}"
+            );
+        }
+    }
+
+    #[test]
+    fn bright_top_half_lights_upward_normals() {
+        let (width, height) = (64, 32);
+        let pixels = (0..height)
+            .flat_map(|y| {
+                let value = if y < height / 2 { 1.0 } else { 0.0 };
+                std::iter::repeat_n(Vec3::splat(value), width)
+            })
+            .collect();
+        let environment = Environment::new("sky", width as u32, height as u32, pixels);
+        // A surface facing the bright hemisphere sees all of it; one facing
+        // away sees none, up to ringing of the band-limited projection.
+        assert!((environment.irradiance_at(Vec3::Y).x - 1.0).abs() < 0.1);
+        assert!(environment.irradiance_at(Vec3::NEG_Y).x.abs() < 0.1);
+        assert!((environment.irradiance_at(Vec3::X).x - 0.5).abs() < 0.05);
+    }
+
+    #[test]
+    fn equirect_center_looks_forward() {
+        let direction = equirect_direction(31.5, 15.5, 64, 32);
+        assert!(direction.abs_diff_eq(Vec3::NEG_Z, 1e-5));
+    }
+
+    #[test]
+    fn decodes_openexr_files() {
+        let pixels = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0];
+        let image = image::Rgb32FImage::from_raw(2, 1, pixels.to_vec()).unwrap();
+        let mut bytes = std::io::Cursor::new(Vec::new());
+        image
+            .write_to(&mut bytes, image::ImageFormat::OpenExr)
+            .unwrap();
+
+        let environment = Environment::decode("sky", bytes.get_ref()).unwrap();
+        assert_eq!((environment.width, environment.height), (2, 1));
+        assert_eq!(
+            environment.pixels,
+            [Vec3::new(0.25, 0.5, 1.0), Vec3::new(2.0, 4.0, 8.0)]
+        );
+        assert!(Environment::decode("sky", b"not an image").is_err());
+    }
+}
@@ -0,0 +1,20 @@
+pub mod gltf;
+pub mod obj;
+
+use anyhow::Context;
+use image::ImageEncoder;
+
+use crate::core::asset_manager::texture::Texture;
+
+fn encode_png(texture: &Texture) -> anyhow::Result<Vec<u8>> {
+    let mut png = Vec::new();
+    image::codecs::png::PngEncoder::new(&mut png)
+        .write_image(
+            &texture.pixels,
+            texture.width,
+            texture.height,
+            image::ExtendedColorType::Rgba8,
+        )
+        .with_context(|| format!("failed to encode texture \"{}\"", texture.name))?;
+    Ok(png)
+}
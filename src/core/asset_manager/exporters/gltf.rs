@@ -0,0 +1,990 @@
+//! Writes a scene as glTF 2.0: `.gltf` with a `.bin` buffer and PNG images
+//! next to it (or embedded as data URIs), or a single `.glb`.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::Path;
+
+use ::gltf::json::{
+    self, Index,
+    accessor::{ComponentType, GenericComponentType, Type},
+    animation::Property,
+    buffer::Target,
+    extensions::scene::khr_lights_punctual,
+    validation::{Checked::Valid, USize64},
+};
+use anyhow::Context;
+use base64::Engine;
+use glam::{Mat4, Quat, Vec3};
+
+use super::encode_png;
+use crate::core::animation::{ChannelTarget, Interpolation};
+use crate::core::asset_manager::{
+    AssetId, AssetManager, Handle,
+    material::{AlphaMode, Material},
+    mesh::Mesh,
+    texture::Texture,
+};
+use crate::core::camera::{Camera, Projection};
+use crate::core::scene::{NodeId, Scene};
+use crate::core::transform::Transform;
+
+const LIGHTS_EXTENSION: &str = "KHR_lights_punctual";
+
+/// A camera written as its own root node.
+pub struct ExportCamera {
+    pub name: String,
+    pub camera: Camera,
+    /// Width over height of the view the camera was used with.
+    pub aspect: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LightKind {
+    Directional,
+    Point,
+    /// Cone angles in radians from the light direction.
+    Spot {
+        inner_cone_angle: f32,
+        outer_cone_angle: f32,
+    },
+}
+
+/// A `KHR_lights_punctual` light written as its own root node.
+pub struct ExportLight {
+    pub name: String,
+    pub kind: LightKind,
+    pub color: Vec3,
+    /// Lux for directional lights, candela otherwise.
+    pub intensity: f32,
+    /// Distance where point and spot lights reach zero; `None` is infinite.
+    pub range: Option<f32>,
+    pub position: Vec3,
+    pub direction: Vec3,
+}
+
+#[derive(Default)]
+pub struct GltfExportOptions {
+    /// For `.gltf`, store images as data URIs instead of PNG files next to
+    /// it. `.glb` always embeds them in its binary chunk.
+    pub embed_textures: bool,
+    pub cameras: Vec<ExportCamera>,
+    pub lights: Vec<ExportLight>,
+}
+
+/// Writes every node of `scene` with its mesh, material, textures and skin,
+/// the clips of every animator, and the cameras and lights in `options`.
+/// The format follows the extension: `.glb` is binary, anything else is
+/// JSON with external files named after it. Point clouds are left out.
+pub fn export(
+    path: &Path,
+    scene: &Scene,
+    assets: &AssetManager,
+    options: &GltfExportOptions,
+) -> anyhow::Result<()> {
+    let binary = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("glb"));
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "scene".to_owned());
+    let images = if binary {
+        ImagePlacement::Buffer
+    } else if options.embed_textures {
+        ImagePlacement::DataUri
+    } else {
+        ImagePlacement::File
+    };
+
+    let mut writer = Writer::new(assets, images, &stem);
+    writer.write_scene(scene, options)?;
+    let Writer {
+        mut root,
+        mut bin,
+        image_files,
+        ..
+    } = writer;
+
+    let directory = path.parent().unwrap_or(Path::new(""));
+    let bin_name = format!("{stem}.bin");
+    if !bin.is_empty() {
+        pad(&mut bin);
+        root.push(json::Buffer {
+            byte_length: USize64::from(bin.len()),
+            name: None,
+            uri: (!binary).then(|| uri_escape(&bin_name)),
+            extensions: None,
+            extras: Default::default(),
+        });
+    }
+    let json = json::serialize::to_vec_pretty(&root).context("failed to serialize glTF")?;
+
+    if binary {
+        let glb = ::gltf::binary::Glb {
+            header: ::gltf::binary::Header {
+                magic: *b"glTF",
+                version: 2,
+                length: 0,
+            },
+            json: Cow::Owned(json),
+            bin: (!bin.is_empty()).then_some(Cow::Owned(bin)),
+        };
+        let bytes = glb.to_vec().context("failed to encode GLB")?;
+        return write(path, &bytes);
+    }
+    write(path, &json)?;
+    if !bin.is_empty() {
+        write(&directory.join(&bin_name), &bin)?;
+    }
+    for (name, png) in image_files {
+        write(&directory.join(name), &png)?;
+    }
+    Ok(())
+}
+
+fn write(path: &Path, bytes: &[u8]) -> anyhow::Result<()> {
+    std::fs::write(path, bytes).with_context(|| format!("failed to write {}", path.display()))
+}
+
+enum ImagePlacement {
+    Buffer,
+    DataUri,
+    File,
+}
+
+struct Writer<'a> {
+    assets: &'a AssetManager,
+    root: json::Root,
+    bin: Vec<u8>,
+    images: ImagePlacement,
+    stem: &'a str,
+    image_files: Vec<(String, Vec<u8>)>,
+    nodes: HashMap<NodeId, Index<json::Node>>,
+    /// Our meshes carry no material, so a glTF mesh is a mesh and material
+    /// pair.
+    meshes: HashMap<(AssetId, Option<AssetId>), Index<json::Mesh>>,
+    materials: HashMap<AssetId, Index<json::Material>>,
+    textures: HashMap<AssetId, Index<json::Texture>>,
+    sampler: Option<Index<json::texture::Sampler>>,
+}
+
+impl<'a> Writer<'a> {
+    fn new(assets: &'a AssetManager, images: ImagePlacement, stem: &'a str) -> Self {
+        let mut root = json::Root::default();
+        root.asset.generator = Some(format!(
+            "{} {}",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION")
+        ));
+        Self {
+            assets,
+            root,
+            bin: Vec::new(),
+            images,
+            stem,
+            image_files: Vec::new(),
+            nodes: HashMap::new(),
+            meshes: HashMap::new(),
+            materials: HashMap::new(),
+            textures: HashMap::new(),
+            sampler: None,
+        }
+    }
+
+    fn write_scene(&mut self, scene: &Scene, options: &GltfExportOptions) -> anyhow::Result<()> {
+        let mut roots = Vec::new();
+        for &id in scene.roots() {
+            roots.push(self.write_node(scene, id)?);
+        }
+        self.write_skins(scene);
+        self.write_animations(scene);
+        for camera in &options.cameras {
+            roots.push(self.write_camera(camera));
+        }
+        for light in &options.lights {
+            roots.push(self.write_light(light));
+        }
+        if !options.lights.is_empty() {
+            self.root.extensions_used.push(LIGHTS_EXTENSION.to_owned());
+        }
+        let scene = self.root.push(json::Scene {
+            extensions: None,
+            extras: Default::default(),
+            name: None,
+            nodes: roots,
+        });
+        self.root.scene = Some(scene);
+        Ok(())
+    }
+
+    /// Writes the node and its subtree, parents before children.
+    fn write_node(&mut self, scene: &Scene, id: NodeId) -> anyhow::Result<Index<json::Node>> {
+        let node = scene.node(id).unwrap();
+        let mut gltf_node = transform_node(&node.name, &node.transform);
+        if let Some(mesh) = &node.mesh {
+            gltf_node.mesh = self.mesh(mesh, node.material.as_ref())?;
+        }
+        if gltf_node.mesh.is_some() && !node.morph_weights.is_empty() {
+            gltf_node.weights = Some(node.morph_weights.clone());
+        }
+        if node.point_cloud.is_some() {
+            log::warn!("node \"{}\": point clouds are not exported", node.name);
+        }
+        let index = self.root.push(gltf_node);
+        self.nodes.insert(id, index);
+        let children = node
+            .children()
+            .iter()
+            .map(|&child| self.write_node(scene, child))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        if !children.is_empty() {
+            self.root.nodes[index.value()].children = Some(children);
+        }
+        Ok(index)
+    }
+
+    fn mesh(
+        &mut self,
+        handle: &Handle<Mesh>,
+        material: Option<&Handle<Material>>,
+    ) -> anyhow::Result<Option<Index<json::Mesh>>> {
+        let key = (handle.id(), material.map(Handle::id));
+        if let Some(&index) = self.meshes.get(&key) {
+            return Ok(Some(index));
+        }
+        let assets = self.assets;
+        let Some(mesh) = assets.get(handle).filter(|mesh| !mesh.positions.is_empty()) else {
+            return Ok(None);
+        };
+        let material = match material.and_then(|handle| Some((handle, assets.get(handle)?))) {
+            Some((handle, material)) => Some(self.material(handle.id(), material)?),
+            None => None,
+        };
+
+        let positions = self.floats(
+            cast(&mesh.positions),
+            Type::Vec3,
+            true,
+            Some(Target::ArrayBuffer),
+        );
+        let mut attributes = std::collections::BTreeMap::new();
+        attributes.insert(Valid(json::mesh::Semantic::Positions), positions);
+        let vertex_count = mesh.positions.len();
+        let streams: [(json::mesh::Semantic, &[f32], Type); 5] = [
+            (
+                json::mesh::Semantic::Normals,
+                cast(&mesh.normals),
+                Type::Vec3,
+            ),
+            (
+                json::mesh::Semantic::TexCoords(0),
+                cast(&mesh.tex_coords),
+                Type::Vec2,
+            ),
+            (
+                json::mesh::Semantic::Tangents,
+                cast(&mesh.tangents),
+                Type::Vec4,
+            ),
+            (
+                json::mesh::Semantic::Colors(0),
+                cast(&mesh.colors),
+                Type::Vec4,
+            ),
+            (
+                json::mesh::Semantic::Weights(0),
+                cast(&mesh.weights),
+                Type::Vec4,
+            ),
+        ];
+        for (semantic, values, type_) in streams {
+            let skinning = semantic == json::mesh::Semantic::Weights(0);
+            if values.len() != vertex_count * components(type_) || (skinning && !mesh.is_skinned())
+            {
+                continue;
+            }
+            let accessor = self.floats(values, type_, false, Some(Target::ArrayBuffer));
+            attributes.insert(Valid(semantic), accessor);
+        }
+        if mesh.is_skinned() && mesh.joints.len() == vertex_count {
+            let view = self.view(cast(&mesh.joints), Some(Target::ArrayBuffer));
+            let joints = self.accessor(view, vertex_count, ComponentType::U16, Type::Vec4, None);
+            attributes.insert(Valid(json::mesh::Semantic::Joints(0)), joints);
+        }
+
+        let indices = (!mesh.indices.is_empty()).then(|| {
+            let view = self.view(cast(&mesh.indices), Some(Target::ElementArrayBuffer));
+            self.accessor(
+                view,
+                mesh.indices.len(),
+                ComponentType::U32,
+                Type::Scalar,
+                None,
+            )
+        });
+
+        let targets: Vec<_> = mesh
+            .morph_targets
+            .iter()
+            .map(|target| {
+                let mut stream = |values: &[Vec3], bounds: bool| {
+                    (values.len() == vertex_count).then(|| {
+                        self.floats(cast(values), Type::Vec3, bounds, Some(Target::ArrayBuffer))
+                    })
+                };
+                json::mesh::MorphTarget {
+                    positions: stream(&target.positions, true),
+                    normals: stream(&target.normals, false),
+                    tangents: stream(&target.tangents, false),
+                }
+            })
+            .collect();
+        let target_names: Vec<_> = mesh
+            .morph_targets
+            .iter()
+            .map(|target| target.name.as_str())
+            .collect();
+
+        let index = self.root.push(json::Mesh {
+            extensions: None,
+            extras: (!targets.is_empty())
+                .then(|| target_names_extras(&target_names))
+                .transpose()?,
+            name: name(&mesh.name),
+            primitives: vec![json::mesh::Primitive {
+                attributes,
+                extensions: None,
+                extras: Default::default(),
+                indices,
+                material,
+                mode: Valid(json::mesh::Mode::Triangles),
+                targets: (!targets.is_empty()).then_some(targets),
+            }],
+            weights: (!mesh.morph_weights.is_empty()).then(|| mesh.morph_weights.clone()),
+        });
+        self.meshes.insert(key, index);
+        Ok(Some(index))
+    }
+
+    fn material(
+        &mut self,
+        id: AssetId,
+        material: &Material,
+    ) -> anyhow::Result<Index<json::Material>> {
+        if let Some(&index) = self.materials.get(&id) {
+            return Ok(index);
+        }
+        let textures = &material.textures;
+        let mut info = |texture: &Option<Handle<Texture>>| -> anyhow::Result<_> {
+            let Some(texture) = texture else {
+                return Ok(None);
+            };
+            Ok(self.texture(texture)?.map(|index| json::texture::Info {
+                index,
+                tex_coord: 0,
+                extensions: None,
+                extras: Default::default(),
+            }))
+        };
+        let base_color_texture = info(&textures.base_color)?;
+        let metallic_roughness_texture = info(&textures.metallic_roughness)?;
+        let emissive_texture = info(&textures.emissive)?;
+        let normal_texture = info(&textures.normal)?.map(|info| json::material::NormalTexture {
+            index: info.index,
+            scale: 1.0,
+            tex_coord: 0,
+            extensions: None,
+            extras: Default::default(),
+        });
+        let occlusion_texture =
+            info(&textures.occlusion)?.map(|info| json::material::OcclusionTexture {
+                index: info.index,
+                strength: json::material::StrengthFactor(1.0),
+                tex_coord: 0,
+                extensions: None,
+                extras: Default::default(),
+            });
+
+        let index = self.root.push(json::Material {
+            alpha_cutoff: (material.alpha_mode == AlphaMode::Mask)
+                .then_some(json::material::AlphaCutoff(material.alpha_cutoff)),
+            alpha_mode: Valid(match material.alpha_mode {
+                AlphaMode::Opaque => json::material::AlphaMode::Opaque,
+                AlphaMode::Mask => json::material::AlphaMode::Mask,
+                AlphaMode::Blend => json::material::AlphaMode::Blend,
+            }),
+            double_sided: material.double_sided,
+            name: name(&material.name),
+            pbr_metallic_roughness: json::material::PbrMetallicRoughness {
+                base_color_factor: json::material::PbrBaseColorFactor(
+                    material.base_color.to_array(),
+                ),
+                base_color_texture,
+                metallic_factor: json::material::StrengthFactor(material.metallic),
+                roughness_factor: json::material::StrengthFactor(material.roughness),
+                metallic_roughness_texture,
+                extensions: None,
+                extras: Default::default(),
+            },
+            normal_texture,
+            occlusion_texture,
+            emissive_texture,
+            emissive_factor: json::material::EmissiveFactor(material.emissive.to_array()),
+            extensions: None,
+            extras: Default::default(),
+        });
+        self.materials.insert(id, index);
+        Ok(index)
+    }
+
+    fn texture(
+        &mut self,
+        handle: &Handle<Texture>,
+    ) -> anyhow::Result<Option<Index<json::Texture>>> {
+        if let Some(&index) = self.textures.get(&handle.id()) {
+            return Ok(Some(index));
+        }
+        let Some(texture) = self.assets.get(handle) else {
+            return Ok(None);
+        };
+        let png = encode_png(texture)?;
+
+        let mime_type = Some(json::image::MimeType("image/png".to_owned()));
+        let image = match self.images {
+            ImagePlacement::Buffer => {
+                let view = self.view(&png, None);
+                json::Image {
+                    buffer_view: Some(view),
+                    mime_type,
+                    name: name(&texture.name),
+                    uri: None,
+                    extensions: None,
+                    extras: Default::default(),
+                }
+            }
+            ImagePlacement::DataUri => json::Image {
+                buffer_view: None,
+                mime_type,
+                name: name(&texture.name),
+                uri: Some(format!(
+                    "data:image/png;base64,{}",
+                    base64::engine::general_purpose::STANDARD.encode(&png)
+                )),
+                extensions: None,
+                extras: Default::default(),
+            },
+            ImagePlacement::File => {
+                let file = format!("{}_{}.png", self.stem, self.root.images.len());
+                let uri = uri_escape(&file);
+                self.image_files.push((file, png));
+                json::Image {
+                    buffer_view: None,
+                    mime_type,
+                    name: name(&texture.name),
+                    uri: Some(uri),
+                    extensions: None,
+                    extras: Default::default(),
+                }
+            }
+        };
+        let source = self.root.push(image);
+        let sampler = *self.sampler.get_or_insert_with(|| {
+            self.root.push(json::texture::Sampler {
+                mag_filter: Some(Valid(json::texture::MagFilter::Linear)),
+                min_filter: Some(Valid(json::texture::MinFilter::LinearMipmapLinear)),
+                ..Default::default()
+            })
+        });
+        let index = self.root.push(json::Texture {
+            name: None,
+            sampler: Some(sampler),
+            source,
+            extensions: None,
+            extras: Default::default(),
+        });
+        self.textures.insert(handle.id(), index);
+        Ok(Some(index))
+    }
+
+    /// One glTF skin per skinned node, with joints among the written nodes.
+    fn write_skins(&mut self, scene: &Scene) {
+        for (&id, &index) in &self.nodes.clone() {
+            let node = scene.node(id).unwrap();
+            let (Some(skin), Some(mesh)) = (&node.skin, &node.mesh) else {
+                continue;
+            };
+            if !self.assets.get(mesh).is_some_and(Mesh::is_skinned) {
+                continue;
+            }
+            let Some(joints) = skin
+                .joints
+                .iter()
+                .map(|joint| self.nodes.get(joint).copied())
+                .collect::<Option<Vec<_>>>()
+            else {
+                log::warn!(
+                    "node \"{}\": skin joints were removed, skipping the skin",
+                    node.name
+                );
+                continue;
+            };
+            let matrices: Vec<Mat4> = (0..joints.len())
+                .map(|joint| skin.inverse_bind_matrix(joint))
+                .collect();
+            let view = self.view(cast(&matrices), None);
+            let inverse_bind_matrices =
+                self.accessor(view, matrices.len(), ComponentType::F32, Type::Mat4, None);
+            let skin = self.root.push(json::Skin {
+                extensions: None,
+                extras: Default::default(),
+                inverse_bind_matrices: Some(inverse_bind_matrices),
+                joints,
+                name: name(&skin.name),
+                skeleton: None,
+            });
+            self.root.nodes[index.value()].skin = Some(skin);
+        }
+    }
+
+    /// Every clip of every animator, keeping channels whose node was
+    /// written.
+    fn write_animations(&mut self, scene: &Scene) {
+        for animator in scene.animators() {
+            for clip in animator.clips() {
+                let mut channels = Vec::new();
+                let mut samplers = Vec::new();
+                for channel in &clip.channels {
+                    let Some(&node) = animator
+                        .targets()
+                        .get(channel.node)
+                        .and_then(|id| self.nodes.get(id))
+                    else {
+                        continue;
+                    };
+                    if channel.times.is_empty() {
+                        continue;
+                    }
+                    let input = self.floats(&channel.times, Type::Scalar, true, None);
+                    let type_ = match channel.target {
+                        ChannelTarget::Translation | ChannelTarget::Scale => Type::Vec3,
+                        ChannelTarget::Rotation => Type::Vec4,
+                        ChannelTarget::Weights => Type::Scalar,
+                    };
+                    let output = self.floats(&channel.values, type_, false, None);
+                    let sampler = Index::push(
+                        &mut samplers,
+                        json::animation::Sampler {
+                            extensions: None,
+                            extras: Default::default(),
+                            input,
+                            interpolation: Valid(match channel.interpolation {
+                                Interpolation::Step => json::animation::Interpolation::Step,
+                                Interpolation::Linear => json::animation::Interpolation::Linear,
+                                Interpolation::CubicSpline => {
+                                    json::animation::Interpolation::CubicSpline
+                                }
+                            }),
+                            output,
+                        },
+                    );
+                    channels.push(json::animation::Channel {
+                        sampler,
+                        target: json::animation::Target {
+                            extensions: None,
+                            extras: Default::default(),
+                            node,
+                            path: Valid(match channel.target {
+                                ChannelTarget::Translation => Property::Translation,
+                                ChannelTarget::Rotation => Property::Rotation,
+                                ChannelTarget::Scale => Property::Scale,
+                                ChannelTarget::Weights => Property::MorphTargetWeights,
+                            }),
+                        },
+                        extensions: None,
+                        extras: Default::default(),
+                    });
+                }
+                if channels.is_empty() {
+                    continue;
+                }
+                self.root.push(json::Animation {
+                    extensions: None,
+                    extras: Default::default(),
+                    channels,
+                    name: name(&clip.name),
+                    samplers,
+                });
+            }
+        }
+    }
+
+    fn write_camera(&mut self, export: &ExportCamera) -> Index<json::Node> {
+        let camera = &export.camera;
+        let (type_, perspective, orthographic) = match camera.projection {
+            Projection::Perspective { fov_y } => (
+                json::camera::Type::Perspective,
+                Some(json::camera::Perspective {
+                    aspect_ratio: Some(export.aspect),
+                    yfov: fov_y,
+                    zfar: Some(camera.far),
+                    znear: camera.near,
+                    extensions: None,
+                    extras: Default::default(),
+                }),
+                None,
+            ),
+            Projection::Orthographic { height } => (
+                json::camera::Type::Orthographic,
+                None,
+                Some(json::camera::Orthographic {
+                    xmag: height * 0.5 * export.aspect,
+                    ymag: height * 0.5,
+                    zfar: camera.far,
+                    znear: camera.near,
+                    extensions: None,
+                    extras: Default::default(),
+                }),
+            ),
+        };
+        let index = self.root.push(json::Camera {
+            name: name(&export.name),
+            orthographic,
+            perspective,
+            type_: Valid(type_),
+            extensions: None,
+            extras: Default::default(),
+        });
+        // glTF cameras look down their local -Z, like our view matrix.
+        let transform = Transform::from_matrix(camera.view().inverse());
+        let mut node = transform_node(&export.name, &transform);
+        node.camera = Some(index);
+        self.root.push(node)
+    }
+
+    fn write_light(&mut self, export: &ExportLight) -> Index<json::Node> {
+        let (type_, spot) = match export.kind {
+            LightKind::Directional => (khr_lights_punctual::Type::Directional, None),
+            LightKind::Point => (khr_lights_punctual::Type::Point, None),
+            LightKind::Spot {
+                inner_cone_angle,
+                outer_cone_angle,
+            } => (
+                khr_lights_punctual::Type::Spot,
+                Some(khr_lights_punctual::Spot {
+                    inner_cone_angle,
+                    outer_cone_angle,
+                }),
+            ),
+        };
+        let light = self.root.push(khr_lights_punctual::Light {
+            color: export.color.to_array(),
+            extensions: None,
+            extras: Default::default(),
+            intensity: export.intensity,
+            name: name(&export.name),
+            range: match export.kind {
+                LightKind::Directional => None,
+                _ => export.range,
+            },
+            spot,
+            type_: Valid(type_),
+        });
+        // Lights shine down their local -Z.
+        let transform = Transform {
+            translation: export.position,
+            rotation: Quat::from_rotation_arc(
+                Vec3::NEG_Z,
+                export.direction.normalize_or(Vec3::NEG_Z),
+            ),
+            scale: Vec3::ONE,
+        };
+        let mut node = transform_node(&export.name, &transform);
+        node.extensions = Some(json::extensions::scene::Node {
+            khr_lights_punctual: Some(khr_lights_punctual::KhrLightsPunctual { light }),
+            ..Default::default()
+        });
+        self.root.push(node)
+    }
+
+    /// Appends `bytes` to the buffer, four-byte aligned as every accessor
+    /// component type requires.
+    fn view(&mut self, bytes: &[u8], target: Option<Target>) -> Index<json::buffer::View> {
+        pad(&mut self.bin);
+        let offset = self.bin.len();
+        self.bin.extend_from_slice(bytes);
+        self.root.push(json::buffer::View {
+            buffer: Index::new(0),
+            byte_length: USize64::from(bytes.len()),
+            byte_offset: Some(USize64::from(offset)),
+            byte_stride: None,
+            name: None,
+            target: target.map(Valid),
+            extensions: None,
+            extras: Default::default(),
+        })
+    }
+
+    fn accessor(
+        &mut self,
+        view: Index<json::buffer::View>,
+        count: usize,
+        component_type: ComponentType,
+        type_: Type,
+        bounds: Option<(Vec<f32>, Vec<f32>)>,
+    ) -> Index<json::Accessor> {
+        let (min, max) = match bounds {
+            Some((min, max)) => (Some(min.into()), Some(max.into())),
+            None => (None, None),
+        };
+        self.root.push(json::Accessor {
+            buffer_view: Some(view),
+            byte_offset: None,
+            count: USize64::from(count),
+            component_type: Valid(GenericComponentType(component_type)),
+            extensions: None,
+            extras: Default::default(),
+            type_: Valid(type_),
+            min,
+            max,
+            name: None,
+            normalized: false,
+            sparse: None,
+        })
+    }
+
+    /// A float accessor over `values`, with per-component bounds where the
+    /// spec requires them (positions and animation inputs).
+    fn floats(
+        &mut self,
+        values: &[f32],
+        type_: Type,
+        with_bounds: bool,
+        target: Option<Target>,
+    ) -> Index<json::Accessor> {
+        let n = components(type_);
+        let bounds = with_bounds.then(|| {
+            let mut min = vec![f32::INFINITY; n];
+            let mut max = vec![f32::NEG_INFINITY; n];
+            for element in values.chunks_exact(n) {
+                for ((min, max), &value) in min.iter_mut().zip(&mut max).zip(element) {
+                    *min = min.min(value);
+                    *max = max.max(value);
+                }
+            }
+            (min, max)
+        });
+        let view = self.view(cast(values), target);
+        self.accessor(view, values.len() / n, ComponentType::F32, type_, bounds)
+    }
+}
+
+/// A node named `name` with the non-default parts of `transform`.
+fn transform_node(node_name: &str, transform: &Transform) -> json::Node {
+    json::Node {
+        name: name(node_name),
+        translation: (transform.translation != Vec3::ZERO)
+            .then(|| transform.translation.to_array()),
+        rotation: (transform.rotation != Quat::IDENTITY)
+            .then(|| json::scene::UnitQuaternion(transform.rotation.normalize().to_array())),
+        scale: (transform.scale != Vec3::ONE).then(|| transform.scale.to_array()),
+        ..Default::default()
+    }
+}
+
+/// Morph target names the way the importer (and most tools) read them.
+fn target_names_extras(names: &[&str]) -> anyhow::Result<Box<json::extras::RawValue>> {
+    let mut extras = json::serialize::to_string(&json::Value::from(names.to_vec()))?;
+    extras.insert_str(0, "{\"targetNames\":");
+    extras.push('}');
+    Ok(json::extras::RawValue::from_string(extras)?)
+}
+
+fn name(name: &str) -> Option<String> {
+    (!name.is_empty()).then(|| name.to_owned())
+}
+
+fn components(type_: Type) -> usize {
+    match type_ {
+        Type::Scalar => 1,
+        Type::Vec2 => 2,
+        Type::Vec3 => 3,
+        Type::Vec4 | Type::Mat2 => 4,
+        Type::Mat3 => 9,
+        Type::Mat4 => 16,
+    }
+}
+
+fn cast<T: bytemuck::Pod, U: bytemuck::Pod>(values: &[T]) -> &[U] {
+    bytemuck::cast_slice(values)
+}
+
+fn pad(bytes: &mut Vec<u8>) {
+    bytes.resize(bytes.len().next_multiple_of(4), 0);
+}
+
+/// Percent-escapes a file name for use as a relative URI.
+fn uri_escape(name: &str) -> String {
+    let mut uri = String::with_capacity(name.len());
+    for byte in name.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    uri
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use glam::{Vec2, Vec4};
+
+    use super::*;
+    use crate::core::animation::{AnimationClip, Channel, Skin};
+    use crate::core::asset_manager::{AssetState, Model, model::ModelNode, texture::ColorSpace};
+
+    /// A textured, skinned triangle under a parent node, with a clip moving
+    /// the parent.
+    fn build_scene(assets: &mut AssetManager) -> Scene {
+        let mut mesh = Mesh::new("triangle");
+        mesh.positions = vec![Vec3::ZERO, Vec3::X, Vec3::Y];
+        mesh.normals = vec![Vec3::Z; 3];
+        mesh.tex_coords = vec![Vec2::ZERO, Vec2::X, Vec2::Y];
+        mesh.joints = vec![[0, 0, 0, 0]; 3];
+        mesh.weights = vec![Vec4::X; 3];
+        mesh.indices = vec![0, 1, 2];
+        let mesh = assets.add(mesh);
+        let texture = assets.add(Texture::from_rgba8(
+            "checker",
+            2,
+            1,
+            vec![255, 0, 0, 255, 0, 0, 255, 255],
+            ColorSpace::Srgb,
+        ));
+        let mut material = Material {
+            name: "red".to_owned(),
+            base_color: Vec4::new(1.0, 0.0, 0.0, 1.0),
+            ..Default::default()
+        };
+        material.textures.base_color = Some(texture);
+        let material = assets.add(material);
+
+        let clip = AnimationClip::new(
+            "move",
+            vec![Channel {
+                node: 0,
+                target: ChannelTarget::Translation,
+                interpolation: Interpolation::Linear,
+                times: vec![0.0, 2.0],
+                values: vec![0.0, 0.0, 0.0, 0.0, 1.0, 0.0],
+            }],
+        );
+        let node = |name: &str, parent| ModelNode {
+            name: name.to_owned(),
+            parent,
+            transform: Transform::default(),
+            mesh: None,
+            material: None,
+            point_cloud: None,
+            skin: None,
+            morph_weights: Vec::new(),
+        };
+        let model = Model {
+            nodes: vec![
+                node("parent", None),
+                ModelNode {
+                    transform: Transform::from_translation(Vec3::new(1.0, 2.0, 3.0)),
+                    mesh: Some(0),
+                    material: Some(0),
+                    skin: Some(0),
+                    ..node("child", Some(0))
+                },
+            ],
+            meshes: vec![mesh],
+            materials: vec![material],
+            textures: Vec::new(),
+            point_clouds: Vec::new(),
+            skins: vec![Skin {
+                name: "skin".to_owned(),
+                joints: vec![0],
+                inverse_bind_matrices: Arc::from([Mat4::IDENTITY]),
+            }],
+            animations: Arc::from([clip]),
+        };
+        let mut scene = Scene::new();
+        scene.spawn_model(&model, None);
+        scene
+    }
+
+    fn options() -> GltfExportOptions {
+        GltfExportOptions {
+            cameras: vec![ExportCamera {
+                name: "view".to_owned(),
+                camera: Camera::new(),
+                aspect: 1.5,
+            }],
+            lights: vec![ExportLight {
+                name: "sun".to_owned(),
+                kind: LightKind::Directional,
+                color: Vec3::ONE,
+                intensity: 3.0,
+                range: None,
+                position: Vec3::ZERO,
+                direction: Vec3::new(0.0, -1.0, 0.0),
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn round_trips_through_the_importer() {
+        let dir = std::env::temp_dir().join(format!("gltf-export-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut assets = AssetManager::new();
+        let scene = build_scene(&mut assets);
+
+        for file in ["scene.glb", "scene.gltf", "embedded.gltf"] {
+            let path = dir.join(file);
+            let options = GltfExportOptions {
+                embed_textures: file == "embedded.gltf",
+                ..options()
+            };
+            export(&path, &scene, &assets, &options).unwrap();
+
+            let bytes = std::fs::read(&path).unwrap();
+            let gltf = ::gltf::Gltf::from_slice(&bytes).unwrap();
+            assert_eq!(gltf.cameras().count(), 1);
+            let lights: Vec<_> = gltf.lights().unwrap().collect();
+            assert_eq!(lights.len(), 1);
+            assert_eq!(lights[0].intensity(), 3.0);
+
+            let handle: Handle<Model> = assets.load_blocking(&path);
+            assert_eq!(assets.state(&handle), AssetState::Loaded, "{file}");
+            let model = assets.get(&handle).unwrap();
+            let names: Vec<_> = model.nodes.iter().map(|node| node.name.as_str()).collect();
+            assert_eq!(names, ["parent", "child", "view", "sun"]);
+            let child = &model.nodes[1];
+            assert_eq!(child.parent, Some(0));
+            assert_eq!(child.transform.translation, Vec3::new(1.0, 2.0, 3.0));
+            let mesh = assets.get(model.mesh(child).unwrap()).unwrap();
+            assert_eq!(mesh.vertex_count(), 3);
+            assert!(mesh.is_skinned());
+            let material = assets.get(model.material(child).unwrap()).unwrap();
+            assert_eq!(material.base_color, Vec4::new(1.0, 0.0, 0.0, 1.0));
+            let texture = assets
+                .get(material.textures.base_color.as_ref().unwrap())
+                .unwrap();
+            assert_eq!((texture.width, texture.height), (2, 1));
+            assert_eq!(texture.pixels[..4], [255, 0, 0, 255]);
+            assert_eq!(model.skin(child).unwrap().joints, [0]);
+            assert_eq!(model.animations.len(), 1);
+            assert_eq!(model.animations[0].duration(), 2.0);
+            assert_eq!(model.animations[0].channels[0].node, 0);
+        }
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,267 @@
+//! Writes a scene as Wavefront OBJ with a `.mtl` material library and PNG
+//! textures next to it. OBJ has no hierarchy, so every mesh is written in
+//! world space as its own object.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::Path;
+
+use anyhow::Context;
+use glam::Mat4;
+
+use super::encode_png;
+use crate::core::asset_manager::{
+    AssetId, AssetManager, Handle, material::Material, texture::Texture,
+};
+use crate::core::mesh_processing;
+use crate::core::scene::Scene;
+
+/// Writes every mesh of `scene` in its rest pose. Skins, morph targets,
+/// animations and point clouds have no OBJ counterpart and are left out.
+pub fn export(path: &Path, scene: &Scene, assets: &AssetManager) -> anyhow::Result<()> {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "scene".to_owned());
+    let directory = path.parent().unwrap_or(Path::new(""));
+    let mut library = Library::new(assets, &stem);
+
+    let mut obj = format!(
+        "# {} {}\n",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+    );
+    let mut body = String::new();
+    // OBJ indices are global and start at one.
+    let mut base = [1usize; 3];
+    let mut stack: Vec<_> = scene
+        .roots()
+        .iter()
+        .rev()
+        .map(|&id| (id, Mat4::IDENTITY))
+        .collect();
+    while let Some((id, parent_world)) = stack.pop() {
+        let node = scene.node(id).unwrap();
+        let world = parent_world * node.transform.to_matrix();
+        stack.extend(node.children().iter().rev().map(|&child| (child, world)));
+        if node.point_cloud.is_some() {
+            log::warn!("node \"{}\": point clouds are not exported", node.name);
+        }
+        let Some(mesh) = node.mesh.as_ref().and_then(|mesh| assets.get(mesh)) else {
+            continue;
+        };
+        if mesh.positions.is_empty() {
+            continue;
+        }
+        let mut mesh = mesh.clone();
+        mesh_processing::transform(&mut mesh, &world);
+        let material = match &node.material {
+            Some(material) => library.material(material)?,
+            None => None,
+        };
+
+        let count = mesh.positions.len();
+        let has_uv = mesh.tex_coords.len() == count;
+        let has_normals = mesh.normals.len() == count;
+        let has_colors = mesh.colors.len() == count;
+        writeln!(body, "o {}", object_name(&node.name))?;
+        for (i, p) in mesh.positions.iter().enumerate() {
+            if has_colors {
+                let c = mesh.colors[i];
+                writeln!(body, "v {} {} {} {} {} {}", p.x, p.y, p.z, c.x, c.y, c.z)?;
+            } else {
+                writeln!(body, "v {} {} {}", p.x, p.y, p.z)?;
+            }
+        }
+        if has_uv {
+            for uv in &mesh.tex_coords {
+                // OBJ texture space has its origin at the bottom left.
+                writeln!(body, "vt {} {}", uv.x, 1.0 - uv.y)?;
+            }
+        }
+        if has_normals {
+            for n in &mesh.normals {
+                writeln!(body, "vn {} {} {}", n.x, n.y, n.z)?;
+            }
+        }
+        if let Some(material) = material {
+            writeln!(body, "usemtl {material}")?;
+        }
+        for triangle in mesh.indices.chunks_exact(3) {
+            body.push('f');
+            for &i in triangle {
+                let i = i as usize;
+                let v = base[0] + i;
+                match (has_uv, has_normals) {
+                    (true, true) => write!(body, " {v}/{}/{}", base[1] + i, base[2] + i)?,
+                    (true, false) => write!(body, " {v}/{}", base[1] + i)?,
+                    (false, true) => write!(body, " {v}//{}", base[2] + i)?,
+                    (false, false) => write!(body, " {v}")?,
+                }
+            }
+            body.push('\n');
+        }
+        base[0] += count;
+        base[1] += if has_uv { count } else { 0 };
+        base[2] += if has_normals { count } else { 0 };
+    }
+
+    let mtl_name = format!("{stem}.mtl");
+    if !library.mtl.is_empty() {
+        writeln!(obj, "mtllib {mtl_name}")?;
+    }
+    obj.push_str(&body);
+    write(path, obj.as_bytes())?;
+    if !library.mtl.is_empty() {
+        write(&directory.join(&mtl_name), library.mtl.as_bytes())?;
+    }
+    for (name, png) in library.images {
+        write(&directory.join(name), &png)?;
+    }
+    Ok(())
+}
+
+fn write(path: &Path, bytes: &[u8]) -> anyhow::Result<()> {
+    std::fs::write(path, bytes).with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// The `.mtl` text and texture files, each material and texture written
+/// once.
+struct Library<'a> {
+    assets: &'a AssetManager,
+    stem: &'a str,
+    mtl: String,
+    materials: HashMap<AssetId, String>,
+    textures: HashMap<AssetId, String>,
+    images: Vec<(String, Vec<u8>)>,
+}
+
+impl<'a> Library<'a> {
+    fn new(assets: &'a AssetManager, stem: &'a str) -> Self {
+        Self {
+            assets,
+            stem,
+            mtl: String::new(),
+            materials: HashMap::new(),
+            textures: HashMap::new(),
+            images: Vec::new(),
+        }
+    }
+
+    /// The material's unique name in the library.
+    fn material(&mut self, handle: &Handle<Material>) -> anyhow::Result<Option<String>> {
+        if let Some(name) = self.materials.get(&handle.id()) {
+            return Ok(Some(name.clone()));
+        }
+        let Some(material) = self.assets.get(handle) else {
+            return Ok(None);
+        };
+        let mut name = object_name(&material.name);
+        if name.is_empty() {
+            name = format!("material{}", self.materials.len());
+        }
+        if self.materials.values().any(|taken| *taken == name) {
+            name = format!("{name}_{}", self.materials.len());
+        }
+
+        let color = material.base_color;
+        let emissive = material.emissive;
+        // Inverse of the importer's Phong exponent to roughness mapping.
+        let shininess = 2.0 / material.roughness.max(0.01).powi(2) - 2.0;
+        let mut entry = format!("newmtl {name}\n");
+        writeln!(entry, "Kd {} {} {}", color.x, color.y, color.z)?;
+        writeln!(entry, "Ke {} {} {}", emissive.x, emissive.y, emissive.z)?;
+        writeln!(entry, "Ns {shininess}")?;
+        writeln!(entry, "d {}", color.w)?;
+        writeln!(entry, "Pr {}", material.roughness)?;
+        writeln!(entry, "Pm {}", material.metallic)?;
+        let textures = &material.textures;
+        for (statement, texture) in [
+            ("map_Kd", &textures.base_color),
+            ("norm", &textures.normal),
+            ("map_Ke", &textures.emissive),
+        ] {
+            if let Some(file) = self.texture(texture)? {
+                writeln!(entry, "{statement} {file}")?;
+            }
+        }
+        entry.push('\n');
+        self.mtl.push_str(&entry);
+        self.materials.insert(handle.id(), name.clone());
+        Ok(Some(name))
+    }
+
+    fn texture(&mut self, handle: &Option<Handle<Texture>>) -> anyhow::Result<Option<String>> {
+        let Some(handle) = handle else {
+            return Ok(None);
+        };
+        if let Some(file) = self.textures.get(&handle.id()) {
+            return Ok(Some(file.clone()));
+        }
+        let Some(texture) = self.assets.get(handle) else {
+            return Ok(None);
+        };
+        let file = format!("{}_{}.png", self.stem, self.images.len());
+        self.images.push((file.clone(), encode_png(texture)?));
+        self.textures.insert(handle.id(), file.clone());
+        Ok(Some(file))
+    }
+}
+
+/// OBJ statements end at whitespace, so names cannot contain any.
+fn object_name(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join("_")
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Vec3, Vec4};
+
+    use super::*;
+    use crate::core::asset_manager::{AssetState, Model, mesh::Mesh};
+    use crate::core::scene::Node;
+    use crate::core::transform::Transform;
+
+    #[test]
+    fn round_trips_through_the_importer() {
+        let dir = std::env::temp_dir().join(format!("obj-export-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut assets = AssetManager::new();
+        let mut mesh = Mesh::new("triangle");
+        mesh.positions = vec![Vec3::ZERO, Vec3::X, Vec3::Y];
+        mesh.normals = vec![Vec3::Z; 3];
+        mesh.indices = vec![0, 1, 2];
+        let material = Material {
+            name: "green paint".to_owned(),
+            base_color: Vec4::new(0.0, 1.0, 0.0, 1.0),
+            roughness: 0.5,
+            ..Default::default()
+        };
+        let mut scene = Scene::new();
+        let parent = scene.add_node(Node::new("parent"), None);
+        scene.node_mut(parent).unwrap().transform = Transform::from_translation(Vec3::Z);
+        let mut child = Node::new("my triangle");
+        child.mesh = Some(assets.add(mesh));
+        child.material = Some(assets.add(material));
+        scene.add_node(child, Some(parent));
+
+        let path = dir.join("scene.obj");
+        export(&path, &scene, &assets).unwrap();
+        let handle: Handle<Model> = assets.load_blocking(&path);
+        assert_eq!(assets.state(&handle), AssetState::Loaded);
+        let model = assets.get(&handle).unwrap();
+        let node = &model.nodes[1];
+        assert_eq!(node.name, "my_triangle");
+        let mesh = assets.get(model.mesh(node).unwrap()).unwrap();
+        assert_eq!(
+            mesh.positions,
+            [Vec3::Z, Vec3::new(1.0, 0.0, 1.0), Vec3::new(0.0, 1.0, 1.0)]
+        );
+        assert_eq!(mesh.normals, [Vec3::Z; 3]);
+        let material = assets.get(model.material(node).unwrap()).unwrap();
+        assert_eq!(material.name, "green_paint");
+        assert_eq!(material.base_color, Vec4::new(0.0, 1.0, 0.0, 1.0));
+        assert!((material.roughness - 0.5).abs() < 1e-5);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,151 @@
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    sync::{
+        Arc, Weak,
+        atomic::{AtomicU64, Ordering},
+        mpsc::Sender,
+    },
+};
+
+static NEXT_ASSET_ID: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub struct AssetId(u64);
+
+impl AssetId {
+    pub(crate) fn next() -> Self {
+        Self(NEXT_ASSET_ID.fetch_add(1, Ordering::Relaxed))
+    }
+
+    pub fn raw(self) -> u64 {
+        self.0
+    }
+}
+
+impl fmt::Display for AssetId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// Shared between all strong handles of one asset. When the last strong
+/// handle goes away the id is sent back to the owning storage, which unloads
+/// the asset on its next `collect_unused` pass.
+pub(crate) struct HandleInner {
+    id: AssetId,
+    drop_tx: Sender<AssetId>,
+}
+
+impl Drop for HandleInner {
+    fn drop(&mut self) {
+        // The storage may already be gone during shutdown.
+        let _ = self.drop_tx.send(self.id);
+    }
+}
+
+pub struct Handle<T> {
+    inner: Arc<HandleInner>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Handle<T> {
+    pub(crate) fn new(id: AssetId, drop_tx: Sender<AssetId>) -> Self {
+        Self {
+            inner: Arc::new(HandleInner { id, drop_tx }),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn id(&self) -> AssetId {
+        self.inner.id
+    }
+
+    pub fn downgrade(&self) -> WeakHandle<T> {
+        WeakHandle {
+            id: self.inner.id,
+            inner: Arc::downgrade(&self.inner),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn strong_count(&self) -> usize {
+        Arc::strong_count(&self.inner)
+    }
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id() == other.id()
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> Hash for Handle<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id().hash(state);
+    }
+}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Handle<{}>({})", short_type_name::<T>(), self.id())
+    }
+}
+
+/// A non-owning reference to an asset. It does not keep the asset loaded and
+/// can be upgraded back to a `Handle` only while some strong handle exists.
+pub struct WeakHandle<T> {
+    id: AssetId,
+    inner: Weak<HandleInner>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> WeakHandle<T> {
+    pub fn id(&self) -> AssetId {
+        self.id
+    }
+
+    pub fn upgrade(&self) -> Option<Handle<T>> {
+        self.inner.upgrade().map(|inner| Handle {
+            inner,
+            _marker: PhantomData,
+        })
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.inner.strong_count() > 0
+    }
+}
+
+impl<T> Clone for WeakHandle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id,
+            inner: self.inner.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> fmt::Debug for WeakHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "WeakHandle<{}>({})", short_type_name::<T>(), self.id)
+    }
+}
+
+fn short_type_name<T>() -> &'static str {
+    let name = std::any::type_name::<T>();
+    name.rsplit("::").next().unwrap_or(name)
+}
@@ -0,0 +1,24 @@
+pub mod gltf;
+pub mod obj;
+pub mod ply;
+pub mod stl;
+
+use std::path::Path;
+
+use anyhow::bail;
+
+use crate::core::asset_manager::{loader::LoadProgress, model::ModelData, vfs::Vfs};
+
+pub fn import_model(path: &Path, vfs: &Vfs, progress: &LoadProgress) -> anyhow::Result<ModelData> {
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "gltf" | "glb" => gltf::import(path, vfs, progress),
+        "obj" => obj::import(path, vfs, progress),
+        "ply" => ply::import(path, vfs, progress),
+        "stl" => stl::import(path, vfs, progress),
+        _ => bail!("unsupported model format: {}", path.display()),
+    }
+}
@@ -0,0 +1,981 @@
+use std::path::Path;
+
+use anyhow::{Context, anyhow, bail};
+use base64::Engine;
+use glam::{Mat4, Quat, Vec2, Vec3, Vec4};
+
+use crate::core::animation::{AnimationClip, Channel, ChannelTarget, Interpolation, Skin};
+use crate::core::asset_manager::{
+    loader::LoadProgress,
+    material::{AlphaMode, Material, MaterialTextures},
+    mesh::{Mesh, MorphTarget},
+    model::{ModelData, ModelNode},
+    texture::{ColorSpace, Texture},
+    vfs::Vfs,
+};
+use crate::core::mesh_processing::LodGeneration;
+use crate::core::transform::Transform;
+
+pub fn import(path: &Path, vfs: &Vfs, progress: &LoadProgress) -> anyhow::Result<ModelData> {
+    let bytes = vfs.read(path)?;
+    import_slice(&bytes, path, vfs, progress)
+}
+
+/// Progress is reported as: buffers read up to 0.2, images decoded up to 0.6,
+/// primitives converted up to 1.0.
+pub fn import_slice(
+    bytes: &[u8],
+    path: &Path,
+    vfs: &Vfs,
+    progress: &LoadProgress,
+) -> anyhow::Result<ModelData> {
+    let gltf = ::gltf::Gltf::from_slice(bytes).context("failed to parse glTF")?;
+    let buffers = load_buffers(&gltf, path, vfs)?;
+    progress.check_cancelled()?;
+    progress.set_fraction(0.2);
+    let document = &gltf.document;
+
+    let mut data = ModelData {
+        textures: load_textures(document, &buffers, path, vfs, progress)?,
+        materials: document.materials().map(convert_material).collect(),
+        ..Default::default()
+    };
+
+    // Every primitive becomes its own mesh; this maps glTF mesh index to the
+    // (mesh, material) pairs created for it.
+    let mut primitives = Vec::new();
+    let mesh_count = document.meshes().len().max(1);
+    for mesh in document.meshes() {
+        progress.check_cancelled()?;
+        progress.set_fraction(0.6 + 0.4 * mesh.index() as f32 / mesh_count as f32);
+        let mut converted = Vec::new();
+        let target_names = target_names(&mesh);
+        for (i, primitive) in mesh.primitives().enumerate() {
+            let name = match mesh.name() {
+                Some(name) if mesh.primitives().len() > 1 => format!("{name}.{i}"),
+                Some(name) => name.to_owned(),
+                None => format!("mesh{}.{i}", mesh.index()),
+            };
+            match read_primitive(&primitive, &buffers, name) {
+                Ok(Some(mut m)) => {
+                    for (target, name) in m.morph_targets.iter_mut().zip(&target_names) {
+                        target.name.clone_from(name);
+                    }
+                    m.morph_weights = mesh.weights().map(<[f32]>::to_vec).unwrap_or_default();
+                    m.morph_weights.resize(m.morph_targets.len(), 0.0);
+                    converted.push((data.meshes.len(), primitive.material().index()));
+                    data.meshes.push(m);
+                }
+                Ok(None) => {}
+                Err(err) => bail!("mesh {}: {:#}", mesh.index(), err),
+            }
+        }
+        primitives.push(converted);
+    }
+    data.lods = read_lods(document, &primitives);
+
+    let scene = document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+        .ok_or_else(|| anyhow!("glTF contains no scene"))?;
+    // glTF node index to model node index, for nodes in the scene.
+    let mut node_map = vec![None; document.nodes().len()];
+    for node in scene.nodes() {
+        add_node(
+            document,
+            &node,
+            None,
+            &primitives,
+            &buffers,
+            &mut data.nodes,
+            &mut node_map,
+        )?;
+    }
+    data.skins = document
+        .skins()
+        .map(|skin| convert_skin(&skin, &buffers, &node_map))
+        .collect::<anyhow::Result<_>>()?;
+    data.animations = document
+        .animations()
+        .map(|animation| convert_animation(&animation, &buffers, &node_map))
+        .collect::<anyhow::Result<_>>()?;
+
+    let buffer_uris = document.buffers().filter_map(|b| match b.source() {
+        ::gltf::buffer::Source::Uri(uri) => Some(uri),
+        ::gltf::buffer::Source::Bin => None,
+    });
+    let image_uris = document.images().filter_map(|i| match i.source() {
+        ::gltf::image::Source::Uri { uri, .. } => Some(uri),
+        ::gltf::image::Source::View { .. } => None,
+    });
+    data.dependencies = buffer_uris
+        .chain(image_uris)
+        .filter(|uri| !uri.starts_with("data:"))
+        .map(|uri| Vfs::resolve(path, percent_decode(uri)))
+        .collect();
+
+    Ok(data)
+}
+
+fn add_node(
+    document: &::gltf::Document,
+    node: &::gltf::Node,
+    parent: Option<usize>,
+    primitives: &[Vec<(usize, Option<usize>)>],
+    buffers: &[Vec<u8>],
+    nodes: &mut Vec<ModelNode>,
+    node_map: &mut [Option<usize>],
+) -> anyhow::Result<()> {
+    let (t, r, s) = node.transform().decomposed();
+    let index = nodes.len();
+    node_map[node.index()] = Some(index);
+    nodes.push(ModelNode {
+        name: node
+            .name()
+            .map(str::to_owned)
+            .unwrap_or_else(|| format!("node{}", node.index())),
+        parent,
+        transform: Transform {
+            translation: Vec3::from(t),
+            rotation: Quat::from_array(r),
+            scale: Vec3::from(s),
+        },
+        mesh: None,
+        material: None,
+        point_cloud: None,
+        skin: node.skin().map(|skin| skin.index()),
+        morph_weights: node.weights().map(<[f32]>::to_vec).unwrap_or_default(),
+    });
+
+    if let Some(mesh) = node.mesh() {
+        let parts = &primitives[mesh.index()];
+        let instances = read_instances(document, node, buffers)
+            .with_context(|| format!("node {}", node.index()))?;
+        match instances {
+            // Instances become children sharing the mesh; the renderer
+            // batches them back into instanced draws.
+            Some(instances) => {
+                for (i, transform) in instances.into_iter().enumerate() {
+                    let instance = nodes.len();
+                    nodes.push(ModelNode {
+                        name: format!("{}#{i}", nodes[index].name),
+                        parent: Some(index),
+                        transform,
+                        mesh: None,
+                        material: None,
+                        point_cloud: None,
+                        skin: nodes[index].skin,
+                        morph_weights: nodes[index].morph_weights.clone(),
+                    });
+                    attach_mesh(instance, parts, nodes);
+                }
+            }
+            None => attach_mesh(index, parts, nodes),
+        }
+    }
+
+    for child in node.children() {
+        add_node(
+            document,
+            &child,
+            Some(index),
+            primitives,
+            buffers,
+            nodes,
+            node_map,
+        )?;
+    }
+    Ok(())
+}
+
+/// Gives the node a single-primitive mesh directly, or one child per
+/// primitive otherwise.
+fn attach_mesh(index: usize, parts: &[(usize, Option<usize>)], nodes: &mut Vec<ModelNode>) {
+    match parts {
+        [(mesh, material)] => {
+            nodes[index].mesh = Some(*mesh);
+            nodes[index].material = *material;
+        }
+        parts => {
+            for (i, (mesh, material)) in parts.iter().enumerate() {
+                nodes.push(ModelNode {
+                    name: format!("{}.{i}", nodes[index].name),
+                    parent: Some(index),
+                    transform: Transform::IDENTITY,
+                    mesh: Some(*mesh),
+                    material: *material,
+                    point_cloud: None,
+                    skin: nodes[index].skin,
+                    morph_weights: nodes[index].morph_weights.clone(),
+                });
+            }
+        }
+    }
+}
+
+/// Level of detail chains from `MSFT_lod`. The extension lists coarser
+/// nodes per node; here the chain is attached to the node's meshes,
+/// primitive by primitive, so the first node using a mesh decides its
+/// levels. `MSFT_screencoverage` in the node extras gives the coverage at
+/// which each level starts; its last entry, where the node disappears, is
+/// not used.
+fn read_lods(
+    document: &::gltf::Document,
+    primitives: &[Vec<(usize, Option<usize>)>],
+) -> Vec<(usize, Vec<(usize, f32)>)> {
+    let parts_of = |node: &::gltf::Node| node.mesh().map(|mesh| &primitives[mesh.index()]);
+    let mut lods: Vec<(usize, Vec<(usize, f32)>)> = Vec::new();
+    for node in document.nodes() {
+        let Some(ids) = node
+            .extension_value("MSFT_lod")
+            .and_then(|lod| lod.get("ids"))
+            .and_then(|ids| ids.as_array())
+        else {
+            continue;
+        };
+        let Some(parts) = parts_of(&node) else {
+            continue;
+        };
+        let coverages: Vec<f32> = node
+            .extras()
+            .as_ref()
+            .and_then(|extras| {
+                ::gltf::json::deserialize::from_str::<::gltf::json::Value>(extras.get()).ok()
+            })
+            .and_then(|extras| {
+                let values = extras.get("MSFT_screencoverage")?.as_array()?;
+                Some(
+                    values
+                        .iter()
+                        .filter_map(|v| v.as_f64())
+                        .map(|v| v as f32)
+                        .collect(),
+                )
+            })
+            .unwrap_or_default();
+        let levels: Vec<&Vec<(usize, Option<usize>)>> = ids
+            .iter()
+            .filter_map(|id| document.nodes().nth(id.as_u64()? as usize))
+            .filter_map(|lod| parts_of(&lod))
+            .collect();
+        if levels.len() != ids.len() || levels.iter().any(|level| level.len() != parts.len()) {
+            log::warn!(
+                "node {}: ignoring MSFT_lod whose levels do not match the node's primitives",
+                node.index()
+            );
+            continue;
+        }
+        for (i, &(base, _)) in parts.iter().enumerate() {
+            if lods.iter().any(|(mesh, _)| *mesh == base) {
+                continue;
+            }
+            let chain = levels
+                .iter()
+                .enumerate()
+                .map(|(level, parts)| {
+                    let coverage = coverages
+                        .get(level)
+                        .copied()
+                        .unwrap_or_else(|| LodGeneration::default().screen_coverage(level + 1));
+                    (parts[i].0, coverage)
+                })
+                .collect();
+            lods.push((base, chain));
+        }
+    }
+    lods
+}
+
+/// Per-instance transforms from `EXT_mesh_gpu_instancing`. They apply
+/// before the node's own transform, so they map onto child nodes.
+fn read_instances(
+    document: &::gltf::Document,
+    node: &::gltf::Node,
+    buffers: &[Vec<u8>],
+) -> anyhow::Result<Option<Vec<Transform>>> {
+    let Some(extension) = node.extension_value("EXT_mesh_gpu_instancing") else {
+        return Ok(None);
+    };
+    let attributes = extension
+        .get("attributes")
+        .and_then(|a| a.as_object())
+        .ok_or_else(|| anyhow!("EXT_mesh_gpu_instancing without attributes"))?;
+    let accessor = |name: &str| -> anyhow::Result<Option<::gltf::Accessor>> {
+        attributes
+            .get(name)
+            .map(|index| {
+                index
+                    .as_u64()
+                    .and_then(|i| document.accessors().nth(i as usize))
+                    .ok_or_else(|| anyhow!("invalid {name} accessor"))
+            })
+            .transpose()
+    };
+    let get_buffer = |buffer: ::gltf::Buffer| buffers.get(buffer.index()).map(Vec::as_slice);
+    let read_vec3 = |name: &str| -> anyhow::Result<Option<Vec<Vec3>>> {
+        accessor(name)?
+            .map(|a| {
+                ::gltf::accessor::Iter::<[f32; 3]>::new(a, get_buffer)
+                    .map(|iter| iter.map(Vec3::from).collect())
+                    .ok_or_else(|| anyhow!("{name} data out of range"))
+            })
+            .transpose()
+    };
+
+    let translations = read_vec3("TRANSLATION")?;
+    let scales = read_vec3("SCALE")?;
+    let rotations: Option<Vec<Quat>> = accessor("ROTATION")?
+        .map(|a| {
+            use ::gltf::accessor::DataType;
+            let rotations = match a.data_type() {
+                DataType::F32 => ::gltf::accessor::Iter::<[f32; 4]>::new(a, get_buffer)
+                    .map(|iter| iter.map(Quat::from_array).collect()),
+                DataType::I8 => ::gltf::accessor::Iter::<[i8; 4]>::new(a, get_buffer).map(|iter| {
+                    iter.map(|q| Quat::from_array(q.map(|c| (c as f32 / 127.0).max(-1.0))))
+                        .collect()
+                }),
+                DataType::I16 => {
+                    ::gltf::accessor::Iter::<[i16; 4]>::new(a, get_buffer).map(|iter| {
+                        iter.map(|q| Quat::from_array(q.map(|c| (c as f32 / 32767.0).max(-1.0))))
+                            .collect()
+                    })
+                }
+                other => bail!("unsupported ROTATION component type {other:?}"),
+            };
+            rotations.ok_or_else(|| anyhow!("ROTATION data out of range"))
+        })
+        .transpose()?;
+
+    let lengths = [
+        translations.as_ref().map(Vec::len),
+        rotations.as_ref().map(Vec::len),
+        scales.as_ref().map(Vec::len),
+    ];
+    let Some(count) = lengths.iter().flatten().copied().max() else {
+        return Ok(None);
+    };
+    if lengths.iter().flatten().any(|&len| len != count) {
+        bail!("EXT_mesh_gpu_instancing attributes differ in length");
+    }
+    Ok(Some(
+        (0..count)
+            .map(|i| Transform {
+                translation: translations.as_ref().map_or(Vec3::ZERO, |t| t[i]),
+                rotation: rotations
+                    .as_ref()
+                    .map_or(Quat::IDENTITY, |r| r[i].normalize()),
+                scale: scales.as_ref().map_or(Vec3::ONE, |s| s[i]),
+            })
+            .collect(),
+    ))
+}
+
+fn read_primitive(
+    primitive: &::gltf::Primitive,
+    buffers: &[Vec<u8>],
+    name: String,
+) -> anyhow::Result<Option<Mesh>> {
+    if primitive.mode() != ::gltf::mesh::Mode::Triangles {
+        log::warn!(
+            "skipping primitive of {name}: unsupported mode {:?}",
+            primitive.mode()
+        );
+        return Ok(None);
+    }
+
+    let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(Vec::as_slice));
+    let positions: Vec<Vec3> = reader
+        .read_positions()
+        .ok_or_else(|| anyhow!("primitive has no POSITION attribute"))?
+        .map(Vec3::from)
+        .collect();
+
+    let mut mesh = Mesh::new(name);
+    if let Some(normals) = reader.read_normals() {
+        mesh.normals = normals.map(Vec3::from).collect();
+    }
+    if let Some(tex_coords) = reader.read_tex_coords(0) {
+        mesh.tex_coords = tex_coords.into_f32().map(Vec2::from).collect();
+    }
+    if let Some(tangents) = reader.read_tangents() {
+        mesh.tangents = tangents.map(Vec4::from).collect();
+    }
+    if let Some(colors) = reader.read_colors(0) {
+        mesh.colors = colors.into_rgba_f32().map(Vec4::from).collect();
+    }
+    if let (Some(joints), Some(weights)) = (reader.read_joints(0), reader.read_weights(0)) {
+        mesh.joints = joints.into_u16().collect();
+        // Exporters do not always normalize; the shader relies on it.
+        mesh.weights = weights
+            .into_f32()
+            .map(|w| {
+                let w = Vec4::from(w);
+                let sum = w.element_sum();
+                if sum > 0.0 { w / sum } else { Vec4::X }
+            })
+            .collect();
+    }
+    for (i, (dp, dn, dt)) in reader.read_morph_targets().enumerate() {
+        let target = MorphTarget {
+            name: format!("target{i}"),
+            positions: dp.map_or_else(Vec::new, |d| d.map(Vec3::from).collect()),
+            normals: dn.map_or_else(Vec::new, |d| d.map(Vec3::from).collect()),
+            tangents: dt.map_or_else(Vec::new, |d| d.map(Vec3::from).collect()),
+        };
+        for stream in [&target.positions, &target.normals, &target.tangents] {
+            if !stream.is_empty() && stream.len() != positions.len() {
+                bail!(
+                    "morph target {i} has {} entries for {} vertices",
+                    stream.len(),
+                    positions.len()
+                );
+            }
+        }
+        mesh.morph_targets.push(target);
+    }
+    mesh.indices = match reader.read_indices() {
+        Some(indices) => indices.into_u32().collect(),
+        None => (0..positions.len() as u32).collect(),
+    };
+    mesh.positions = positions;
+
+    if let Some(&bad) = mesh
+        .indices
+        .iter()
+        .find(|&&i| i as usize >= mesh.positions.len())
+    {
+        bail!("index {bad} out of range");
+    }
+    Ok(Some(mesh))
+}
+
+/// Morph target names from the `targetNames` extra most exporters write.
+fn target_names(mesh: &::gltf::Mesh) -> Vec<String> {
+    let Some(extras) = mesh.extras() else {
+        return Vec::new();
+    };
+    let Ok(value) = ::gltf::json::deserialize::from_str::<::gltf::json::Value>(extras.get()) else {
+        return Vec::new();
+    };
+    value
+        .get("targetNames")
+        .and_then(|names| names.as_array())
+        .map(|names| {
+            names
+                .iter()
+                .map(|name| name.as_str().unwrap_or_default().to_owned())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn convert_skin(
+    skin: &::gltf::Skin,
+    buffers: &[Vec<u8>],
+    node_map: &[Option<usize>],
+) -> anyhow::Result<Skin<usize>> {
+    let joints = skin
+        .joints()
+        .map(|joint| {
+            node_map[joint.index()].ok_or_else(|| {
+                anyhow!(
+                    "skin {}: joint {} is not in the scene",
+                    skin.index(),
+                    joint.index()
+                )
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let reader = skin.reader(|buffer| buffers.get(buffer.index()).map(Vec::as_slice));
+    let inverse_bind_matrices = match reader.read_inverse_bind_matrices() {
+        Some(matrices) => matrices.map(|m| Mat4::from_cols_array_2d(&m)).collect(),
+        None => vec![Mat4::IDENTITY; joints.len()].into(),
+    };
+    Ok(Skin {
+        name: skin
+            .name()
+            .map(str::to_owned)
+            .unwrap_or_else(|| format!("skin{}", skin.index())),
+        joints,
+        inverse_bind_matrices,
+    })
+}
+
+/// Channels targeting nodes outside the imported scene are dropped.
+fn convert_animation(
+    animation: &::gltf::Animation,
+    buffers: &[Vec<u8>],
+    node_map: &[Option<usize>],
+) -> anyhow::Result<AnimationClip> {
+    use ::gltf::animation::{Interpolation as GltfInterpolation, util::ReadOutputs};
+
+    let mut channels = Vec::new();
+    for channel in animation.channels() {
+        let Some(node) = node_map[channel.target().node().index()] else {
+            continue;
+        };
+        let reader = channel.reader(|buffer| buffers.get(buffer.index()).map(Vec::as_slice));
+        let times: Vec<f32> = reader
+            .read_inputs()
+            .ok_or_else(|| anyhow!("animation {}: missing input", animation.index()))?
+            .collect();
+        let outputs = reader
+            .read_outputs()
+            .ok_or_else(|| anyhow!("animation {}: missing output", animation.index()))?;
+        let (target, values): (_, Vec<f32>) = match outputs {
+            ReadOutputs::Translations(v) => (ChannelTarget::Translation, v.flatten().collect()),
+            ReadOutputs::Rotations(v) => {
+                (ChannelTarget::Rotation, v.into_f32().flatten().collect())
+            }
+            ReadOutputs::Scales(v) => (ChannelTarget::Scale, v.flatten().collect()),
+            ReadOutputs::MorphTargetWeights(v) => (ChannelTarget::Weights, v.into_f32().collect()),
+        };
+        let interpolation = match channel.sampler().interpolation() {
+            GltfInterpolation::Step => Interpolation::Step,
+            GltfInterpolation::Linear => Interpolation::Linear,
+            GltfInterpolation::CubicSpline => Interpolation::CubicSpline,
+        };
+        let per_key = if interpolation == Interpolation::CubicSpline {
+            3
+        } else {
+            1
+        };
+        if times.is_empty() || values.len() % (times.len() * per_key) != 0 {
+            bail!(
+                "animation {}: {} keys do not match {} output values",
+                animation.index(),
+                times.len(),
+                values.len()
+            );
+        }
+        channels.push(Channel {
+            node,
+            target,
+            interpolation,
+            times,
+            values,
+        });
+    }
+    let name = animation
+        .name()
+        .map(str::to_owned)
+        .unwrap_or_else(|| format!("animation{}", animation.index()));
+    Ok(AnimationClip::new(name, channels))
+}
+
+fn convert_material(material: ::gltf::Material) -> Material<usize> {
+    let pbr = material.pbr_metallic_roughness();
+    let texture_index = |info: Option<::gltf::texture::Texture>| info.map(|t| t.source().index());
+    Material {
+        name: material.name().unwrap_or_default().to_owned(),
+        base_color: Vec4::from(pbr.base_color_factor()),
+        metallic: pbr.metallic_factor(),
+        roughness: pbr.roughness_factor(),
+        emissive: Vec3::from(material.emissive_factor()),
+        alpha_mode: match material.alpha_mode() {
+            ::gltf::material::AlphaMode::Opaque => AlphaMode::Opaque,
+            ::gltf::material::AlphaMode::Mask => AlphaMode::Mask,
+            ::gltf::material::AlphaMode::Blend => AlphaMode::Blend,
+        },
+        alpha_cutoff: material.alpha_cutoff().unwrap_or(0.5),
+        double_sided: material.double_sided(),
+        textures: MaterialTextures {
+            base_color: texture_index(pbr.base_color_texture().map(|i| i.texture())),
+            metallic_roughness: texture_index(
+                pbr.metallic_roughness_texture().map(|i| i.texture()),
+            ),
+            normal: texture_index(material.normal_texture().map(|i| i.texture())),
+            occlusion: texture_index(material.occlusion_texture().map(|i| i.texture())),
+            emissive: texture_index(material.emissive_texture().map(|i| i.texture())),
+        },
+    }
+}
+
+fn load_buffers(gltf: &::gltf::Gltf, path: &Path, vfs: &Vfs) -> anyhow::Result<Vec<Vec<u8>>> {
+    gltf.document
+        .buffers()
+        .map(|buffer| {
+            let data = match buffer.source() {
+                ::gltf::buffer::Source::Bin => gltf
+                    .blob
+                    .clone()
+                    .ok_or_else(|| anyhow!("GLB has no BIN chunk"))?,
+                ::gltf::buffer::Source::Uri(uri) => read_uri(uri, path, vfs)
+                    .with_context(|| format!("failed to load buffer {}", buffer.index()))?,
+            };
+            if data.len() < buffer.length() {
+                bail!(
+                    "buffer {} is {} bytes, expected {}",
+                    buffer.index(),
+                    data.len(),
+                    buffer.length()
+                );
+            }
+            Ok(data)
+        })
+        .collect()
+}
+
+fn load_textures(
+    document: &::gltf::Document,
+    buffers: &[Vec<u8>],
+    path: &Path,
+    vfs: &Vfs,
+    progress: &LoadProgress,
+) -> anyhow::Result<Vec<Texture>> {
+    // Color data is stored in sRGB, everything else is linear. An image used
+    // for both keeps the sRGB interpretation.
+    let mut color_space = vec![ColorSpace::Linear; document.images().len()];
+    for material in document.materials() {
+        let pbr = material.pbr_metallic_roughness();
+        for info in [pbr.base_color_texture(), material.emissive_texture()]
+            .into_iter()
+            .flatten()
+        {
+            color_space[info.texture().source().index()] = ColorSpace::Srgb;
+        }
+    }
+
+    let image_count = document.images().len().max(1);
+    document
+        .images()
+        .map(|image| {
+            progress.check_cancelled()?;
+            progress.set_fraction(0.2 + 0.4 * image.index() as f32 / image_count as f32);
+            let bytes = match image.source() {
+                ::gltf::image::Source::View { view, .. } => {
+                    let buffer = &buffers[view.buffer().index()];
+                    buffer
+                        .get(view.offset()..view.offset() + view.length())
+                        .ok_or_else(|| anyhow!("image view out of range"))?
+                        .to_vec()
+                }
+                ::gltf::image::Source::Uri { uri, .. } => read_uri(uri, path, vfs)?,
+            };
+            let name = image
+                .name()
+                .map(str::to_owned)
+                .unwrap_or_else(|| format!("image{}", image.index()));
+            Texture::decode(name, &bytes, color_space[image.index()])
+                .with_context(|| format!("failed to load image {}", image.index()))
+        })
+        .collect()
+}
+
+/// Reads a data URI or a file referenced relative to the glTF at `path`.
+fn read_uri(uri: &str, path: &Path, vfs: &Vfs) -> anyhow::Result<Vec<u8>> {
+    if let Some(rest) = uri.strip_prefix("data:") {
+        let (_, payload) = rest
+            .split_once(";base64,")
+            .ok_or_else(|| anyhow!("unsupported data URI"))?;
+        return base64::engine::general_purpose::STANDARD
+            .decode(payload)
+            .context("invalid base64 in data URI");
+    }
+    vfs.read(Vfs::resolve(path, percent_decode(uri)))
+}
+
+fn percent_decode(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(value) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                out.push(value);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triangle_gltf() -> String {
+        triangle_gltf_instanced(&[])
+    }
+
+    /// With translations, the node uses `EXT_mesh_gpu_instancing`.
+    fn triangle_gltf_instanced(translations: &[[f32; 3]]) -> String {
+        let mut bytes: Vec<u8> = [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        bytes.extend([0u16, 1, 2, 0].iter().flat_map(|v| v.to_le_bytes()));
+        let (mut extension, mut view, mut accessor) = Default::default();
+        if !translations.is_empty() {
+            bytes.extend(translations.iter().flatten().flat_map(|v| v.to_le_bytes()));
+            extension = r#", "extensions": { "EXT_mesh_gpu_instancing": { "attributes": { "TRANSLATION": 2 } } }"#.to_owned();
+            view = format!(
+                r#", {{ "buffer": 0, "byteOffset": 44, "byteLength": {} }}"#,
+                translations.len() * 12
+            );
+            accessor = format!(
+                r#", {{ "bufferView": 2, "componentType": 5126, "count": {}, "type": "VEC3" }}"#,
+                translations.len()
+            );
+        }
+        let data = base64::engine::general_purpose::STANDARD.encode(&bytes);
+        format!(
+            r#"{{
+                "asset": {{ "version": "2.0" }},
+                "scene": 0,
+                "scenes": [{{ "nodes": [0] }}],
+                "nodes": [{{ "name": "tri", "mesh": 0, "translation": [1, 2, 3]{extension} }}],
+                "meshes": [{{ "primitives": [{{ "attributes": {{ "POSITION": 0 }}, "indices": 1, "material": 0 }}] }}],
+                "materials": [{{ "pbrMetallicRoughness": {{ "baseColorFactor": [1, 0, 0, 1] }} }}],
+                "buffers": [{{ "byteLength": {len}, "uri": "data:application/octet-stream;base64,{data}" }}],
+                "bufferViews": [
+                    {{ "buffer": 0, "byteOffset": 0, "byteLength": 36 }},
+                    {{ "buffer": 0, "byteOffset": 36, "byteLength": 6 }}{view}
+                ],
+                "accessors": [
+                    {{ "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+                       "min": [0, 0, 0], "max": [1, 1, 0] }},
+                    {{ "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" }}{accessor}
+                ]
+            }}"#,
+            len = bytes.len(),
+        )
+    }
+
+    /// A triangle bound to one bone, which an animation moves along X.
+    fn skinned_triangle_gltf() -> String {
+        let mut bytes: Vec<u8> = [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        bytes.extend([0u16, 1, 2, 0].iter().flat_map(|v| v.to_le_bytes()));
+        bytes.extend([0u16; 12].iter().flat_map(|v| v.to_le_bytes()));
+        let weights = [
+            [2.0f32, 0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0, 0.0],
+            [0.5, 0.5, 0.0, 0.0],
+        ];
+        bytes.extend(weights.iter().flatten().flat_map(|v| v.to_le_bytes()));
+        bytes.extend([0.0f32, 2.0].iter().flat_map(|v| v.to_le_bytes()));
+        bytes.extend(
+            [0.0f32, 0.0, 0.0, 4.0, 0.0, 0.0]
+                .iter()
+                .flat_map(|v| v.to_le_bytes()),
+        );
+        let data = base64::engine::general_purpose::STANDARD.encode(&bytes);
+        format!(
+            r#"{{
+                "asset": {{ "version": "2.0" }},
+                "scenes": [{{ "nodes": [0] }}],
+                "nodes": [
+                    {{ "name": "root", "children": [1, 2] }},
+                    {{ "name": "body", "mesh": 0, "skin": 0 }},
+                    {{ "name": "bone" }}
+                ],
+                "skins": [{{ "name": "rig", "joints": [2] }}],
+                "animations": [{{
+                    "name": "slide",
+                    "channels": [{{ "sampler": 0, "target": {{ "node": 2, "path": "translation" }} }}],
+                    "samplers": [{{ "input": 4, "output": 5 }}]
+                }}],
+                "meshes": [{{ "primitives": [{{
+                    "attributes": {{ "POSITION": 0, "JOINTS_0": 2, "WEIGHTS_0": 3 }},
+                    "indices": 1
+                }}] }}],
+                "buffers": [{{ "byteLength": {len}, "uri": "data:application/octet-stream;base64,{data}" }}],
+                "bufferViews": [
+                    {{ "buffer": 0, "byteOffset": 0, "byteLength": 36 }},
+                    {{ "buffer": 0, "byteOffset": 36, "byteLength": 6 }},
+                    {{ "buffer": 0, "byteOffset": 44, "byteLength": 24 }},
+                    {{ "buffer": 0, "byteOffset": 68, "byteLength": 48 }},
+                    {{ "buffer": 0, "byteOffset": 116, "byteLength": 8 }},
+                    {{ "buffer": 0, "byteOffset": 124, "byteLength": 24 }}
+                ],
+                "accessors": [
+                    {{ "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+                       "min": [0, 0, 0], "max": [1, 1, 0] }},
+                    {{ "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" }},
+                    {{ "bufferView": 2, "componentType": 5123, "count": 3, "type": "VEC4" }},
+                    {{ "bufferView": 3, "componentType": 5126, "count": 3, "type": "VEC4" }},
+                    {{ "bufferView": 4, "componentType": 5126, "count": 2, "type": "SCALAR",
+                       "min": [0], "max": [2] }},
+                    {{ "bufferView": 5, "componentType": 5126, "count": 2, "type": "VEC3" }}
+                ]
+            }}"#,
+            len = bytes.len(),
+        )
+    }
+
+    /// A triangle with one named morph target lifting its first vertex.
+    fn morph_triangle_gltf() -> String {
+        let mut bytes: Vec<u8> = [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        bytes.extend(
+            [0.0f32, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]
+                .iter()
+                .flat_map(|v| v.to_le_bytes()),
+        );
+        let data = base64::engine::general_purpose::STANDARD.encode(&bytes);
+        format!(
+            r#"{{
+                "asset": {{ "version": "2.0" }},
+                "scenes": [{{ "nodes": [0] }}],
+                "nodes": [{{ "mesh": 0 }}],
+                "meshes": [{{
+                    "primitives": [{{ "attributes": {{ "POSITION": 0 }}, "targets": [{{ "POSITION": 1 }}] }}],
+                    "weights": [0.5],
+                    "extras": {{ "targetNames": ["lift"] }}
+                }}],
+                "buffers": [{{ "byteLength": {len}, "uri": "data:application/octet-stream;base64,{data}" }}],
+                "bufferViews": [
+                    {{ "buffer": 0, "byteOffset": 0, "byteLength": 36 }},
+                    {{ "buffer": 0, "byteOffset": 36, "byteLength": 36 }}
+                ],
+                "accessors": [
+                    {{ "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+                       "min": [0, 0, 0], "max": [1, 1, 0] }},
+                    {{ "bufferView": 1, "componentType": 5126, "count": 3, "type": "VEC3",
+                       "min": [0, 0, 0], "max": [0, 0, 1] }}
+                ]
+            }}"#,
+            len = bytes.len(),
+        )
+    }
+
+    #[test]
+    fn imports_embedded_triangle() {
+        let data = import_slice(
+            triangle_gltf().as_bytes(),
+            Path::new("triangle.gltf"),
+            &Vfs::new(),
+            &LoadProgress::new(),
+        )
+        .unwrap();
+        assert_eq!(data.meshes.len(), 1);
+        assert_eq!(data.meshes[0].positions[1], Vec3::X);
+        assert_eq!(data.meshes[0].indices, vec![0, 1, 2]);
+        assert_eq!(data.materials[0].base_color, Vec4::new(1.0, 0.0, 0.0, 1.0));
+        assert_eq!(data.nodes.len(), 1);
+        assert_eq!(data.nodes[0].mesh, Some(0));
+        assert_eq!(data.nodes[0].material, Some(0));
+        assert_eq!(
+            data.nodes[0].transform.translation,
+            Vec3::new(1.0, 2.0, 3.0)
+        );
+    }
+
+    #[test]
+    fn msft_lod_levels_attach_to_the_base_mesh() {
+        let primitive = r#"{ "primitives": [{ "attributes": { "POSITION": 0 }, "indices": 1 }] }"#;
+        let gltf = triangle_gltf()
+            .replace(
+                r#""nodes": [{ "name": "tri", "mesh": 0, "translation": [1, 2, 3] }]"#,
+                r#""nodes": [
+                    { "name": "tri", "mesh": 0,
+                      "extensions": { "MSFT_lod": { "ids": [1, 2] } },
+                      "extras": { "MSFT_screencoverage": [0.4] } },
+                    { "mesh": 1 }, { "mesh": 2 }
+                ]"#,
+            )
+            .replace(
+                r#""meshes": [{ "primitives": [{ "attributes": { "POSITION": 0 }, "indices": 1, "material": 0 }] }]"#,
+                &format!(r#""meshes": [{primitive}, {primitive}, {primitive}]"#),
+            );
+        let data = import_slice(
+            gltf.as_bytes(),
+            Path::new("lod.gltf"),
+            &Vfs::new(),
+            &LoadProgress::new(),
+        )
+        .unwrap();
+        assert_eq!(data.meshes.len(), 3);
+        // Only the base node is in the scene.
+        assert_eq!(data.nodes.len(), 1);
+        let default_coverage = LodGeneration::default().screen_coverage(2);
+        assert_eq!(data.lods, [(0, vec![(1, 0.4), (2, default_coverage)])]);
+    }
+
+    #[test]
+    fn gpu_instances_become_child_nodes() {
+        let gltf = triangle_gltf_instanced(&[[0.0, 0.0, 0.0], [5.0, 0.0, 0.0], [0.0, 5.0, 0.0]]);
+        let data = import_slice(
+            gltf.as_bytes(),
+            Path::new("instanced.gltf"),
+            &Vfs::new(),
+            &LoadProgress::new(),
+        )
+        .unwrap();
+        assert_eq!(data.meshes.len(), 1);
+        assert_eq!(data.nodes.len(), 4);
+        assert_eq!(data.nodes[0].mesh, None);
+        for instance in &data.nodes[1..] {
+            assert_eq!(instance.parent, Some(0));
+            assert_eq!(instance.mesh, Some(0));
+            assert_eq!(instance.material, Some(0));
+        }
+        assert_eq!(
+            data.nodes[2].transform.translation,
+            Vec3::new(5.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn imports_skins_and_animations() {
+        let data = import_slice(
+            skinned_triangle_gltf().as_bytes(),
+            Path::new("skinned.gltf"),
+            &Vfs::new(),
+            &LoadProgress::new(),
+        )
+        .unwrap();
+        let mesh = &data.meshes[0];
+        assert!(mesh.is_skinned());
+        assert_eq!(mesh.joints, vec![[0; 4]; 3]);
+        assert_eq!(mesh.weights[0], Vec4::X);
+
+        assert_eq!(data.nodes[1].skin, Some(0));
+        assert_eq!(data.skins[0].name, "rig");
+        assert_eq!(data.skins[0].joints, vec![2]);
+        assert_eq!(data.skins[0].inverse_bind_matrix(0), Mat4::IDENTITY);
+
+        let clip = &data.animations[0];
+        assert_eq!(clip.name, "slide");
+        assert_eq!(clip.duration(), 2.0);
+        assert_eq!(clip.channels[0].node, 2);
+        assert_eq!(clip.channels[0].target, ChannelTarget::Translation);
+        let mut values = Vec::new();
+        clip.channels[0].sample(1.0, &mut values);
+        assert_eq!(values, vec![2.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn imports_named_morph_targets() {
+        let data = import_slice(
+            morph_triangle_gltf().as_bytes(),
+            Path::new("morph.gltf"),
+            &Vfs::new(),
+            &LoadProgress::new(),
+        )
+        .unwrap();
+        let mesh = &data.meshes[0];
+        assert!(mesh.has_morph_targets());
+        assert_eq!(mesh.morph_weights, vec![0.5]);
+        let target = &mesh.morph_targets[0];
+        assert_eq!(target.name, "lift");
+        assert_eq!(target.positions[0], Vec3::Z);
+        assert!(target.normals.is_empty());
+    }
+
+    #[test]
+    fn decodes_percent_escapes() {
+        assert_eq!(percent_decode("my%20mesh.bin"), "my mesh.bin");
+        assert_eq!(percent_decode("plain.bin"), "plain.bin");
+        assert_eq!(percent_decode("trailing%2"), "trailing%2");
+    }
+}
@@ -0,0 +1,187 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use glam::{Vec2, Vec3, Vec4};
+
+use crate::core::asset_manager::{
+    loader::LoadProgress,
+    material::{AlphaMode, Material, MaterialTextures},
+    mesh::Mesh,
+    model::{ModelData, ModelNode},
+    texture::{ColorSpace, Texture},
+    vfs::Vfs,
+};
+use crate::core::transform::Transform;
+
+pub fn import(path: &Path, vfs: &Vfs, progress: &LoadProgress) -> anyhow::Result<ModelData> {
+    let bytes = vfs.read(path)?;
+
+    let options = tobj::LoadOptions {
+        single_index: true,
+        triangulate: true,
+        ignore_points: true,
+        ignore_lines: true,
+    };
+    let mtl_paths = RefCell::new(Vec::new());
+    let (models, materials) = tobj::load_obj_buf(&mut bytes.as_slice(), &options, |mtl| {
+        let mtl = Vfs::resolve(path, mtl);
+        let bytes = vfs.read(&mtl);
+        mtl_paths.borrow_mut().push(mtl);
+        let bytes = bytes.map_err(|_| tobj::LoadError::OpenFileFailed)?;
+        tobj::load_mtl_buf(&mut bytes.as_slice())
+    })
+    .context("failed to parse OBJ")?;
+    progress.check_cancelled()?;
+    progress.set_fraction(0.5);
+
+    let materials = materials.unwrap_or_else(|err| {
+        log::warn!("{}: ignoring material library: {}", path.display(), err);
+        Vec::new()
+    });
+
+    let mut data = ModelData {
+        dependencies: mtl_paths.into_inner(),
+        ..Default::default()
+    };
+    let mut texture_indices = HashMap::new();
+    for material in &materials {
+        let converted = convert_material(
+            material,
+            path,
+            vfs,
+            &mut data.textures,
+            &mut texture_indices,
+            &mut data.dependencies,
+        );
+        data.materials.push(converted);
+        progress.check_cancelled()?;
+    }
+    progress.set_fraction(0.8);
+
+    let root_name = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    data.nodes.push(ModelNode {
+        name: root_name,
+        parent: None,
+        transform: Transform::IDENTITY,
+        mesh: None,
+        material: None,
+        point_cloud: None,
+        skin: None,
+        morph_weights: Vec::new(),
+    });
+
+    for model in models {
+        let mesh = convert_mesh(&model);
+        data.nodes.push(ModelNode {
+            name: model.name,
+            parent: Some(0),
+            transform: Transform::IDENTITY,
+            mesh: Some(data.meshes.len()),
+            material: model.mesh.material_id.filter(|&i| i < data.materials.len()),
+            point_cloud: None,
+            skin: None,
+            morph_weights: Vec::new(),
+        });
+        data.meshes.push(mesh);
+    }
+
+    Ok(data)
+}
+
+fn convert_mesh(model: &tobj::Model) -> Mesh {
+    let source = &model.mesh;
+    let mut mesh = Mesh::new(model.name.clone());
+    mesh.positions = source
+        .positions
+        .chunks_exact(3)
+        .map(Vec3::from_slice)
+        .collect();
+    mesh.normals = source
+        .normals
+        .chunks_exact(3)
+        .map(Vec3::from_slice)
+        .collect();
+    // OBJ texture space has its origin at the bottom left.
+    mesh.tex_coords = source
+        .texcoords
+        .chunks_exact(2)
+        .map(|uv| Vec2::new(uv[0], 1.0 - uv[1]))
+        .collect();
+    mesh.colors = source
+        .vertex_color
+        .chunks_exact(3)
+        .map(|c| Vec4::new(c[0], c[1], c[2], 1.0))
+        .collect();
+    mesh.indices = source.indices.clone();
+    mesh
+}
+
+fn convert_material(
+    material: &tobj::Material,
+    path: &Path,
+    vfs: &Vfs,
+    textures: &mut Vec<Texture>,
+    texture_indices: &mut HashMap<String, usize>,
+    dependencies: &mut Vec<PathBuf>,
+) -> Material<usize> {
+    let mut load_texture = |name: &Option<String>, color_space| {
+        let name = name.as_ref()?;
+        if let Some(&index) = texture_indices.get(name) {
+            return Some(index);
+        }
+        let texture_path = Vfs::resolve(path, name);
+        if !dependencies.contains(&texture_path) {
+            dependencies.push(texture_path.clone());
+        }
+        let texture = vfs
+            .read(&texture_path)
+            .and_then(|bytes| Texture::decode(name.clone(), &bytes, color_space));
+        match texture {
+            Ok(texture) => {
+                texture_indices.insert(name.clone(), textures.len());
+                textures.push(texture);
+                Some(textures.len() - 1)
+            }
+            Err(err) => {
+                log::warn!(
+                    "failed to load texture {}: {:#}",
+                    texture_path.display(),
+                    err
+                );
+                None
+            }
+        }
+    };
+
+    let diffuse = material.diffuse.unwrap_or([0.8, 0.8, 0.8]);
+    let alpha = material.dissolve.unwrap_or(1.0);
+    // Map the Phong exponent onto perceptual roughness.
+    let roughness = material
+        .shininess
+        .map_or(0.5, |ns| (2.0 / (ns.max(0.0) + 2.0)).sqrt());
+
+    Material {
+        name: material.name.clone(),
+        base_color: Vec4::new(diffuse[0], diffuse[1], diffuse[2], alpha),
+        metallic: 0.0,
+        roughness,
+        alpha_mode: if alpha < 1.0 {
+            AlphaMode::Blend
+        } else {
+            AlphaMode::Opaque
+        },
+        textures: MaterialTextures {
+            base_color: load_texture(&material.diffuse_texture, ColorSpace::Srgb),
+            normal: load_texture(&material.normal_texture, ColorSpace::Linear),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
@@ -0,0 +1,628 @@
+use std::path::Path;
+
+use anyhow::{Context, anyhow, bail};
+use glam::{Vec2, Vec3, Vec4};
+
+use crate::core::asset_manager::{
+    loader::LoadProgress,
+    material::Material,
+    mesh::Mesh,
+    model::{ModelData, ModelNode},
+    point_cloud::PointCloud,
+    vfs::Vfs,
+};
+use crate::core::mesh_processing;
+use crate::core::transform::Transform;
+
+/// Scans without normals are mostly smooth surfaces; only sharp folds keep
+/// hard edges.
+const CREASE_ANGLE_DEGREES: f32 = 60.0;
+
+/// Rows between progress updates and cancellation checks.
+const ROWS_PER_CHECK: usize = 1 << 16;
+
+pub fn import(path: &Path, vfs: &Vfs, progress: &LoadProgress) -> anyhow::Result<ModelData> {
+    let bytes = vfs.read(path)?;
+    let name = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    import_slice(&bytes, name, progress).with_context(|| format!("{}", path.display()))
+}
+
+/// Meshes come out as a single node with one mesh; files without faces
+/// become a point cloud instead.
+pub fn import_slice(
+    bytes: &[u8],
+    name: String,
+    progress: &LoadProgress,
+) -> anyhow::Result<ModelData> {
+    let (header, body) = parse_header(bytes)?;
+    let mut reader: Box<dyn ValueReader + '_> = match header.format {
+        Format::Ascii => Box::new(AsciiReader::new(body)),
+        Format::BinaryLittleEndian => Box::new(BinaryReader::new(body, false)),
+        Format::BinaryBigEndian => Box::new(BinaryReader::new(body, true)),
+    };
+
+    let total_rows: usize = header
+        .elements
+        .iter()
+        .map(|e| e.count)
+        .sum::<usize>()
+        .max(1);
+    let mut rows_done = 0;
+    let mut vertices = Vertices::default();
+    let mut faces = Vec::new();
+    for element in &header.elements {
+        let layout = match element.name.as_str() {
+            "vertex" => RowLayout::Vertex(VertexLayout::new(element)),
+            "face" => RowLayout::Face(
+                element
+                    .properties
+                    .iter()
+                    .position(|p| {
+                        matches!(p.name.as_str(), "vertex_indices" | "vertex_index")
+                            && matches!(p.kind, PropertyKind::List(..))
+                    })
+                    .ok_or_else(|| anyhow!("face element has no vertex_indices list"))?,
+            ),
+            _ => RowLayout::Skip,
+        };
+
+        let mut scalars = Vec::with_capacity(element.properties.len());
+        let mut list = Vec::new();
+        for row in 0..element.count {
+            if row % ROWS_PER_CHECK == 0 {
+                progress.check_cancelled()?;
+                progress.set_fraction((rows_done + row) as f32 / total_rows as f32);
+            }
+            scalars.clear();
+            for (i, property) in element.properties.iter().enumerate() {
+                match property.kind {
+                    PropertyKind::Scalar(ty) => scalars.push(reader.read(ty)?),
+                    PropertyKind::List(count_ty, item_ty) => {
+                        scalars.push(0.0);
+                        let count = reader.read(count_ty)? as usize;
+                        let keep = matches!(layout, RowLayout::Face(index) if index == i);
+                        list.clear();
+                        for _ in 0..count {
+                            let value = reader.read(item_ty)?;
+                            if keep {
+                                list.push(value);
+                            }
+                        }
+                    }
+                }
+            }
+            match &layout {
+                RowLayout::Vertex(layout) => vertices.push(layout, &scalars),
+                RowLayout::Face(_) => {
+                    let valid = |v: &f64| v.fract() == 0.0 && (0.0..=u32::MAX as f64).contains(v);
+                    if let Some(bad) = list.iter().find(|v| !valid(v)) {
+                        bail!("face {row} has invalid vertex index {bad}");
+                    }
+                    // Triangulate polygons as fans.
+                    for i in 1..list.len().saturating_sub(1) {
+                        faces.extend([list[0], list[i], list[i + 1]].map(|v| v as u32));
+                    }
+                }
+                RowLayout::Skip => {}
+            }
+        }
+        rows_done += element.count;
+    }
+
+    let vertex_count = vertices.positions.len();
+    if let Some(&bad) = faces.iter().find(|&&i| i as usize >= vertex_count) {
+        bail!("face references vertex {bad}, but there are only {vertex_count}");
+    }
+
+    let has_colors = !vertices.colors.is_empty();
+    let mut data = ModelData::default();
+    let mut node = ModelNode {
+        name: name.clone(),
+        parent: None,
+        transform: Transform::IDENTITY,
+        mesh: None,
+        material: None,
+        point_cloud: None,
+        skin: None,
+        morph_weights: Vec::new(),
+    };
+    if faces.is_empty() {
+        let mut cloud = PointCloud::new(name);
+        cloud.positions = vertices.positions;
+        cloud.normals = vertices.normals;
+        cloud.colors = vertices.colors;
+        cloud.intensities = normalize_intensities(vertices.intensities);
+        progress.check_cancelled()?;
+        cloud.build_octree();
+        data.point_clouds.push(cloud);
+        node.point_cloud = Some(0);
+    } else {
+        let mut mesh = Mesh::new(name.clone());
+        mesh.positions = vertices.positions;
+        mesh.normals = vertices.normals;
+        mesh.colors = vertices.colors;
+        mesh.tex_coords = vertices.tex_coords;
+        mesh.indices = faces;
+        if mesh.normals.is_empty() {
+            mesh_processing::compute_normals(&mut mesh, CREASE_ANGLE_DEGREES.to_radians());
+        }
+        data.meshes.push(mesh);
+        node.mesh = Some(0);
+        if has_colors {
+            // Vertex colors carry the look; keep the material from tinting them.
+            data.materials.push(Material {
+                name,
+                ..Default::default()
+            });
+            node.material = Some(0);
+        }
+    }
+    data.nodes.push(node);
+    progress.set_fraction(1.0);
+    Ok(data)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum ScalarType {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl ScalarType {
+    fn parse(name: &str) -> anyhow::Result<Self> {
+        Ok(match name {
+            "char" | "int8" => Self::I8,
+            "uchar" | "uint8" => Self::U8,
+            "short" | "int16" => Self::I16,
+            "ushort" | "uint16" => Self::U16,
+            "int" | "int32" => Self::I32,
+            "uint" | "uint32" => Self::U32,
+            "float" | "float32" => Self::F32,
+            "double" | "float64" => Self::F64,
+            _ => bail!("unknown property type {name:?}"),
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Self::I8 | Self::U8 => 1,
+            Self::I16 | Self::U16 => 2,
+            Self::I32 | Self::U32 | Self::F32 => 4,
+            Self::F64 => 8,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum PropertyKind {
+    Scalar(ScalarType),
+    List(ScalarType, ScalarType),
+}
+
+#[derive(Debug)]
+struct Property {
+    name: String,
+    kind: PropertyKind,
+}
+
+#[derive(Debug)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+struct Header {
+    format: Format,
+    elements: Vec<Element>,
+}
+
+/// Splits off and parses the text header; returns it with the body bytes.
+fn parse_header(bytes: &[u8]) -> anyhow::Result<(Header, &[u8])> {
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    let mut offset = 0;
+    let mut line_number = 0;
+    loop {
+        let end = bytes[offset..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map(|i| offset + i)
+            .ok_or_else(|| anyhow!("PLY header has no end_header line"))?;
+        let line = std::str::from_utf8(&bytes[offset..end])
+            .context("PLY header is not valid text")?
+            .trim_end_matches('\r');
+        offset = end + 1;
+        line_number += 1;
+
+        let mut words = line.split_whitespace();
+        let keyword = words.next().unwrap_or("");
+        if line_number == 1 {
+            if keyword != "ply" {
+                bail!("not a PLY file");
+            }
+            continue;
+        }
+        let error = || anyhow!("PLY header line {line_number}: malformed {keyword:?} line");
+        match keyword {
+            "format" => {
+                format = Some(match words.next() {
+                    Some("ascii") => Format::Ascii,
+                    Some("binary_little_endian") => Format::BinaryLittleEndian,
+                    Some("binary_big_endian") => Format::BinaryBigEndian,
+                    other => bail!("unsupported PLY format {:?}", other.unwrap_or("")),
+                });
+            }
+            "element" => {
+                let name = words.next().ok_or_else(error)?.to_owned();
+                let count = words
+                    .next()
+                    .and_then(|c| c.parse().ok())
+                    .ok_or_else(error)?;
+                elements.push(Element {
+                    name,
+                    count,
+                    properties: Vec::new(),
+                });
+            }
+            "property" => {
+                let element = elements.last_mut().ok_or_else(error)?;
+                let first = words.next().ok_or_else(error)?;
+                let kind = if first == "list" {
+                    let count = ScalarType::parse(words.next().ok_or_else(error)?)?;
+                    let item = ScalarType::parse(words.next().ok_or_else(error)?)?;
+                    PropertyKind::List(count, item)
+                } else {
+                    PropertyKind::Scalar(ScalarType::parse(first)?)
+                };
+                let name = words.next().ok_or_else(error)?.to_owned();
+                element.properties.push(Property { name, kind });
+            }
+            "end_header" => break,
+            "comment" | "obj_info" | "" => {}
+            _ => bail!("PLY header line {line_number}: unknown keyword {keyword:?}"),
+        }
+    }
+    let format = format.ok_or_else(|| anyhow!("PLY header has no format line"))?;
+    Ok((Header { format, elements }, &bytes[offset..]))
+}
+
+trait ValueReader {
+    fn read(&mut self, ty: ScalarType) -> anyhow::Result<f64>;
+}
+
+struct AsciiReader<'a> {
+    tokens: std::str::SplitAsciiWhitespace<'a>,
+}
+
+impl<'a> AsciiReader<'a> {
+    fn new(body: &'a [u8]) -> Self {
+        // Anything after the first non-UTF-8 byte cannot be ASCII PLY data.
+        let text = match std::str::from_utf8(body) {
+            Ok(text) => text,
+            Err(err) => std::str::from_utf8(&body[..err.valid_up_to()]).unwrap_or(""),
+        };
+        Self {
+            tokens: text.split_ascii_whitespace(),
+        }
+    }
+}
+
+impl ValueReader for AsciiReader<'_> {
+    fn read(&mut self, _ty: ScalarType) -> anyhow::Result<f64> {
+        let token = self
+            .tokens
+            .next()
+            .ok_or_else(|| anyhow!("unexpected end of PLY data"))?;
+        token
+            .parse()
+            .map_err(|_| anyhow!("invalid number {token:?} in PLY data"))
+    }
+}
+
+struct BinaryReader<'a> {
+    body: &'a [u8],
+    offset: usize,
+    big_endian: bool,
+}
+
+impl<'a> BinaryReader<'a> {
+    fn new(body: &'a [u8], big_endian: bool) -> Self {
+        Self {
+            body,
+            offset: 0,
+            big_endian,
+        }
+    }
+}
+
+impl ValueReader for BinaryReader<'_> {
+    fn read(&mut self, ty: ScalarType) -> anyhow::Result<f64> {
+        let size = ty.size();
+        let mut raw = [0u8; 8];
+        raw[..size].copy_from_slice(
+            self.body
+                .get(self.offset..self.offset + size)
+                .ok_or_else(|| anyhow!("unexpected end of PLY data"))?,
+        );
+        self.offset += size;
+        // Decode everything as little-endian.
+        if self.big_endian {
+            raw[..size].reverse();
+        }
+        let [b0, b1, b2, b3, ..] = raw;
+        Ok(match ty {
+            ScalarType::I8 => b0 as i8 as f64,
+            ScalarType::U8 => b0 as f64,
+            ScalarType::I16 => i16::from_le_bytes([b0, b1]) as f64,
+            ScalarType::U16 => u16::from_le_bytes([b0, b1]) as f64,
+            ScalarType::I32 => i32::from_le_bytes([b0, b1, b2, b3]) as f64,
+            ScalarType::U32 => u32::from_le_bytes([b0, b1, b2, b3]) as f64,
+            ScalarType::F32 => f32::from_le_bytes([b0, b1, b2, b3]) as f64,
+            ScalarType::F64 => f64::from_le_bytes(raw),
+        })
+    }
+}
+
+enum RowLayout {
+    Vertex(VertexLayout),
+    /// Index of the vertex index list property.
+    Face(usize),
+    Skip,
+}
+
+/// Property indices of the vertex attributes we understand; everything else
+/// is read and dropped.
+struct VertexLayout {
+    position: [Option<usize>; 3],
+    normal: Option<[usize; 3]>,
+    color: Option<([usize; 3], Option<usize>, f64)>,
+    tex_coord: Option<[usize; 2]>,
+    intensity: Option<usize>,
+}
+
+impl VertexLayout {
+    fn new(element: &Element) -> Self {
+        let find = |names: &[&str]| {
+            element
+                .properties
+                .iter()
+                .position(|p| names.contains(&p.name.as_str()))
+        };
+        let all = |indices: [Option<usize>; 3]| -> Option<[usize; 3]> {
+            Some([indices[0]?, indices[1]?, indices[2]?])
+        };
+
+        let color_indices = all([
+            find(&["red", "r", "diffuse_red"]),
+            find(&["green", "g", "diffuse_green"]),
+            find(&["blue", "b", "diffuse_blue"]),
+        ]);
+        let color = color_indices.map(|rgb| {
+            let alpha = find(&["alpha", "a", "diffuse_alpha"]);
+            // Integer colors are 0..=255 (or 0..=65535); floats are 0..=1.
+            let scale = match element.properties[rgb[0]].kind {
+                PropertyKind::Scalar(ScalarType::U16) => 65535.0,
+                PropertyKind::Scalar(ScalarType::F32 | ScalarType::F64) => 1.0,
+                _ => 255.0,
+            };
+            (rgb, alpha, scale)
+        });
+
+        Self {
+            position: [find(&["x"]), find(&["y"]), find(&["z"])],
+            normal: all([find(&["nx"]), find(&["ny"]), find(&["nz"])]),
+            color,
+            tex_coord: match (
+                find(&["s", "u", "texture_u", "texture_s"]),
+                find(&["t", "v", "texture_v", "texture_t"]),
+            ) {
+                (Some(u), Some(v)) => Some([u, v]),
+                _ => None,
+            },
+            intensity: find(&[
+                "intensity",
+                "scalar_intensity",
+                "scalar_Intensity",
+                "reflectance",
+            ]),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Vertices {
+    positions: Vec<Vec3>,
+    normals: Vec<Vec3>,
+    colors: Vec<Vec4>,
+    tex_coords: Vec<Vec2>,
+    intensities: Vec<f32>,
+}
+
+impl Vertices {
+    fn push(&mut self, layout: &VertexLayout, row: &[f64]) {
+        let value = |index: usize| row[index] as f32;
+        let position = layout.position.map(|i| i.map_or(0.0, value));
+        self.positions.push(Vec3::from_array(position));
+        if let Some(normal) = layout.normal {
+            self.normals.push(Vec3::from_array(normal.map(value)));
+        }
+        if let Some(intensity) = layout.intensity {
+            self.intensities.push(value(intensity));
+        }
+        if let Some((rgb, alpha, scale)) = layout.color {
+            let channel = |i: usize| (row[i] / scale) as f32;
+            let [r, g, b] = rgb.map(|i| srgb_to_linear(channel(i)));
+            self.colors
+                .push(Vec4::new(r, g, b, alpha.map_or(1.0, channel)));
+        }
+        if let Some([u, v]) = layout.tex_coord {
+            // PLY follows the OpenGL convention with V pointing up.
+            self.tex_coords.push(Vec2::new(value(u), 1.0 - value(v)));
+        }
+    }
+}
+
+/// Scanners use arbitrary intensity ranges; map the file's range onto
+/// `0..=1`.
+fn normalize_intensities(mut intensities: Vec<f32>) -> Vec<f32> {
+    let (min, max) = intensities
+        .iter()
+        .fold((f32::MAX, f32::MIN), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+    let range = max - min;
+    if range > 0.0 {
+        for v in &mut intensities {
+            *v = (*v - min) / range;
+        }
+    } else {
+        intensities.fill(1.0);
+    }
+    intensities
+}
+
+/// Vertex colors in scans are stored like image pixels, in sRGB.
+fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER_TAIL: &str = "element vertex 3\n\
+        property float x\nproperty float y\nproperty float z\n\
+        property uchar red\nproperty uchar green\nproperty uchar blue\n\
+        property float confidence\n\
+        element face 1\nproperty list uchar int vertex_indices\nend_header\n";
+
+    fn binary_triangle(big_endian: bool) -> Vec<u8> {
+        let format = if big_endian {
+            "binary_big_endian"
+        } else {
+            "binary_little_endian"
+        };
+        let mut bytes =
+            format!("ply\nformat {format} 1.0\ncomment test\n{HEADER_TAIL}").into_bytes();
+        let f32_bytes = |v: f32| {
+            if big_endian {
+                v.to_be_bytes()
+            } else {
+                v.to_le_bytes()
+            }
+        };
+        for (position, red) in [
+            ([0.0, 0.0, 0.0], 255u8),
+            ([1.0, 0.0, 0.0], 0),
+            ([0.0, 1.0, 0.0], 0),
+        ] {
+            for c in position {
+                bytes.extend(f32_bytes(c));
+            }
+            bytes.extend([red, 0, 0]);
+            bytes.extend(f32_bytes(0.5));
+        }
+        bytes.push(3);
+        for i in [0i32, 1, 2] {
+            bytes.extend(if big_endian {
+                i.to_be_bytes()
+            } else {
+                i.to_le_bytes()
+            });
+        }
+        bytes
+    }
+
+    #[test]
+    fn reads_binary_meshes_in_both_byte_orders() {
+        for big_endian in [false, true] {
+            let data = import_slice(
+                &binary_triangle(big_endian),
+                "tri".into(),
+                &LoadProgress::new(),
+            )
+            .unwrap();
+            let mesh = &data.meshes[0];
+            assert_eq!(mesh.positions[1], Vec3::X);
+            assert_eq!(mesh.colors[0], Vec4::new(1.0, 0.0, 0.0, 1.0));
+            assert_eq!(mesh.triangle_count(), 1);
+            assert_eq!(mesh.normals.len(), mesh.vertex_count());
+        }
+    }
+
+    #[test]
+    fn ascii_without_faces_is_a_point_cloud() {
+        let text = "ply\nformat ascii 1.0\nelement vertex 2\n\
+            property double x\nproperty double y\nproperty double z\nproperty int flags\n\
+            property ushort intensity\nend_header\n0 0 0 7 100\n1 2 3 7 300\n";
+        let data = import_slice(text.as_bytes(), "scan".into(), &LoadProgress::new()).unwrap();
+        assert!(data.meshes.is_empty());
+        let cloud = &data.point_clouds[0];
+        assert!(cloud.octree().is_some());
+        let far = cloud
+            .positions
+            .iter()
+            .position(|&p| p == Vec3::new(1.0, 2.0, 3.0));
+        assert_eq!(cloud.intensities[far.unwrap()], 1.0);
+        assert_eq!(data.nodes[0].point_cloud, Some(0));
+    }
+
+    #[test]
+    fn quads_are_triangulated() {
+        let text = "ply\nformat ascii 1.0\nelement vertex 4\n\
+            property float x\nproperty float y\nproperty float z\n\
+            element face 1\nproperty list uchar uint vertex_index\nend_header\n\
+            0 0 0\n1 0 0\n1 1 0\n0 1 0\n4 0 1 2 3\n";
+        let data = import_slice(text.as_bytes(), "quad".into(), &LoadProgress::new()).unwrap();
+        assert_eq!(data.meshes[0].triangle_count(), 2);
+    }
+
+    #[test]
+    fn negative_and_fractional_indices_are_errors() {
+        for index in ["-1", "1.5"] {
+            let text = format!(
+                "ply\nformat ascii 1.0\nelement vertex 3\n\
+                property float x\nproperty float y\nproperty float z\n\
+                element face 2\nproperty list uchar int vertex_index\nend_header\n\
+                0 0 0\n1 0 0\n1 1 0\n3 0 1 2\n3 0 {index} 2\n"
+            );
+            let err = import_slice(text.as_bytes(), "bad".into(), &LoadProgress::new())
+                .err()
+                .unwrap();
+            assert_eq!(
+                err.to_string(),
+                format!("face 1 has invalid vertex index {index}")
+            );
+        }
+    }
+
+    #[test]
+    fn truncated_binary_data_is_an_error() {
+        let bytes = binary_triangle(false);
+        let result = import_slice(
+            &bytes[..bytes.len() - 3],
+            "tri".into(),
+            &LoadProgress::new(),
+        );
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,201 @@
+use std::path::Path;
+
+use anyhow::{Context, anyhow, bail};
+use glam::Vec3;
+
+use crate::core::asset_manager::{
+    loader::LoadProgress,
+    mesh::Mesh,
+    model::{ModelData, ModelNode},
+    vfs::Vfs,
+};
+use crate::core::mesh_processing;
+use crate::core::transform::Transform;
+
+/// CAD parts have many deliberate hard edges; faces meeting at a steeper
+/// angle than this keep separate normals.
+const CREASE_ANGLE_DEGREES: f32 = 30.0;
+
+/// Vertices closer than this fraction of the bounding box diagonal are
+/// merged. STL stores every triangle separately, so shared corners only
+/// match up to float rounding of the exporter.
+const WELD_TOLERANCE: f32 = 1e-6;
+
+const BINARY_HEADER_SIZE: usize = 84;
+const BINARY_TRIANGLE_SIZE: usize = 50;
+
+pub fn import(path: &Path, vfs: &Vfs, progress: &LoadProgress) -> anyhow::Result<ModelData> {
+    let bytes = vfs.read(path)?;
+    let name = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    import_slice(&bytes, name, progress).with_context(|| format!("{}", path.display()))
+}
+
+/// Reads ASCII or binary STL into a single welded mesh with crease-angle
+/// normals. The facet normals stored in the file are ignored; many
+/// exporters write zeros there.
+pub fn import_slice(
+    bytes: &[u8],
+    name: String,
+    progress: &LoadProgress,
+) -> anyhow::Result<ModelData> {
+    let positions = if is_binary(bytes) {
+        read_binary(bytes)
+    } else {
+        read_ascii(bytes, progress)?
+    };
+    progress.check_cancelled()?;
+    progress.set_fraction(0.5);
+
+    let mut mesh = Mesh::new(name.clone());
+    mesh.indices = (0..positions.len() as u32).collect();
+    mesh.positions = positions;
+
+    let (min, max) = mesh.positions.iter().fold(
+        (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+        |(min, max), &p| (min.min(p), max.max(p)),
+    );
+    let diagonal = if mesh.positions.is_empty() {
+        0.0
+    } else {
+        (max - min).length()
+    };
+    mesh_processing::weld(&mut mesh, diagonal * WELD_TOLERANCE);
+    progress.check_cancelled()?;
+    progress.set_fraction(0.75);
+    mesh_processing::compute_normals(&mut mesh, CREASE_ANGLE_DEGREES.to_radians());
+
+    Ok(ModelData {
+        nodes: vec![ModelNode {
+            name,
+            parent: None,
+            transform: Transform::IDENTITY,
+            mesh: Some(0),
+            material: None,
+            point_cloud: None,
+            skin: None,
+            morph_weights: Vec::new(),
+        }],
+        meshes: vec![mesh],
+        ..Default::default()
+    })
+}
+
+/// Binary files start with an 80-byte header that may itself begin with
+/// "solid", so the size implied by the triangle count is the reliable test.
+fn is_binary(bytes: &[u8]) -> bool {
+    if bytes.len() < BINARY_HEADER_SIZE {
+        return false;
+    }
+    let count = u32::from_le_bytes([bytes[80], bytes[81], bytes[82], bytes[83]]) as usize;
+    bytes.len() == BINARY_HEADER_SIZE + count * BINARY_TRIANGLE_SIZE
+}
+
+fn read_binary(bytes: &[u8]) -> Vec<Vec3> {
+    let read_f32 = |b: &[u8]| f32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+    bytes[BINARY_HEADER_SIZE..]
+        .chunks_exact(BINARY_TRIANGLE_SIZE)
+        .flat_map(|triangle| {
+            // A 12-byte facet normal comes first and a 2-byte attribute word
+            // after the vertices; both are unused.
+            let vertices = &triangle[12..48];
+            [0, 12, 24].map(|o| {
+                let v = &vertices[o..o + 12];
+                Vec3::new(read_f32(&v[0..]), read_f32(&v[4..]), read_f32(&v[8..]))
+            })
+        })
+        .collect()
+}
+
+fn read_ascii(bytes: &[u8], progress: &LoadProgress) -> anyhow::Result<Vec<Vec3>> {
+    let text = std::str::from_utf8(bytes).context("STL is neither valid binary nor text")?;
+    if !text.trim_start().starts_with("solid") {
+        bail!("not an STL file");
+    }
+    let mut positions = Vec::new();
+    let mut tokens = text.split_ascii_whitespace();
+    while let Some(token) = tokens.next() {
+        if token != "vertex" {
+            continue;
+        }
+        let mut coordinate = || -> anyhow::Result<f32> {
+            let token = tokens
+                .next()
+                .ok_or_else(|| anyhow!("unexpected end of STL data"))?;
+            token
+                .parse()
+                .map_err(|_| anyhow!("invalid number {token:?} in STL data"))
+        };
+        positions.push(Vec3::new(coordinate()?, coordinate()?, coordinate()?));
+        if positions.len() % (1 << 16) == 0 {
+            progress.check_cancelled()?;
+        }
+    }
+    if positions.len() % 3 != 0 {
+        bail!("STL facet with {} vertices", positions.len() % 3);
+    }
+    Ok(positions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A unit cube's bottom face plus one side face, sharing an edge at 90°.
+    const ASCII: &str = "solid part
+facet normal 0 0 -1
+ outer loop
+  vertex 0 0 0
+  vertex 0 1 0
+  vertex 1 1 0
+ endloop
+endfacet
+facet normal 0 0 -1
+ outer loop
+  vertex 0 0 0
+  vertex 1 1 0
+  vertex 1 0 0
+ endloop
+endfacet
+facet normal 0 -1 0
+ outer loop
+  vertex 0 0 0
+  vertex 1 0 0
+  vertex 1 0 1
+ endloop
+endfacet
+endsolid part
+";
+
+    #[test]
+    fn ascii_is_welded_and_split_at_creases() {
+        let data = import_slice(ASCII.as_bytes(), "part".into(), &LoadProgress::new()).unwrap();
+        let mesh = &data.meshes[0];
+        assert_eq!(mesh.triangle_count(), 3);
+        // The bottom quad shares 4 vertices, the side face adds 3 of its own
+        // because of the 90° crease.
+        assert_eq!(mesh.vertex_count(), 7);
+        assert_eq!(mesh.normals[mesh.indices[0] as usize], -Vec3::Z);
+        assert_eq!(mesh.normals[mesh.indices[6] as usize], -Vec3::Y);
+    }
+
+    #[test]
+    fn binary_with_solid_header_is_detected() {
+        let mut bytes = b"solid but actually binary".to_vec();
+        bytes.resize(80, 0);
+        bytes.extend(1u32.to_le_bytes());
+        bytes.extend([0u8; 12]);
+        for v in [Vec3::ZERO, Vec3::X, Vec3::Y] {
+            for c in v.to_array() {
+                bytes.extend(c.to_le_bytes());
+            }
+        }
+        bytes.extend([0u8; 2]);
+
+        let data = import_slice(&bytes, "tri".into(), &LoadProgress::new()).unwrap();
+        assert_eq!(data.meshes[0].positions, vec![Vec3::ZERO, Vec3::X, Vec3::Y]);
+        assert_eq!(data.meshes[0].normals[0], Vec3::Z);
+    }
+}
@@ -0,0 +1,275 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    path::PathBuf,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        mpsc::{self, Receiver, Sender},
+    },
+    thread::JoinHandle,
+};
+
+use crate::core::asset_manager::{AssetId, AssetKind, AssetManager};
+
+/// Shared between a load task and the worker running it. Importers report
+/// their progress through it and poll it for cancellation.
+pub struct LoadProgress {
+    fraction: AtomicU32,
+    cancelled: AtomicBool,
+}
+
+impl LoadProgress {
+    pub fn new() -> Self {
+        Self {
+            fraction: AtomicU32::new(0.0f32.to_bits()),
+            cancelled: AtomicBool::new(false),
+        }
+    }
+
+    pub fn set_fraction(&self, fraction: f32) {
+        self.fraction
+            .store(fraction.clamp(0.0, 1.0).to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn fraction(&self) -> f32 {
+        f32::from_bits(self.fraction.load(Ordering::Relaxed))
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Returns `Err(Cancelled)` once the load has been cancelled, so importers
+    /// can bail out with `?` between stages.
+    pub fn check_cancelled(&self) -> Result<(), Cancelled> {
+        if self.is_cancelled() {
+            Err(Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Default for LoadProgress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("load cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Runs on the main thread once the worker is done; registers the result with
+/// the `AssetManager`.
+pub(crate) type Finish = Box<dyn FnOnce(&mut AssetManager) + Send>;
+
+struct Job {
+    id: AssetId,
+    generation: u64,
+    run: Box<dyn FnOnce() -> Finish + Send>,
+}
+
+struct Completed {
+    id: AssetId,
+    generation: u64,
+    finish: Finish,
+}
+
+#[derive(Clone)]
+pub struct LoadTask {
+    pub id: AssetId,
+    pub kind: AssetKind,
+    pub path: PathBuf,
+    pub progress: Arc<LoadProgress>,
+    pub reload: bool,
+}
+
+/// A small pool of worker threads doing file IO and parsing. Results come
+/// back over a channel and are applied on the main thread, where the GL
+/// context is current.
+pub(crate) struct AssetLoader {
+    job_tx: Option<Sender<Job>>,
+    result_tx: Sender<Completed>,
+    result_rx: Receiver<Completed>,
+    workers: Vec<JoinHandle<()>>,
+    /// The latest task per asset and its generation; results of older
+    /// generations are stale.
+    tasks: HashMap<AssetId, (u64, LoadTask)>,
+    next_generation: u64,
+}
+
+impl AssetLoader {
+    pub fn new() -> Self {
+        let (result_tx, result_rx) = mpsc::channel();
+        Self {
+            job_tx: None,
+            result_tx,
+            result_rx,
+            workers: Vec::new(),
+            tasks: HashMap::new(),
+            next_generation: 0,
+        }
+    }
+
+    pub fn spawn(&mut self, task: LoadTask, run: impl FnOnce() -> Finish + Send + 'static) {
+        self.next_generation += 1;
+        let job = Job {
+            id: task.id,
+            generation: self.next_generation,
+            run: Box::new(run),
+        };
+        // A newer load of the same asset supersedes the running one.
+        if let Some((_, previous)) = self.tasks.insert(task.id, (job.generation, task)) {
+            previous.progress.cancel();
+        }
+        if let Err(err) = self.job_sender().send(job) {
+            log::error!("asset worker pool is gone, dropping load {}", err.0.id);
+        }
+    }
+
+    fn job_sender(&mut self) -> &Sender<Job> {
+        self.job_tx.get_or_insert_with(|| {
+            let (job_tx, job_rx) = mpsc::channel::<Job>();
+            let job_rx = Arc::new(Mutex::new(job_rx));
+            let count = std::thread::available_parallelism()
+                .map_or(2, |n| n.get())
+                .clamp(1, 4);
+            for i in 0..count {
+                let job_rx = job_rx.clone();
+                let result_tx = self.result_tx.clone();
+                let worker = std::thread::Builder::new()
+                    .name(format!("asset-worker-{i}"))
+                    .spawn(move || worker_loop(job_rx, result_tx))
+                    .expect("failed to spawn asset worker");
+                self.workers.push(worker);
+            }
+            job_tx
+        })
+    }
+
+    pub fn task(&self, id: AssetId) -> Option<&LoadTask> {
+        self.tasks.get(&id).map(|(_, task)| task)
+    }
+
+    pub fn tasks(&self) -> impl Iterator<Item = &LoadTask> {
+        self.tasks.values().map(|(_, task)| task)
+    }
+
+    /// Forgets the task and flags it as cancelled. A result that still
+    /// arrives for it is discarded.
+    pub fn cancel(&mut self, id: AssetId) -> bool {
+        match self.tasks.remove(&id) {
+            Some((_, task)) => {
+                task.progress.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns finished jobs whose tasks have not been cancelled or
+    /// superseded, in whatever order the workers finish them: only the
+    /// latest generation of an asset is accepted.
+    pub fn take_completed(&mut self) -> Vec<(AssetId, Finish)> {
+        let mut completed = Vec::new();
+        while let Ok(done) = self.result_rx.try_recv() {
+            let current = self
+                .tasks
+                .get(&done.id)
+                .is_some_and(|&(generation, _)| generation == done.generation);
+            if current {
+                self.tasks.remove(&done.id);
+                completed.push((done.id, done.finish));
+            }
+        }
+        completed
+    }
+}
+
+impl Drop for AssetLoader {
+    fn drop(&mut self) {
+        for (_, task) in self.tasks.values() {
+            task.progress.cancel();
+        }
+        self.job_tx = None;
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn worker_loop(job_rx: Arc<Mutex<Receiver<Job>>>, result_tx: Sender<Completed>) {
+    loop {
+        let job = match job_rx.lock() {
+            Ok(rx) => rx.recv(),
+            Err(_) => return,
+        };
+        let Ok(job) = job else {
+            return;
+        };
+        let finish = (job.run)();
+        let done = Completed {
+            id: job.id,
+            generation: job.generation,
+            finish,
+        };
+        if result_tx.send(done).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    fn task(id: AssetId) -> LoadTask {
+        LoadTask {
+            id,
+            kind: AssetKind::Model,
+            path: PathBuf::from("model.obj"),
+            progress: Arc::new(LoadProgress::new()),
+            reload: true,
+        }
+    }
+
+    #[test]
+    fn only_the_latest_load_of_an_asset_finishes() {
+        let mut loader = AssetLoader::new();
+        let id = AssetId::next();
+        let applied = Arc::new(Mutex::new(Vec::new()));
+        for (version, delay) in [(1, 50), (2, 0)] {
+            let applied = applied.clone();
+            loader.spawn(task(id), move || {
+                // The older import finishes last when workers run in parallel.
+                std::thread::sleep(Duration::from_millis(delay));
+                Box::new(move |_: &mut AssetManager| applied.lock().unwrap().push(version))
+            });
+        }
+
+        let mut assets = AssetManager::new();
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_millis(300) {
+            for (_, finish) in loader.take_completed() {
+                finish(&mut assets);
+            }
+            std::thread::yield_now();
+        }
+        assert_eq!(*applied.lock().unwrap(), [2]);
+        assert!(loader.task(id).is_none());
+    }
+}
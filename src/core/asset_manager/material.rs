@@ -0,0 +1,117 @@
+use std::mem::size_of;
+
+use glam::{Vec3, Vec4};
+
+use crate::core::asset_manager::texture::Texture;
+use crate::core::asset_manager::{
+    Asset, AssetKind, AssetManager, AssetStorage, Handle, MemoryUsage,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum AlphaMode {
+    #[default]
+    Opaque,
+    Mask,
+    Blend,
+}
+
+/// Texture slots of a material. Importers fill it with indices into their own
+/// texture list, which are swapped for handles once the textures are stored.
+#[derive(Clone, Debug)]
+pub struct MaterialTextures<T> {
+    pub base_color: Option<T>,
+    pub metallic_roughness: Option<T>,
+    pub normal: Option<T>,
+    pub occlusion: Option<T>,
+    pub emissive: Option<T>,
+}
+
+impl<T> MaterialTextures<T> {
+    pub fn map<U>(self, mut f: impl FnMut(T) -> U) -> MaterialTextures<U> {
+        MaterialTextures {
+            base_color: self.base_color.map(&mut f),
+            metallic_roughness: self.metallic_roughness.map(&mut f),
+            normal: self.normal.map(&mut f),
+            occlusion: self.occlusion.map(&mut f),
+            emissive: self.emissive.map(&mut f),
+        }
+    }
+}
+
+impl<T> Default for MaterialTextures<T> {
+    fn default() -> Self {
+        Self {
+            base_color: None,
+            metallic_roughness: None,
+            normal: None,
+            occlusion: None,
+            emissive: None,
+        }
+    }
+}
+
+/// Metallic-roughness PBR material. Holding a `Material` keeps its textures
+/// loaded.
+#[derive(Clone, Debug)]
+pub struct Material<T = Handle<Texture>> {
+    pub name: String,
+    pub base_color: Vec4,
+    pub metallic: f32,
+    pub roughness: f32,
+    pub emissive: Vec3,
+    pub alpha_mode: AlphaMode,
+    pub alpha_cutoff: f32,
+    pub double_sided: bool,
+    pub textures: MaterialTextures<T>,
+}
+
+impl<T> Material<T> {
+    pub fn map_textures<U>(self, f: impl FnMut(T) -> U) -> Material<U> {
+        Material {
+            name: self.name,
+            base_color: self.base_color,
+            metallic: self.metallic,
+            roughness: self.roughness,
+            emissive: self.emissive,
+            alpha_mode: self.alpha_mode,
+            alpha_cutoff: self.alpha_cutoff,
+            double_sided: self.double_sided,
+            textures: self.textures.map(f),
+        }
+    }
+}
+
+impl<T> Default for Material<T> {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            base_color: Vec4::ONE,
+            metallic: 0.0,
+            roughness: 0.5,
+            emissive: Vec3::ZERO,
+            alpha_mode: AlphaMode::Opaque,
+            alpha_cutoff: 0.5,
+            double_sided: false,
+            textures: MaterialTextures::default(),
+        }
+    }
+}
+
+impl Asset for Material {
+    const KIND: AssetKind = AssetKind::Material;
+
+    fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage {
+            cpu_bytes: size_of::<Self>() + self.name.len(),
+            gpu_bytes: 0,
+        }
+    }
+
+    fn storage(assets: &AssetManager) -> &AssetStorage<Self> {
+        &assets.materials
+    }
+
+    fn storage_mut(assets: &mut AssetManager) -> &mut AssetStorage<Self> {
+        &mut assets.materials
+    }
+}
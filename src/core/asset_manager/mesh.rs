@@ -0,0 +1,190 @@
+use std::{mem::size_of, sync::OnceLock};
+
+use glam::{Vec2, Vec3, Vec4};
+
+use crate::core::asset_manager::{
+    Asset, AssetKind, AssetManager, AssetStorage, Handle, MemoryUsage,
+};
+use crate::core::bounds::Aabb;
+use crate::core::renderer::GpuMesh;
+
+/// Triangle mesh in CPU memory. Optional vertex streams are either empty or
+/// have exactly one entry per position.
+#[derive(Default)]
+pub struct Mesh {
+    pub name: String,
+    pub positions: Vec<Vec3>,
+    pub normals: Vec<Vec3>,
+    pub tex_coords: Vec<Vec2>,
+    pub tangents: Vec<Vec4>,
+    pub colors: Vec<Vec4>,
+    /// Up to four joint indices per vertex, into the skin of the node
+    /// drawing the mesh.
+    pub joints: Vec<[u16; 4]>,
+    /// Joint weights per vertex, summing to one.
+    pub weights: Vec<Vec4>,
+    pub morph_targets: Vec<MorphTarget>,
+    /// Weights used when the drawing node does not set its own.
+    pub morph_weights: Vec<f32>,
+    pub indices: Vec<u32>,
+    /// Coarser versions, finest first, that the renderer draws instead of
+    /// this mesh once it covers little of the screen.
+    pub lods: Vec<MeshLod>,
+    pub(crate) gpu: Option<GpuMesh>,
+    pub(crate) upload_error: Option<String>,
+    bounds: OnceLock<Aabb>,
+    surface_area: OnceLock<f32>,
+}
+
+/// Blend shape: offsets added to the base vertices, scaled by the target's
+/// weight. Delta streams are empty or have one entry per vertex.
+#[derive(Clone, Default, Debug)]
+pub struct MorphTarget {
+    pub name: String,
+    pub positions: Vec<Vec3>,
+    pub normals: Vec<Vec3>,
+    pub tangents: Vec<Vec3>,
+}
+
+/// One level of detail of a mesh.
+#[derive(Clone, Debug)]
+pub struct MeshLod {
+    pub mesh: Handle<Mesh>,
+    /// Drawn once the mesh's bounding sphere covers less than this fraction
+    /// of the viewport height.
+    pub screen_coverage: f32,
+}
+
+impl MorphTarget {
+    fn cpu_bytes(&self) -> usize {
+        (self.positions.len() + self.normals.len() + self.tangents.len()) * size_of::<Vec3>()
+    }
+}
+
+impl Mesh {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    pub fn is_skinned(&self) -> bool {
+        !self.joints.is_empty() && self.joints.len() == self.weights.len()
+    }
+
+    pub fn has_morph_targets(&self) -> bool {
+        !self.morph_targets.is_empty()
+    }
+
+    pub fn gpu(&self) -> Option<&GpuMesh> {
+        self.gpu.as_ref()
+    }
+
+    /// Why the last GPU upload failed; it is not retried until the mesh is
+    /// invalidated or replaced.
+    pub fn upload_error(&self) -> Option<&str> {
+        self.upload_error.as_deref()
+    }
+
+    pub(crate) fn needs_upload(&self) -> bool {
+        self.gpu.is_none() && self.upload_error.is_none()
+    }
+
+    /// Object-space bounds, computed on first use.
+    pub fn bounds(&self) -> Aabb {
+        *self
+            .bounds
+            .get_or_init(|| Aabb::from_points(self.positions.iter().copied()))
+    }
+
+    /// Total area of the triangles in object space, computed on first use.
+    pub fn surface_area(&self) -> f32 {
+        *self.surface_area.get_or_init(|| {
+            self.indices
+                .chunks_exact(3)
+                .map(|t| {
+                    let [a, b, c] = [t[0], t[1], t[2]].map(|i| self.positions[i as usize]);
+                    (b - a).cross(c - a).length() * 0.5
+                })
+                .sum()
+        })
+    }
+
+    /// Drops the uploaded buffers and cached bounds so the next upload pass
+    /// picks up edits to the CPU-side data.
+    pub fn invalidate_gpu(&mut self) {
+        self.gpu = None;
+        self.upload_error = None;
+        self.bounds = OnceLock::new();
+        self.surface_area = OnceLock::new();
+    }
+
+    pub fn cpu_bytes(&self) -> usize {
+        self.positions.len() * size_of::<Vec3>()
+            + self.normals.len() * size_of::<Vec3>()
+            + self.tex_coords.len() * size_of::<Vec2>()
+            + self.tangents.len() * size_of::<Vec4>()
+            + self.colors.len() * size_of::<Vec4>()
+            + self.joints.len() * size_of::<[u16; 4]>()
+            + self.weights.len() * size_of::<Vec4>()
+            + self
+                .morph_targets
+                .iter()
+                .map(MorphTarget::cpu_bytes)
+                .sum::<usize>()
+            + self.morph_weights.len() * size_of::<f32>()
+            + self.indices.len() * size_of::<u32>()
+    }
+}
+
+/// Copies the CPU-side data; the copy uploads its own GPU buffers.
+impl Clone for Mesh {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            positions: self.positions.clone(),
+            normals: self.normals.clone(),
+            tex_coords: self.tex_coords.clone(),
+            tangents: self.tangents.clone(),
+            colors: self.colors.clone(),
+            joints: self.joints.clone(),
+            weights: self.weights.clone(),
+            morph_targets: self.morph_targets.clone(),
+            morph_weights: self.morph_weights.clone(),
+            indices: self.indices.clone(),
+            lods: self.lods.clone(),
+            gpu: None,
+            upload_error: None,
+            bounds: OnceLock::new(),
+            surface_area: OnceLock::new(),
+        }
+    }
+}
+
+impl Asset for Mesh {
+    const KIND: AssetKind = AssetKind::Mesh;
+
+    fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage {
+            cpu_bytes: self.cpu_bytes(),
+            gpu_bytes: self.gpu.as_ref().map_or(0, GpuMesh::gpu_bytes),
+        }
+    }
+
+    fn storage(assets: &AssetManager) -> &AssetStorage<Self> {
+        &assets.meshes
+    }
+
+    fn storage_mut(assets: &mut AssetManager) -> &mut AssetStorage<Self> {
+        &mut assets.meshes
+    }
+}
@@ -0,0 +1,219 @@
+use std::{
+    mem::size_of,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::core::animation::{AnimationClip, Skin};
+
+use crate::core::asset_manager::{
+    Asset, AssetKind, AssetManager, AssetStorage, Handle, LoadProgress, LoadableAsset, MemoryUsage,
+    Vfs, importers, material::Material, mesh::Mesh, mesh::MeshLod, point_cloud::PointCloud,
+    texture::Texture,
+};
+use crate::core::transform::Transform;
+
+/// One node of an imported hierarchy. `mesh`, `material`, `point_cloud` and
+/// `skin` index into the owning model's lists; parents always come before
+/// their children.
+#[derive(Clone, Debug)]
+pub struct ModelNode {
+    pub name: String,
+    pub parent: Option<usize>,
+    pub transform: Transform,
+    pub mesh: Option<usize>,
+    pub material: Option<usize>,
+    pub point_cloud: Option<usize>,
+    pub skin: Option<usize>,
+    /// Overrides the mesh's default morph weights when not empty.
+    pub morph_weights: Vec<f32>,
+}
+
+/// Importer output before anything is registered with the `AssetManager`.
+#[derive(Default)]
+pub struct ModelData {
+    pub nodes: Vec<ModelNode>,
+    pub meshes: Vec<Mesh>,
+    pub materials: Vec<Material<usize>>,
+    pub textures: Vec<Texture>,
+    pub point_clouds: Vec<PointCloud>,
+    pub skins: Vec<Skin<usize>>,
+    pub animations: Vec<AnimationClip>,
+    /// Levels of detail by mesh index: the index of each coarser mesh and
+    /// the screen coverage below which it is drawn, finest first.
+    pub lods: Vec<(usize, Vec<(usize, f32)>)>,
+    /// External files read besides the main one, for hot reload.
+    pub dependencies: Vec<PathBuf>,
+}
+
+/// An imported file: a node hierarchy plus handles to the meshes, materials
+/// and textures it uses. Spawning it into a `Scene` copies the hierarchy.
+pub struct Model {
+    pub nodes: Vec<ModelNode>,
+    pub meshes: Vec<Handle<Mesh>>,
+    pub materials: Vec<Handle<Material>>,
+    pub textures: Vec<Handle<Texture>>,
+    pub point_clouds: Vec<Handle<PointCloud>>,
+    /// Joints are model node indices.
+    pub skins: Vec<Skin<usize>>,
+    /// Channels target model node indices.
+    pub animations: Arc<[AnimationClip]>,
+}
+
+impl Model {
+    pub fn mesh(&self, node: &ModelNode) -> Option<&Handle<Mesh>> {
+        node.mesh.and_then(|i| self.meshes.get(i))
+    }
+
+    pub fn material(&self, node: &ModelNode) -> Option<&Handle<Material>> {
+        node.material.and_then(|i| self.materials.get(i))
+    }
+
+    pub fn point_cloud(&self, node: &ModelNode) -> Option<&Handle<PointCloud>> {
+        node.point_cloud.and_then(|i| self.point_clouds.get(i))
+    }
+
+    pub fn skin(&self, node: &ModelNode) -> Option<&Skin<usize>> {
+        node.skin.and_then(|i| self.skins.get(i))
+    }
+}
+
+impl Asset for Model {
+    const KIND: AssetKind = AssetKind::Model;
+
+    fn memory_usage(&self) -> MemoryUsage {
+        let names: usize = self.nodes.iter().map(|n| n.name.len()).sum();
+        MemoryUsage {
+            cpu_bytes: self.nodes.len() * size_of::<ModelNode>() + names,
+            gpu_bytes: 0,
+        }
+    }
+
+    fn storage(assets: &AssetManager) -> &AssetStorage<Self> {
+        &assets.models
+    }
+
+    fn storage_mut(assets: &mut AssetManager) -> &mut AssetStorage<Self> {
+        &mut assets.models
+    }
+}
+
+impl LoadableAsset for Model {
+    type Data = ModelData;
+
+    fn import(path: &Path, vfs: &Vfs, progress: &LoadProgress) -> anyhow::Result<Self::Data> {
+        importers::import_model(path, vfs, progress)
+    }
+
+    fn finish(data: Self::Data, assets: &mut AssetManager) -> Self {
+        let textures: Vec<Handle<Texture>> =
+            data.textures.into_iter().map(|t| assets.add(t)).collect();
+        let materials = data
+            .materials
+            .into_iter()
+            .map(|m| assets.add(m.map_textures(|i| textures[i].clone())))
+            .collect();
+        let meshes: Vec<Handle<Mesh>> = data.meshes.into_iter().map(|m| assets.add(m)).collect();
+        attach_lods(assets, &meshes, &data.lods);
+        let point_clouds = data
+            .point_clouds
+            .into_iter()
+            .map(|p| assets.add(p))
+            .collect();
+        Model {
+            nodes: data.nodes,
+            meshes,
+            materials,
+            textures,
+            point_clouds,
+            skins: data.skins,
+            animations: data.animations.into(),
+        }
+    }
+
+    fn dependencies(data: &Self::Data) -> Vec<PathBuf> {
+        data.dependencies.clone()
+    }
+
+    /// Sub-assets are replaced in place, by index, so that handles held by
+    /// scene nodes pick up the new data. Anything the new version adds gets
+    /// fresh handles.
+    fn reload(
+        data: Self::Data,
+        previous: &Self,
+        assets: &mut AssetManager,
+    ) -> anyhow::Result<Self> {
+        let textures: Vec<Handle<Texture>> = data
+            .textures
+            .into_iter()
+            .enumerate()
+            .map(|(i, texture)| replace_or_add(assets, previous.textures.get(i), texture))
+            .collect();
+        let materials = data
+            .materials
+            .into_iter()
+            .enumerate()
+            .map(|(i, material)| {
+                let material = material.map_textures(|t| textures[t].clone());
+                replace_or_add(assets, previous.materials.get(i), material)
+            })
+            .collect();
+        let meshes: Vec<Handle<Mesh>> = data
+            .meshes
+            .into_iter()
+            .enumerate()
+            .map(|(i, mesh)| replace_or_add(assets, previous.meshes.get(i), mesh))
+            .collect();
+        attach_lods(assets, &meshes, &data.lods);
+        let point_clouds = data
+            .point_clouds
+            .into_iter()
+            .enumerate()
+            .map(|(i, points)| replace_or_add(assets, previous.point_clouds.get(i), points))
+            .collect();
+        Ok(Model {
+            nodes: data.nodes,
+            meshes,
+            materials,
+            textures,
+            point_clouds,
+            skins: data.skins,
+            animations: data.animations.into(),
+        })
+    }
+}
+
+fn attach_lods(
+    assets: &mut AssetManager,
+    meshes: &[Handle<Mesh>],
+    lods: &[(usize, Vec<(usize, f32)>)],
+) {
+    for (base, levels) in lods {
+        let levels = levels
+            .iter()
+            .filter_map(|&(mesh, screen_coverage)| {
+                Some(MeshLod {
+                    mesh: meshes.get(mesh)?.clone(),
+                    screen_coverage,
+                })
+            })
+            .collect();
+        if let Some(mesh) = meshes.get(*base).and_then(|mesh| assets.get_mut(mesh)) {
+            mesh.lods = levels;
+        }
+    }
+}
+
+fn replace_or_add<T: Asset>(
+    assets: &mut AssetManager,
+    previous: Option<&Handle<T>>,
+    asset: T,
+) -> Handle<T> {
+    match previous {
+        Some(handle) => {
+            T::storage_mut(assets).set_loaded(handle.id(), asset);
+            handle.clone()
+        }
+        None => assets.add(asset),
+    }
+}
@@ -0,0 +1,105 @@
+pub mod octree;
+
+use std::{
+    mem::{size_of, size_of_val},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use glam::{Vec3, Vec4};
+
+use crate::core::asset_manager::{Asset, AssetKind, AssetManager, AssetStorage, MemoryUsage};
+use crate::core::bounds::Aabb;
+
+pub use octree::{OctreeNode, PointOctree};
+
+static NEXT_REVISION: AtomicU64 = AtomicU64::new(1);
+
+/// Unconnected points, e.g. from a laser scan. Optional streams are either
+/// empty or have exactly one entry per position. Intensities are normalized
+/// to `0..=1`.
+#[derive(Default)]
+pub struct PointCloud {
+    pub name: String,
+    pub positions: Vec<Vec3>,
+    pub normals: Vec<Vec3>,
+    pub colors: Vec<Vec4>,
+    pub intensities: Vec<f32>,
+    octree: Option<PointOctree>,
+    bounds: Aabb,
+    revision: u64,
+}
+
+impl PointCloud {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn point_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Builds the level-of-detail octree, reordering the point streams so that
+    /// every node's points are contiguous. Call again after editing points;
+    /// the renderer only draws clouds that have an octree.
+    pub fn build_octree(&mut self) {
+        let (octree, order) = PointOctree::build(&self.positions);
+        fn reorder<T: Copy>(stream: &mut Vec<T>, order: &[u32]) {
+            if !stream.is_empty() {
+                *stream = order.iter().map(|&i| stream[i as usize]).collect();
+            }
+        }
+        reorder(&mut self.positions, &order);
+        reorder(&mut self.normals, &order);
+        reorder(&mut self.colors, &order);
+        reorder(&mut self.intensities, &order);
+        self.octree = Some(octree);
+        self.bounds = Aabb::from_points(self.positions.iter().copied());
+        self.revision = NEXT_REVISION.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn octree(&self) -> Option<&PointOctree> {
+        self.octree.as_ref()
+    }
+
+    /// Tight bounds of the points as of the last `build_octree`.
+    pub fn bounds(&self) -> Aabb {
+        self.bounds
+    }
+
+    /// Changes whenever the octree is rebuilt, so GPU caches keyed by it can
+    /// tell stale chunks apart.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn cpu_bytes(&self) -> usize {
+        self.positions.len() * size_of::<Vec3>()
+            + self.normals.len() * size_of::<Vec3>()
+            + self.colors.len() * size_of::<Vec4>()
+            + self.intensities.len() * size_of::<f32>()
+            + self.octree.as_ref().map_or(0, |o| size_of_val(o.nodes()))
+    }
+}
+
+impl Asset for PointCloud {
+    const KIND: AssetKind = AssetKind::PointCloud;
+
+    /// GPU memory is owned by the renderer's chunk cache and reported there.
+    fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage {
+            cpu_bytes: self.cpu_bytes(),
+            gpu_bytes: 0,
+        }
+    }
+
+    fn storage(assets: &AssetManager) -> &AssetStorage<Self> {
+        &assets.point_clouds
+    }
+
+    fn storage_mut(assets: &mut AssetManager) -> &mut AssetStorage<Self> {
+        &mut assets.point_clouds
+    }
+}
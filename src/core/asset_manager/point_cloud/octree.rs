@@ -0,0 +1,180 @@
+use std::collections::HashSet;
+
+use glam::Vec3;
+
+use crate::core::bounds::Aabb;
+
+/// Nodes holding at most this many points are not split further.
+pub const MAX_POINTS_PER_NODE: usize = 20_000;
+
+/// Inner nodes keep one point per cell of a grid this fine; the rest moves
+/// down to the children.
+const SAMPLE_GRID: u32 = 48;
+
+const MAX_DEPTH: u8 = 20;
+
+/// One octree cell. Its points are `start..start + count` of the owning
+/// point cloud's streams: a coarse subsample for inner nodes, all remaining
+/// points for leaves. Drawing a node together with its ancestors gives a
+/// uniformly dense preview of the cell.
+#[derive(Clone, Debug)]
+pub struct OctreeNode {
+    pub bounds: Aabb,
+    pub start: u32,
+    pub count: u32,
+    pub depth: u8,
+    children: [u32; 8],
+}
+
+impl OctreeNode {
+    /// Indices of child nodes in `PointOctree::nodes`.
+    pub fn children(&self) -> impl Iterator<Item = usize> + '_ {
+        self.children
+            .iter()
+            .filter(|&&c| c != 0)
+            .map(|&c| c as usize)
+    }
+
+    pub fn is_leaf(&self) -> bool {
+        self.children.iter().all(|&c| c == 0)
+    }
+}
+
+/// Level-of-detail hierarchy over a point cloud; node 0 is the root.
+#[derive(Clone, Debug, Default)]
+pub struct PointOctree {
+    nodes: Vec<OctreeNode>,
+}
+
+impl PointOctree {
+    /// Builds the hierarchy and returns it together with the point order it
+    /// expects: `order[i]` is the original index of the point stored at `i`.
+    pub fn build(positions: &[Vec3]) -> (Self, Vec<u32>) {
+        let mut octree = Self::default();
+        let mut order = Vec::with_capacity(positions.len());
+        if positions.is_empty() {
+            return (octree, order);
+        }
+        let bounds = Aabb::from_points(positions.iter().copied());
+        // Cubic cells keep the subsampling grid isotropic.
+        let half = bounds.extents().max_element().max(f32::EPSILON) * 0.5;
+        let cube = Aabb::new(bounds.center() - half, bounds.center() + half);
+        let all = (0..positions.len() as u32).collect();
+        octree.build_node(positions, all, cube, 0, &mut order);
+        (octree, order)
+    }
+
+    fn build_node(
+        &mut self,
+        positions: &[Vec3],
+        indices: Vec<u32>,
+        bounds: Aabb,
+        depth: u8,
+        order: &mut Vec<u32>,
+    ) -> usize {
+        let index = self.nodes.len();
+        self.nodes.push(OctreeNode {
+            bounds,
+            start: order.len() as u32,
+            count: 0,
+            depth,
+            children: [0; 8],
+        });
+
+        if indices.len() <= MAX_POINTS_PER_NODE || depth >= MAX_DEPTH {
+            self.nodes[index].count = indices.len() as u32;
+            order.extend(indices);
+            return index;
+        }
+
+        let cell_size = bounds.extents() / SAMPLE_GRID as f32;
+        let mut taken = HashSet::new();
+        let mut rest = Vec::with_capacity(indices.len());
+        for i in indices {
+            let cell = ((positions[i as usize] - bounds.min) / cell_size)
+                .as_uvec3()
+                .min(glam::UVec3::splat(SAMPLE_GRID - 1));
+            if taken.insert(cell) {
+                order.push(i);
+            } else {
+                rest.push(i);
+            }
+        }
+        self.nodes[index].count = taken.len() as u32;
+
+        let center = bounds.center();
+        let mut octants: [Vec<u32>; 8] = Default::default();
+        for i in rest {
+            octants[octant(positions[i as usize], center)].push(i);
+        }
+        for (o, points) in octants.into_iter().enumerate() {
+            if points.is_empty() {
+                continue;
+            }
+            let child_bounds = child_bounds(&bounds, o);
+            let child = self.build_node(positions, points, child_bounds, depth + 1, order);
+            self.nodes[index].children[o] = child as u32;
+        }
+        index
+    }
+
+    pub fn nodes(&self) -> &[OctreeNode] {
+        &self.nodes
+    }
+
+    pub fn root(&self) -> Option<&OctreeNode> {
+        self.nodes.first()
+    }
+}
+
+fn octant(p: Vec3, center: Vec3) -> usize {
+    (p.x >= center.x) as usize
+        | ((p.y >= center.y) as usize) << 1
+        | ((p.z >= center.z) as usize) << 2
+}
+
+fn child_bounds(bounds: &Aabb, octant: usize) -> Aabb {
+    let center = bounds.center();
+    let pick = |bit: usize, axis: usize| {
+        if octant & bit != 0 {
+            (center[axis], bounds.max[axis])
+        } else {
+            (bounds.min[axis], center[axis])
+        }
+    };
+    let (x0, x1) = pick(1, 0);
+    let (y0, y1) = pick(2, 1);
+    let (z0, z1) = pick(4, 2);
+    Aabb::new(Vec3::new(x0, y0, z0), Vec3::new(x1, y1, z1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_point_lands_in_exactly_one_node() {
+        // A dense plane, so inner nodes subsample and leaves get the rest.
+        let side = 400;
+        let positions: Vec<Vec3> = (0..side * side)
+            .map(|i| Vec3::new((i % side) as f32, 0.0, (i / side) as f32))
+            .collect();
+        let (octree, order) = PointOctree::build(&positions);
+
+        assert!(octree.nodes().len() > 1);
+        let total: u32 = octree.nodes().iter().map(|n| n.count).sum();
+        assert_eq!(total as usize, positions.len());
+        let mut seen = order.clone();
+        seen.sort_unstable();
+        seen.dedup();
+        assert_eq!(seen.len(), positions.len());
+
+        for node in octree.nodes() {
+            for i in node.start..node.start + node.count {
+                let p = positions[order[i as usize] as usize];
+                assert!(node.bounds.contains(p));
+            }
+            assert!(node.count as usize <= MAX_POINTS_PER_NODE || !node.is_leaf());
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use std::path::Path;
+
+use anyhow::anyhow;
+
+use crate::core::asset_manager::{
+    Asset, AssetKind, AssetManager, AssetStorage, LoadProgress, LoadableAsset, MemoryUsage, Vfs,
+};
+use crate::core::renderer::ShaderProgram;
+
+/// GLSL source of a vertex + fragment program, see `ShaderProgram` for the
+/// file layout. The program is compiled during the GPU upload pass.
+pub struct Shader {
+    pub name: String,
+    pub source: String,
+    pub(crate) program: Option<ShaderProgram>,
+    pub(crate) compile_error: Option<String>,
+}
+
+impl Shader {
+    pub fn from_source(name: impl Into<String>, source: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            source: source.into(),
+            program: None,
+            compile_error: None,
+        }
+    }
+
+    pub fn program(&self) -> Option<&ShaderProgram> {
+        self.program.as_ref()
+    }
+
+    pub fn compile_error(&self) -> Option<&str> {
+        self.compile_error.as_deref()
+    }
+
+    pub(crate) fn compile(&mut self, gl: &std::sync::Arc<glow::Context>) -> anyhow::Result<()> {
+        match ShaderProgram::compile(gl.clone(), &self.name, &self.source) {
+            Ok(program) => {
+                self.program = Some(program);
+                self.compile_error = None;
+                Ok(())
+            }
+            Err(err) => {
+                self.compile_error = Some(format!("{err:#}"));
+                Err(err)
+            }
+        }
+    }
+}
+
+impl Asset for Shader {
+    const KIND: AssetKind = AssetKind::Shader;
+
+    fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage {
+            cpu_bytes: self.source.len(),
+            gpu_bytes: 0,
+        }
+    }
+
+    fn storage(assets: &AssetManager) -> &AssetStorage<Self> {
+        &assets.shaders
+    }
+
+    fn storage_mut(assets: &mut AssetManager) -> &mut AssetStorage<Self> {
+        &mut assets.shaders
+    }
+}
+
+impl LoadableAsset for Shader {
+    type Data = Shader;
+
+    fn import(path: &Path, vfs: &Vfs, _progress: &LoadProgress) -> anyhow::Result<Self::Data> {
+        let source = vfs.read_to_string(path)?;
+        let name = path
+            .file_name()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Ok(Shader::from_source(name, source))
+    }
+
+    fn finish(data: Self::Data, _assets: &mut AssetManager) -> Self {
+        data
+    }
+
+    /// Compiles right away so that a broken edit never replaces a working
+    /// program.
+    fn reload(
+        mut data: Self::Data,
+        _previous: &Self,
+        assets: &mut AssetManager,
+    ) -> anyhow::Result<Self> {
+        let gl = assets
+            .gl
+            .clone()
+            .ok_or_else(|| anyhow!("no GL context to compile {}", data.name))?;
+        data.compile(&gl)?;
+        Ok(data)
+    }
+}
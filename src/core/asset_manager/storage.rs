@@ -0,0 +1,216 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, Sender},
+};
+
+use crate::core::asset_manager::{
+    handle::{AssetId, Handle, WeakHandle},
+    vfs::normalize,
+};
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum AssetState {
+    Loading,
+    Loaded,
+    Failed(String),
+    /// The storage has no such asset: it was never added or has been
+    /// unloaded since.
+    Unloaded,
+}
+
+enum Slot<T> {
+    Loading,
+    Loaded(T),
+    Failed(String),
+}
+
+struct Entry<T> {
+    slot: Slot<T>,
+    path: Option<PathBuf>,
+    weak: WeakHandle<T>,
+}
+
+/// All assets of one type, keyed by id. Entries are removed by
+/// `collect_unused` once every strong handle to them has been dropped.
+/// Paths are normalized, so `./a.glb` and `a.glb` name the same asset.
+pub struct AssetStorage<T> {
+    entries: HashMap<AssetId, Entry<T>>,
+    by_path: HashMap<PathBuf, AssetId>,
+    drop_tx: Sender<AssetId>,
+    drop_rx: Receiver<AssetId>,
+}
+
+impl<T> AssetStorage<T> {
+    pub fn new() -> Self {
+        let (drop_tx, drop_rx) = mpsc::channel();
+        Self {
+            entries: HashMap::new(),
+            by_path: HashMap::new(),
+            drop_tx,
+            drop_rx,
+        }
+    }
+
+    /// Creates an entry in the `Loading` state. The caller is expected to
+    /// follow up with `set_loaded` or `set_failed`.
+    pub(crate) fn reserve(&mut self, path: Option<PathBuf>) -> Handle<T> {
+        let id = AssetId::next();
+        let handle = Handle::new(id, self.drop_tx.clone());
+        let path = path.map(|path| normalize(&path));
+        if let Some(path) = &path {
+            self.by_path.insert(path.clone(), id);
+        }
+        self.entries.insert(
+            id,
+            Entry {
+                slot: Slot::Loading,
+                path,
+                weak: handle.downgrade(),
+            },
+        );
+        handle
+    }
+
+    pub fn insert(&mut self, asset: T) -> Handle<T> {
+        let handle = self.reserve(None);
+        self.set_loaded(handle.id(), asset);
+        handle
+    }
+
+    /// Returns a new strong handle to the asset loaded from `path`, if it is
+    /// still referenced somewhere.
+    pub fn find_by_path(&self, path: &Path) -> Option<Handle<T>> {
+        let id = self.by_path.get(&normalize(path))?;
+        self.entries.get(id)?.weak.upgrade()
+    }
+
+    pub(crate) fn set_loaded(&mut self, id: AssetId, asset: T) {
+        if let Some(entry) = self.entries.get_mut(&id) {
+            entry.slot = Slot::Loaded(asset);
+        }
+    }
+
+    /// Puts a failed entry back into the `Loading` state for another
+    /// attempt.
+    pub(crate) fn set_loading(&mut self, id: AssetId) {
+        if let Some(entry) = self.entries.get_mut(&id) {
+            entry.slot = Slot::Loading;
+        }
+    }
+
+    /// Moves the asset out, leaving the entry in the `Loading` state until
+    /// `set_loaded` puts a value back.
+    pub(crate) fn take(&mut self, id: AssetId) -> Option<T> {
+        let entry = self.entries.get_mut(&id)?;
+        match std::mem::replace(&mut entry.slot, Slot::Loading) {
+            Slot::Loaded(asset) => Some(asset),
+            other => {
+                entry.slot = other;
+                None
+            }
+        }
+    }
+
+    pub(crate) fn set_failed(&mut self, id: AssetId, error: String) {
+        if let Some(entry) = self.entries.get_mut(&id) {
+            entry.slot = Slot::Failed(error);
+        }
+    }
+
+    pub fn get(&self, handle: &Handle<T>) -> Option<&T> {
+        self.get_by_id(handle.id())
+    }
+
+    pub fn get_mut(&mut self, handle: &Handle<T>) -> Option<&mut T> {
+        self.get_by_id_mut(handle.id())
+    }
+
+    pub fn get_by_id(&self, id: AssetId) -> Option<&T> {
+        match &self.entries.get(&id)?.slot {
+            Slot::Loaded(asset) => Some(asset),
+            _ => None,
+        }
+    }
+
+    pub fn get_by_id_mut(&mut self, id: AssetId) -> Option<&mut T> {
+        match &mut self.entries.get_mut(&id)?.slot {
+            Slot::Loaded(asset) => Some(asset),
+            _ => None,
+        }
+    }
+
+    pub fn state(&self, id: AssetId) -> Option<AssetState> {
+        Some(match &self.entries.get(&id)?.slot {
+            Slot::Loading => AssetState::Loading,
+            Slot::Loaded(_) => AssetState::Loaded,
+            Slot::Failed(error) => AssetState::Failed(error.clone()),
+        })
+    }
+
+    pub fn path(&self, id: AssetId) -> Option<&Path> {
+        self.entries.get(&id)?.path.as_deref()
+    }
+
+    pub fn contains(&self, id: AssetId) -> bool {
+        self.entries.contains_key(&id)
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = AssetId> + '_ {
+        self.entries.keys().copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (AssetId, &T)> {
+        self.entries
+            .iter()
+            .filter_map(|(id, entry)| match &entry.slot {
+                Slot::Loaded(asset) => Some((*id, asset)),
+                _ => None,
+            })
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (AssetId, &mut T)> {
+        self.entries
+            .iter_mut()
+            .filter_map(|(id, entry)| match &mut entry.slot {
+                Slot::Loaded(asset) => Some((*id, asset)),
+                _ => None,
+            })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Removes every asset whose last strong handle has been dropped and
+    /// returns their ids.
+    pub fn collect_unused(&mut self) -> Vec<AssetId> {
+        let mut removed = Vec::new();
+        while let Ok(id) = self.drop_rx.try_recv() {
+            let Some(entry) = self.entries.get(&id) else {
+                continue;
+            };
+            if entry.weak.is_alive() {
+                continue;
+            }
+            let entry = self.entries.remove(&id).unwrap();
+            if let Some(path) = &entry.path
+                && self.by_path.get(path) == Some(&id)
+            {
+                self.by_path.remove(path);
+            }
+            removed.push(id);
+        }
+        removed
+    }
+}
+
+impl<T> Default for AssetStorage<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,121 @@
+use std::path::Path;
+
+use anyhow::Context;
+
+use crate::core::asset_manager::{
+    Asset, AssetKind, AssetManager, AssetStorage, LoadProgress, LoadableAsset, MemoryUsage, Vfs,
+};
+use crate::core::renderer::GpuTexture;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ColorSpace {
+    Srgb,
+    Linear,
+}
+
+pub struct Texture {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    pub color_space: ColorSpace,
+    /// Tightly packed RGBA8 rows, top row first.
+    pub pixels: Vec<u8>,
+    pub(crate) gpu: Option<GpuTexture>,
+    pub(crate) upload_error: Option<String>,
+}
+
+impl Texture {
+    pub fn from_rgba8(
+        name: impl Into<String>,
+        width: u32,
+        height: u32,
+        pixels: Vec<u8>,
+        color_space: ColorSpace,
+    ) -> Self {
+        debug_assert_eq!(pixels.len(), (width * height * 4) as usize);
+        Self {
+            name: name.into(),
+            width,
+            height,
+            color_space,
+            pixels,
+            gpu: None,
+            upload_error: None,
+        }
+    }
+
+    pub fn decode(
+        name: impl Into<String>,
+        bytes: &[u8],
+        color_space: ColorSpace,
+    ) -> anyhow::Result<Self> {
+        let image = image::load_from_memory(bytes)
+            .context("failed to decode image")?
+            .to_rgba8();
+        let (width, height) = image.dimensions();
+        Ok(Self::from_rgba8(
+            name,
+            width,
+            height,
+            image.into_raw(),
+            color_space,
+        ))
+    }
+
+    pub fn gpu(&self) -> Option<&GpuTexture> {
+        self.gpu.as_ref()
+    }
+
+    /// Why the last GPU upload failed; it is not retried until the texture
+    /// is invalidated or replaced.
+    pub fn upload_error(&self) -> Option<&str> {
+        self.upload_error.as_deref()
+    }
+
+    pub(crate) fn needs_upload(&self) -> bool {
+        self.gpu.is_none() && self.upload_error.is_none()
+    }
+
+    pub fn invalidate_gpu(&mut self) {
+        self.gpu = None;
+        self.upload_error = None;
+    }
+}
+
+impl Asset for Texture {
+    const KIND: AssetKind = AssetKind::Texture;
+
+    fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage {
+            cpu_bytes: self.pixels.len(),
+            gpu_bytes: self.gpu.as_ref().map_or(0, GpuTexture::gpu_bytes),
+        }
+    }
+
+    fn storage(assets: &AssetManager) -> &AssetStorage<Self> {
+        &assets.textures
+    }
+
+    fn storage_mut(assets: &mut AssetManager) -> &mut AssetStorage<Self> {
+        &mut assets.textures
+    }
+}
+
+impl LoadableAsset for Texture {
+    type Data = Texture;
+
+    fn import(path: &Path, vfs: &Vfs, progress: &LoadProgress) -> anyhow::Result<Self::Data> {
+        let bytes = vfs.read(path)?;
+        progress.check_cancelled()?;
+        progress.set_fraction(0.5);
+        let name = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Texture::decode(name, &bytes, ColorSpace::Srgb)
+    }
+
+    fn finish(data: Self::Data, _assets: &mut AssetManager) -> Self {
+        data
+    }
+}
@@ -0,0 +1,218 @@
+pub mod archive;
+pub mod directory;
+pub mod memory;
+
+use std::{
+    path::{Component, Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+use anyhow::{Context, anyhow};
+
+pub use archive::ZipArchiveFiles;
+pub use directory::DirectoryFiles;
+pub use memory::MemoryFiles;
+
+/// A source of files mounted into the `Vfs`. Paths handed to a provider are
+/// relative to its mount point and already normalized.
+pub trait FileProvider: Send + Sync {
+    fn read(&self, path: &Path) -> anyhow::Result<Vec<u8>>;
+    fn exists(&self, path: &Path) -> bool;
+
+    /// The file on disk whose changes affect `path`, if there is one. Hot
+    /// reload watches it.
+    fn watch_path(&self, _path: &Path) -> Option<PathBuf> {
+        None
+    }
+}
+
+struct Mount {
+    prefix: PathBuf,
+    provider: Arc<dyn FileProvider>,
+}
+
+/// Maps virtual paths onto mounted providers. Later mounts shadow earlier
+/// ones; paths that match no mount are read from the native filesystem, so
+/// plain paths (e.g. dropped files) keep working.
+///
+/// Clones share the mount table, which lets importers on worker threads read
+/// through the same view as the main thread.
+#[derive(Clone, Default)]
+pub struct Vfs {
+    mounts: Arc<RwLock<Vec<Mount>>>,
+}
+
+impl Vfs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn mount(&self, prefix: impl AsRef<Path>, provider: impl FileProvider + 'static) {
+        self.mount_shared(prefix, Arc::new(provider));
+    }
+
+    pub fn mount_shared(&self, prefix: impl AsRef<Path>, provider: Arc<dyn FileProvider>) {
+        let prefix = normalize(prefix.as_ref());
+        self.mounts
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(Mount { prefix, provider });
+    }
+
+    /// Removes every mount at `prefix` and returns how many there were.
+    pub fn unmount(&self, prefix: impl AsRef<Path>) -> usize {
+        let prefix = normalize(prefix.as_ref());
+        let mut mounts = self.mounts.write().unwrap_or_else(|e| e.into_inner());
+        let before = mounts.len();
+        mounts.retain(|m| m.prefix != prefix);
+        before - mounts.len()
+    }
+
+    /// Resolves `reference` as written inside the file at `base`: relative
+    /// references are taken from `base`'s directory, absolute ones as-is.
+    pub fn resolve(base: &Path, reference: impl AsRef<Path>) -> PathBuf {
+        let reference = reference.as_ref();
+        if reference.is_absolute() {
+            return normalize(reference);
+        }
+        let dir = base.parent().unwrap_or(Path::new(""));
+        normalize(&dir.join(reference))
+    }
+
+    pub fn read(&self, path: impl AsRef<Path>) -> anyhow::Result<Vec<u8>> {
+        let path = normalize(path.as_ref());
+        match self.find(&path, |provider, rel| provider.exists(rel)) {
+            Some((provider, rel)) => provider
+                .read(&rel)
+                .with_context(|| format!("failed to read {}", path.display())),
+            None => {
+                std::fs::read(&path).with_context(|| format!("failed to read {}", path.display()))
+            }
+        }
+    }
+
+    pub fn read_to_string(&self, path: impl AsRef<Path>) -> anyhow::Result<String> {
+        let path = path.as_ref();
+        let bytes = self.read(path)?;
+        String::from_utf8(bytes).map_err(|_| anyhow!("{} is not valid UTF-8", path.display()))
+    }
+
+    pub fn exists(&self, path: impl AsRef<Path>) -> bool {
+        let path = normalize(path.as_ref());
+        match self.find(&path, |provider, rel| provider.exists(rel)) {
+            Some((provider, rel)) => provider.exists(&rel),
+            None => path.is_file(),
+        }
+    }
+
+    /// The native file to watch for changes of `path`, if any.
+    pub fn watch_path(&self, path: impl AsRef<Path>) -> Option<PathBuf> {
+        let path = normalize(path.as_ref());
+        match self.find(&path, |_, _| true) {
+            Some((provider, rel)) => provider.watch_path(&rel),
+            None => Some(path),
+        }
+    }
+
+    /// Picks the most recently mounted provider whose prefix matches `path`
+    /// and that `accept`s the file, falling back to the newest prefix match.
+    fn find(
+        &self,
+        path: &Path,
+        accept: impl Fn(&dyn FileProvider, &Path) -> bool,
+    ) -> Option<(Arc<dyn FileProvider>, PathBuf)> {
+        let mounts = self.mounts.read().unwrap_or_else(|e| e.into_inner());
+        let mut fallback = None;
+        for mount in mounts.iter().rev() {
+            let Ok(rel) = path.strip_prefix(&mount.prefix) else {
+                continue;
+            };
+            if accept(mount.provider.as_ref(), rel) {
+                return Some((mount.provider.clone(), rel.to_path_buf()));
+            }
+            fallback.get_or_insert_with(|| (mount.provider.clone(), rel.to_path_buf()));
+        }
+        fallback
+    }
+}
+
+/// Lexically removes `.` and `..` components. `..` past the start of a
+/// relative path is kept, past the root it is dropped.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match out.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    out.pop();
+                }
+                Some(Component::RootDir | Component::Prefix(_)) => {}
+                _ => out.push(".."),
+            },
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_relative_references() {
+        let base = Path::new("pak/models/ship.gltf");
+        assert_eq!(
+            Vfs::resolve(base, "ship.bin"),
+            PathBuf::from("pak/models/ship.bin")
+        );
+        assert_eq!(
+            Vfs::resolve(base, "../textures/./hull.png"),
+            PathBuf::from("pak/textures/hull.png")
+        );
+        assert_eq!(
+            Vfs::resolve(Path::new("a.obj"), "b.mtl"),
+            PathBuf::from("b.mtl")
+        );
+    }
+
+    #[test]
+    fn later_mounts_shadow_earlier_ones() {
+        let vfs = Vfs::new();
+        vfs.mount(
+            "data",
+            MemoryFiles::new()
+                .with_file("a.txt", "base")
+                .with_file("b.txt", "b"),
+        );
+        vfs.mount("data", MemoryFiles::new().with_file("a.txt", "patch"));
+
+        assert_eq!(vfs.read_to_string("data/a.txt").unwrap(), "patch");
+        assert_eq!(vfs.read_to_string("data/./b.txt").unwrap(), "b");
+        assert!(!vfs.exists("data/c.txt"));
+        assert!(vfs.read("data/c.txt").is_err());
+
+        assert_eq!(vfs.unmount("data"), 2);
+        assert!(!vfs.exists("data/a.txt"));
+    }
+
+    #[test]
+    fn reads_zip_archives() {
+        use std::io::Write;
+
+        let path = std::env::temp_dir().join(format!("vfs-{}.zip", std::process::id()));
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        zip.start_file("models/cube.obj", options).unwrap();
+        zip.write_all(b"v 0 0 0").unwrap();
+        zip.finish().unwrap();
+
+        let vfs = Vfs::new();
+        vfs.mount("pak", ZipArchiveFiles::open(&path).unwrap());
+        assert_eq!(vfs.read("pak/models/cube.obj").unwrap(), b"v 0 0 0");
+        assert!(!vfs.exists("pak/models/missing.obj"));
+        assert_eq!(vfs.watch_path("pak/models/cube.obj"), Some(path.clone()));
+        std::fs::remove_file(path).unwrap();
+    }
+}
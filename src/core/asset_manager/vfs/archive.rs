@@ -0,0 +1,97 @@
+use std::{
+    collections::HashSet,
+    io::Read,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use anyhow::{Context, anyhow};
+
+use crate::core::asset_manager::vfs::FileProvider;
+
+/// Files inside a zip archive. The archive is reopened when it changes on
+/// disk, so hot reload picks up a rebuilt archive.
+pub struct ZipArchiveFiles {
+    path: PathBuf,
+    state: Mutex<Option<Opened>>,
+}
+
+struct Opened {
+    modified: Option<std::time::SystemTime>,
+    archive: zip::ZipArchive<std::fs::File>,
+    names: HashSet<String>,
+}
+
+impl ZipArchiveFiles {
+    pub fn open(path: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let files = Self {
+            path: path.into(),
+            state: Mutex::new(None),
+        };
+        files.with_archive(|_| Ok(()))?;
+        Ok(files)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn with_archive<R>(
+        &self,
+        f: impl FnOnce(&mut Opened) -> anyhow::Result<R>,
+    ) -> anyhow::Result<R> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let modified = std::fs::metadata(&self.path)
+            .and_then(|m| m.modified())
+            .ok();
+        let stale = state.as_ref().is_none_or(|s| s.modified != modified);
+        if stale {
+            let file = std::fs::File::open(&self.path)
+                .with_context(|| format!("failed to open {}", self.path.display()))?;
+            let archive = zip::ZipArchive::new(file)
+                .with_context(|| format!("failed to read zip archive {}", self.path.display()))?;
+            let names = archive.file_names().map(str::to_owned).collect();
+            *state = Some(Opened {
+                modified,
+                archive,
+                names,
+            });
+        }
+        f(state.as_mut().expect("archive opened above"))
+    }
+}
+
+/// Entry names in zip files always use forward slashes.
+fn entry_name(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+impl FileProvider for ZipArchiveFiles {
+    fn read(&self, path: &Path) -> anyhow::Result<Vec<u8>> {
+        let name = entry_name(path);
+        self.with_archive(|opened| {
+            let mut entry = opened
+                .archive
+                .by_name(&name)
+                .map_err(|_| anyhow!("{} not found in {}", name, self.path.display()))?;
+            let mut bytes = Vec::with_capacity(entry.size() as usize);
+            entry
+                .read_to_end(&mut bytes)
+                .with_context(|| format!("failed to extract {name}"))?;
+            Ok(bytes)
+        })
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        let name = entry_name(path);
+        self.with_archive(|opened| Ok(opened.names.contains(&name)))
+            .unwrap_or(false)
+    }
+
+    fn watch_path(&self, _path: &Path) -> Option<PathBuf> {
+        Some(self.path.clone())
+    }
+}
@@ -0,0 +1,35 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use crate::core::asset_manager::vfs::FileProvider;
+
+/// Files under a directory on disk.
+pub struct DirectoryFiles {
+    root: PathBuf,
+}
+
+impl DirectoryFiles {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+}
+
+impl FileProvider for DirectoryFiles {
+    fn read(&self, path: &Path) -> anyhow::Result<Vec<u8>> {
+        let path = self.root.join(path);
+        std::fs::read(&path).with_context(|| format!("failed to read {}", path.display()))
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.root.join(path).is_file()
+    }
+
+    fn watch_path(&self, path: &Path) -> Option<PathBuf> {
+        Some(self.root.join(path))
+    }
+}
@@ -0,0 +1,59 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+use anyhow::anyhow;
+
+use crate::core::asset_manager::vfs::{FileProvider, normalize};
+
+/// Files held in memory, mainly for tests and generated content.
+#[derive(Default)]
+pub struct MemoryFiles {
+    files: RwLock<HashMap<PathBuf, Arc<[u8]>>>,
+}
+
+impl MemoryFiles {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_file(self, path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> Self {
+        self.insert(path, contents);
+        self
+    }
+
+    pub fn insert(&self, path: impl AsRef<Path>, contents: impl AsRef<[u8]>) {
+        self.files
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(normalize(path.as_ref()), Arc::from(contents.as_ref()));
+    }
+
+    pub fn remove(&self, path: impl AsRef<Path>) -> bool {
+        self.files
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&normalize(path.as_ref()))
+            .is_some()
+    }
+}
+
+impl FileProvider for MemoryFiles {
+    fn read(&self, path: &Path) -> anyhow::Result<Vec<u8>> {
+        self.files
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(path)
+            .map(|bytes| bytes.to_vec())
+            .ok_or_else(|| anyhow!("no such file"))
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.files
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains_key(path)
+    }
+}
@@ -0,0 +1,150 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver},
+    time::{Duration, Instant},
+};
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::core::asset_manager::AssetId;
+
+/// Editors tend to write a file in several steps; changes are only reported
+/// once a file has been quiet for this long.
+const DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// Watches the source files of loaded assets. Directories are watched rather
+/// than files, because many editors save by replacing the file, which would
+/// silently end a per-file watch.
+pub(crate) struct AssetWatcher {
+    watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<notify::Event>>,
+    watched_dirs: HashMap<PathBuf, usize>,
+    files: HashMap<PathBuf, HashSet<AssetId>>,
+    assets: HashMap<AssetId, Vec<PathBuf>>,
+    changed: HashMap<PathBuf, Instant>,
+}
+
+impl AssetWatcher {
+    pub fn new() -> notify::Result<Self> {
+        let (tx, events) = mpsc::channel();
+        let watcher = notify::recommended_watcher(move |event| {
+            let _ = tx.send(event);
+        })?;
+        Ok(Self {
+            watcher,
+            events,
+            watched_dirs: HashMap::new(),
+            files: HashMap::new(),
+            assets: HashMap::new(),
+            changed: HashMap::new(),
+        })
+    }
+
+    /// Replaces the set of files `id` depends on.
+    pub fn watch(&mut self, id: AssetId, files: Vec<PathBuf>) {
+        self.unwatch(id);
+        let files: Vec<PathBuf> = files.into_iter().map(|f| normalize(&f)).collect();
+        for file in &files {
+            self.files.entry(file.clone()).or_default().insert(id);
+            if let Some(dir) = file.parent() {
+                self.watch_dir(dir);
+            }
+        }
+        self.assets.insert(id, files);
+    }
+
+    pub fn unwatch(&mut self, id: AssetId) {
+        let Some(files) = self.assets.remove(&id) else {
+            return;
+        };
+        for file in files {
+            if let Some(ids) = self.files.get_mut(&file) {
+                ids.remove(&id);
+                if ids.is_empty() {
+                    self.files.remove(&file);
+                }
+            }
+            if let Some(dir) = file.parent() {
+                self.unwatch_dir(dir);
+            }
+        }
+    }
+
+    fn watch_dir(&mut self, dir: &Path) {
+        let count = self.watched_dirs.entry(dir.to_path_buf()).or_insert(0);
+        if *count == 0
+            && let Err(err) = self.watcher.watch(dir, RecursiveMode::NonRecursive)
+        {
+            log::warn!("cannot watch {}: {}", dir.display(), err);
+        }
+        *count += 1;
+    }
+
+    fn unwatch_dir(&mut self, dir: &Path) {
+        let Some(count) = self.watched_dirs.get_mut(dir) else {
+            return;
+        };
+        *count -= 1;
+        if *count == 0 {
+            self.watched_dirs.remove(dir);
+            let _ = self.watcher.unwatch(dir);
+        }
+    }
+
+    /// Returns the assets whose files changed and have since settled.
+    pub fn poll(&mut self) -> Vec<AssetId> {
+        let now = Instant::now();
+        while let Ok(event) = self.events.try_recv() {
+            let event = match event {
+                Ok(event) => event,
+                Err(err) => {
+                    log::warn!("file watcher error: {}", err);
+                    continue;
+                }
+            };
+            if event.kind.is_access() {
+                continue;
+            }
+            for path in event.paths {
+                let path = normalize(&path);
+                if self.files.contains_key(&path) {
+                    self.changed.insert(path, now);
+                }
+            }
+        }
+
+        let settled: Vec<PathBuf> = self
+            .changed
+            .iter()
+            .filter(|(_, time)| now.duration_since(**time) >= DEBOUNCE)
+            .map(|(path, _)| path.clone())
+            .collect();
+
+        let mut ids = HashSet::new();
+        for path in settled {
+            self.changed.remove(&path);
+            if let Some(assets) = self.files.get(&path) {
+                ids.extend(assets.iter().copied());
+            }
+        }
+        ids.into_iter().collect()
+    }
+}
+
+/// Canonicalizes the directory part only, so that paths of files that are
+/// momentarily missing (mid-save) still compare equal.
+fn normalize(path: &Path) -> PathBuf {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return path.to_path_buf();
+    };
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    match std::fs::canonicalize(dir) {
+        Ok(dir) => dir.join(name),
+        Err(_) => path.to_path_buf(),
+    }
+}
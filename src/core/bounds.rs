@@ -0,0 +1,273 @@
+use glam::{Mat4, Vec3, Vec4, Vec4Swizzles};
+
+/// Axis-aligned bounding box. An empty box has `min > max` and contains
+/// nothing; growing it by a point makes it valid.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub const EMPTY: Self = Self {
+        min: Vec3::splat(f32::MAX),
+        max: Vec3::splat(f32::MIN),
+    };
+
+    pub fn new(min: Vec3, max: Vec3) -> Self {
+        Self { min, max }
+    }
+
+    pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Self {
+        points.into_iter().fold(Self::EMPTY, |mut aabb, p| {
+            aabb.grow(p);
+            aabb
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.cmpgt(self.max).any()
+    }
+
+    pub fn grow(&mut self, point: Vec3) {
+        self.min = self.min.min(point);
+        self.max = self.max.max(point);
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb::new(self.min.min(other.min), self.max.max(other.max))
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn extents(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    /// Radius of the bounding sphere around `center`.
+    pub fn radius(&self) -> f32 {
+        self.extents().length() * 0.5
+    }
+
+    pub fn contains(&self, point: Vec3) -> bool {
+        point.cmpge(self.min).all() && point.cmple(self.max).all()
+    }
+
+    pub fn bounding_sphere(&self) -> Sphere {
+        Sphere::new(self.center(), self.radius())
+    }
+
+    /// Half the surface area; the cost measure for building hierarchies.
+    pub fn half_area(&self) -> f32 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        let e = self.extents();
+        e.x * e.y + e.y * e.z + e.z * e.x
+    }
+
+    /// Bounds of this box after transforming it by `matrix`.
+    pub fn transformed(&self, matrix: &Mat4) -> Aabb {
+        if self.is_empty() {
+            return *self;
+        }
+        // Arvo's method: accumulate the extremes of each matrix column.
+        let mut min = matrix.w_axis.xyz();
+        let mut max = min;
+        for (axis, column) in [matrix.x_axis, matrix.y_axis, matrix.z_axis]
+            .into_iter()
+            .enumerate()
+        {
+            let a = column.xyz() * self.min[axis];
+            let b = column.xyz() * self.max[axis];
+            min += a.min(b);
+            max += a.max(b);
+        }
+        Aabb::new(min, max)
+    }
+}
+
+impl Default for Aabb {
+    fn default() -> Self {
+        Self::EMPTY
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Sphere {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl Sphere {
+    pub fn new(center: Vec3, radius: f32) -> Self {
+        Self { center, radius }
+    }
+
+    /// Encloses the sphere after `matrix`, scaling the radius by the
+    /// largest axis scale.
+    pub fn transformed(&self, matrix: &Mat4) -> Sphere {
+        let scale = matrix
+            .x_axis
+            .xyz()
+            .length()
+            .max(matrix.y_axis.xyz().length())
+            .max(matrix.z_axis.xyz().length());
+        Sphere::new(matrix.transform_point3(self.center), self.radius * scale)
+    }
+}
+
+/// Half-line `origin + t * direction` for `t >= 0`. The direction need not
+/// be normalized; hit distances are in units of its length.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Ray {
+    pub origin: Vec3,
+    pub direction: Vec3,
+}
+
+impl Ray {
+    pub fn new(origin: Vec3, direction: Vec3) -> Self {
+        Self { origin, direction }
+    }
+
+    pub fn at(&self, t: f32) -> Vec3 {
+        self.origin + self.direction * t
+    }
+
+    /// The same ray in another space. Distances along it are preserved
+    /// because the direction is not renormalized.
+    pub fn transformed(&self, matrix: &Mat4) -> Ray {
+        Ray::new(
+            matrix.transform_point3(self.origin),
+            matrix.transform_vector3(self.direction),
+        )
+    }
+
+    /// Distance to where the ray enters the box, or 0 if it starts inside.
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<f32> {
+        let inv = self.direction.recip();
+        let t0 = (aabb.min - self.origin) * inv;
+        let t1 = (aabb.max - self.origin) * inv;
+        // NaN from 0 * inf on a slab boundary is dropped by min/max.
+        let near = t0.min(t1).max_element().max(0.0);
+        let far = t0.max(t1).min_element();
+        (near <= far).then_some(near)
+    }
+
+    /// Möller–Trumbore; hits either side of the triangle.
+    pub fn intersect_triangle(&self, a: Vec3, b: Vec3, c: Vec3) -> Option<f32> {
+        let ab = b - a;
+        let ac = c - a;
+        let p = self.direction.cross(ac);
+        let det = ab.dot(p);
+        if det.abs() < f32::EPSILON * ab.length_squared().max(ac.length_squared()) {
+            return None;
+        }
+        let inv_det = 1.0 / det;
+        let s = self.origin - a;
+        let u = s.dot(p) * inv_det;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let q = s.cross(ab);
+        let v = self.direction.dot(q) * inv_det;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        let t = ac.dot(q) * inv_det;
+        (t >= 0.0).then_some(t)
+    }
+}
+
+/// The six clip planes of a view-projection matrix, pointing inwards.
+#[derive(Clone, Copy, Debug)]
+pub struct Frustum {
+    planes: [Vec4; 6],
+}
+
+impl Frustum {
+    /// Extracts the planes of a GL-style clip space (`-w <= z <= w`). Pass
+    /// `projection * view * model` to test boxes in model space.
+    pub fn from_matrix(m: &Mat4) -> Self {
+        let row = |i| m.row(i);
+        let planes = [
+            row(3) + row(0),
+            row(3) - row(0),
+            row(3) + row(1),
+            row(3) - row(1),
+            row(3) + row(2),
+            row(3) - row(2),
+        ]
+        .map(|p| p / p.xyz().length().max(f32::MIN_POSITIVE));
+        Self { planes }
+    }
+
+    pub fn intersects_sphere(&self, sphere: &Sphere) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.xyz().dot(sphere.center) + plane.w >= -sphere.radius)
+    }
+
+    /// Conservative: may report boxes near the corners as visible.
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            // The box corner furthest along the plane normal.
+            let normal = plane.xyz();
+            let corner = Vec3::select(normal.cmpge(Vec3::ZERO), aabb.max, aabb.min);
+            normal.dot(corner) + plane.w >= 0.0
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transformed_box_contains_transformed_corners() {
+        let aabb = Aabb::new(Vec3::new(-1.0, 0.0, 0.0), Vec3::new(1.0, 2.0, 3.0));
+        let matrix = Mat4::from_rotation_y(0.7) * Mat4::from_translation(Vec3::X);
+        let moved = aabb.transformed(&matrix);
+        for x in [aabb.min.x, aabb.max.x] {
+            for y in [aabb.min.y, aabb.max.y] {
+                for z in [aabb.min.z, aabb.max.z] {
+                    let p = matrix.transform_point3(Vec3::new(x, y, z));
+                    assert!(moved.contains(p + (moved.center() - p) * 1e-5));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn frustum_rejects_boxes_behind_the_camera() {
+        let view = Mat4::look_at_rh(Vec3::new(0.0, 0.0, 5.0), Vec3::ZERO, Vec3::Y);
+        let projection = Mat4::perspective_rh_gl(1.0, 1.0, 0.1, 100.0);
+        let frustum = Frustum::from_matrix(&(projection * view));
+        let unit = Aabb::new(Vec3::splat(-0.5), Vec3::splat(0.5));
+        assert!(frustum.intersects_aabb(&unit));
+        let behind = Aabb::new(Vec3::new(-0.5, -0.5, 10.0), Vec3::new(0.5, 0.5, 11.0));
+        assert!(!frustum.intersects_aabb(&behind));
+        assert!(Aabb::EMPTY.is_empty());
+        assert!(frustum.intersects_sphere(&unit.bounding_sphere()));
+        assert!(!frustum.intersects_sphere(&behind.bounding_sphere()));
+    }
+
+    #[test]
+    fn ray_hits_box_and_triangle_at_the_same_distance() {
+        let ray = Ray::new(Vec3::new(0.25, 0.25, 5.0), Vec3::new(0.0, 0.0, -2.0));
+        let aabb = Aabb::new(Vec3::ZERO, Vec3::new(1.0, 1.0, 0.0));
+        assert_eq!(ray.intersect_aabb(&aabb), Some(2.5));
+        let t = ray
+            .intersect_triangle(Vec3::ZERO, Vec3::X, Vec3::Y)
+            .unwrap();
+        assert!((t - 2.5).abs() < 1e-6);
+        assert!(
+            ray.intersect_triangle(Vec3::X, Vec3::new(2.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 0.0))
+                .is_none()
+        );
+        let away = Ray::new(ray.origin, -ray.direction);
+        assert!(away.intersect_aabb(&aabb).is_none());
+    }
+}
@@ -0,0 +1,291 @@
+use glam::{Mat4, Vec2, Vec3};
+use serde::{Deserialize, Serialize};
+
+use crate::core::bounds::{Aabb, Ray};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Projection {
+    Perspective { fov_y: f32 },
+    Orthographic { height: f32 },
+}
+
+/// Which world axis points up: the glTF convention or the one of most CAD
+/// and DCC tools.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpAxis {
+    #[default]
+    Y,
+    Z,
+}
+
+impl UpAxis {
+    pub fn label(self) -> &'static str {
+        match self {
+            UpAxis::Y => "Y-up",
+            UpAxis::Z => "Z-up",
+        }
+    }
+
+    pub fn vector(self) -> Vec3 {
+        self.from_y_up(Vec3::Y)
+    }
+
+    /// Maps a direction given in the Y-up frame (right X, up Y, towards the
+    /// front viewer Z) to world space. Z-up keeps X and looks at the front
+    /// along +Y.
+    pub fn from_y_up(self, v: Vec3) -> Vec3 {
+        match self {
+            UpAxis::Y => v,
+            UpAxis::Z => Vec3::new(v.x, -v.z, v.y),
+        }
+    }
+
+    /// Inverse of `from_y_up`.
+    pub fn to_y_up(self, v: Vec3) -> Vec3 {
+        match self {
+            UpAxis::Y => v,
+            UpAxis::Z => Vec3::new(v.x, v.z, -v.y),
+        }
+    }
+}
+
+/// Axis-aligned views the camera can snap to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AxisView {
+    Front,
+    Back,
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+impl AxisView {
+    pub const ALL: [AxisView; 6] = [
+        AxisView::Front,
+        AxisView::Back,
+        AxisView::Left,
+        AxisView::Right,
+        AxisView::Top,
+        AxisView::Bottom,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            AxisView::Front => "Front",
+            AxisView::Back => "Back",
+            AxisView::Left => "Left",
+            AxisView::Right => "Right",
+            AxisView::Top => "Top",
+            AxisView::Bottom => "Bottom",
+        }
+    }
+
+    /// Yaw and pitch of the camera looking from this side.
+    pub fn yaw_pitch(self) -> (f32, f32) {
+        use std::f32::consts::{FRAC_PI_2, PI};
+        match self {
+            AxisView::Front => (0.0, 0.0),
+            AxisView::Back => (PI, 0.0),
+            AxisView::Left => (-FRAC_PI_2, 0.0),
+            AxisView::Right => (FRAC_PI_2, 0.0),
+            AxisView::Top => (0.0, MAX_PITCH),
+            AxisView::Bottom => (0.0, -MAX_PITCH),
+        }
+    }
+
+    /// World direction from the target towards a camera on this side.
+    pub fn direction(self, up: UpAxis) -> Vec3 {
+        let v = match self {
+            AxisView::Front => Vec3::Z,
+            AxisView::Back => Vec3::NEG_Z,
+            AxisView::Left => Vec3::NEG_X,
+            AxisView::Right => Vec3::X,
+            AxisView::Top => Vec3::Y,
+            AxisView::Bottom => Vec3::NEG_Y,
+        };
+        up.from_y_up(v)
+    }
+}
+
+/// Orbit camera looking at `target` from `distance` away. Yaw and pitch are
+/// in radians around the `up` axis; pitch is kept short of the poles so the
+/// up vector stays valid.
+#[derive(Clone, Debug)]
+pub struct Camera {
+    pub target: Vec3,
+    pub distance: f32,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub projection: Projection,
+    pub near: f32,
+    pub far: f32,
+    pub up: UpAxis,
+}
+
+const MAX_PITCH: f32 = std::f32::consts::FRAC_PI_2 - 0.01;
+
+impl Camera {
+    pub fn new() -> Self {
+        Self {
+            target: Vec3::ZERO,
+            distance: 5.0,
+            yaw: 0.6,
+            pitch: 0.4,
+            projection: Projection::Perspective {
+                fov_y: 45f32.to_radians(),
+            },
+            near: 0.05,
+            far: 1000.0,
+            up: UpAxis::Y,
+        }
+    }
+
+    pub fn position(&self) -> Vec3 {
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
+        let offset = Vec3::new(cos_pitch * sin_yaw, sin_pitch, cos_pitch * cos_yaw);
+        self.target + self.distance * self.up.from_y_up(offset)
+    }
+
+    pub fn view(&self) -> Mat4 {
+        Mat4::look_at_rh(self.position(), self.target, self.up.vector())
+    }
+
+    /// Looks at the target from `view`, keeping target and distance.
+    pub fn snap_to(&mut self, view: AxisView) {
+        (self.yaw, self.pitch) = view.yaw_pitch();
+    }
+
+    /// Switches between perspective and orthographic projection, keeping
+    /// the height visible at the target.
+    pub fn toggle_projection(&mut self) {
+        self.projection = match self.projection {
+            Projection::Perspective { fov_y } => Projection::Orthographic {
+                height: 2.0 * self.distance * (fov_y * 0.5).tan(),
+            },
+            Projection::Orthographic { height } => {
+                let fov_y = 45f32.to_radians();
+                self.distance =
+                    (height * 0.5 / (fov_y * 0.5).tan()).clamp(self.near * 2.0, self.far * 0.5);
+                Projection::Perspective { fov_y }
+            }
+        };
+    }
+
+    pub fn is_orthographic(&self) -> bool {
+        matches!(self.projection, Projection::Orthographic { .. })
+    }
+
+    pub fn projection(&self, aspect: f32) -> Mat4 {
+        match self.projection {
+            Projection::Perspective { fov_y } => {
+                Mat4::perspective_rh_gl(fov_y, aspect, self.near, self.far)
+            }
+            Projection::Orthographic { height } => {
+                let half_h = height * 0.5;
+                let half_w = half_h * aspect;
+                Mat4::orthographic_rh_gl(-half_w, half_w, -half_h, half_h, self.near, self.far)
+            }
+        }
+    }
+
+    /// World-space ray through a point given in normalized device
+    /// coordinates (`-1..=1`, y up), starting on the near plane.
+    pub fn ray(&self, ndc: Vec2, aspect: f32) -> Ray {
+        let inverse = (self.projection(aspect) * self.view()).inverse();
+        let near = inverse.project_point3(ndc.extend(-1.0));
+        let far = inverse.project_point3(ndc.extend(1.0));
+        Ray::new(near, (far - near).normalize_or_zero())
+    }
+
+    pub fn orbit(&mut self, delta_yaw: f32, delta_pitch: f32) {
+        self.yaw += delta_yaw;
+        self.pitch = (self.pitch + delta_pitch).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    /// Moves the target in the view plane; `dx`/`dy` are fractions of the
+    /// visible height.
+    pub fn pan(&mut self, dx: f32, dy: f32) {
+        let view = self.view().inverse();
+        let right = view.x_axis.truncate();
+        let up = view.y_axis.truncate();
+        let height = match self.projection {
+            Projection::Perspective { fov_y } => 2.0 * self.distance * (fov_y * 0.5).tan(),
+            Projection::Orthographic { height } => height,
+        };
+        self.target += (-right * dx + up * dy) * height;
+    }
+
+    /// Scales the orbit distance; `factor < 1` moves closer.
+    pub fn zoom(&mut self, factor: f32) {
+        self.distance = (self.distance * factor).clamp(self.near * 2.0, self.far * 0.5);
+        if let Projection::Orthographic { height } = &mut self.projection {
+            *height = (*height * factor).max(1e-3);
+        }
+    }
+
+    /// Centers `bounds` and moves back until its bounding sphere fits the
+    /// view, keeping the current direction.
+    pub fn frame(&mut self, bounds: &Aabb) {
+        if bounds.is_empty() {
+            return;
+        }
+        const MARGIN: f32 = 1.1;
+        let radius = bounds.radius().max(1e-3) * MARGIN;
+        self.target = bounds.center();
+        match &mut self.projection {
+            Projection::Perspective { fov_y } => {
+                self.distance = radius / (*fov_y * 0.5).sin();
+            }
+            Projection::Orthographic { height } => {
+                *height = 2.0 * radius;
+                self.distance = 2.0 * radius;
+            }
+        }
+        self.distance = self.distance.clamp(self.near * 2.0, self.far * 0.5);
+    }
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn axis_views_look_from_their_side() {
+        for up in [UpAxis::Y, UpAxis::Z] {
+            let mut camera = Camera::new();
+            camera.up = up;
+            for view in AxisView::ALL {
+                camera.snap_to(view);
+                let direction = (camera.position() - camera.target).normalize();
+                assert!(
+                    direction.dot(view.direction(up)) > 0.999,
+                    "{view:?} with {up:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn toggling_projection_keeps_visible_height() {
+        let mut camera = Camera::new();
+        let visible = 2.0 * camera.distance * (45f32.to_radians() * 0.5).tan();
+        camera.toggle_projection();
+        let Projection::Orthographic { height } = camera.projection else {
+            panic!("expected an orthographic projection");
+        };
+        assert!((height - visible).abs() < 1e-4);
+        camera.zoom(2.0);
+        camera.toggle_projection();
+        assert!(!camera.is_orthographic());
+        assert!((camera.distance - 10.0).abs() < 1e-3);
+    }
+}
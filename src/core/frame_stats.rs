@@ -0,0 +1,152 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Parts of a frame timed on the CPU.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Phase {
+    SceneUpdate,
+    UiBuild,
+    SceneRender,
+    EguiPaint,
+    Swap,
+}
+
+impl Phase {
+    pub const ALL: [Phase; 5] = [
+        Phase::SceneUpdate,
+        Phase::UiBuild,
+        Phase::SceneRender,
+        Phase::EguiPaint,
+        Phase::Swap,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Phase::SceneUpdate => "update",
+            Phase::UiBuild => "ui",
+            Phase::SceneRender => "render",
+            Phase::EguiPaint => "paint",
+            Phase::Swap => "swap",
+        }
+    }
+}
+
+/// Rolling history of frame times plus the phase breakdown of the last
+/// complete frame.
+pub struct FrameStats {
+    history: VecDeque<Duration>,
+    capacity: usize,
+    phases: [Duration; Phase::ALL.len()],
+    last_phases: [Duration; Phase::ALL.len()],
+    gpu_time: Option<Duration>,
+}
+
+impl FrameStats {
+    pub const DEFAULT_CAPACITY: usize = 240;
+
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            history: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+            phases: Default::default(),
+            last_phases: Default::default(),
+            gpu_time: None,
+        }
+    }
+
+    /// Closes the current frame: records its length and makes the phases
+    /// timed since the previous call available through `phase`.
+    pub fn record_frame(&mut self, delta: Duration) {
+        if self.history.len() == self.capacity {
+            self.history.pop_front();
+        }
+        self.history.push_back(delta);
+        self.last_phases = std::mem::take(&mut self.phases);
+    }
+
+    /// Adds `duration` to `phase` of the frame in progress.
+    pub fn record_phase(&mut self, phase: Phase, duration: Duration) {
+        self.phases[phase as usize] += duration;
+    }
+
+    /// Time spent in `phase` during the last complete frame.
+    pub fn phase(&self, phase: Phase) -> Duration {
+        self.last_phases[phase as usize]
+    }
+
+    /// GPU time of the scene pass, when timer queries are available.
+    pub fn gpu_time(&self) -> Option<Duration> {
+        self.gpu_time
+    }
+
+    pub fn set_gpu_time(&mut self, gpu_time: Option<Duration>) {
+        self.gpu_time = gpu_time;
+    }
+
+    /// Frame times, oldest first.
+    pub fn frame_times(&self) -> impl ExactSizeIterator<Item = Duration> + '_ {
+        self.history.iter().copied()
+    }
+
+    pub fn min(&self) -> Duration {
+        self.history.iter().min().copied().unwrap_or_default()
+    }
+
+    pub fn max(&self) -> Duration {
+        self.history.iter().max().copied().unwrap_or_default()
+    }
+
+    pub fn average(&self) -> Duration {
+        if self.history.is_empty() {
+            return Duration::ZERO;
+        }
+        self.history.iter().sum::<Duration>() / self.history.len() as u32
+    }
+
+    /// Nearest-rank percentile, `p` in `[0, 100]`.
+    pub fn percentile(&self, p: f64) -> Duration {
+        if self.history.is_empty() {
+            return Duration::ZERO;
+        }
+        let mut sorted: Vec<Duration> = self.history.iter().copied().collect();
+        sorted.sort_unstable();
+        let rank = (p.clamp(0.0, 100.0) / 100.0 * sorted.len() as f64).ceil() as usize;
+        sorted[rank.clamp(1, sorted.len()) - 1]
+    }
+
+    pub fn fps(&self) -> f64 {
+        let average = self.average().as_secs_f64();
+        if average > 0.0 { 1.0 / average } else { 0.0 }
+    }
+}
+
+impl Default for FrameStats {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarizes_rolling_history() {
+        let mut stats = FrameStats::new(4);
+        for ms in [50, 10, 20, 30, 40] {
+            stats.record_frame(Duration::from_millis(ms));
+        }
+        assert_eq!(stats.frame_times().len(), 4);
+        assert_eq!(stats.min(), Duration::from_millis(10));
+        assert_eq!(stats.max(), Duration::from_millis(40));
+        assert_eq!(stats.average(), Duration::from_millis(25));
+        assert_eq!(stats.percentile(50.0), Duration::from_millis(20));
+        assert_eq!(stats.percentile(99.0), Duration::from_millis(40));
+        assert!((stats.fps() - 40.0).abs() < 1e-9);
+
+        stats.record_phase(Phase::UiBuild, Duration::from_millis(3));
+        assert_eq!(stats.phase(Phase::UiBuild), Duration::ZERO);
+        stats.record_frame(Duration::from_millis(10));
+        assert_eq!(stats.phase(Phase::UiBuild), Duration::from_millis(3));
+    }
+}
@@ -0,0 +1,107 @@
+pub mod action_map;
+pub mod binding;
+pub mod state;
+
+pub use action_map::ActionMap;
+pub use binding::{Binding, Modifiers, Trigger};
+pub use state::InputState;
+
+use winit::event::WindowEvent;
+
+/// Per-frame input state plus the action bindings that interpret it. Code
+/// asks about actions (`camera.orbit`, `selection.delete`) rather than
+/// concrete keys so users can rebind them.
+#[derive(Default)]
+pub struct Input {
+    state: InputState,
+    actions: ActionMap,
+}
+
+impl Input {
+    pub fn new(actions: ActionMap) -> Self {
+        Self {
+            state: InputState::new(),
+            actions,
+        }
+    }
+
+    pub fn handle_event(&mut self, event: &WindowEvent) {
+        self.state.handle_event(event);
+    }
+
+    pub fn end_frame(&mut self) {
+        self.state.end_frame();
+    }
+
+    pub fn state(&self) -> &InputState {
+        &self.state
+    }
+
+    pub fn actions(&self) -> &ActionMap {
+        &self.actions
+    }
+
+    pub fn actions_mut(&mut self) -> &mut ActionMap {
+        &mut self.actions
+    }
+
+    /// A binding of `action` went down this frame with exactly its modifiers.
+    pub fn pressed(&self, action: &str) -> bool {
+        self.matching(action)
+            .any(|binding| self.state.was_pressed(binding.trigger))
+    }
+
+    /// A binding of `action` is down with exactly its modifiers.
+    pub fn held(&self, action: &str) -> bool {
+        self.matching(action)
+            .any(|binding| self.state.is_down(binding.trigger))
+    }
+
+    /// A binding of `action` came up this frame. Modifiers are not checked,
+    /// since they are often let go first.
+    pub fn released(&self, action: &str) -> bool {
+        self.actions
+            .bindings(action)
+            .iter()
+            .any(|binding| self.state.was_released(binding.trigger))
+    }
+
+    fn matching(&self, action: &str) -> impl Iterator<Item = &Binding> {
+        let modifiers = self.state.modifiers();
+        self.actions
+            .bindings(action)
+            .iter()
+            .filter(move |binding| binding.modifiers == modifiers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winit::event::MouseButton;
+
+    #[test]
+    fn actions_require_exact_modifiers() {
+        let mut actions = ActionMap::new();
+        actions.bind("camera.orbit", "MouseLeft".parse().unwrap());
+        actions.bind("camera.pan", "Shift+MouseLeft".parse().unwrap());
+        actions.bind("camera.pan", "MouseMiddle".parse().unwrap());
+        let mut input = Input::new(actions);
+
+        input.state.press(Trigger::Mouse(MouseButton::Left));
+        assert!(input.pressed("camera.orbit") && input.held("camera.orbit"));
+        assert!(!input.held("camera.pan"));
+
+        input.end_frame();
+        input.state.set_modifiers(Modifiers {
+            shift: true,
+            ..Modifiers::NONE
+        });
+        assert!(!input.held("camera.orbit") && !input.pressed("camera.pan"));
+        assert!(input.held("camera.pan"));
+
+        input.state.release(Trigger::Mouse(MouseButton::Left));
+        assert!(input.released("camera.orbit") && input.released("camera.pan"));
+        assert!(!input.held("unknown.action"));
+    }
+}
@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+
+use anyhow::Context;
+
+use super::binding::Binding;
+
+/// Named actions such as `camera.orbit` and the bindings that fire them. An
+/// action may have any number of bindings; a binding bound to more than one
+/// action is a conflict.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct ActionMap {
+    actions: BTreeMap<String, Vec<Binding>>,
+}
+
+impl ActionMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses `{ action = ["Ctrl+Z", ...] }` tables as found in the config.
+    /// Actions that fail to parse are left out and reported together.
+    pub fn from_config(config: &BTreeMap<String, Vec<String>>) -> (Self, Vec<anyhow::Error>) {
+        let mut map = Self::new();
+        let mut errors = Vec::new();
+        for (action, bindings) in config {
+            let parsed: anyhow::Result<Vec<Binding>> = bindings
+                .iter()
+                .map(|text| text.parse())
+                .collect::<anyhow::Result<_>>()
+                .with_context(|| format!("invalid binding for action \"{action}\""));
+            match parsed {
+                Ok(bindings) => map.set(action, bindings),
+                Err(err) => errors.push(err),
+            }
+        }
+        (map, errors)
+    }
+
+    /// Serializes back into the config table form.
+    pub fn to_config(&self) -> BTreeMap<String, Vec<String>> {
+        self.actions
+            .iter()
+            .map(|(action, bindings)| {
+                let bindings = bindings.iter().map(Binding::to_string).collect();
+                (action.clone(), bindings)
+            })
+            .collect()
+    }
+
+    /// Replaces all bindings of `action`, declaring it if needed.
+    pub fn set(&mut self, action: &str, bindings: Vec<Binding>) {
+        self.actions.insert(action.to_owned(), bindings);
+    }
+
+    /// Adds a binding; binding the same thing twice is a no-op.
+    pub fn bind(&mut self, action: &str, binding: Binding) {
+        let bindings = self.actions.entry(action.to_owned()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// Removes a binding, keeping the action declared even without any.
+    pub fn unbind(&mut self, action: &str, binding: Binding) {
+        if let Some(bindings) = self.actions.get_mut(action) {
+            bindings.retain(|b| *b != binding);
+        }
+    }
+
+    pub fn bindings(&self, action: &str) -> &[Binding] {
+        self.actions.get(action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Declared actions in name order.
+    pub fn actions(&self) -> impl Iterator<Item = (&str, &[Binding])> {
+        self.actions
+            .iter()
+            .map(|(action, bindings)| (action.as_str(), bindings.as_slice()))
+    }
+
+    /// Bindings shared by more than one action, with the actions sharing them.
+    pub fn conflicts(&self) -> Vec<(Binding, Vec<&str>)> {
+        let mut owners: Vec<(Binding, Vec<&str>)> = Vec::new();
+        for (action, bindings) in self.actions() {
+            for binding in bindings {
+                match owners.iter_mut().find(|(b, _)| b == binding) {
+                    Some((_, actions)) => actions.push(action),
+                    None => owners.push((*binding, vec![action])),
+                }
+            }
+        }
+        owners.retain(|(_, actions)| actions.len() > 1);
+        owners
+    }
+
+    pub fn is_conflicting(&self, binding: Binding) -> bool {
+        self.actions
+            .values()
+            .filter(|bindings| bindings.contains(&binding))
+            .count()
+            > 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(entries: &[(&str, &[&str])]) -> BTreeMap<String, Vec<String>> {
+        entries
+            .iter()
+            .map(|(action, bindings)| {
+                let bindings = bindings.iter().map(|b| b.to_string()).collect();
+                (action.to_string(), bindings)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn loads_config_and_reports_conflicts() {
+        let config = table(&[
+            ("edit.redo", &["Ctrl+Shift+Z", "Ctrl+Y"]),
+            ("edit.undo", &["Ctrl+Z"]),
+            ("view.frame_selected", &["F"]),
+            ("view.fullscreen", &["Ctrl+Y", "F11"]),
+            ("broken", &["Ctrl+Nope"]),
+        ]);
+        let (mut map, errors) = ActionMap::from_config(&config);
+        assert_eq!(errors.len(), 1);
+        assert!(format!("{:#}", errors[0]).contains("broken"));
+        assert!(map.bindings("broken").is_empty());
+
+        let ctrl_y: Binding = "Ctrl+Y".parse().unwrap();
+        let conflicts = map.conflicts();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].0, ctrl_y);
+        assert_eq!(conflicts[0].1, ["edit.redo", "view.fullscreen"]);
+        assert!(map.is_conflicting(ctrl_y));
+
+        map.unbind("view.fullscreen", ctrl_y);
+        assert!(map.conflicts().is_empty());
+        map.bind("edit.undo", "Ctrl+Z".parse().unwrap());
+        assert_eq!(map.bindings("edit.undo").len(), 1);
+
+        let (reloaded, errors) = ActionMap::from_config(&map.to_config());
+        assert!(errors.is_empty());
+        assert_eq!(reloaded, map);
+    }
+}
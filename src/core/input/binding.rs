@@ -0,0 +1,373 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail};
+use winit::event::MouseButton;
+use winit::keyboard::KeyCode;
+
+/// Modifier keys a binding requires; all others must be released.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    pub super_key: bool,
+}
+
+impl Modifiers {
+    pub const NONE: Self = Self {
+        ctrl: false,
+        shift: false,
+        alt: false,
+        super_key: false,
+    };
+
+    pub fn from_winit(state: winit::keyboard::ModifiersState) -> Self {
+        Self {
+            ctrl: state.control_key(),
+            shift: state.shift_key(),
+            alt: state.alt_key(),
+            super_key: state.super_key(),
+        }
+    }
+}
+
+/// The key, button or wheel that fires a binding.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Trigger {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    /// Any wheel or touchpad scrolling.
+    Scroll,
+}
+
+/// A trigger plus the exact modifiers held with it, written as e.g.
+/// `Ctrl+Shift+Z`, `Shift+MouseLeft` or `Scroll`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Binding {
+    pub modifiers: Modifiers,
+    pub trigger: Trigger,
+}
+
+impl Binding {
+    pub fn new(modifiers: Modifiers, trigger: Trigger) -> Self {
+        Self { modifiers, trigger }
+    }
+}
+
+const NAMED_KEYS: &[(&str, KeyCode)] = &[
+    ("Escape", KeyCode::Escape),
+    ("Enter", KeyCode::Enter),
+    ("Tab", KeyCode::Tab),
+    ("Space", KeyCode::Space),
+    ("Backspace", KeyCode::Backspace),
+    ("Delete", KeyCode::Delete),
+    ("Insert", KeyCode::Insert),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+    ("Up", KeyCode::ArrowUp),
+    ("Down", KeyCode::ArrowDown),
+    ("Left", KeyCode::ArrowLeft),
+    ("Right", KeyCode::ArrowRight),
+    ("Minus", KeyCode::Minus),
+    ("Equal", KeyCode::Equal),
+    ("Comma", KeyCode::Comma),
+    ("Period", KeyCode::Period),
+    ("Slash", KeyCode::Slash),
+    ("Backquote", KeyCode::Backquote),
+    ("BracketLeft", KeyCode::BracketLeft),
+    ("BracketRight", KeyCode::BracketRight),
+    ("Semicolon", KeyCode::Semicolon),
+    ("Quote", KeyCode::Quote),
+    ("Backslash", KeyCode::Backslash),
+    ("IntlBackslash", KeyCode::IntlBackslash),
+    ("CapsLock", KeyCode::CapsLock),
+    ("PrintScreen", KeyCode::PrintScreen),
+    ("ScrollLock", KeyCode::ScrollLock),
+    ("Pause", KeyCode::Pause),
+    ("ContextMenu", KeyCode::ContextMenu),
+    ("Numpad0", KeyCode::Numpad0),
+    ("Numpad1", KeyCode::Numpad1),
+    ("Numpad2", KeyCode::Numpad2),
+    ("Numpad3", KeyCode::Numpad3),
+    ("Numpad4", KeyCode::Numpad4),
+    ("Numpad5", KeyCode::Numpad5),
+    ("Numpad6", KeyCode::Numpad6),
+    ("Numpad7", KeyCode::Numpad7),
+    ("Numpad8", KeyCode::Numpad8),
+    ("Numpad9", KeyCode::Numpad9),
+    ("NumpadDecimal", KeyCode::NumpadDecimal),
+    ("NumpadAdd", KeyCode::NumpadAdd),
+    ("NumpadSubtract", KeyCode::NumpadSubtract),
+    ("NumpadMultiply", KeyCode::NumpadMultiply),
+    ("NumpadDivide", KeyCode::NumpadDivide),
+    ("NumpadEnter", KeyCode::NumpadEnter),
+    ("NumpadEqual", KeyCode::NumpadEqual),
+];
+
+const LETTER_KEYS: [KeyCode; 26] = [
+    KeyCode::KeyA,
+    KeyCode::KeyB,
+    KeyCode::KeyC,
+    KeyCode::KeyD,
+    KeyCode::KeyE,
+    KeyCode::KeyF,
+    KeyCode::KeyG,
+    KeyCode::KeyH,
+    KeyCode::KeyI,
+    KeyCode::KeyJ,
+    KeyCode::KeyK,
+    KeyCode::KeyL,
+    KeyCode::KeyM,
+    KeyCode::KeyN,
+    KeyCode::KeyO,
+    KeyCode::KeyP,
+    KeyCode::KeyQ,
+    KeyCode::KeyR,
+    KeyCode::KeyS,
+    KeyCode::KeyT,
+    KeyCode::KeyU,
+    KeyCode::KeyV,
+    KeyCode::KeyW,
+    KeyCode::KeyX,
+    KeyCode::KeyY,
+    KeyCode::KeyZ,
+];
+
+const DIGIT_KEYS: [KeyCode; 10] = [
+    KeyCode::Digit0,
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
+const FUNCTION_KEYS: [KeyCode; 24] = [
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+    KeyCode::F13,
+    KeyCode::F14,
+    KeyCode::F15,
+    KeyCode::F16,
+    KeyCode::F17,
+    KeyCode::F18,
+    KeyCode::F19,
+    KeyCode::F20,
+    KeyCode::F21,
+    KeyCode::F22,
+    KeyCode::F23,
+    KeyCode::F24,
+];
+
+const MOUSE_BUTTONS: &[(&str, MouseButton)] = &[
+    ("MouseLeft", MouseButton::Left),
+    ("MouseRight", MouseButton::Right),
+    ("MouseMiddle", MouseButton::Middle),
+    ("MouseBack", MouseButton::Back),
+    ("MouseForward", MouseButton::Forward),
+];
+
+/// Prefix of further mouse buttons, written with their number as in
+/// `Mouse8`.
+const OTHER_MOUSE_BUTTON: &str = "Mouse";
+
+fn parse_key(name: &str) -> Option<KeyCode> {
+    if let Some(&(_, key)) = NAMED_KEYS
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+    {
+        return Some(key);
+    }
+    let bytes = name.as_bytes();
+    match bytes {
+        [c] if c.is_ascii_alphabetic() => {
+            Some(LETTER_KEYS[(c.to_ascii_uppercase() - b'A') as usize])
+        }
+        [c] if c.is_ascii_digit() => Some(DIGIT_KEYS[(c - b'0') as usize]),
+        [b'F' | b'f', ..] => {
+            let n: usize = name[1..].parse().ok()?;
+            FUNCTION_KEYS.get(n.checked_sub(1)?).copied()
+        }
+        _ => None,
+    }
+}
+
+/// The name `parse_key` reads back, or `None` for keys that have none.
+fn key_name(key: KeyCode) -> Option<String> {
+    if let Some((name, _)) = NAMED_KEYS.iter().find(|(_, k)| *k == key) {
+        return Some((*name).to_owned());
+    }
+    if let Some(i) = LETTER_KEYS.iter().position(|&k| k == key) {
+        return Some(char::from(b'A' + i as u8).to_string());
+    }
+    if let Some(i) = DIGIT_KEYS.iter().position(|&k| k == key) {
+        return Some(i.to_string());
+    }
+    if let Some(i) = FUNCTION_KEYS.iter().position(|&k| k == key) {
+        return Some(format!("F{}", i + 1));
+    }
+    None
+}
+
+fn parse_mouse_button(name: &str) -> Option<MouseButton> {
+    if let Some(&(_, button)) = MOUSE_BUTTONS
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+    {
+        return Some(button);
+    }
+    let prefix = name.get(..OTHER_MOUSE_BUTTON.len())?;
+    if !prefix.eq_ignore_ascii_case(OTHER_MOUSE_BUTTON) {
+        return None;
+    }
+    name[OTHER_MOUSE_BUTTON.len()..]
+        .parse()
+        .ok()
+        .map(MouseButton::Other)
+}
+
+impl Trigger {
+    /// Whether the trigger prints as a name that parses back to it. Only
+    /// such triggers can be written to a bindings file.
+    pub fn has_name(self) -> bool {
+        match self {
+            Trigger::Key(key) => key_name(key).is_some(),
+            Trigger::Mouse(_) | Trigger::Scroll => true,
+        }
+    }
+}
+
+impl FromStr for Trigger {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> anyhow::Result<Self> {
+        if name.eq_ignore_ascii_case("Scroll") {
+            return Ok(Trigger::Scroll);
+        }
+        if let Some(button) = parse_mouse_button(name) {
+            return Ok(Trigger::Mouse(button));
+        }
+        parse_key(name)
+            .map(Trigger::Key)
+            .ok_or_else(|| anyhow!("unknown key or button \"{name}\""))
+    }
+}
+
+impl fmt::Display for Trigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Trigger::Key(key) => match key_name(*key) {
+                Some(name) => f.write_str(&name),
+                None => write!(f, "{key:?}"),
+            },
+            Trigger::Mouse(button) => match MOUSE_BUTTONS.iter().find(|(_, b)| b == button) {
+                Some((name, _)) => f.write_str(name),
+                None => match button {
+                    MouseButton::Other(n) => write!(f, "{OTHER_MOUSE_BUTTON}{n}"),
+                    _ => write!(f, "{button:?}"),
+                },
+            },
+            Trigger::Scroll => f.write_str("Scroll"),
+        }
+    }
+}
+
+impl FromStr for Binding {
+    type Err = anyhow::Error;
+
+    fn from_str(text: &str) -> anyhow::Result<Self> {
+        let mut modifiers = Modifiers::NONE;
+        let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+        let Some(trigger) = parts.pop().filter(|t| !t.is_empty()) else {
+            bail!("binding \"{text}\" has no key");
+        };
+        for part in parts {
+            let flag = match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => &mut modifiers.ctrl,
+                "shift" => &mut modifiers.shift,
+                "alt" | "option" => &mut modifiers.alt,
+                "super" | "cmd" | "meta" => &mut modifiers.super_key,
+                _ => bail!("unknown modifier \"{part}\" in \"{text}\""),
+            };
+            *flag = true;
+        }
+        Ok(Binding::new(modifiers, trigger.parse()?))
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let m = self.modifiers;
+        for (held, name) in [
+            (m.ctrl, "Ctrl"),
+            (m.shift, "Shift"),
+            (m.alt, "Alt"),
+            (m.super_key, "Super"),
+        ] {
+            if held {
+                write!(f, "{name}+")?;
+            }
+        }
+        write!(f, "{}", self.trigger)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_prints_bindings() {
+        let binding: Binding = "ctrl+shift+z".parse().unwrap();
+        assert_eq!(binding.trigger, Trigger::Key(KeyCode::KeyZ));
+        assert!(binding.modifiers.ctrl && binding.modifiers.shift);
+        assert_eq!(binding.to_string(), "Ctrl+Shift+Z");
+
+        for text in ["F5", "Delete", "Shift+MouseLeft", "Scroll", "Alt+7", "Up"] {
+            assert_eq!(text.parse::<Binding>().unwrap().to_string(), text);
+        }
+        assert!("Hyper+A".parse::<Binding>().is_err());
+        assert!("Ctrl+".parse::<Binding>().is_err());
+        assert!("F25".parse::<Binding>().is_err());
+    }
+
+    #[test]
+    fn every_named_trigger_round_trips() {
+        let keys = NAMED_KEYS
+            .iter()
+            .map(|&(_, key)| key)
+            .chain(LETTER_KEYS)
+            .chain(DIGIT_KEYS)
+            .chain(FUNCTION_KEYS)
+            .map(Trigger::Key);
+        let buttons = MOUSE_BUTTONS
+            .iter()
+            .map(|&(_, button)| button)
+            .chain([MouseButton::Other(8)])
+            .map(Trigger::Mouse);
+        for trigger in keys.chain(buttons).chain([Trigger::Scroll]) {
+            assert!(trigger.has_name(), "{trigger:?}");
+            assert_eq!(trigger.to_string().parse::<Trigger>().unwrap(), trigger);
+        }
+        assert_eq!(Trigger::Mouse(MouseButton::Other(8)).to_string(), "Mouse8");
+        assert!(!Trigger::Key(KeyCode::LaunchMail).has_name());
+        assert!("LaunchMail".parse::<Trigger>().is_err());
+    }
+}
@@ -0,0 +1,188 @@
+use std::collections::HashSet;
+
+use glam::Vec2;
+use winit::event::{ElementState, MouseScrollDelta, WindowEvent};
+use winit::keyboard::PhysicalKey;
+
+use super::binding::{Modifiers, Trigger};
+
+/// Pixels of touchpad scrolling that count as one wheel line.
+const PIXELS_PER_LINE: f32 = 40.0;
+
+/// Keyboard, mouse and scroll state for the current frame. Events are fed in
+/// as they arrive; `end_frame` clears the per-frame edges and deltas once the
+/// frame has been drawn.
+#[derive(Default)]
+pub struct InputState {
+    down: HashSet<Trigger>,
+    pressed: HashSet<Trigger>,
+    released: HashSet<Trigger>,
+    modifiers: Modifiers,
+    cursor: Option<Vec2>,
+    cursor_delta: Vec2,
+    scroll: Vec2,
+}
+
+impl InputState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applies a window event; events that carry no input are ignored.
+    pub fn handle_event(&mut self, event: &WindowEvent) {
+        match event {
+            WindowEvent::KeyboardInput { event, .. } => {
+                // Auto-repeat neither presses again nor releases.
+                if event.repeat {
+                    return;
+                }
+                if let PhysicalKey::Code(key) = event.physical_key {
+                    match event.state {
+                        ElementState::Pressed => self.press(Trigger::Key(key)),
+                        ElementState::Released => self.release(Trigger::Key(key)),
+                    }
+                }
+            }
+            WindowEvent::MouseInput { state, button, .. } => match state {
+                ElementState::Pressed => self.press(Trigger::Mouse(*button)),
+                ElementState::Released => self.release(Trigger::Mouse(*button)),
+            },
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = Modifiers::from_winit(modifiers.state());
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                self.move_cursor(Vec2::new(position.x as f32, position.y as f32));
+            }
+            WindowEvent::CursorLeft { .. } => self.cursor = None,
+            WindowEvent::MouseWheel { delta, .. } => match delta {
+                MouseScrollDelta::LineDelta(x, y) => self.scroll_by(Vec2::new(*x, *y)),
+                MouseScrollDelta::PixelDelta(pos) => {
+                    self.scroll_by(Vec2::new(pos.x as f32, pos.y as f32) / PIXELS_PER_LINE)
+                }
+            },
+            WindowEvent::Focused(false) => self.release_all(),
+            _ => {}
+        }
+    }
+
+    pub fn press(&mut self, trigger: Trigger) {
+        if self.down.insert(trigger) {
+            self.pressed.insert(trigger);
+        }
+    }
+
+    pub fn release(&mut self, trigger: Trigger) {
+        if self.down.remove(&trigger) {
+            self.released.insert(trigger);
+        }
+    }
+
+    /// Releases everything held, e.g. when the window loses focus and the
+    /// matching release events would never arrive.
+    pub fn release_all(&mut self) {
+        for trigger in std::mem::take(&mut self.down) {
+            self.released.insert(trigger);
+        }
+        self.modifiers = Modifiers::NONE;
+    }
+
+    pub fn set_modifiers(&mut self, modifiers: Modifiers) {
+        self.modifiers = modifiers;
+    }
+
+    pub fn move_cursor(&mut self, position: Vec2) {
+        if let Some(previous) = self.cursor {
+            self.cursor_delta += position - previous;
+        }
+        self.cursor = Some(position);
+    }
+
+    pub fn scroll_by(&mut self, lines: Vec2) {
+        self.scroll += lines;
+    }
+
+    /// Clears the pressed and released edges and the frame's deltas.
+    pub fn end_frame(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+        self.cursor_delta = Vec2::ZERO;
+        self.scroll = Vec2::ZERO;
+    }
+
+    pub fn is_down(&self, trigger: Trigger) -> bool {
+        match trigger {
+            Trigger::Scroll => self.scroll != Vec2::ZERO,
+            _ => self.down.contains(&trigger),
+        }
+    }
+
+    /// Went down this frame.
+    pub fn was_pressed(&self, trigger: Trigger) -> bool {
+        match trigger {
+            Trigger::Scroll => self.scroll != Vec2::ZERO,
+            _ => self.pressed.contains(&trigger),
+        }
+    }
+
+    /// Came up this frame.
+    pub fn was_released(&self, trigger: Trigger) -> bool {
+        self.released.contains(&trigger)
+    }
+
+    /// Triggers that went down this frame.
+    pub fn pressed_triggers(&self) -> impl Iterator<Item = Trigger> + '_ {
+        self.pressed
+            .iter()
+            .copied()
+            .chain((self.scroll != Vec2::ZERO).then_some(Trigger::Scroll))
+    }
+
+    pub fn modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
+
+    /// Cursor position in physical pixels, `None` outside the window.
+    pub fn cursor(&self) -> Option<Vec2> {
+        self.cursor
+    }
+
+    /// Cursor movement this frame in physical pixels.
+    pub fn cursor_delta(&self) -> Vec2 {
+        self.cursor_delta
+    }
+
+    /// Scrolling this frame in lines; positive `y` scrolls up.
+    pub fn scroll(&self) -> Vec2 {
+        self.scroll
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winit::event::MouseButton;
+
+    #[test]
+    fn tracks_edges_and_deltas_per_frame() {
+        let mut state = InputState::new();
+        let left = Trigger::Mouse(MouseButton::Left);
+        state.move_cursor(Vec2::new(10.0, 10.0));
+        state.press(left);
+        state.move_cursor(Vec2::new(14.0, 7.0));
+        state.scroll_by(Vec2::new(0.0, 1.5));
+        assert!(state.is_down(left) && state.was_pressed(left));
+        assert_eq!(state.cursor_delta(), Vec2::new(4.0, -3.0));
+        assert!(state.was_pressed(Trigger::Scroll));
+
+        state.end_frame();
+        assert!(state.is_down(left) && !state.was_pressed(left));
+        assert_eq!(state.cursor_delta(), Vec2::ZERO);
+        assert!(!state.is_down(Trigger::Scroll));
+
+        state.release(left);
+        assert!(!state.is_down(left) && state.was_released(left));
+        state.release(left);
+        state.end_frame();
+        assert!(!state.was_released(left));
+    }
+}
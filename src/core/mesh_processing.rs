@@ -0,0 +1,569 @@
+pub mod lod;
+pub mod simplify;
+pub mod vertex_cache;
+
+pub use lod::{LodGeneration, build_lods};
+pub use simplify::simplify;
+pub use vertex_cache::{average_cache_miss_ratio, optimize_vertex_cache};
+
+use std::collections::HashMap;
+
+use glam::{Mat3, Mat4, Vec3, Vec4};
+
+use crate::core::asset_manager::Mesh;
+
+/// One processing step with its settings, as offered by the inspector and
+/// the `convert` subcommand.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MeshOperation {
+    /// Smooth normals that keep hard edges sharper than `crease_angle`
+    /// (radians).
+    SmoothNormals {
+        crease_angle: f32,
+    },
+    FlatNormals,
+    Tangents,
+    Weld {
+        epsilon: f32,
+    },
+    RemoveDegenerateTriangles,
+    OptimizeVertexCache,
+    Simplify {
+        target_triangles: usize,
+    },
+}
+
+impl MeshOperation {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::SmoothNormals { .. } => "Smooth normals",
+            Self::FlatNormals => "Flat normals",
+            Self::Tangents => "Tangents",
+            Self::Weld { .. } => "Weld vertices",
+            Self::RemoveDegenerateTriangles => "Remove degenerate triangles",
+            Self::OptimizeVertexCache => "Optimize vertex cache",
+            Self::Simplify { .. } => "Simplify",
+        }
+    }
+
+    /// Runs the step on `mesh`. Fails only when the mesh lacks the data
+    /// the step needs.
+    pub fn apply(&self, mesh: &mut Mesh) -> anyhow::Result<()> {
+        // Levels of detail were built from the old data.
+        mesh.lods.clear();
+        match *self {
+            Self::SmoothNormals { crease_angle } => compute_normals(mesh, crease_angle),
+            Self::FlatNormals => compute_normals(mesh, 0.0),
+            Self::Tangents => {
+                if !compute_tangents(mesh) {
+                    anyhow::bail!("tangents need normals and texture coordinates");
+                }
+            }
+            Self::Weld { epsilon } => {
+                weld(mesh, epsilon);
+            }
+            Self::RemoveDegenerateTriangles => {
+                remove_degenerate_triangles(mesh);
+            }
+            Self::OptimizeVertexCache => optimize_vertex_cache(mesh),
+            Self::Simplify { target_triangles } => {
+                simplify(mesh, target_triangles);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Merges vertices whose positions lie within `epsilon` of each other (on a
+/// grid of that size) and whose other attributes are identical. Returns the
+/// number of vertices removed.
+pub fn weld(mesh: &mut Mesh, epsilon: f32) -> usize {
+    let inv = if epsilon > 0.0 { 1.0 / epsilon } else { 0.0 };
+    let quantize = |p: Vec3| -> [i64; 3] {
+        if inv == 0.0 {
+            p.to_array().map(|c| c.to_bits() as i64)
+        } else {
+            p.to_array().map(|c| (c * inv).round() as i64)
+        }
+    };
+
+    let count = mesh.positions.len();
+    let mut unique: HashMap<([i64; 3], Vec<u32>), u32> = HashMap::with_capacity(count);
+    let mut remap = Vec::with_capacity(count);
+    let mut kept = Vec::new();
+    for i in 0..count {
+        let key = (quantize(mesh.positions[i]), attribute_bits(mesh, i));
+        let next = kept.len() as u32;
+        let index = *unique.entry(key).or_insert(next);
+        if index == next {
+            kept.push(i);
+        }
+        remap.push(index);
+    }
+
+    let removed = count - kept.len();
+    if removed > 0 {
+        retain_vertices(mesh, &kept);
+        for index in &mut mesh.indices {
+            *index = remap[*index as usize];
+        }
+        mesh.invalidate_gpu();
+    }
+    removed
+}
+
+fn attribute_bits(mesh: &Mesh, i: usize) -> Vec<u32> {
+    let mut bits = Vec::new();
+    if let Some(n) = mesh.normals.get(i) {
+        bits.extend(n.to_array().map(f32::to_bits));
+    }
+    if let Some(uv) = mesh.tex_coords.get(i) {
+        bits.extend(uv.to_array().map(f32::to_bits));
+    }
+    if let Some(t) = mesh.tangents.get(i) {
+        bits.extend(t.to_array().map(f32::to_bits));
+    }
+    if let Some(c) = mesh.colors.get(i) {
+        bits.extend(c.to_array().map(f32::to_bits));
+    }
+    if let Some(j) = mesh.joints.get(i) {
+        bits.extend(j.map(u32::from));
+    }
+    if let Some(w) = mesh.weights.get(i) {
+        bits.extend(w.to_array().map(f32::to_bits));
+    }
+    for target in &mesh.morph_targets {
+        for stream in [&target.positions, &target.normals, &target.tangents] {
+            if let Some(d) = stream.get(i) {
+                bits.extend(d.to_array().map(f32::to_bits));
+            }
+        }
+    }
+    bits
+}
+
+/// Rebuilds every vertex stream from the listed source vertices, in order.
+fn retain_vertices(mesh: &mut Mesh, sources: &[usize]) {
+    fn pick<T: Copy>(stream: &[T], sources: &[usize]) -> Vec<T> {
+        if stream.is_empty() {
+            return Vec::new();
+        }
+        sources.iter().map(|&i| stream[i]).collect()
+    }
+    mesh.positions = pick(&mesh.positions, sources);
+    mesh.normals = pick(&mesh.normals, sources);
+    mesh.tex_coords = pick(&mesh.tex_coords, sources);
+    mesh.tangents = pick(&mesh.tangents, sources);
+    mesh.colors = pick(&mesh.colors, sources);
+    mesh.joints = pick(&mesh.joints, sources);
+    mesh.weights = pick(&mesh.weights, sources);
+    for target in &mut mesh.morph_targets {
+        target.positions = pick(&target.positions, sources);
+        target.normals = pick(&target.normals, sources);
+        target.tangents = pick(&target.tangents, sources);
+    }
+}
+
+/// Computes area-weighted vertex normals. Faces meeting at an angle larger
+/// than `crease_angle` (radians) do not smooth across each other; vertices on
+/// such creases are split so each side keeps its own normal.
+pub fn compute_normals(mesh: &mut Mesh, crease_angle: f32) {
+    let triangle_count = mesh.indices.len() / 3;
+    let face_normals: Vec<Vec3> = (0..triangle_count)
+        .map(|f| {
+            let [a, b, c] = corners(mesh, f).map(|i| mesh.positions[i]);
+            (b - a).cross(c - a)
+        })
+        .collect();
+    let face_units: Vec<Vec3> = face_normals.iter().map(|n| n.normalize_or_zero()).collect();
+
+    let mut faces_of_vertex = vec![Vec::new(); mesh.positions.len()];
+    for f in 0..triangle_count {
+        for i in corners(mesh, f) {
+            faces_of_vertex[i].push(f);
+        }
+    }
+
+    let cos_crease = crease_angle.cos();
+    let mut sources = Vec::new();
+    let mut normals = Vec::new();
+    let mut split: HashMap<(usize, [u32; 3]), u32> = HashMap::new();
+    let mut indices = Vec::with_capacity(mesh.indices.len());
+    for f in 0..triangle_count {
+        let own = face_units[f];
+        for v in corners(mesh, f) {
+            let mut sum = Vec3::ZERO;
+            for &g in &faces_of_vertex[v] {
+                // Degenerate faces have no direction to compare against and
+                // take part in every group.
+                if own == Vec3::ZERO || face_units[g].dot(own) >= cos_crease {
+                    sum += face_normals[g];
+                }
+            }
+            let normal = sum.normalize_or_zero();
+            let key = (v, normal.to_array().map(f32::to_bits));
+            let index = *split.entry(key).or_insert_with(|| {
+                sources.push(v);
+                normals.push(normal);
+                (sources.len() - 1) as u32
+            });
+            indices.push(index);
+        }
+    }
+
+    retain_vertices(mesh, &sources);
+    mesh.normals = normals;
+    mesh.indices = indices;
+    mesh.invalidate_gpu();
+}
+
+/// Computes per-vertex tangents from the texture coordinates with
+/// MikkTSpace, so normal maps baked by other tools line up. MikkTSpace
+/// works per face corner; vertices whose corners end up with different
+/// tangent frames, e.g. where mirrored UVs meet, are split. Needs normals
+/// and texture coordinates; returns false and leaves the mesh alone without
+/// them.
+pub fn compute_tangents(mesh: &mut Mesh) -> bool {
+    let count = mesh.positions.len();
+    if mesh.normals.len() != count || mesh.tex_coords.len() != count {
+        return false;
+    }
+    let mut geometry = TangentGeometry {
+        mesh,
+        corners: vec![Vec4::ZERO; mesh.indices.len() / 3 * 3],
+    };
+    if !bevy_mikktspace::generate_tangents(&mut geometry) {
+        return false;
+    }
+    let corner_tangents = geometry.corners;
+
+    let mut sources = Vec::new();
+    let mut tangents = Vec::new();
+    let mut split: HashMap<(u32, [u32; 4]), u32> = HashMap::new();
+    let mut indices = Vec::with_capacity(corner_tangents.len());
+    for (&v, &tangent) in mesh.indices.iter().zip(&corner_tangents) {
+        let key = (v, tangent.to_array().map(f32::to_bits));
+        let index = *split.entry(key).or_insert_with(|| {
+            sources.push(v as usize);
+            tangents.push(tangent);
+            (sources.len() - 1) as u32
+        });
+        indices.push(index);
+    }
+
+    retain_vertices(mesh, &sources);
+    mesh.indices = indices;
+    mesh.tangents = tangents;
+    mesh.invalidate_gpu();
+    true
+}
+
+/// A triangle list as MikkTSpace sees it, collecting one tangent per
+/// corner.
+struct TangentGeometry<'a> {
+    mesh: &'a Mesh,
+    corners: Vec<Vec4>,
+}
+
+impl TangentGeometry<'_> {
+    fn vertex(&self, face: usize, vert: usize) -> usize {
+        self.mesh.indices[face * 3 + vert] as usize
+    }
+}
+
+impl bevy_mikktspace::Geometry for TangentGeometry<'_> {
+    fn num_faces(&self) -> usize {
+        self.corners.len() / 3
+    }
+
+    fn num_vertices_of_face(&self, _face: usize) -> usize {
+        3
+    }
+
+    fn position(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.mesh.positions[self.vertex(face, vert)].to_array()
+    }
+
+    fn normal(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.mesh.normals[self.vertex(face, vert)].to_array()
+    }
+
+    fn tex_coord(&self, face: usize, vert: usize) -> [f32; 2] {
+        self.mesh.tex_coords[self.vertex(face, vert)].to_array()
+    }
+
+    fn set_tangent_encoded(&mut self, tangent: [f32; 4], face: usize, vert: usize) {
+        self.corners[face * 3 + vert] = Vec4::from_array(tangent);
+    }
+}
+
+/// Drops triangles that repeat a vertex or whose corners are collinear,
+/// then the vertices no triangle uses. Returns the number of triangles
+/// removed.
+pub fn remove_degenerate_triangles(mesh: &mut Mesh) -> usize {
+    let before = mesh.triangle_count();
+    let positions = &mesh.positions;
+    let indices: Vec<u32> = mesh
+        .indices
+        .chunks_exact(3)
+        .filter(|t| {
+            if t[0] == t[1] || t[1] == t[2] || t[0] == t[2] {
+                return false;
+            }
+            let [a, b, c] = [t[0], t[1], t[2]].map(|i| positions[i as usize]);
+            let longest = (b - a)
+                .length_squared()
+                .max((c - b).length_squared())
+                .max((a - c).length_squared());
+            // Twice the area against the longest edge squared: the sine of
+            // the widest angle, scale independent.
+            (b - a).cross(c - a).length() > longest * 1e-6
+        })
+        .flatten()
+        .copied()
+        .collect();
+    let removed = before - indices.len() / 3;
+    if removed > 0 {
+        mesh.indices = indices;
+        remove_unused_vertices(mesh);
+        mesh.invalidate_gpu();
+    }
+    removed
+}
+
+/// Drops vertices no triangle references, keeping the order of the rest.
+/// Returns the number of vertices removed.
+pub fn remove_unused_vertices(mesh: &mut Mesh) -> usize {
+    let mut remap = vec![u32::MAX; mesh.positions.len()];
+    for &i in &mesh.indices {
+        remap[i as usize] = 0;
+    }
+    let mut kept = Vec::new();
+    for (i, slot) in remap.iter_mut().enumerate() {
+        if *slot == 0 {
+            *slot = kept.len() as u32;
+            kept.push(i);
+        }
+    }
+    let removed = mesh.positions.len() - kept.len();
+    if removed > 0 {
+        retain_vertices(mesh, &kept);
+        for index in &mut mesh.indices {
+            *index = remap[*index as usize];
+        }
+        mesh.invalidate_gpu();
+    }
+    removed
+}
+
+/// Bakes `matrix` into the vertex data. Normals use the inverse transpose,
+/// and mirroring transforms reverse the winding so faces keep pointing out.
+pub fn transform(mesh: &mut Mesh, matrix: &Mat4) {
+    let linear = Mat3::from_mat4(*matrix);
+    let normal_matrix = linear.inverse().transpose();
+    for p in &mut mesh.positions {
+        *p = matrix.transform_point3(*p);
+    }
+    for n in &mut mesh.normals {
+        *n = (normal_matrix * *n).normalize_or_zero();
+    }
+    for t in &mut mesh.tangents {
+        *t = (linear * t.truncate()).normalize_or_zero().extend(t.w);
+    }
+    for target in &mut mesh.morph_targets {
+        for d in &mut target.positions {
+            *d = linear * *d;
+        }
+        for d in &mut target.normals {
+            *d = normal_matrix * *d;
+        }
+        for d in &mut target.tangents {
+            *d = linear * *d;
+        }
+    }
+    if linear.determinant() < 0.0 {
+        for triangle in mesh.indices.chunks_exact_mut(3) {
+            triangle.swap(1, 2);
+        }
+        for t in &mut mesh.tangents {
+            t.w = -t.w;
+        }
+    }
+    mesh.invalidate_gpu();
+}
+
+/// Concatenates meshes into one. Vertex streams missing from any part are
+/// left out of the result; skinning and morph targets are not merged.
+pub fn merge(name: impl Into<String>, parts: &[&Mesh]) -> Mesh {
+    let mut mesh = Mesh::new(name);
+    let all = |has: fn(&Mesh) -> bool| parts.iter().all(|part| has(part));
+    let normals = all(|m| m.normals.len() == m.positions.len());
+    let tex_coords = all(|m| m.tex_coords.len() == m.positions.len());
+    let tangents = all(|m| m.tangents.len() == m.positions.len());
+    let colors = all(|m| m.colors.len() == m.positions.len());
+    for part in parts {
+        let base = mesh.positions.len() as u32;
+        mesh.positions.extend_from_slice(&part.positions);
+        if normals {
+            mesh.normals.extend_from_slice(&part.normals);
+        }
+        if tex_coords {
+            mesh.tex_coords.extend_from_slice(&part.tex_coords);
+        }
+        if tangents {
+            mesh.tangents.extend_from_slice(&part.tangents);
+        }
+        if colors {
+            mesh.colors.extend_from_slice(&part.colors);
+        }
+        mesh.indices.extend(part.indices.iter().map(|&i| base + i));
+    }
+    mesh
+}
+
+fn corners(mesh: &Mesh, face: usize) -> [usize; 3] {
+    let i = face * 3;
+    [
+        mesh.indices[i] as usize,
+        mesh.indices[i + 1] as usize,
+        mesh.indices[i + 2] as usize,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+
+    use super::*;
+
+    /// Two triangles of a unit quad, as a triangle soup.
+    fn quad_soup() -> Mesh {
+        let mut mesh = Mesh::new("quad");
+        mesh.positions = vec![
+            Vec3::ZERO,
+            Vec3::X,
+            Vec3::new(1.0, 1.0, 0.0),
+            Vec3::ZERO,
+            Vec3::new(1.0, 1.0, 0.0),
+            Vec3::Y,
+        ];
+        mesh.indices = (0..6).collect();
+        mesh
+    }
+
+    #[test]
+    fn weld_merges_shared_corners() {
+        let mut mesh = quad_soup();
+        mesh.positions[3] += Vec3::splat(1e-7);
+        assert_eq!(weld(&mut mesh, 1e-5), 2);
+        assert_eq!(mesh.vertex_count(), 4);
+        assert_eq!(mesh.indices, vec![0, 1, 2, 0, 2, 3]);
+    }
+
+    #[test]
+    fn crease_angle_splits_sharp_edges() {
+        // Two faces folded 90 degrees along the X axis.
+        let mut mesh = Mesh::new("fold");
+        mesh.positions = vec![Vec3::ZERO, Vec3::X, Vec3::Y, Vec3::Z];
+        mesh.indices = vec![0, 1, 2, 1, 0, 3];
+
+        let mut smooth = Mesh::new("fold");
+        smooth.positions = mesh.positions.clone();
+        smooth.indices = mesh.indices.clone();
+        compute_normals(&mut smooth, 100f32.to_radians());
+        assert_eq!(smooth.vertex_count(), 4);
+
+        compute_normals(&mut mesh, 30f32.to_radians());
+        assert_eq!(mesh.vertex_count(), 6);
+        assert_eq!(mesh.normals[mesh.indices[0] as usize], Vec3::Z);
+        assert_eq!(mesh.normals[mesh.indices[3] as usize], Vec3::Y);
+    }
+
+    #[test]
+    fn tangents_follow_the_u_direction() {
+        let mut mesh = quad_soup();
+        mesh.normals = vec![Vec3::Z; 6];
+        assert!(!compute_tangents(&mut mesh));
+        mesh.tex_coords = mesh.positions.iter().map(|p| p.truncate()).collect();
+        assert!(compute_tangents(&mut mesh));
+        assert!(
+            mesh.tangents
+                .iter()
+                .all(|&t| t == Vec4::new(1.0, 0.0, 0.0, 1.0))
+        );
+    }
+
+    #[test]
+    fn tangents_match_mikktspace_across_a_fold() {
+        // Two quads folded by 90 degrees along the X axis with shared,
+        // averaged normals and a continuous mapping running up the fold in
+        // v. MikkTSpace keeps the shared vertices and gives every corner the
+        // +X tangent with a positive bitangent sign.
+        let mut mesh = Mesh::new("fold");
+        mesh.positions = vec![
+            Vec3::new(0.0, 0.0, 1.0),
+            Vec3::new(1.0, 0.0, 1.0),
+            Vec3::ZERO,
+            Vec3::X,
+            Vec3::Y,
+            Vec3::new(1.0, 1.0, 0.0),
+        ];
+        let bent = Vec3::new(0.0, 1.0, 1.0).normalize();
+        mesh.normals = vec![Vec3::Y, Vec3::Y, bent, bent, Vec3::Z, Vec3::Z];
+        mesh.tex_coords = vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(0.0, 1.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(0.0, 2.0),
+            Vec2::new(1.0, 2.0),
+        ];
+        mesh.indices = vec![0, 1, 3, 0, 3, 2, 2, 3, 5, 2, 5, 4];
+        assert!(compute_tangents(&mut mesh));
+        assert_eq!(mesh.vertex_count(), 6);
+        for t in &mesh.tangents {
+            assert!(t.abs_diff_eq(Vec4::new(1.0, 0.0, 0.0, 1.0), 1e-5), "{t}");
+        }
+    }
+
+    #[test]
+    fn tangents_follow_a_rotated_mapping() {
+        let mut mesh = quad_soup();
+        mesh.normals = vec![Vec3::Z; 6];
+        mesh.tex_coords = mesh
+            .positions
+            .iter()
+            .map(|p| Vec2::new(p.y, -p.x))
+            .collect();
+        assert!(compute_tangents(&mut mesh));
+        for t in &mesh.tangents {
+            assert!(t.abs_diff_eq(Vec4::new(0.0, 1.0, 0.0, 1.0), 1e-5), "{t}");
+        }
+    }
+
+    #[test]
+    fn tangents_split_mirrored_uvs() {
+        // The second triangle's UVs mirror the first across the shared
+        // diagonal, so both diagonal vertices need two tangent frames.
+        let mut mesh = Mesh::new("quad");
+        mesh.positions = vec![Vec3::ZERO, Vec3::X, Vec3::new(1.0, 1.0, 0.0), Vec3::Y];
+        mesh.normals = vec![Vec3::Z; 4];
+        mesh.tex_coords = vec![Vec2::ZERO, Vec2::X, Vec2::ONE, Vec2::X];
+        mesh.indices = vec![0, 1, 2, 0, 2, 3];
+        assert!(compute_tangents(&mut mesh));
+        assert_eq!(mesh.vertex_count(), 6);
+        let sign = |corner: usize| mesh.tangents[mesh.indices[corner] as usize].w;
+        assert_eq!(sign(0), -sign(3));
+        assert_eq!(sign(2), -sign(4));
+    }
+
+    #[test]
+    fn mirroring_reverses_winding() {
+        let mut mesh = quad_soup();
+        mesh.normals = vec![Vec3::Z; 6];
+        transform(&mut mesh, &Mat4::from_scale(Vec3::new(-2.0, 2.0, 2.0)));
+        assert_eq!(mesh.positions[1], Vec3::new(-2.0, 0.0, 0.0));
+        assert_eq!(mesh.normals[0], Vec3::Z);
+        assert_eq!(mesh.indices[..3], [0, 2, 1]);
+    }
+}
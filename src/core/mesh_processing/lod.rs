@@ -0,0 +1,118 @@
+//! Levels of detail built by repeated simplification.
+
+use super::{Mesh, remove_unused_vertices, simplify};
+
+/// Coverage of the viewport height below which full detail is no longer
+/// needed. Each level's threshold follows from its triangle count, keeping
+/// the triangle density on screen roughly constant.
+const FULL_DETAIL_COVERAGE: f32 = 0.5;
+
+/// How `build_lods` reduces a mesh.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LodGeneration {
+    /// Coarser levels besides the original mesh.
+    pub levels: usize,
+    /// Triangle count of each level relative to the one before.
+    pub reduction: f32,
+    /// Meshes with fewer triangles keep full detail only.
+    pub min_triangles: usize,
+}
+
+impl LodGeneration {
+    pub fn new() -> Self {
+        Self {
+            levels: 3,
+            reduction: 0.5,
+            min_triangles: 2000,
+        }
+    }
+
+    /// Coverage below which `level` (1 for the first coarser one) is drawn.
+    /// Triangle count scales with the covered area, hence the square root.
+    pub fn screen_coverage(&self, level: usize) -> f32 {
+        FULL_DETAIL_COVERAGE * self.reduction.clamp(0.01, 1.0).sqrt().powi(level as i32)
+    }
+}
+
+impl Default for LodGeneration {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Simplifies `mesh` level by level, each from the previous one, and pairs
+/// every level with its screen coverage threshold. Stops early once
+/// simplification no longer makes real progress, e.g. when only seams and
+/// borders are left.
+pub fn build_lods(mesh: &Mesh, settings: &LodGeneration) -> Vec<(Mesh, f32)> {
+    let mut levels = Vec::new();
+    if mesh.triangle_count() < settings.min_triangles.max(1) {
+        return levels;
+    }
+    let mut previous = mesh.clone();
+    previous.lods.clear();
+    for level in 1..=settings.levels {
+        let target = (mesh.triangle_count() as f32 * settings.reduction.powi(level as i32)).ceil();
+        let mut next = previous.clone();
+        next.name = format!("{}.lod{level}", mesh.name);
+        simplify(&mut next, target as usize);
+        if next.triangle_count() as f32 > previous.triangle_count() as f32 * 0.9 {
+            break;
+        }
+        remove_unused_vertices(&mut next);
+        levels.push((next.clone(), settings.screen_coverage(level)));
+        previous = next;
+    }
+    levels
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+
+    use super::*;
+
+    #[test]
+    fn builds_decreasing_levels() {
+        let size = 40;
+        let mut mesh = Mesh::new("terrain");
+        for y in 0..=size {
+            for x in 0..=size {
+                let (fx, fy) = (x as f32 / size as f32, y as f32 / size as f32);
+                let height = (fx * 6.0).sin() * (fy * 4.0).cos() * 0.1;
+                mesh.positions.push(Vec3::new(fx, height, fy));
+            }
+        }
+        for y in 0..size {
+            for x in 0..size {
+                let i = y * (size + 1) + x;
+                mesh.indices
+                    .extend([i, i + size + 1, i + 1, i + 1, i + size + 1, i + size + 2]);
+            }
+        }
+        let settings = LodGeneration {
+            min_triangles: 100,
+            ..LodGeneration::new()
+        };
+
+        let levels = build_lods(&mesh, &settings);
+        assert_eq!(levels.len(), 3);
+        let mut triangles = mesh.triangle_count();
+        let mut coverage = f32::INFINITY;
+        for (i, (level, level_coverage)) in levels.iter().enumerate() {
+            assert_eq!(level.name, format!("terrain.lod{}", i + 1));
+            assert!(level.triangle_count() < triangles);
+            assert!(*level_coverage < coverage);
+            assert!(level.vertex_count() < mesh.vertex_count());
+            triangles = level.triangle_count();
+            coverage = *level_coverage;
+        }
+        assert!(triangles <= mesh.triangle_count() / 8 + 1);
+
+        let small = LodGeneration {
+            min_triangles: 10_000,
+            ..settings
+        };
+        assert!(build_lods(&mesh, &small).is_empty());
+    }
+}
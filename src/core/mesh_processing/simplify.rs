@@ -0,0 +1,406 @@
+//! Quadric error metric simplification (Garland and Heckbert), collapsing
+//! half-edges so every remaining vertex keeps its original attributes.
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use glam::DVec3;
+
+use super::{Mesh, remove_unused_vertices};
+
+/// How strongly open borders resist moving, relative to surface planes.
+const BORDER_WEIGHT: f64 = 1000.0;
+
+/// Collapses edges in order of least geometric error until at most
+/// `target_triangles` remain or no collapse is safe. Open borders are held
+/// in place, vertices shared by UV or normal seams are never moved, and
+/// collapses that would flip a face or pinch the surface are skipped.
+/// Returns the number of triangles removed.
+pub fn simplify(mesh: &mut Mesh, target_triangles: usize) -> usize {
+    let before = mesh.triangle_count();
+    if before <= target_triangles {
+        return 0;
+    }
+    let mut state = State::new(mesh);
+    let mut heap = BinaryHeap::new();
+    for t in 0..state.triangles.len() {
+        for (a, b) in state.edges(t) {
+            state.push_candidate(&mut heap, a, b);
+            state.push_candidate(&mut heap, b, a);
+        }
+    }
+
+    let mut alive = before;
+    while alive > target_triangles {
+        let Some(candidate) = heap.pop() else {
+            break;
+        };
+        let Candidate { from, to, .. } = candidate;
+        if state.removed[from as usize]
+            || state.removed[to as usize]
+            || state.versions[from as usize] != candidate.from_version
+            || state.versions[to as usize] != candidate.to_version
+            || !state.can_collapse(from, to)
+        {
+            continue;
+        }
+        alive -= state.collapse(from, to);
+        for neighbour in state.neighbours(to) {
+            state.push_candidate(&mut heap, neighbour, to);
+            state.push_candidate(&mut heap, to, neighbour);
+        }
+    }
+
+    mesh.indices = state
+        .triangles
+        .iter()
+        .zip(&state.alive)
+        .filter(|(_, alive)| **alive)
+        .flat_map(|(triangle, _)| *triangle)
+        .collect();
+    remove_unused_vertices(mesh);
+    mesh.invalidate_gpu();
+    before - mesh.triangle_count()
+}
+
+/// Symmetric 4x4 matrix summing squared distances to planes.
+#[derive(Clone, Copy, Default)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    fn plane(normal: DVec3, point: DVec3, weight: f64) -> Self {
+        let [a, b, c] = normal.to_array();
+        let d = -normal.dot(point);
+        Self(
+            [
+                a * a,
+                a * b,
+                a * c,
+                a * d,
+                b * b,
+                b * c,
+                b * d,
+                c * c,
+                c * d,
+                d * d,
+            ]
+            .map(|q| q * weight),
+        )
+    }
+
+    fn add(&mut self, other: &Self) {
+        for (q, o) in self.0.iter_mut().zip(other.0) {
+            *q += o;
+        }
+    }
+
+    fn error(&self, p: DVec3) -> f64 {
+        let [a2, ab, ac, ad, b2, bc, bd, c2, cd, d2] = self.0;
+        let (x, y, z) = (p.x, p.y, p.z);
+        (a2 * x * x + 2.0 * ab * x * y + 2.0 * ac * x * z + 2.0 * ad * x)
+            + (b2 * y * y + 2.0 * bc * y * z + 2.0 * bd * y)
+            + (c2 * z * z + 2.0 * cd * z)
+            + d2
+    }
+}
+
+/// Moving `from` onto `to`, ordered so the heap pops the cheapest first.
+struct Candidate {
+    cost: f64,
+    from: u32,
+    to: u32,
+    from_version: u32,
+    to_version: u32,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+struct State {
+    positions: Vec<DVec3>,
+    triangles: Vec<[u32; 3]>,
+    alive: Vec<bool>,
+    vertex_triangles: Vec<Vec<u32>>,
+    quadrics: Vec<Quadric>,
+    /// Bumped whenever a vertex's quadric changes, invalidating queued
+    /// candidates that involve it.
+    versions: Vec<u32>,
+    removed: Vec<bool>,
+    locked: Vec<bool>,
+}
+
+impl State {
+    fn new(mesh: &Mesh) -> Self {
+        let positions: Vec<DVec3> = mesh.positions.iter().map(|p| p.as_dvec3()).collect();
+        let triangles: Vec<[u32; 3]> = mesh
+            .indices
+            .chunks_exact(3)
+            .map(|t| [t[0], t[1], t[2]])
+            .collect();
+        let mut vertex_triangles = vec![Vec::new(); positions.len()];
+        let mut quadrics = vec![Quadric::default(); positions.len()];
+        let mut edge_uses: HashMap<(u32, u32), u32> = HashMap::new();
+        for (t, triangle) in triangles.iter().enumerate() {
+            let [a, b, c] = triangle.map(|v| positions[v as usize]);
+            let cross = (b - a).cross(c - a);
+            let area = cross.length() * 0.5;
+            let plane = Quadric::plane(cross.normalize_or_zero(), a, area);
+            for (k, &v) in triangle.iter().enumerate() {
+                vertex_triangles[v as usize].push(t as u32);
+                quadrics[v as usize].add(&plane);
+                let next = triangle[(k + 1) % 3];
+                *edge_uses.entry((v.min(next), v.max(next))).or_default() += 1;
+            }
+        }
+
+        // A plane through each border edge, perpendicular to its face,
+        // keeps borders from shrinking.
+        for triangle in &triangles {
+            let [a, b, c] = triangle.map(|v| positions[v as usize]);
+            let normal = (b - a).cross(c - a).normalize_or_zero();
+            for k in 0..3 {
+                let (u, v) = (triangle[k], triangle[(k + 1) % 3]);
+                if edge_uses[&(u.min(v), u.max(v))] != 1 {
+                    continue;
+                }
+                let (pu, pv) = (positions[u as usize], positions[v as usize]);
+                let edge = pv - pu;
+                let plane = Quadric::plane(
+                    edge.cross(normal).normalize_or_zero(),
+                    pu,
+                    edge.length_squared() * BORDER_WEIGHT,
+                );
+                quadrics[u as usize].add(&plane);
+                quadrics[v as usize].add(&plane);
+            }
+        }
+
+        // Vertices split along seams share a position; moving one copy
+        // would tear the surface open.
+        let mut first_at: HashMap<[u32; 3], usize> = HashMap::new();
+        let mut locked = vec![false; positions.len()];
+        for (v, p) in mesh.positions.iter().enumerate() {
+            let key = p.to_array().map(f32::to_bits);
+            if let Some(&other) = first_at.get(&key) {
+                locked[other] = true;
+                locked[v] = true;
+            } else {
+                first_at.insert(key, v);
+            }
+        }
+
+        Self {
+            alive: vec![true; triangles.len()],
+            versions: vec![0; positions.len()],
+            removed: vec![false; positions.len()],
+            positions,
+            triangles,
+            vertex_triangles,
+            quadrics,
+            locked,
+        }
+    }
+
+    fn edges(&self, t: usize) -> [(u32, u32); 3] {
+        let [a, b, c] = self.triangles[t];
+        [(a, b), (b, c), (c, a)]
+    }
+
+    fn push_candidate(&self, heap: &mut BinaryHeap<Candidate>, from: u32, to: u32) {
+        if self.locked[from as usize] {
+            return;
+        }
+        let mut quadric = self.quadrics[from as usize];
+        quadric.add(&self.quadrics[to as usize]);
+        heap.push(Candidate {
+            cost: quadric.error(self.positions[to as usize]),
+            from,
+            to,
+            from_version: self.versions[from as usize],
+            to_version: self.versions[to as usize],
+        });
+    }
+
+    fn live_triangles(&self, v: u32) -> impl Iterator<Item = usize> + '_ {
+        self.vertex_triangles[v as usize]
+            .iter()
+            .map(|&t| t as usize)
+            .filter(|&t| self.alive[t])
+    }
+
+    fn neighbours(&self, v: u32) -> HashSet<u32> {
+        self.live_triangles(v)
+            .flat_map(|t| self.triangles[t])
+            .filter(|&other| other != v)
+            .collect()
+    }
+
+    /// The edge still exists, the surface stays manifold (the endpoints
+    /// share no neighbours besides the faces on the edge) and no face
+    /// around `from` turns over.
+    fn can_collapse(&self, from: u32, to: u32) -> bool {
+        let shared: Vec<usize> = self
+            .live_triangles(from)
+            .filter(|&t| self.triangles[t].contains(&to))
+            .collect();
+        if shared.is_empty() {
+            return false;
+        }
+        let common = self
+            .neighbours(from)
+            .intersection(&self.neighbours(to))
+            .count();
+        if common != shared.len() {
+            return false;
+        }
+
+        let target = self.positions[to as usize];
+        self.live_triangles(from)
+            .filter(|t| !shared.contains(t))
+            .all(|t| {
+                let corners = self.triangles[t].map(|v| self.positions[v as usize]);
+                let moved = self.triangles[t].map(|v| {
+                    if v == from {
+                        target
+                    } else {
+                        self.positions[v as usize]
+                    }
+                });
+                let normal = |[a, b, c]: [DVec3; 3]| (b - a).cross(c - a);
+                let (old, new) = (normal(corners), normal(moved));
+                new.length_squared() > 0.0 && old.dot(new) > 0.0
+            })
+    }
+
+    /// Returns the number of triangles that disappeared.
+    fn collapse(&mut self, from: u32, to: u32) -> usize {
+        let mut dropped = 0;
+        let triangles = std::mem::take(&mut self.vertex_triangles[from as usize]);
+        for t in triangles {
+            let t = t as usize;
+            if !self.alive[t] {
+                continue;
+            }
+            if self.triangles[t].contains(&to) {
+                self.alive[t] = false;
+                dropped += 1;
+            } else {
+                for v in &mut self.triangles[t] {
+                    if *v == from {
+                        *v = to;
+                    }
+                }
+                self.vertex_triangles[to as usize].push(t as u32);
+            }
+        }
+        self.removed[from as usize] = true;
+        let quadric = self.quadrics[from as usize];
+        self.quadrics[to as usize].add(&quadric);
+        self.versions[to as usize] += 1;
+        dropped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+
+    use super::*;
+
+    /// A closed, finely tessellated sphere.
+    fn sphere(rings: u32, segments: u32) -> Mesh {
+        let mut mesh = Mesh::new("sphere");
+        mesh.positions.push(Vec3::Y);
+        for ring in 1..rings {
+            let theta = std::f32::consts::PI * ring as f32 / rings as f32;
+            for segment in 0..segments {
+                let phi = std::f32::consts::TAU * segment as f32 / segments as f32;
+                mesh.positions.push(Vec3::new(
+                    theta.sin() * phi.cos(),
+                    theta.cos(),
+                    theta.sin() * phi.sin(),
+                ));
+            }
+        }
+        mesh.positions.push(Vec3::NEG_Y);
+        let bottom = mesh.positions.len() as u32 - 1;
+        let at = |ring: u32, segment: u32| 1 + (ring - 1) * segments + segment % segments;
+        for s in 0..segments {
+            mesh.indices.extend([0, at(1, s + 1), at(1, s)]);
+            mesh.indices
+                .extend([bottom, at(rings - 1, s), at(rings - 1, s + 1)]);
+        }
+        for ring in 1..rings - 1 {
+            for s in 0..segments {
+                let (a, b) = (at(ring, s), at(ring, s + 1));
+                let (c, d) = (at(ring + 1, s), at(ring + 1, s + 1));
+                mesh.indices.extend([a, b, d, a, d, c]);
+            }
+        }
+        mesh
+    }
+
+    #[test]
+    fn reaches_the_target_and_keeps_the_shape() {
+        let mut mesh = sphere(24, 48);
+        let before = mesh.triangle_count();
+        let removed = simplify(&mut mesh, 200);
+        assert_eq!(mesh.triangle_count(), before - removed);
+        assert!(mesh.triangle_count() <= 200, "{}", mesh.triangle_count());
+        assert!(mesh.triangle_count() >= 150);
+        // Surviving vertices are original ones, all on the unit sphere.
+        assert!(
+            mesh.positions
+                .iter()
+                .all(|p| (p.length() - 1.0).abs() < 1e-5)
+        );
+        let indexed = mesh
+            .indices
+            .iter()
+            .all(|&i| (i as usize) < mesh.vertex_count());
+        assert!(indexed);
+        assert!(mesh.bounds().max.y > 0.9 && mesh.bounds().min.y < -0.9);
+    }
+
+    #[test]
+    fn keeps_open_borders() {
+        // A flat grid simplifies down to its corners without its outline
+        // moving.
+        let size = 8;
+        let mut mesh = Mesh::new("grid");
+        for y in 0..=size {
+            for x in 0..=size {
+                mesh.positions.push(Vec3::new(x as f32, y as f32, 0.0));
+            }
+        }
+        for y in 0..size {
+            for x in 0..size {
+                let i = y * (size + 1) + x;
+                mesh.indices
+                    .extend([i, i + 1, i + size + 2, i, i + size + 2, i + size + 1]);
+            }
+        }
+        simplify(&mut mesh, 2);
+        let bounds = mesh.bounds();
+        assert_eq!(bounds.min, Vec3::ZERO);
+        assert_eq!(bounds.max, Vec3::new(size as f32, size as f32, 0.0));
+        assert!(mesh.triangle_count() < 40, "{}", mesh.triangle_count());
+    }
+}
@@ -0,0 +1,122 @@
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RedrawMode {
+    /// Draw frames back to back, up to the frame-rate cap.
+    Continuous,
+    /// Draw only when something asked for a frame: input, a UI repaint
+    /// request, asset activity or animation playback.
+    Reactive,
+}
+
+/// Decides when the next frame is due. Anything that changes what is on
+/// screen calls `request_redraw`; the event loop sleeps until `next_frame`.
+pub struct RedrawScheduler {
+    mode: RedrawMode,
+    min_interval: Duration,
+    requested: Option<Instant>,
+    last_frame: Option<Instant>,
+}
+
+impl RedrawScheduler {
+    pub fn new(mode: RedrawMode) -> Self {
+        Self {
+            mode,
+            min_interval: Duration::ZERO,
+            requested: None,
+            last_frame: None,
+        }
+    }
+
+    pub fn mode(&self) -> RedrawMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: RedrawMode) {
+        self.mode = mode;
+    }
+
+    /// Frame-rate cap; `None` or zero for uncapped.
+    pub fn fps_cap(&self) -> Option<f64> {
+        (!self.min_interval.is_zero()).then(|| 1.0 / self.min_interval.as_secs_f64())
+    }
+
+    pub fn set_fps_cap(&mut self, fps: Option<f64>) {
+        self.min_interval = match fps {
+            Some(fps) if fps > 0.0 => Duration::from_secs_f64(1.0 / fps),
+            _ => Duration::ZERO,
+        };
+    }
+
+    pub fn request_redraw(&mut self) {
+        self.request_redraw_at(Instant::now());
+    }
+
+    /// Asks for a frame once `delay` has passed; very long delays, such as
+    /// egui's "never", are ignored.
+    pub fn request_redraw_after(&mut self, delay: Duration) {
+        if let Some(at) = Instant::now().checked_add(delay) {
+            self.request_redraw_at(at);
+        }
+    }
+
+    pub fn request_redraw_at(&mut self, at: Instant) {
+        self.requested = Some(self.requested.map_or(at, |requested| requested.min(at)));
+    }
+
+    /// When the next frame should start, or `None` to wait for events.
+    pub fn next_frame(&self, now: Instant) -> Option<Instant> {
+        let wanted = match self.mode {
+            RedrawMode::Continuous => Some(now),
+            RedrawMode::Reactive => self.requested,
+        }?;
+        let earliest = self
+            .last_frame
+            .map_or(wanted, |last| last + self.min_interval);
+        Some(wanted.max(earliest))
+    }
+
+    /// Marks a frame as started, consuming the pending requests.
+    pub fn frame_started(&mut self, now: Instant) {
+        self.last_frame = Some(now);
+        self.requested = None;
+    }
+}
+
+impl Default for RedrawScheduler {
+    fn default() -> Self {
+        Self::new(RedrawMode::Continuous)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reactive_mode_waits_for_requests_and_respects_the_cap() {
+        let mut redraw = RedrawScheduler::new(RedrawMode::Reactive);
+        redraw.set_fps_cap(Some(10.0));
+        let now = Instant::now();
+        assert_eq!(redraw.next_frame(now), None);
+
+        redraw.request_redraw_at(now);
+        assert_eq!(redraw.next_frame(now), Some(now));
+        redraw.frame_started(now);
+        assert_eq!(redraw.next_frame(now), None);
+
+        redraw.request_redraw_at(now);
+        redraw.request_redraw_after(Duration::MAX);
+        assert_eq!(
+            redraw.next_frame(now),
+            Some(now + Duration::from_millis(100))
+        );
+
+        redraw.set_mode(RedrawMode::Continuous);
+        redraw.frame_started(now);
+        assert_eq!(
+            redraw.next_frame(now),
+            Some(now + Duration::from_millis(100))
+        );
+    }
+}
@@ -1,14 +1,277 @@
-use std::sync::Arc;
+pub mod debug_lines;
+pub mod draw_list;
+pub mod environment;
+pub mod gpu_mesh;
+pub mod gpu_texture;
+pub mod gpu_timer;
+pub mod grid;
+pub mod instance_buffer;
+pub mod joint_texture;
+pub mod lod;
+pub mod point_cloud_renderer;
+pub mod shader_program;
 
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    path::Path,
+    sync::Arc,
+    time::Duration,
+};
+
+use glam::{Mat4, Vec3, Vec4};
 use glow::HasContext;
+use winit::dpi::PhysicalSize;
+
+use crate::core::{
+    asset_manager::{
+        AssetManager, Handle, Material, Shader,
+        material::AlphaMode,
+        texture::{ColorSpace, Texture},
+    },
+    bounds::Frustum,
+    camera::Camera,
+    scene::{NodeId, Scene},
+};
+
+pub use debug_lines::{DebugLines, LineList};
+pub use draw_list::{DrawBatch, DrawItem};
+pub use environment::{Background, EnvironmentRenderer, EnvironmentSettings};
+pub use gpu_mesh::GpuMesh;
+pub use gpu_texture::GpuTexture;
+pub use gpu_timer::GpuTimer;
+pub use grid::GridRenderer;
+pub use instance_buffer::InstanceBuffer;
+pub use joint_texture::JointTexture;
+pub use lod::{LodSettings, LodState};
+pub use point_cloud_renderer::{
+    PointCloudRenderer, PointCloudSettings, PointCloudStats, PointColorMode, PointSizeMode,
+};
+pub use shader_program::ShaderProgram;
+
+const MESH_SHADER_PATH: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/src/core/renderer/shaders/mesh.glsl"
+);
+
+/// Direction of the single directional light meshes are shaded with.
+pub const LIGHT_DIRECTION: Vec3 = Vec3::new(-0.4, -1.0, -0.6);
+
+const JOINT_TEXTURE_UNIT: u32 = 1;
+const MORPH_TARGET_UNIT: u32 = 2;
+const SPECULAR_MAP_UNIT: u32 = 3;
+const BRDF_LUT_UNIT: u32 = 4;
+
+/// Counts from the last scene pass.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct RenderStats {
+    pub visible_objects: usize,
+    pub culled_objects: usize,
+    /// Mesh draw calls issued after batching repeated meshes.
+    pub draw_calls: usize,
+    /// Mesh instances drawn; one draw call each without batching.
+    pub instances: usize,
+    pub triangles: usize,
+}
+
+/// What the mesh pass shows. The discriminants are the `u_view_mode`
+/// values of `mesh.glsl`.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum ViewMode {
+    /// Material color under the scene light.
+    #[default]
+    Lit = 0,
+    /// Base color and emissive without lighting.
+    Unlit = 1,
+    /// Lit, with triangle edges drawn on top.
+    Wireframe = 2,
+    /// World-space normals as colors.
+    Normals = 3,
+    /// A checkerboard in texture space, for judging stretching and seams.
+    UvChecker = 4,
+    /// World-space tangents as colors; black where a mesh has none.
+    Tangents = 5,
+    /// Distance from the camera across the visible objects, near is light.
+    Depth = 6,
+    /// Surfaces shaded per pixel, from dark red for one to white for many.
+    Overdraw = 7,
+    /// A distinct color per material.
+    MaterialId = 8,
+    /// On-screen triangle size: red below a pixel, then yellow and green,
+    /// up to blue for triangles of hundreds of pixels.
+    TriangleDensity = 9,
+    /// Shading tinted by the level of detail drawn: full detail green, then
+    /// yellow, orange, red and magenta.
+    LodLevel = 10,
+}
+
+/// `u_view_mode` of the second, line-only pass of `ViewMode::Wireframe`.
+const WIREFRAME_LINES: i32 = 11;
+
+impl ViewMode {
+    pub const ALL: [ViewMode; 11] = [
+        ViewMode::Lit,
+        ViewMode::Unlit,
+        ViewMode::Wireframe,
+        ViewMode::Normals,
+        ViewMode::UvChecker,
+        ViewMode::Tangents,
+        ViewMode::Depth,
+        ViewMode::Overdraw,
+        ViewMode::MaterialId,
+        ViewMode::TriangleDensity,
+        ViewMode::LodLevel,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ViewMode::Lit => "Lit",
+            ViewMode::Unlit => "Unlit",
+            ViewMode::Wireframe => "Wireframe",
+            ViewMode::Normals => "Normals",
+            ViewMode::UvChecker => "UV checker",
+            ViewMode::Tangents => "Tangents",
+            ViewMode::Depth => "Depth",
+            ViewMode::Overdraw => "Overdraw",
+            ViewMode::MaterialId => "Material ID",
+            ViewMode::TriangleDensity => "Triangle density",
+            ViewMode::LodLevel => "LOD level",
+        }
+    }
+}
+
+/// Guides drawn over the scene pass.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Overlays {
+    /// The ground grid with the world axes.
+    pub grid: bool,
+    /// A short line along each vertex normal, in the rest pose of morph
+    /// targets.
+    pub normals: bool,
+    /// World bounds of every visible mesh and point cloud.
+    pub bounds: bool,
+    /// Joints of skinned meshes, linked to their parent joints.
+    pub skeleton: bool,
+}
+
+impl Overlays {
+    pub fn new() -> Self {
+        Self {
+            grid: true,
+            normals: false,
+            bounds: false,
+            skeleton: false,
+        }
+    }
+
+    /// Whether any of the per-object line overlays is on.
+    pub fn any_lines(&self) -> bool {
+        self.normals || self.bounds || self.skeleton
+    }
+}
+
+impl Default for Overlays {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Per-view state of the scene pass, kept by whoever owns the camera.
+#[derive(Default)]
+pub struct ViewState {
+    pub mode: ViewMode,
+    pub overlays: Overlays,
+    pub lods: LodState,
+}
 
 pub struct Renderer {
     gl: Arc<glow::Context>,
+    mesh_shader: Handle<Shader>,
+    white_texture: Option<GpuTexture>,
+    instances: Option<InstanceBuffer>,
+    joint_texture: Option<JointTexture>,
+    point_clouds: PointCloudRenderer,
+    grid: GridRenderer,
+    environment: EnvironmentRenderer,
+    environment_settings: EnvironmentSettings,
+    debug_lines: DebugLines,
+    lod_settings: LodSettings,
+    stats: RenderStats,
+    gpu_timer: Option<GpuTimer>,
+    gpu_time: Option<Duration>,
 }
 
 impl Renderer {
-    pub fn new(gl: Arc<glow::Context>) -> Self {
-        Self { gl }
+    pub fn new(gl: Arc<glow::Context>, assets: &mut AssetManager) -> Self {
+        let white = Texture::from_rgba8("white", 1, 1, vec![255; 4], ColorSpace::Linear);
+        let white_texture = GpuTexture::upload(gl.clone(), &white)
+            .inspect_err(|err| log::error!("failed to create default texture: {:#}", err))
+            .ok();
+        let instances = InstanceBuffer::new(gl.clone())
+            .inspect_err(|err| log::error!("{:#}", err))
+            .ok();
+        let joint_texture = JointTexture::new(gl.clone())
+            .inspect_err(|err| log::error!("{:#}", err))
+            .ok();
+        let point_clouds = PointCloudRenderer::new(gl.clone(), assets);
+        let grid = GridRenderer::new(gl.clone(), assets);
+        let environment = EnvironmentRenderer::new(gl.clone(), assets);
+        let debug_lines = DebugLines::new(gl.clone(), assets);
+        let gpu_timer = GpuTimer::new(gl.clone());
+        if gpu_timer.is_none() {
+            log::info!("timer queries unavailable, GPU times will not be shown");
+        }
+        Self {
+            gl,
+            mesh_shader: builtin_shader(
+                assets,
+                MESH_SHADER_PATH,
+                include_str!("renderer/shaders/mesh.glsl"),
+            ),
+            white_texture,
+            instances,
+            joint_texture,
+            point_clouds,
+            grid,
+            environment,
+            environment_settings: EnvironmentSettings::new(),
+            debug_lines,
+            lod_settings: LodSettings::new(),
+            stats: RenderStats::default(),
+            gpu_timer,
+            gpu_time: None,
+        }
+    }
+
+    pub fn stats(&self) -> RenderStats {
+        self.stats
+    }
+
+    pub fn point_cloud_settings(&self) -> &PointCloudSettings {
+        self.point_clouds.settings()
+    }
+
+    pub fn point_cloud_settings_mut(&mut self) -> &mut PointCloudSettings {
+        self.point_clouds.settings_mut()
+    }
+
+    pub fn point_cloud_stats(&self) -> PointCloudStats {
+        self.point_clouds.stats()
+    }
+
+    pub fn environment_settings(&self) -> &EnvironmentSettings {
+        &self.environment_settings
+    }
+
+    pub fn environment_settings_mut(&mut self) -> &mut EnvironmentSettings {
+        &mut self.environment_settings
+    }
+
+    pub fn lod_settings(&self) -> &LodSettings {
+        &self.lod_settings
+    }
+
+    pub fn lod_settings_mut(&mut self) -> &mut LodSettings {
+        &mut self.lod_settings
     }
 
     pub fn render_color(&mut self, red: f32, green: f32, blue: f32) {
@@ -18,11 +281,372 @@ impl Renderer {
         }
     }
 
-    pub fn render_scene_pass(&mut self) {
+    /// Starts timing the scene passes of a frame on the GPU.
+    pub fn begin_frame(&mut self) {
+        if let Some(timer) = &mut self.gpu_timer {
+            self.gpu_time = timer.poll();
+            timer.begin();
+        }
+    }
+
+    /// Ends the timing started by `begin_frame`.
+    pub fn end_frame(&mut self) {
+        if let Some(timer) = &mut self.gpu_timer {
+            timer.end();
+        }
+    }
+
+    /// Draws every visible node with a mesh or point cloud into the bound
+    /// framebuffer.
+    pub fn render_scene_pass(
+        &mut self,
+        scene: &Scene,
+        assets: &AssetManager,
+        camera: &Camera,
+        size: PhysicalSize<u32>,
+        view: &mut ViewState,
+    ) {
+        self.environment
+            .draw_background(assets, camera, size, &self.environment_settings);
+        self.draw_meshes(scene, assets, camera, size, view);
+        self.point_clouds.render(scene, assets, camera, size);
+        if view.overlays.grid {
+            self.grid.render(assets, camera, size);
+        }
+        if !self.debug_lines.is_empty() {
+            let aspect = size.width.max(1) as f32 / size.height.max(1) as f32;
+            let view_projection = camera.projection(aspect) * camera.view();
+            self.debug_lines.draw(assets, &view_projection);
+        }
+    }
+
+    /// GPU time of a recent frame's scene passes; `None` without timer queries.
+    pub fn gpu_time(&self) -> Option<Duration> {
+        self.gpu_time
+    }
+
+    fn draw_meshes(
+        &mut self,
+        scene: &Scene,
+        assets: &AssetManager,
+        camera: &Camera,
+        size: PhysicalSize<u32>,
+        view: &mut ViewState,
+    ) {
+        let (Some(program), Some(instances)) = (
+            assets.get(&self.mesh_shader).and_then(Shader::program),
+            self.instances.as_mut(),
+        ) else {
+            return;
+        };
+        unsafe {
+            self.gl.enable(glow::DEPTH_TEST);
+            self.gl.depth_func(glow::LESS);
+            self.gl.disable(glow::SCISSOR_TEST);
+            self.gl.disable(glow::BLEND);
+            // Streams a mesh lacks read the current generic attribute value.
+            self.gl
+                .vertex_attrib_4_f32(gpu_mesh::attrib::COLOR, 1.0, 1.0, 1.0, 1.0);
+            self.gl
+                .vertex_attrib_4_f32(gpu_mesh::attrib::TANGENT, 0.0, 0.0, 0.0, 0.0);
+        }
+
+        let aspect = size.width.max(1) as f32 / size.height.max(1) as f32;
+        let view_projection = camera.projection(aspect) * camera.view();
+        let culled = scene.cull(&Frustum::from_matrix(&view_projection));
+        view.lods.begin_frame();
+        let items = culled.visible.iter().filter_map(|&id| {
+            let node = scene.node(id)?;
+            let item = DrawItem::new(node)?;
+            let Some(mesh) = assets.get(item.mesh) else {
+                return Some(item);
+            };
+            let sphere = node.bounding_sphere();
+            let lod = view
+                .lods
+                .select(id, &sphere, mesh, camera, &self.lod_settings);
+            // Levels still waiting for upload fall back to full detail.
+            match lod.checked_sub(1).and_then(|i| mesh.lods.get(i)) {
+                Some(level) if assets.get(&level.mesh).is_some_and(|m| m.gpu().is_some()) => {
+                    Some(DrawItem {
+                        node,
+                        mesh: &level.mesh,
+                        lod,
+                    })
+                }
+                _ => Some(item),
+            }
+        });
+        let batches = draw_list::build_batches(items);
+        let transforms: Vec<Mat4> = batches
+            .iter()
+            .flat_map(|batch| batch.transforms.iter().copied())
+            .collect();
+        instances.upload(&transforms);
+        self.stats = RenderStats {
+            visible_objects: culled.visible.len(),
+            culled_objects: culled.culled,
+            ..Default::default()
+        };
+
+        program.bind();
+        program.set_mat4("u_view_projection", &view_projection);
+        program.set_vec3("u_camera_position", camera.position());
+        program.set_vec3("u_light_direction", LIGHT_DIRECTION);
+        program.set_i32("u_base_color_texture", 0);
+        program.set_i32("u_joint_matrices", JOINT_TEXTURE_UNIT as i32);
+        program.set_i32("u_morph_targets", MORPH_TARGET_UNIT as i32);
+        program.set_i32("u_view_mode", view.mode as i32);
+        self.environment.bind_lighting(
+            program,
+            assets,
+            &self.environment_settings,
+            camera.up,
+            (SPECULAR_MAP_UNIT, BRDF_LUT_UNIT),
+        );
+        if view.mode == ViewMode::Depth {
+            let (near, far) = depth_range(scene, &culled.visible, camera);
+            program.set_vec2("u_depth_range", near, far);
+        }
+        if view.mode == ViewMode::Overdraw {
+            unsafe {
+                self.gl.disable(glow::DEPTH_TEST);
+                self.gl.enable(glow::BLEND);
+                self.gl.blend_func(glow::ONE, glow::ONE);
+            }
+        }
+
+        let draw = BatchDraw {
+            gl: &self.gl,
+            program,
+            assets,
+            instances,
+            joint_texture: self.joint_texture.as_ref(),
+            white_texture: self.white_texture.as_ref(),
+        };
+        let mut first = 0;
+        for batch in &batches {
+            let count = batch.transforms.len();
+            if let Some(triangles) = draw.draw(batch, first) {
+                self.stats.draw_calls += 1;
+                self.stats.instances += count;
+                self.stats.triangles += count * triangles;
+            }
+            first += count;
+        }
+        if view.mode == ViewMode::Wireframe {
+            program.set_i32("u_view_mode", WIREFRAME_LINES);
+            unsafe {
+                self.gl.polygon_mode(glow::FRONT_AND_BACK, glow::LINE);
+                self.gl.enable(glow::POLYGON_OFFSET_LINE);
+                self.gl.polygon_offset(-1.0, -1.0);
+                self.gl.depth_func(glow::LEQUAL);
+            }
+            let mut first = 0;
+            for batch in &batches {
+                draw.draw(batch, first);
+                first += batch.transforms.len();
+            }
+            unsafe {
+                self.gl.polygon_mode(glow::FRONT_AND_BACK, glow::FILL);
+                self.gl.disable(glow::POLYGON_OFFSET_LINE);
+                self.gl.depth_func(glow::LESS);
+            }
+        }
+
+        self.environment
+            .unbind_lighting((SPECULAR_MAP_UNIT, BRDF_LUT_UNIT));
+        unsafe {
+            self.gl.active_texture(glow::TEXTURE0 + JOINT_TEXTURE_UNIT);
+            self.gl.bind_texture(glow::TEXTURE_2D, None);
+            self.gl.active_texture(glow::TEXTURE0 + MORPH_TARGET_UNIT);
+            self.gl.bind_texture(glow::TEXTURE_2D, None);
+            self.gl.active_texture(glow::TEXTURE0);
+            self.gl.bind_texture(glow::TEXTURE_2D, None);
+            self.gl.use_program(None);
+            self.gl.disable(glow::CULL_FACE);
+            self.gl.disable(glow::DEPTH_TEST);
+            self.gl.disable(glow::BLEND);
+        }
+
+        if view.overlays.any_lines() {
+            self.debug_lines.lines_mut().gather_overlays(
+                scene,
+                assets,
+                &culled.visible,
+                view.overlays,
+            );
+        }
+    }
+}
+
+/// Everything needed to issue the draw call of one batch.
+struct BatchDraw<'a> {
+    gl: &'a glow::Context,
+    program: &'a ShaderProgram,
+    assets: &'a AssetManager,
+    instances: &'a InstanceBuffer,
+    joint_texture: Option<&'a JointTexture>,
+    white_texture: Option<&'a GpuTexture>,
+}
+
+impl BatchDraw<'_> {
+    /// Draws the batch's instances starting at `first` in the instance
+    /// buffer. Returns the triangles per instance, or `None` when the mesh
+    /// is not ready.
+    fn draw(&self, batch: &DrawBatch, first: usize) -> Option<usize> {
+        let (program, assets) = (self.program, self.assets);
+        let mesh = assets.get(&batch.mesh)?;
+        let gpu_mesh = mesh.gpu()?;
+        let skinned = mesh.is_skinned() && !batch.joint_matrices.is_empty();
+        if skinned && let Some(joint_texture) = self.joint_texture {
+            joint_texture.upload(JOINT_TEXTURE_UNIT, &batch.joint_matrices);
+        }
+        program.set_i32("u_skinned", skinned as i32);
+        program.set_i32("u_lod_level", batch.lod as i32);
+        let morph_targets = gpu_mesh.morph_target_count();
+        program.set_i32("u_morph_target_count", morph_targets as i32);
+        if morph_targets > 0 {
+            let weights = if batch.morph_weights.is_empty() {
+                &mesh.morph_weights
+            } else {
+                &batch.morph_weights
+            };
+            gpu_mesh.bind_morph_targets(MORPH_TARGET_UNIT);
+            program.set_i32("u_vertex_count", mesh.vertex_count() as i32);
+            let mut padded = [0.0; gpu_mesh::MAX_MORPH_TARGETS];
+            let n = weights.len().min(morph_targets);
+            padded[..n].copy_from_slice(&weights[..n]);
+            program.set_f32_slice("u_morph_weights", &padded[..morph_targets]);
+        }
+        let triangles = mesh.triangle_count();
+        program.set_f32(
+            "u_triangle_area",
+            mesh.surface_area() / triangles.max(1) as f32,
+        );
+        program.set_vec3("u_material_color", material_color(batch.material.as_ref()));
+        let material = batch.material.as_ref().and_then(|m| assets.get(m));
+
+        let (base_color, emissive, alpha_cutoff, double_sided) = match material {
+            Some(m) => (
+                m.base_color,
+                m.emissive,
+                if m.alpha_mode == AlphaMode::Mask {
+                    m.alpha_cutoff
+                } else {
+                    0.0
+                },
+                m.double_sided,
+            ),
+            None => (Vec4::splat(0.8).with_w(1.0), Vec3::ZERO, 0.0, false),
+        };
+        let (metallic, roughness) = material.map_or((0.0, 0.5), |m| (m.metallic, m.roughness));
+        program.set_vec4("u_base_color", base_color);
+        program.set_f32("u_metallic", metallic);
+        program.set_f32("u_roughness", roughness);
+        program.set_vec3("u_emissive", emissive);
+        program.set_f32("u_alpha_cutoff", alpha_cutoff);
+
+        let texture = material
+            .and_then(|m| m.textures.base_color.as_ref())
+            .and_then(|t| assets.get(t))
+            .and_then(|t| t.gpu())
+            .or(self.white_texture);
         unsafe {
-            self.gl.clear_color(0.2, 0.22, 0.26, 1.0);
+            if double_sided {
+                self.gl.disable(glow::CULL_FACE);
+            } else {
+                self.gl.enable(glow::CULL_FACE);
+            }
+            self.gl.active_texture(glow::TEXTURE0);
             self.gl
-                .clear(glow::COLOR_BUFFER_BIT | glow::DEPTH_BUFFER_BIT);
+                .bind_texture(glow::TEXTURE_2D, texture.map(GpuTexture::native));
+        }
+        gpu_mesh.draw_instanced(self.instances, first, batch.transforms.len());
+        Some(triangles)
+    }
+}
+
+/// Nearest and farthest distance of the visible objects from the camera,
+/// for `ViewMode::Depth`.
+fn depth_range(scene: &Scene, visible: &[NodeId], camera: &Camera) -> (f32, f32) {
+    let eye = camera.position();
+    let (near, far) = visible
+        .iter()
+        .filter_map(|&id| scene.node(id))
+        .map(|node| node.bounding_sphere())
+        .filter(|sphere| sphere.radius.is_finite())
+        .fold((f32::INFINITY, 0.0f32), |(near, far), sphere| {
+            let distance = sphere.center.distance(eye);
+            (
+                near.min(distance - sphere.radius),
+                far.max(distance + sphere.radius),
+            )
+        });
+    if near > far {
+        return (camera.near, camera.far);
+    }
+    let near = near.max(camera.near);
+    (near, far.max(near * 1.001))
+}
+
+/// A stable, well separated color per material for `ViewMode::MaterialId`;
+/// gray without one.
+fn material_color(material: Option<&Handle<Material>>) -> Vec3 {
+    let Some(material) = material else {
+        return Vec3::splat(0.5);
+    };
+    let mut hasher = DefaultHasher::new();
+    material.id().hash(&mut hasher);
+    // Golden-ratio hue steps spread consecutive hashes around the circle.
+    let hue = (hasher.finish() % 1024) as f32 * 0.618_034 % 1.0;
+    let channel = |n: f32| {
+        let k = (n + hue * 6.0) % 6.0;
+        0.9 - 0.9 * 0.65 * k.min(4.0 - k).clamp(0.0, 1.0)
+    };
+    Vec3::new(channel(5.0), channel(3.0), channel(1.0))
+}
+
+/// Debug builds run from a checkout load shaders from the source tree, so
+/// edits are hot-reloaded; otherwise the copy embedded at build time is used.
+fn builtin_shader(assets: &mut AssetManager, path: &str, embedded: &str) -> Handle<Shader> {
+    let path = Path::new(path);
+    if cfg!(debug_assertions) && path.is_file() {
+        return assets.load_blocking(path);
+    }
+    let name = path
+        .file_name()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    assets.add(Shader::from_source(name, embedded))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn view_modes_match_the_shader_defines() {
+        let source = include_str!("renderer/shaders/mesh.glsl");
+        let define = |name: &str| {
+            source.lines().find_map(|line| {
+                let value = line.strip_prefix("#define ")?.strip_prefix(name)?;
+                value.trim().parse::<i32>().ok()
+            })
+        };
+        for (mode, name) in [
+            (ViewMode::Unlit, "VIEW_UNLIT"),
+            (ViewMode::Normals, "VIEW_NORMALS"),
+            (ViewMode::UvChecker, "VIEW_UV_CHECKER"),
+            (ViewMode::Tangents, "VIEW_TANGENTS"),
+            (ViewMode::Depth, "VIEW_DEPTH"),
+            (ViewMode::Overdraw, "VIEW_OVERDRAW"),
+            (ViewMode::MaterialId, "VIEW_MATERIAL_ID"),
+            (ViewMode::TriangleDensity, "VIEW_TRIANGLE_DENSITY"),
+            (ViewMode::LodLevel, "VIEW_LOD_LEVEL"),
+        ] {
+            assert_eq!(define(name), Some(mode as i32), "{name}");
         }
+        assert_eq!(define("VIEW_WIREFRAME_LINES"), Some(WIREFRAME_LINES));
     }
 }
@@ -0,0 +1,416 @@
+use std::{collections::HashSet, sync::Arc};
+
+use glam::{Mat3, Mat4, Vec3, Vec4};
+use glow::HasContext;
+
+use crate::core::{
+    asset_manager::{AssetManager, Handle, Shader},
+    bounds::Aabb,
+    renderer::{Overlays, builtin_shader, gpu_mesh::attrib},
+    scene::{NodeId, Scene},
+};
+
+/// Vertex normals drawn per frame at most; denser meshes skip vertices.
+const MAX_NORMAL_LINES: usize = 200_000;
+
+const BOUNDS_COLOR: Vec4 = Vec4::new(1.0, 0.6, 0.1, 1.0);
+const NORMAL_COLOR: Vec4 = Vec4::new(0.2, 0.5, 1.0, 1.0);
+const BONE_COLOR: Vec4 = Vec4::new(0.9, 0.9, 0.2, 1.0);
+const JOINT_COLOR: Vec4 = Vec4::new(1.0, 0.3, 0.8, 1.0);
+
+const LINES_SHADER_PATH: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/src/core/renderer/shaders/lines.glsl"
+);
+
+/// Overlay lines gathered on the CPU during a frame and drawn with one
+/// call, depth-tested against the scene.
+pub struct DebugLines {
+    gl: Arc<glow::Context>,
+    shader: Handle<Shader>,
+    vertex_array: Option<glow::NativeVertexArray>,
+    position_buffer: Option<glow::NativeBuffer>,
+    color_buffer: Option<glow::NativeBuffer>,
+    lines: LineList,
+}
+
+/// Line segments as pairs of end points with a linear RGBA color each.
+pub struct LineList {
+    positions: Vec<Vec3>,
+    colors: Vec<Vec4>,
+}
+
+impl DebugLines {
+    pub fn new(gl: Arc<glow::Context>, assets: &mut AssetManager) -> Self {
+        let (vertex_array, position_buffer, color_buffer) = unsafe {
+            (
+                gl.create_vertex_array(),
+                gl.create_buffer(),
+                gl.create_buffer(),
+            )
+        };
+        Self {
+            gl,
+            shader: builtin_shader(
+                assets,
+                LINES_SHADER_PATH,
+                include_str!("shaders/lines.glsl"),
+            ),
+            vertex_array: vertex_array
+                .inspect_err(|err| log::error!("failed to create debug lines: {err}"))
+                .ok(),
+            position_buffer: position_buffer
+                .inspect_err(|err| log::error!("failed to create debug lines: {err}"))
+                .ok(),
+            color_buffer: color_buffer
+                .inspect_err(|err| log::error!("failed to create debug lines: {err}"))
+                .ok(),
+            lines: LineList::new(),
+        }
+    }
+
+    pub fn lines_mut(&mut self) -> &mut LineList {
+        &mut self.lines
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Draws the gathered lines into the bound framebuffer and clears them.
+    pub fn draw(&mut self, assets: &AssetManager, view_projection: &Mat4) {
+        let (Some(program), Some(vertex_array), Some(positions), Some(colors)) = (
+            assets.get(&self.shader).and_then(Shader::program),
+            self.vertex_array,
+            self.position_buffer,
+            self.color_buffer,
+        ) else {
+            self.lines.clear();
+            return;
+        };
+        if self.is_empty() {
+            return;
+        }
+        program.bind();
+        program.set_mat4("u_view_projection", view_projection);
+        unsafe {
+            self.gl.enable(glow::DEPTH_TEST);
+            self.gl.depth_func(glow::LEQUAL);
+            self.gl.bind_vertex_array(Some(vertex_array));
+            for (buffer, location, components, bytes) in [
+                (
+                    positions,
+                    attrib::POSITION,
+                    3,
+                    bytemuck::cast_slice::<Vec3, u8>(&self.lines.positions),
+                ),
+                (
+                    colors,
+                    attrib::COLOR,
+                    4,
+                    bytemuck::cast_slice::<Vec4, u8>(&self.lines.colors),
+                ),
+            ] {
+                self.gl.bind_buffer(glow::ARRAY_BUFFER, Some(buffer));
+                self.gl
+                    .buffer_data_u8_slice(glow::ARRAY_BUFFER, bytes, glow::STREAM_DRAW);
+                self.gl.enable_vertex_attrib_array(location);
+                self.gl
+                    .vertex_attrib_pointer_f32(location, components, glow::FLOAT, false, 0, 0);
+            }
+            self.gl
+                .draw_arrays(glow::LINES, 0, self.lines.positions.len() as i32);
+            self.gl.bind_vertex_array(None);
+            self.gl.bind_buffer(glow::ARRAY_BUFFER, None);
+            self.gl.use_program(None);
+            self.gl.depth_func(glow::LESS);
+            self.gl.disable(glow::DEPTH_TEST);
+        }
+        self.lines.clear();
+    }
+}
+
+impl LineList {
+    pub fn new() -> Self {
+        Self {
+            positions: Vec::new(),
+            colors: Vec::new(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.positions.clear();
+        self.colors.clear();
+    }
+
+    /// Number of lines gathered so far.
+    pub fn len(&self) -> usize {
+        self.positions.len() / 2
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Adds a line; colors are linear RGBA.
+    pub fn line(&mut self, from: Vec3, to: Vec3, color: Vec4) {
+        self.positions.extend([from, to]);
+        self.colors.extend([color, color]);
+    }
+
+    /// The twelve edges of `aabb`.
+    pub fn aabb(&mut self, aabb: &Aabb, color: Vec4) {
+        if aabb.is_empty() {
+            return;
+        }
+        let corner = |i: usize| {
+            Vec3::new(
+                if i & 1 == 0 { aabb.min.x } else { aabb.max.x },
+                if i & 2 == 0 { aabb.min.y } else { aabb.max.y },
+                if i & 4 == 0 { aabb.min.z } else { aabb.max.z },
+            )
+        };
+        for i in 0..8 {
+            for axis in [1, 2, 4] {
+                if i & axis == 0 {
+                    self.line(corner(i), corner(i | axis), color);
+                }
+            }
+        }
+    }
+
+    /// Small axis-aligned cross marking a point.
+    pub fn cross(&mut self, center: Vec3, size: f32, color: Vec4) {
+        for axis in [Vec3::X, Vec3::Y, Vec3::Z] {
+            self.line(center - axis * size, center + axis * size, color);
+        }
+    }
+
+    /// Adds the enabled `overlays` for the `visible` nodes: world bounds,
+    /// vertex normals (skinned, without morph targets) and the joints of
+    /// every skin.
+    pub fn gather_overlays(
+        &mut self,
+        scene: &Scene,
+        assets: &AssetManager,
+        visible: &[NodeId],
+        overlays: Overlays,
+    ) {
+        let nodes = || visible.iter().filter_map(|&id| scene.node(id));
+        if overlays.bounds {
+            for node in nodes() {
+                self.aabb(&node.world_bounds(), BOUNDS_COLOR);
+            }
+        }
+        if overlays.normals {
+            let meshes: Vec<_> = nodes()
+                .filter_map(|node| Some((node, assets.get(node.mesh.as_ref()?)?)))
+                .collect();
+            let total: usize = meshes.iter().map(|(_, mesh)| mesh.normals.len()).sum();
+            let stride = total.div_ceil(MAX_NORMAL_LINES).max(1);
+            for (node, mesh) in meshes {
+                let length = node.bounding_sphere().radius * 0.04;
+                let world = node.world_matrix();
+                let joints = node.joint_matrices();
+                let skinned = mesh.is_skinned() && !joints.is_empty();
+                for i in (0..mesh.normals.len().min(mesh.positions.len())).step_by(stride) {
+                    let mut model = world;
+                    if skinned {
+                        let weights = mesh.weights[i];
+                        model *= mesh.joints[i]
+                            .iter()
+                            .zip(weights.to_array())
+                            .filter_map(|(&j, w)| Some(*joints.get(j as usize)? * w))
+                            .fold(Mat4::ZERO, |sum, m| sum + m);
+                    }
+                    let normal_matrix = Mat3::from_mat4(model).inverse().transpose();
+                    let position = model.transform_point3(mesh.positions[i]);
+                    let normal = (normal_matrix * mesh.normals[i]).normalize_or_zero();
+                    self.line(position, position + normal * length, NORMAL_COLOR);
+                }
+            }
+        }
+        if overlays.skeleton {
+            let mut joints = HashSet::new();
+            for node in nodes() {
+                if let Some(skin) = &node.skin {
+                    joints.extend(skin.joints.iter().copied());
+                }
+            }
+            for &id in &joints {
+                let Some(joint) = scene.node(id) else {
+                    continue;
+                };
+                let position = joint.world_matrix().w_axis.truncate();
+                if let Some(parent) = joint.parent().filter(|p| joints.contains(p))
+                    && let Some(parent) = scene.node(parent)
+                {
+                    let parent_position = parent.world_matrix().w_axis.truncate();
+                    self.line(parent_position, position, BONE_COLOR);
+                    let size = parent_position.distance(position) * 0.1;
+                    self.cross(position, size, JOINT_COLOR);
+                } else {
+                    self.cross(position, 0.01, JOINT_COLOR);
+                }
+            }
+        }
+    }
+
+    /// End points of every line, two per line.
+    pub fn positions(&self) -> &[Vec3] {
+        &self.positions
+    }
+
+    pub fn colors(&self) -> &[Vec4] {
+        &self.colors
+    }
+}
+
+impl Default for LineList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for DebugLines {
+    fn drop(&mut self) {
+        unsafe {
+            if let Some(vertex_array) = self.vertex_array {
+                self.gl.delete_vertex_array(vertex_array);
+            }
+            for buffer in [self.position_buffer, self.color_buffer]
+                .into_iter()
+                .flatten()
+            {
+                self.gl.delete_buffer(buffer);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::core::{
+        animation::Skin, asset_manager::Mesh, scene::Node, time::Time, transform::Transform,
+    };
+
+    fn overlays(normals: bool, bounds: bool, skeleton: bool) -> Overlays {
+        Overlays {
+            grid: false,
+            normals,
+            bounds,
+            skeleton,
+        }
+    }
+
+    #[test]
+    fn aabb_edges_join_neighbouring_corners() {
+        let aabb = Aabb::new(Vec3::new(-1.0, 0.0, 2.0), Vec3::new(1.0, 3.0, 4.0));
+        let mut lines = LineList::new();
+        lines.aabb(&aabb, BOUNDS_COLOR);
+        assert_eq!(lines.len(), 12);
+        let is_corner = |p: Vec3| {
+            [p.x, p.y, p.z]
+                .into_iter()
+                .zip([(-1.0, 1.0), (0.0, 3.0), (2.0, 4.0)])
+                .all(|(v, (min, max))| v == min || v == max)
+        };
+        for edge in lines.positions().chunks_exact(2) {
+            assert!(is_corner(edge[0]) && is_corner(edge[1]));
+            // Every edge runs along exactly one axis.
+            let delta = (edge[1] - edge[0]).abs();
+            assert_eq!((delta.cmpgt(Vec3::ZERO)).bitmask().count_ones(), 1);
+        }
+
+        lines.aabb(&Aabb::EMPTY, BOUNDS_COLOR);
+        assert_eq!(lines.len(), 12);
+    }
+
+    #[test]
+    fn normals_and_bounds_follow_the_node_transform() {
+        let mut assets = AssetManager::new();
+        let mut quad = Mesh::new("quad");
+        quad.positions = vec![
+            Vec3::new(-1.0, -1.0, 0.0),
+            Vec3::new(1.0, -1.0, 0.0),
+            Vec3::new(1.0, 1.0, 0.0),
+            Vec3::new(-1.0, 1.0, 0.0),
+        ];
+        quad.normals = vec![Vec3::Z; 4];
+        quad.indices = vec![0, 1, 2, 0, 2, 3];
+        let mut node = Node::new("quad");
+        node.mesh = Some(assets.add(quad));
+        node.transform = Transform::from_translation(Vec3::new(5.0, 0.0, 0.0));
+        let mut scene = Scene::new();
+        let id = scene.add_node(node, None);
+        scene.update(&Time::new(), &assets);
+
+        let mut lines = LineList::new();
+        lines.gather_overlays(&scene, &assets, &[id], overlays(true, false, false));
+        assert_eq!(lines.len(), 4);
+        let length = scene.node(id).unwrap().bounding_sphere().radius * 0.04;
+        for (edge, corner) in lines.positions().chunks_exact(2).zip([
+            Vec3::new(4.0, -1.0, 0.0),
+            Vec3::new(6.0, -1.0, 0.0),
+            Vec3::new(6.0, 1.0, 0.0),
+            Vec3::new(4.0, 1.0, 0.0),
+        ]) {
+            assert!(edge[0].abs_diff_eq(corner, 1e-5));
+            assert!(edge[1].abs_diff_eq(corner + Vec3::Z * length, 1e-5));
+        }
+
+        let mut lines = LineList::new();
+        lines.gather_overlays(&scene, &assets, &[id], overlays(false, true, false));
+        let mut expected = LineList::new();
+        expected.aabb(
+            &Aabb::new(Vec3::new(4.0, -1.0, 0.0), Vec3::new(6.0, 1.0, 0.0)),
+            BOUNDS_COLOR,
+        );
+        assert_eq!(lines.positions(), expected.positions());
+    }
+
+    #[test]
+    fn skeleton_connects_joints_to_their_parents() {
+        let mut scene = Scene::new();
+        let root = scene.add_node(Node::new("root"), None);
+        let mut elbow = Node::new("elbow");
+        elbow.transform = Transform::from_translation(Vec3::Y);
+        let elbow = scene.add_node(elbow, Some(root));
+        let mut hand = Node::new("hand");
+        hand.transform = Transform::from_translation(Vec3::X * 2.0);
+        let hand = scene.add_node(hand, Some(elbow));
+        let mut skinned = Node::new("skinned");
+        skinned.skin = Some(Skin {
+            name: "arm".to_owned(),
+            joints: vec![root, elbow, hand],
+            inverse_bind_matrices: Arc::from([Mat4::IDENTITY; 3]),
+        });
+        let skinned = scene.add_node(skinned, None);
+        scene.update(&Time::new(), &AssetManager::new());
+
+        let mut lines = LineList::new();
+        lines.gather_overlays(
+            &scene,
+            &AssetManager::new(),
+            &[skinned],
+            overlays(false, false, true),
+        );
+        // Two bones plus a three-line cross on every joint.
+        assert_eq!(lines.len(), 2 + 3 * 3);
+        let mut bones: Vec<_> = lines
+            .positions()
+            .chunks_exact(2)
+            .zip(lines.colors().iter().step_by(2))
+            .filter(|&(_, &color)| color == BONE_COLOR)
+            .map(|(edge, _)| (edge[0], edge[1]))
+            .collect();
+        bones.sort_by(|a, b| a.1.x.total_cmp(&b.1.x));
+        assert_eq!(
+            bones,
+            [(Vec3::ZERO, Vec3::Y), (Vec3::Y, Vec3::new(2.0, 1.0, 0.0)),]
+        );
+    }
+}
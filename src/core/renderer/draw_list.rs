@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+
+use glam::Mat4;
+
+use crate::core::{
+    asset_manager::{AssetId, Handle, Material, Mesh},
+    scene::Node,
+};
+
+/// A node to draw with the mesh picked for it, which is the node's own
+/// mesh or one of its levels of detail.
+pub struct DrawItem<'a> {
+    pub node: &'a Node,
+    pub mesh: &'a Handle<Mesh>,
+    /// 0 for the node's own mesh.
+    pub lod: usize,
+}
+
+impl<'a> DrawItem<'a> {
+    /// Draws the node's own mesh, if it has one.
+    pub fn new(node: &'a Node) -> Option<Self> {
+        Some(Self {
+            node,
+            mesh: node.mesh.as_ref()?,
+            lod: 0,
+        })
+    }
+}
+
+/// Nodes sharing a mesh and material, drawn with one instanced call.
+pub struct DrawBatch {
+    pub mesh: Handle<Mesh>,
+    pub lod: usize,
+    pub material: Option<Handle<Material>>,
+    pub transforms: Vec<Mat4>,
+    /// Skinning matrices and morph weights. Nodes with either are never
+    /// batched with others.
+    pub joint_matrices: Vec<Mat4>,
+    pub morph_weights: Vec<f32>,
+}
+
+/// Groups items by mesh and material, keeping the order in which each
+/// combination first appears. Skinned nodes and nodes with their own morph
+/// weights get a batch each.
+pub fn build_batches<'a>(items: impl IntoIterator<Item = DrawItem<'a>>) -> Vec<DrawBatch> {
+    let mut batches: Vec<DrawBatch> = Vec::new();
+    let mut index: HashMap<(AssetId, Option<AssetId>), usize> = HashMap::new();
+    for DrawItem { node, mesh, lod } in items {
+        if !node.joint_matrices().is_empty() || !node.morph_weights.is_empty() {
+            batches.push(DrawBatch {
+                mesh: mesh.clone(),
+                lod,
+                material: node.material.clone(),
+                transforms: vec![node.world_matrix()],
+                joint_matrices: node.joint_matrices().to_vec(),
+                morph_weights: node.morph_weights.clone(),
+            });
+            continue;
+        }
+        let key = (mesh.id(), node.material.as_ref().map(Handle::id));
+        let i = *index.entry(key).or_insert_with(|| {
+            batches.push(DrawBatch {
+                mesh: mesh.clone(),
+                lod,
+                material: node.material.clone(),
+                transforms: Vec::new(),
+                joint_matrices: Vec::new(),
+                morph_weights: Vec::new(),
+            });
+            batches.len() - 1
+        });
+        batches[i].transforms.push(node.world_matrix());
+    }
+    batches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::asset_manager::AssetManager;
+
+    #[test]
+    fn nodes_sharing_mesh_and_material_are_batched() {
+        let mut assets = AssetManager::new();
+        let bolt = assets.add(Mesh::new("bolt"));
+        let leaf = assets.add(Mesh::new("leaf"));
+        let steel = assets.add(Material::default());
+
+        let mut nodes = Vec::new();
+        for i in 0..10 {
+            let mut node = Node::new(format!("bolt{i}"));
+            node.mesh = Some(bolt.clone());
+            node.material = Some(steel.clone());
+            nodes.push(node);
+        }
+        let mut plain_bolt = Node::new("plain");
+        plain_bolt.mesh = Some(bolt.clone());
+        nodes.push(plain_bolt);
+        let mut leaf_node = Node::new("leaf");
+        leaf_node.mesh = Some(leaf);
+        nodes.push(leaf_node);
+        nodes.push(Node::new("empty"));
+
+        let batches = build_batches(nodes.iter().filter_map(DrawItem::new));
+        assert_eq!(batches.len(), 3);
+        assert_eq!(batches[0].transforms.len(), 10);
+        assert_eq!(batches[0].mesh, bolt);
+        assert!(batches[1].material.is_none());
+        assert_eq!(batches[1].transforms.len(), 1);
+    }
+}
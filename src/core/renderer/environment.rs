@@ -0,0 +1,508 @@
+use std::sync::Arc;
+
+use glam::{Mat3, Vec3};
+use glow::HasContext;
+use winit::dpi::PhysicalSize;
+
+use crate::core::{
+    asset_manager::{AssetManager, Environment, Handle, Shader},
+    camera::{Camera, UpAxis},
+    renderer::{ShaderProgram, builtin_shader},
+};
+
+const BACKGROUND_SHADER_PATH: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/src/core/renderer/shaders/background.glsl"
+);
+const PREFILTER_SHADER_PATH: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/src/core/renderer/shaders/prefilter.glsl"
+);
+const BRDF_SHADER_PATH: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/src/core/renderer/shaders/brdf_lut.glsl"
+);
+
+/// Edge length of the base level of the prefiltered specular cubemap.
+const SPECULAR_SIZE: i32 = 128;
+/// Mip levels of the specular cubemap, one roughness step each, down to
+/// 4x4 texels per face.
+const SPECULAR_LEVELS: i32 = 6;
+const BRDF_LUT_SIZE: i32 = 128;
+
+/// What the scene pass clears to.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Background {
+    Solid,
+    /// Vertical blend between two colors across the viewport.
+    Gradient,
+    /// The environment map, falling back to the solid color without one.
+    Environment,
+}
+
+impl Background {
+    pub fn label(self) -> &'static str {
+        match self {
+            Background::Solid => "Solid",
+            Background::Gradient => "Gradient",
+            Background::Environment => "Environment",
+        }
+    }
+}
+
+/// Background and image-based lighting. Colors are display values, written
+/// to the target as they are.
+#[derive(Clone, Debug)]
+pub struct EnvironmentSettings {
+    pub background: Background,
+    pub solid_color: Vec3,
+    pub gradient_top: Vec3,
+    pub gradient_bottom: Vec3,
+    pub map: Option<Handle<Environment>>,
+    /// Turn of the map around the up axis, in radians.
+    pub rotation: f32,
+    /// Scales the map both as background and as light.
+    pub intensity: f32,
+    /// Light meshes with the map's irradiance and reflections instead of a
+    /// flat ambient term.
+    pub lighting: bool,
+}
+
+impl EnvironmentSettings {
+    pub fn new() -> Self {
+        Self {
+            background: Background::Solid,
+            solid_color: Vec3::new(0.2, 0.22, 0.26),
+            gradient_top: Vec3::new(0.32, 0.35, 0.4),
+            gradient_bottom: Vec3::new(0.1, 0.11, 0.13),
+            map: None,
+            rotation: 0.0,
+            intensity: 1.0,
+            lighting: true,
+        }
+    }
+
+    /// World directions to the map's Y-up frame, undoing the rotation.
+    pub fn map_from_world(&self, up: UpAxis) -> Mat3 {
+        let to_y_up = Mat3::from_cols(
+            up.to_y_up(Vec3::X),
+            up.to_y_up(Vec3::Y),
+            up.to_y_up(Vec3::Z),
+        );
+        Mat3::from_rotation_y(-self.rotation) * to_y_up
+    }
+}
+
+impl Default for EnvironmentSettings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Specular cubemap prefiltered from one version of an environment map.
+struct SpecularMap {
+    revision: u64,
+    texture: glow::NativeTexture,
+}
+
+/// Draws backgrounds and owns the GPU side of image-based lighting: the
+/// prefiltered specular cubemap of the current map and the split-sum BRDF
+/// lookup table, both rendered on demand.
+pub struct EnvironmentRenderer {
+    gl: Arc<glow::Context>,
+    background_shader: Handle<Shader>,
+    prefilter_shader: Handle<Shader>,
+    brdf_shader: Handle<Shader>,
+    empty_vertex_array: Option<glow::NativeVertexArray>,
+    framebuffer: Option<glow::NativeFramebuffer>,
+    specular: Option<SpecularMap>,
+    brdf_lut: Option<glow::NativeTexture>,
+    /// Set once rendering the lookup table or a specular map failed, e.g.
+    /// on drivers that cannot render to half-float targets. Lighting then
+    /// keeps the flat ambient term instead of retrying every frame.
+    render_failed: bool,
+}
+
+impl EnvironmentRenderer {
+    pub fn new(gl: Arc<glow::Context>, assets: &mut AssetManager) -> Self {
+        let (empty_vertex_array, framebuffer) =
+            unsafe { (gl.create_vertex_array(), gl.create_framebuffer()) };
+        Self {
+            gl,
+            background_shader: builtin_shader(
+                assets,
+                BACKGROUND_SHADER_PATH,
+                include_str!("shaders/background.glsl"),
+            ),
+            prefilter_shader: builtin_shader(
+                assets,
+                PREFILTER_SHADER_PATH,
+                include_str!("shaders/prefilter.glsl"),
+            ),
+            brdf_shader: builtin_shader(
+                assets,
+                BRDF_SHADER_PATH,
+                include_str!("shaders/brdf_lut.glsl"),
+            ),
+            empty_vertex_array: empty_vertex_array
+                .inspect_err(|err| log::error!("failed to create background: {err}"))
+                .ok(),
+            framebuffer: framebuffer
+                .inspect_err(|err| log::error!("failed to create prefilter target: {err}"))
+                .ok(),
+            specular: None,
+            brdf_lut: None,
+            render_failed: false,
+        }
+    }
+
+    /// Clears the bound target to the background. Renders the lookup table
+    /// and the specular cubemap first when they are missing or stale, which
+    /// is why it needs the target's `size` to restore the viewport.
+    pub fn draw_background(
+        &mut self,
+        assets: &AssetManager,
+        camera: &Camera,
+        size: PhysicalSize<u32>,
+        settings: &EnvironmentSettings,
+    ) {
+        let environment = settings.map.as_ref().and_then(|map| assets.get(map));
+        if settings.lighting
+            && let Some(environment) = environment
+        {
+            self.prepare(assets, environment);
+            unsafe {
+                self.gl
+                    .viewport(0, 0, size.width as i32, size.height as i32);
+            }
+        }
+
+        let clear = settings.solid_color;
+        unsafe {
+            self.gl.clear_color(clear.x, clear.y, clear.z, 1.0);
+            self.gl
+                .clear(glow::COLOR_BUFFER_BIT | glow::DEPTH_BUFFER_BIT);
+        }
+        let source = environment.and_then(Environment::gpu);
+        let mode = match (settings.background, source) {
+            (Background::Solid, _) | (Background::Environment, None) => return,
+            (Background::Gradient, _) => 0,
+            (Background::Environment, Some(_)) => 1,
+        };
+        let Some(program) = assets
+            .get(&self.background_shader)
+            .and_then(Shader::program)
+        else {
+            return;
+        };
+        let aspect = size.width.max(1) as f32 / size.height.max(1) as f32;
+        let view_projection = camera.projection(aspect) * camera.view();
+        program.bind();
+        program.set_i32("u_mode", mode);
+        program.set_vec3("u_gradient_top", settings.gradient_top);
+        program.set_vec3("u_gradient_bottom", settings.gradient_bottom);
+        program.set_mat4("u_inverse_view_projection", &view_projection.inverse());
+        program.set_mat3("u_map_from_world", &settings.map_from_world(camera.up));
+        program.set_f32("u_intensity", settings.intensity);
+        program.set_i32("u_environment", 0);
+        unsafe {
+            self.gl.disable(glow::DEPTH_TEST);
+            self.gl.disable(glow::BLEND);
+            self.gl.active_texture(glow::TEXTURE0);
+            self.gl
+                .bind_texture(glow::TEXTURE_2D, source.map(|t| t.native()));
+            self.gl.bind_vertex_array(self.empty_vertex_array);
+            self.gl.draw_arrays(glow::TRIANGLES, 0, 3);
+            self.gl.bind_vertex_array(None);
+            self.gl.bind_texture(glow::TEXTURE_2D, None);
+            self.gl.use_program(None);
+        }
+    }
+
+    /// Points the mesh program's image-based lighting uniforms at the
+    /// current map and binds its textures to `specular_unit` and
+    /// `brdf_unit`. Leaves lighting off while the map is loading.
+    pub fn bind_lighting(
+        &self,
+        program: &ShaderProgram,
+        assets: &AssetManager,
+        settings: &EnvironmentSettings,
+        up: UpAxis,
+        units: (u32, u32),
+    ) {
+        let (specular_unit, brdf_unit) = units;
+        program.set_i32("u_specular_map", specular_unit as i32);
+        program.set_i32("u_brdf_lut", brdf_unit as i32);
+        let environment = settings.map.as_ref().and_then(|map| assets.get(map));
+        let ready = match (environment, &self.specular, self.brdf_lut) {
+            (Some(environment), Some(specular), Some(brdf_lut))
+                if settings.lighting && specular.revision == environment.revision() =>
+            {
+                Some((environment, specular.texture, brdf_lut))
+            }
+            _ => None,
+        };
+        program.set_i32("u_ibl", ready.is_some() as i32);
+        let Some((environment, specular, brdf_lut)) = ready else {
+            return;
+        };
+        for (i, coefficient) in environment.irradiance.iter().enumerate() {
+            program.set_vec3(&format!("u_irradiance[{i}]"), *coefficient);
+        }
+        program.set_mat3("u_map_from_world", &settings.map_from_world(up));
+        program.set_f32("u_environment_intensity", settings.intensity);
+        program.set_f32("u_specular_max_lod", (SPECULAR_LEVELS - 1) as f32);
+        unsafe {
+            self.gl.active_texture(glow::TEXTURE0 + specular_unit);
+            self.gl.bind_texture(glow::TEXTURE_CUBE_MAP, Some(specular));
+            self.gl.active_texture(glow::TEXTURE0 + brdf_unit);
+            self.gl.bind_texture(glow::TEXTURE_2D, Some(brdf_lut));
+            self.gl.active_texture(glow::TEXTURE0);
+        }
+    }
+
+    /// Unbinds what `bind_lighting` bound.
+    pub fn unbind_lighting(&self, units: (u32, u32)) {
+        unsafe {
+            self.gl.active_texture(glow::TEXTURE0 + units.0);
+            self.gl.bind_texture(glow::TEXTURE_CUBE_MAP, None);
+            self.gl.active_texture(glow::TEXTURE0 + units.1);
+            self.gl.bind_texture(glow::TEXTURE_2D, None);
+            self.gl.active_texture(glow::TEXTURE0);
+        }
+    }
+
+    fn prepare(&mut self, assets: &AssetManager, environment: &Environment) {
+        let Some(framebuffer) = self.framebuffer.filter(|_| !self.render_failed) else {
+            return;
+        };
+        if self.brdf_lut.is_none()
+            && let Some(program) = assets.get(&self.brdf_shader).and_then(Shader::program)
+        {
+            self.brdf_lut = self.render_brdf_lut(program, framebuffer);
+            self.render_failed = self.brdf_lut.is_none();
+        }
+        let stale = self
+            .specular
+            .as_ref()
+            .is_none_or(|s| s.revision != environment.revision());
+        if stale
+            && let Some(source) = environment.gpu()
+            && let Some(program) = assets.get(&self.prefilter_shader).and_then(Shader::program)
+        {
+            let texture = self.prefilter(program, framebuffer, source.native(), environment);
+            if let Some(old) = self.specular.take() {
+                unsafe { self.gl.delete_texture(old.texture) };
+            }
+            self.render_failed = texture.is_none();
+            self.specular = texture.map(|texture| SpecularMap {
+                revision: environment.revision(),
+                texture,
+            });
+        }
+    }
+
+    fn render_brdf_lut(
+        &self,
+        program: &ShaderProgram,
+        framebuffer: glow::NativeFramebuffer,
+    ) -> Option<glow::NativeTexture> {
+        let gl = &self.gl;
+        unsafe {
+            let texture = gl
+                .create_texture()
+                .inspect_err(|err| log::error!("failed to create BRDF lookup table: {err}"))
+                .ok()?;
+            gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+            gl.tex_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                glow::RG16F as i32,
+                BRDF_LUT_SIZE,
+                BRDF_LUT_SIZE,
+                0,
+                glow::RG,
+                glow::FLOAT,
+                glow::PixelUnpackData::Slice(None),
+            );
+            for (parameter, value) in [
+                (glow::TEXTURE_MIN_FILTER, glow::LINEAR),
+                (glow::TEXTURE_MAG_FILTER, glow::LINEAR),
+                (glow::TEXTURE_WRAP_S, glow::CLAMP_TO_EDGE),
+                (glow::TEXTURE_WRAP_T, glow::CLAMP_TO_EDGE),
+            ] {
+                gl.tex_parameter_i32(glow::TEXTURE_2D, parameter, value as i32);
+            }
+            gl.bind_texture(glow::TEXTURE_2D, None);
+
+            let previous = gl.get_parameter_framebuffer(glow::FRAMEBUFFER_BINDING);
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(framebuffer));
+            gl.framebuffer_texture_2d(
+                glow::FRAMEBUFFER,
+                glow::COLOR_ATTACHMENT0,
+                glow::TEXTURE_2D,
+                Some(texture),
+                0,
+            );
+            if !framebuffer_complete(gl, "BRDF lookup table") {
+                gl.bind_framebuffer(glow::FRAMEBUFFER, previous);
+                gl.delete_texture(texture);
+                return None;
+            }
+            gl.viewport(0, 0, BRDF_LUT_SIZE, BRDF_LUT_SIZE);
+            gl.disable(glow::DEPTH_TEST);
+            gl.disable(glow::BLEND);
+            program.bind();
+            gl.bind_vertex_array(self.empty_vertex_array);
+            gl.draw_arrays(glow::TRIANGLES, 0, 3);
+            gl.bind_vertex_array(None);
+            gl.use_program(None);
+            gl.bind_framebuffer(glow::FRAMEBUFFER, previous);
+            Some(texture)
+        }
+    }
+
+    /// Renders every face of every mip level of the specular cubemap with
+    /// GGX importance sampling of the equirectangular `source`.
+    fn prefilter(
+        &self,
+        program: &ShaderProgram,
+        framebuffer: glow::NativeFramebuffer,
+        source: glow::NativeTexture,
+        environment: &Environment,
+    ) -> Option<glow::NativeTexture> {
+        let gl = &self.gl;
+        unsafe {
+            let texture = gl
+                .create_texture()
+                .inspect_err(|err| log::error!("failed to create specular cubemap: {err}"))
+                .ok()?;
+            gl.bind_texture(glow::TEXTURE_CUBE_MAP, Some(texture));
+            for level in 0..SPECULAR_LEVELS {
+                let size = SPECULAR_SIZE >> level;
+                for face in 0..6 {
+                    gl.tex_image_2d(
+                        glow::TEXTURE_CUBE_MAP_POSITIVE_X + face,
+                        level,
+                        // RGB16F need not be color-renderable in GL 3.3.
+                        glow::RGBA16F as i32,
+                        size,
+                        size,
+                        0,
+                        glow::RGBA,
+                        glow::FLOAT,
+                        glow::PixelUnpackData::Slice(None),
+                    );
+                }
+            }
+            for (parameter, value) in [
+                (glow::TEXTURE_MIN_FILTER, glow::LINEAR_MIPMAP_LINEAR),
+                (glow::TEXTURE_MAG_FILTER, glow::LINEAR),
+                (glow::TEXTURE_WRAP_S, glow::CLAMP_TO_EDGE),
+                (glow::TEXTURE_WRAP_T, glow::CLAMP_TO_EDGE),
+                (glow::TEXTURE_WRAP_R, glow::CLAMP_TO_EDGE),
+            ] {
+                gl.tex_parameter_i32(glow::TEXTURE_CUBE_MAP, parameter, value as i32);
+            }
+            gl.tex_parameter_i32(
+                glow::TEXTURE_CUBE_MAP,
+                glow::TEXTURE_MAX_LEVEL,
+                SPECULAR_LEVELS - 1,
+            );
+            gl.bind_texture(glow::TEXTURE_CUBE_MAP, None);
+            gl.enable(glow::TEXTURE_CUBE_MAP_SEAMLESS);
+
+            let previous = gl.get_parameter_framebuffer(glow::FRAMEBUFFER_BINDING);
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(framebuffer));
+            gl.framebuffer_texture_2d(
+                glow::FRAMEBUFFER,
+                glow::COLOR_ATTACHMENT0,
+                glow::TEXTURE_CUBE_MAP_POSITIVE_X,
+                Some(texture),
+                0,
+            );
+            if !framebuffer_complete(gl, "specular cubemap") {
+                gl.bind_framebuffer(glow::FRAMEBUFFER, previous);
+                gl.delete_texture(texture);
+                return None;
+            }
+            gl.disable(glow::DEPTH_TEST);
+            gl.disable(glow::BLEND);
+            program.bind();
+            program.set_i32("u_environment", 0);
+            program.set_vec2(
+                "u_source_size",
+                environment.width as f32,
+                environment.height as f32,
+            );
+            gl.active_texture(glow::TEXTURE0);
+            gl.bind_texture(glow::TEXTURE_2D, Some(source));
+            gl.bind_vertex_array(self.empty_vertex_array);
+            for level in 0..SPECULAR_LEVELS {
+                let size = SPECULAR_SIZE >> level;
+                gl.viewport(0, 0, size, size);
+                program.set_f32("u_roughness", level as f32 / (SPECULAR_LEVELS - 1) as f32);
+                for face in 0..6 {
+                    gl.framebuffer_texture_2d(
+                        glow::FRAMEBUFFER,
+                        glow::COLOR_ATTACHMENT0,
+                        glow::TEXTURE_CUBE_MAP_POSITIVE_X + face,
+                        Some(texture),
+                        level,
+                    );
+                    program.set_i32("u_face", face as i32);
+                    gl.draw_arrays(glow::TRIANGLES, 0, 3);
+                }
+            }
+            gl.bind_vertex_array(None);
+            gl.bind_texture(glow::TEXTURE_2D, None);
+            gl.use_program(None);
+            gl.bind_framebuffer(glow::FRAMEBUFFER, previous);
+            log::debug!("prefiltered environment {}", environment.name);
+            Some(texture)
+        }
+    }
+}
+
+/// Whether the bound framebuffer can be rendered to; logs why not.
+fn framebuffer_complete(gl: &glow::Context, target: &str) -> bool {
+    let status = unsafe { gl.check_framebuffer_status(glow::FRAMEBUFFER) };
+    if status != glow::FRAMEBUFFER_COMPLETE {
+        log::error!("{target} framebuffer incomplete: {status:#x}, image-based lighting is off");
+    }
+    status == glow::FRAMEBUFFER_COMPLETE
+}
+
+impl Drop for EnvironmentRenderer {
+    fn drop(&mut self) {
+        unsafe {
+            if let Some(vertex_array) = self.empty_vertex_array {
+                self.gl.delete_vertex_array(vertex_array);
+            }
+            if let Some(framebuffer) = self.framebuffer {
+                self.gl.delete_framebuffer(framebuffer);
+            }
+            let textures = [self.specular.as_ref().map(|s| s.texture), self.brdf_lut];
+            for texture in textures.into_iter().flatten() {
+                self.gl.delete_texture(texture);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn map_frame_follows_up_axis_and_rotation() {
+        let mut settings = EnvironmentSettings::new();
+        let z_up = settings.map_from_world(UpAxis::Z);
+        assert!((z_up * Vec3::Z).abs_diff_eq(Vec3::Y, 1e-6));
+        settings.rotation = std::f32::consts::FRAC_PI_2;
+        let turned = settings.map_from_world(UpAxis::Y);
+        // A quarter turn brings what was on the map's +X side to the front.
+        assert!((turned * Vec3::NEG_Z).abs_diff_eq(Vec3::X, 1e-6));
+        assert!((turned * Vec3::Y).abs_diff_eq(Vec3::Y, 1e-6));
+    }
+}
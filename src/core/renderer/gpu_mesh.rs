@@ -0,0 +1,252 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use glam::Vec4;
+use glow::HasContext;
+
+use crate::core::{asset_manager::Mesh, renderer::InstanceBuffer};
+
+/// Fixed vertex attribute locations shared by every mesh shader.
+pub mod attrib {
+    pub const POSITION: u32 = 0;
+    pub const NORMAL: u32 = 1;
+    pub const TEX_COORD: u32 = 2;
+    pub const TANGENT: u32 = 3;
+    pub const COLOR: u32 = 4;
+    /// Point clouds only.
+    pub const INTENSITY: u32 = 5;
+    /// Per-instance model matrix; takes four consecutive locations.
+    pub const INSTANCE_MODEL: u32 = 6;
+    pub const JOINTS: u32 = 10;
+    pub const WEIGHTS: u32 = 11;
+}
+
+/// Morph targets blended per draw; the size of `u_morph_weights` in
+/// `mesh.glsl`. Further targets are ignored.
+pub const MAX_MORPH_TARGETS: usize = 64;
+
+/// Row length of the morph target texture; the delta list wraps onto
+/// further rows. Matches `MORPH_TEXTURE_WIDTH` in `mesh.glsl`.
+pub const MORPH_TEXTURE_WIDTH: usize = 2048;
+
+pub struct GpuMesh {
+    gl: Arc<glow::Context>,
+    vertex_array: glow::NativeVertexArray,
+    buffers: Vec<glow::NativeBuffer>,
+    index_count: i32,
+    gpu_bytes: usize,
+    /// RGBA32F texture with a position and a normal delta per vertex,
+    /// target after target, wrapped at `MORPH_TEXTURE_WIDTH`.
+    morph_texture: Option<glow::NativeTexture>,
+    morph_target_count: usize,
+}
+
+impl GpuMesh {
+    pub fn upload(gl: Arc<glow::Context>, mesh: &Mesh) -> anyhow::Result<Self> {
+        let vertex_array = unsafe {
+            gl.create_vertex_array()
+                .map_err(anyhow::Error::msg)
+                .context("failed to create vertex array")?
+        };
+
+        let mut this = Self {
+            gl,
+            vertex_array,
+            buffers: Vec::new(),
+            index_count: mesh.indices.len() as i32,
+            gpu_bytes: 0,
+            morph_texture: None,
+            morph_target_count: 0,
+        };
+
+        unsafe {
+            this.gl.bind_vertex_array(Some(vertex_array));
+        }
+        let result = this.upload_streams(mesh);
+        unsafe {
+            this.gl.bind_vertex_array(None);
+            this.gl.bind_buffer(glow::ARRAY_BUFFER, None);
+        }
+        result?;
+        Ok(this)
+    }
+
+    fn upload_streams(&mut self, mesh: &Mesh) -> anyhow::Result<()> {
+        let vertex_count = mesh.positions.len();
+        self.attribute(attrib::POSITION, 3, bytemuck::cast_slice(&mesh.positions))?;
+        if mesh.normals.len() == vertex_count {
+            self.attribute(attrib::NORMAL, 3, bytemuck::cast_slice(&mesh.normals))?;
+        }
+        if mesh.tex_coords.len() == vertex_count {
+            self.attribute(attrib::TEX_COORD, 2, bytemuck::cast_slice(&mesh.tex_coords))?;
+        }
+        if mesh.tangents.len() == vertex_count {
+            self.attribute(attrib::TANGENT, 4, bytemuck::cast_slice(&mesh.tangents))?;
+        }
+        if mesh.colors.len() == vertex_count {
+            self.attribute(attrib::COLOR, 4, bytemuck::cast_slice(&mesh.colors))?;
+        }
+        if mesh.is_skinned() && mesh.joints.len() == vertex_count {
+            self.buffer(glow::ARRAY_BUFFER, bytemuck::cast_slice(&mesh.joints))?;
+            unsafe {
+                self.gl.enable_vertex_attrib_array(attrib::JOINTS);
+                self.gl
+                    .vertex_attrib_pointer_i32(attrib::JOINTS, 4, glow::UNSIGNED_SHORT, 0, 0);
+            }
+            self.attribute(attrib::WEIGHTS, 4, bytemuck::cast_slice(&mesh.weights))?;
+        }
+
+        if mesh.has_morph_targets() {
+            self.upload_morph_targets(mesh)?;
+        }
+
+        let index_buffer = self.buffer(
+            glow::ELEMENT_ARRAY_BUFFER,
+            bytemuck::cast_slice(&mesh.indices),
+        )?;
+        unsafe {
+            self.gl
+                .bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(index_buffer));
+        }
+        Ok(())
+    }
+
+    fn upload_morph_targets(&mut self, mesh: &Mesh) -> anyhow::Result<()> {
+        if mesh.morph_targets.len() > MAX_MORPH_TARGETS {
+            log::warn!(
+                "{}: only the first {MAX_MORPH_TARGETS} of {} morph targets are used",
+                mesh.name,
+                mesh.morph_targets.len()
+            );
+        }
+        let targets = &mesh.morph_targets[..mesh.morph_targets.len().min(MAX_MORPH_TARGETS)];
+        let vertex_count = mesh.positions.len();
+        let texels = targets.len() * vertex_count * 2;
+        let rows = texels.div_ceil(MORPH_TEXTURE_WIDTH).max(1);
+        let mut deltas = Vec::with_capacity(rows * MORPH_TEXTURE_WIDTH);
+        for target in targets {
+            for i in 0..vertex_count {
+                let position = target.positions.get(i).copied().unwrap_or_default();
+                let normal = target.normals.get(i).copied().unwrap_or_default();
+                deltas.push(position.extend(0.0));
+                deltas.push(normal.extend(0.0));
+            }
+        }
+        deltas.resize(rows * MORPH_TEXTURE_WIDTH, Vec4::ZERO);
+
+        let bytes: &[u8] = bytemuck::cast_slice(&deltas);
+        unsafe {
+            let texture = self
+                .gl
+                .create_texture()
+                .map_err(anyhow::Error::msg)
+                .context("failed to create morph target texture")?;
+            self.gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+            for parameter in [glow::TEXTURE_MIN_FILTER, glow::TEXTURE_MAG_FILTER] {
+                self.gl
+                    .tex_parameter_i32(glow::TEXTURE_2D, parameter, glow::NEAREST as i32);
+            }
+            self.gl.tex_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                glow::RGBA32F as i32,
+                MORPH_TEXTURE_WIDTH as i32,
+                rows as i32,
+                0,
+                glow::RGBA,
+                glow::FLOAT,
+                glow::PixelUnpackData::Slice(Some(bytes)),
+            );
+            self.gl.bind_texture(glow::TEXTURE_2D, None);
+            self.morph_texture = Some(texture);
+        }
+        self.gpu_bytes += bytes.len();
+        self.morph_target_count = targets.len();
+        Ok(())
+    }
+
+    fn buffer(&mut self, target: u32, bytes: &[u8]) -> anyhow::Result<glow::NativeBuffer> {
+        let buffer = unsafe {
+            self.gl
+                .create_buffer()
+                .map_err(anyhow::Error::msg)
+                .context("failed to create buffer")?
+        };
+        unsafe {
+            self.gl.bind_buffer(target, Some(buffer));
+            self.gl
+                .buffer_data_u8_slice(target, bytes, glow::STATIC_DRAW);
+        }
+        self.buffers.push(buffer);
+        self.gpu_bytes += bytes.len();
+        Ok(buffer)
+    }
+
+    fn attribute(&mut self, location: u32, components: i32, bytes: &[u8]) -> anyhow::Result<()> {
+        self.buffer(glow::ARRAY_BUFFER, bytes)?;
+        unsafe {
+            self.gl.enable_vertex_attrib_array(location);
+            self.gl
+                .vertex_attrib_pointer_f32(location, components, glow::FLOAT, false, 0, 0);
+        }
+        Ok(())
+    }
+
+    pub fn vertex_array(&self) -> glow::NativeVertexArray {
+        self.vertex_array
+    }
+
+    pub fn index_count(&self) -> i32 {
+        self.index_count
+    }
+
+    pub fn gpu_bytes(&self) -> usize {
+        self.gpu_bytes
+    }
+
+    pub fn morph_target_count(&self) -> usize {
+        self.morph_target_count
+    }
+
+    /// Binds the morph target deltas to `unit`. Texel
+    /// `(target * vertex_count + vertex) * 2` holds the position delta, the
+    /// next one the normal delta.
+    pub fn bind_morph_targets(&self, unit: u32) {
+        unsafe {
+            self.gl.active_texture(glow::TEXTURE0 + unit);
+            self.gl.bind_texture(glow::TEXTURE_2D, self.morph_texture);
+            self.gl.active_texture(glow::TEXTURE0);
+        }
+    }
+
+    /// Draws `count` copies using the matrices from `first` on in
+    /// `instances`.
+    pub fn draw_instanced(&self, instances: &InstanceBuffer, first: usize, count: usize) {
+        unsafe {
+            self.gl.bind_vertex_array(Some(self.vertex_array));
+            instances.bind_attributes(first);
+            self.gl.draw_elements_instanced(
+                glow::TRIANGLES,
+                self.index_count,
+                glow::UNSIGNED_INT,
+                0,
+                count as i32,
+            );
+            self.gl.bind_vertex_array(None);
+        }
+    }
+}
+
+impl Drop for GpuMesh {
+    fn drop(&mut self) {
+        unsafe {
+            self.gl.delete_vertex_array(self.vertex_array);
+            if let Some(texture) = self.morph_texture {
+                self.gl.delete_texture(texture);
+            }
+            for buffer in &self.buffers {
+                self.gl.delete_buffer(*buffer);
+            }
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use glam::Vec3;
+use glow::HasContext;
+
+use crate::core::asset_manager::{Texture, texture::ColorSpace};
+
+pub struct GpuTexture {
+    gl: Arc<glow::Context>,
+    texture: glow::NativeTexture,
+    gpu_bytes: usize,
+}
+
+impl GpuTexture {
+    pub fn upload(gl: Arc<glow::Context>, texture: &Texture) -> anyhow::Result<Self> {
+        let native = unsafe {
+            gl.create_texture()
+                .map_err(anyhow::Error::msg)
+                .context("failed to create texture")?
+        };
+        let internal_format = match texture.color_space {
+            ColorSpace::Srgb => glow::SRGB8_ALPHA8,
+            ColorSpace::Linear => glow::RGBA8,
+        };
+
+        unsafe {
+            gl.bind_texture(glow::TEXTURE_2D, Some(native));
+            gl.tex_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                internal_format as i32,
+                texture.width as i32,
+                texture.height as i32,
+                0,
+                glow::RGBA,
+                glow::UNSIGNED_BYTE,
+                glow::PixelUnpackData::Slice(Some(&texture.pixels)),
+            );
+            gl.generate_mipmap(glow::TEXTURE_2D);
+            gl.tex_parameter_i32(
+                glow::TEXTURE_2D,
+                glow::TEXTURE_MIN_FILTER,
+                glow::LINEAR_MIPMAP_LINEAR as i32,
+            );
+            gl.tex_parameter_i32(
+                glow::TEXTURE_2D,
+                glow::TEXTURE_MAG_FILTER,
+                glow::LINEAR as i32,
+            );
+            gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_WRAP_S, glow::REPEAT as i32);
+            gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_WRAP_T, glow::REPEAT as i32);
+            gl.bind_texture(glow::TEXTURE_2D, None);
+        }
+
+        // The full mip chain adds roughly a third on top of the base level.
+        let gpu_bytes = texture.pixels.len() * 4 / 3;
+        Ok(Self {
+            gl,
+            texture: native,
+            gpu_bytes,
+        })
+    }
+
+    /// Uploads linear RGB texels as half floats with mipmaps, wrapping
+    /// horizontally and clamped vertically as an equirectangular map needs.
+    pub fn upload_hdr(
+        gl: Arc<glow::Context>,
+        width: u32,
+        height: u32,
+        pixels: &[Vec3],
+    ) -> anyhow::Result<Self> {
+        let native = unsafe {
+            gl.create_texture()
+                .map_err(anyhow::Error::msg)
+                .context("failed to create texture")?
+        };
+        unsafe {
+            gl.bind_texture(glow::TEXTURE_2D, Some(native));
+            gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 4);
+            gl.tex_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                glow::RGB16F as i32,
+                width as i32,
+                height as i32,
+                0,
+                glow::RGB,
+                glow::FLOAT,
+                glow::PixelUnpackData::Slice(Some(bytemuck::cast_slice(pixels))),
+            );
+            gl.generate_mipmap(glow::TEXTURE_2D);
+            gl.tex_parameter_i32(
+                glow::TEXTURE_2D,
+                glow::TEXTURE_MIN_FILTER,
+                glow::LINEAR_MIPMAP_LINEAR as i32,
+            );
+            gl.tex_parameter_i32(
+                glow::TEXTURE_2D,
+                glow::TEXTURE_MAG_FILTER,
+                glow::LINEAR as i32,
+            );
+            gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_WRAP_S, glow::REPEAT as i32);
+            gl.tex_parameter_i32(
+                glow::TEXTURE_2D,
+                glow::TEXTURE_WRAP_T,
+                glow::CLAMP_TO_EDGE as i32,
+            );
+            gl.bind_texture(glow::TEXTURE_2D, None);
+        }
+
+        let gpu_bytes = pixels.len() * 6 * 4 / 3;
+        Ok(Self {
+            gl,
+            texture: native,
+            gpu_bytes,
+        })
+    }
+
+    pub fn native(&self) -> glow::NativeTexture {
+        self.texture
+    }
+
+    pub fn gpu_bytes(&self) -> usize {
+        self.gpu_bytes
+    }
+}
+
+impl Drop for GpuTexture {
+    fn drop(&mut self) {
+        unsafe {
+            self.gl.delete_texture(self.texture);
+        }
+    }
+}
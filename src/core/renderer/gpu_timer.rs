@@ -0,0 +1,94 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+use glow::HasContext;
+
+/// Measures GPU time with `GL_TIME_ELAPSED` queries. Results are read a few
+/// frames late, once available, so timing never stalls the pipeline.
+pub struct GpuTimer {
+    gl: Arc<glow::Context>,
+    free: Vec<glow::NativeQuery>,
+    in_flight: VecDeque<glow::NativeQuery>,
+    active: Option<glow::NativeQuery>,
+    latest: Option<Duration>,
+}
+
+impl GpuTimer {
+    /// Queries kept in flight before a frame goes untimed.
+    const MAX_IN_FLIGHT: usize = 4;
+
+    /// Returns `None` when the context has no timer queries.
+    pub fn new(gl: Arc<glow::Context>) -> Option<Self> {
+        let version = gl.version();
+        let supported = (version.major, version.minor) >= (3, 3)
+            || gl.supported_extensions().contains("GL_ARB_timer_query");
+        supported.then(|| Self {
+            gl,
+            free: Vec::new(),
+            in_flight: VecDeque::new(),
+            active: None,
+            latest: None,
+        })
+    }
+
+    pub fn begin(&mut self) {
+        if self.active.is_some() || self.in_flight.len() >= Self::MAX_IN_FLIGHT {
+            return;
+        }
+        let query = match self.free.pop() {
+            Some(query) => query,
+            None => match unsafe { self.gl.create_query() } {
+                Ok(query) => query,
+                Err(err) => {
+                    log::warn!("failed to create timer query: {err}");
+                    return;
+                }
+            },
+        };
+        unsafe {
+            self.gl.begin_query(glow::TIME_ELAPSED, query);
+        }
+        self.active = Some(query);
+    }
+
+    pub fn end(&mut self) {
+        if let Some(query) = self.active.take() {
+            unsafe {
+                self.gl.end_query(glow::TIME_ELAPSED);
+            }
+            self.in_flight.push_back(query);
+        }
+    }
+
+    /// Collects finished queries and returns the newest result.
+    pub fn poll(&mut self) -> Option<Duration> {
+        while let Some(&query) = self.in_flight.front() {
+            let available = unsafe {
+                self.gl
+                    .get_query_parameter_u32(query, glow::QUERY_RESULT_AVAILABLE)
+            };
+            if available == 0 {
+                break;
+            }
+            let nanos = unsafe { self.gl.get_query_parameter_u32(query, glow::QUERY_RESULT) };
+            self.latest = Some(Duration::from_nanos(nanos.into()));
+            self.in_flight.pop_front();
+            self.free.push(query);
+        }
+        self.latest
+    }
+}
+
+impl Drop for GpuTimer {
+    fn drop(&mut self) {
+        unsafe {
+            for query in self.free.drain(..).chain(self.in_flight.drain(..)) {
+                self.gl.delete_query(query);
+            }
+            if let Some(query) = self.active.take() {
+                self.gl.delete_query(query);
+            }
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use std::sync::Arc;
+
+use glow::HasContext;
+use winit::dpi::PhysicalSize;
+
+use crate::core::{
+    asset_manager::{AssetManager, Handle, Shader},
+    camera::{Camera, UpAxis},
+    renderer::builtin_shader,
+};
+
+const GRID_SHADER_PATH: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/src/core/renderer/shaders/grid.glsl"
+);
+
+/// Grid cells per orbit distance, roughly, before the spacing steps up.
+const CELLS_PER_DISTANCE: f32 = 10.0;
+
+/// Infinite ground grid through the origin, perpendicular to the camera's
+/// up axis, with the two world axes in the plane drawn in their colors.
+/// Drawn as one full-screen triangle that intersects each pixel's view ray
+/// with the plane and writes the hit's depth.
+pub struct GridRenderer {
+    gl: Arc<glow::Context>,
+    shader: Handle<Shader>,
+    empty_vertex_array: Option<glow::NativeVertexArray>,
+}
+
+impl GridRenderer {
+    pub fn new(gl: Arc<glow::Context>, assets: &mut AssetManager) -> Self {
+        let empty_vertex_array = unsafe { gl.create_vertex_array() }
+            .inspect_err(|err| log::error!("failed to create grid vertex array: {err}"))
+            .ok();
+        Self {
+            gl,
+            shader: builtin_shader(assets, GRID_SHADER_PATH, include_str!("shaders/grid.glsl")),
+            empty_vertex_array,
+        }
+    }
+
+    /// Blends the grid over the bound framebuffer, depth-tested against
+    /// what is already drawn.
+    pub fn render(&self, assets: &AssetManager, camera: &Camera, size: PhysicalSize<u32>) {
+        let Some(program) = assets.get(&self.shader).and_then(Shader::program) else {
+            return;
+        };
+        let aspect = size.width.max(1) as f32 / size.height.max(1) as f32;
+        let view_projection = camera.projection(aspect) * camera.view();
+        let (spacing, fade) = grid_spacing(camera.distance);
+
+        program.bind();
+        program.set_mat4("u_view_projection", &view_projection);
+        program.set_mat4("u_inverse_view_projection", &view_projection.inverse());
+        program.set_vec3("u_camera_position", camera.position());
+        program.set_i32("u_z_up", (camera.up == UpAxis::Z) as i32);
+        program.set_f32("u_spacing", spacing);
+        program.set_f32("u_fade", fade);
+        program.set_f32(
+            "u_fade_distance",
+            camera.distance * CELLS_PER_DISTANCE * 2.0,
+        );
+        unsafe {
+            self.gl.enable(glow::DEPTH_TEST);
+            self.gl.depth_func(glow::LEQUAL);
+            self.gl.depth_mask(false);
+            self.gl.enable(glow::BLEND);
+            self.gl
+                .blend_func(glow::SRC_ALPHA, glow::ONE_MINUS_SRC_ALPHA);
+            self.gl.bind_vertex_array(self.empty_vertex_array);
+            self.gl.draw_arrays(glow::TRIANGLES, 0, 3);
+            self.gl.bind_vertex_array(None);
+            self.gl.use_program(None);
+            self.gl.disable(glow::BLEND);
+            self.gl.depth_mask(true);
+            self.gl.depth_func(glow::LESS);
+            self.gl.disable(glow::DEPTH_TEST);
+        }
+    }
+}
+
+impl Drop for GridRenderer {
+    fn drop(&mut self) {
+        if let Some(vertex_array) = self.empty_vertex_array {
+            unsafe { self.gl.delete_vertex_array(vertex_array) };
+        }
+    }
+}
+
+/// Power-of-ten cell size for an orbit distance, and how far the camera is
+/// towards the next step up, which fades the fine lines out.
+fn grid_spacing(distance: f32) -> (f32, f32) {
+    let level = (distance.max(1e-4) / CELLS_PER_DISTANCE).log10();
+    (10f32.powf(level.floor()), level - level.floor())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spacing_steps_by_powers_of_ten() {
+        let (spacing, fade) = grid_spacing(10.0);
+        assert!((spacing - 1.0).abs() < 1e-6 && fade.abs() < 1e-6);
+        let (spacing, fade) = grid_spacing(50.0);
+        assert!((spacing - 1.0).abs() < 1e-6 && (fade - 0.699).abs() < 1e-3);
+        let (spacing, _) = grid_spacing(0.5);
+        assert!((spacing - 0.01).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,83 @@
+use std::{mem::size_of, sync::Arc};
+
+use anyhow::Context;
+use glam::Mat4;
+use glow::HasContext;
+
+use crate::core::renderer::gpu_mesh::attrib;
+
+/// Per-instance model matrices for one frame, streamed into a single
+/// buffer. Batches draw ranges of it via `GpuMesh::draw_instanced`.
+pub struct InstanceBuffer {
+    gl: Arc<glow::Context>,
+    buffer: glow::NativeBuffer,
+    capacity: usize,
+}
+
+impl InstanceBuffer {
+    pub fn new(gl: Arc<glow::Context>) -> anyhow::Result<Self> {
+        let buffer = unsafe {
+            gl.create_buffer()
+                .map_err(anyhow::Error::msg)
+                .context("failed to create instance buffer")?
+        };
+        Ok(Self {
+            gl,
+            buffer,
+            capacity: 0,
+        })
+    }
+
+    pub fn upload(&mut self, matrices: &[Mat4]) {
+        let bytes: &[u8] = bytemuck::cast_slice(matrices);
+        unsafe {
+            self.gl.bind_buffer(glow::ARRAY_BUFFER, Some(self.buffer));
+            if bytes.len() > self.capacity {
+                self.capacity = bytes.len().next_power_of_two();
+                self.gl.buffer_data_size(
+                    glow::ARRAY_BUFFER,
+                    self.capacity as i32,
+                    glow::STREAM_DRAW,
+                );
+            }
+            self.gl
+                .buffer_sub_data_u8_slice(glow::ARRAY_BUFFER, 0, bytes);
+            self.gl.bind_buffer(glow::ARRAY_BUFFER, None);
+        }
+    }
+
+    pub fn gpu_bytes(&self) -> usize {
+        self.capacity
+    }
+
+    /// Points the instance attributes of the bound vertex array at the
+    /// matrices starting with `first`.
+    pub(crate) fn bind_attributes(&self, first: usize) {
+        let stride = size_of::<Mat4>() as i32;
+        unsafe {
+            self.gl.bind_buffer(glow::ARRAY_BUFFER, Some(self.buffer));
+            for column in 0..4 {
+                let location = attrib::INSTANCE_MODEL + column;
+                self.gl.enable_vertex_attrib_array(location);
+                self.gl.vertex_attrib_pointer_f32(
+                    location,
+                    4,
+                    glow::FLOAT,
+                    false,
+                    stride,
+                    (first * size_of::<Mat4>()) as i32 + column as i32 * 16,
+                );
+                self.gl.vertex_attrib_divisor(location, 1);
+            }
+            self.gl.bind_buffer(glow::ARRAY_BUFFER, None);
+        }
+    }
+}
+
+impl Drop for InstanceBuffer {
+    fn drop(&mut self) {
+        unsafe {
+            self.gl.delete_buffer(self.buffer);
+        }
+    }
+}
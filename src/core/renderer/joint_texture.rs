@@ -0,0 +1,63 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use glam::Mat4;
+use glow::HasContext;
+
+/// Skinning matrices in an RGBA32F texture, one row of four texels (the
+/// matrix columns) per joint. A texture avoids the small uniform limits of
+/// GL 3.3, so skins of any size work.
+pub struct JointTexture {
+    gl: Arc<glow::Context>,
+    texture: glow::NativeTexture,
+}
+
+impl JointTexture {
+    pub fn new(gl: Arc<glow::Context>) -> anyhow::Result<Self> {
+        let texture = unsafe {
+            gl.create_texture()
+                .map_err(anyhow::Error::msg)
+                .context("failed to create joint texture")?
+        };
+        unsafe {
+            gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+            for parameter in [glow::TEXTURE_MIN_FILTER, glow::TEXTURE_MAG_FILTER] {
+                gl.tex_parameter_i32(glow::TEXTURE_2D, parameter, glow::NEAREST as i32);
+            }
+            gl.bind_texture(glow::TEXTURE_2D, None);
+        }
+        Ok(Self { gl, texture })
+    }
+
+    /// Uploads the matrices and leaves the texture bound to `unit`.
+    pub fn upload(&self, unit: u32, matrices: &[Mat4]) {
+        unsafe {
+            self.gl.active_texture(glow::TEXTURE0 + unit);
+            self.gl.bind_texture(glow::TEXTURE_2D, Some(self.texture));
+            self.gl.tex_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                glow::RGBA32F as i32,
+                4,
+                matrices.len().max(1) as i32,
+                0,
+                glow::RGBA,
+                glow::FLOAT,
+                glow::PixelUnpackData::Slice(Some(bytemuck::cast_slice(if matrices.is_empty() {
+                    &[Mat4::IDENTITY]
+                } else {
+                    matrices
+                }))),
+            );
+            self.gl.active_texture(glow::TEXTURE0);
+        }
+    }
+}
+
+impl Drop for JointTexture {
+    fn drop(&mut self) {
+        unsafe {
+            self.gl.delete_texture(self.texture);
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+
+use crate::core::{
+    asset_manager::Mesh,
+    bounds::Sphere,
+    camera::{Camera, Projection},
+    scene::NodeId,
+};
+
+/// How the scene pass picks levels of detail.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LodSettings {
+    pub enabled: bool,
+    /// Each step of one halves the coverage used for selection, switching
+    /// to coarser levels earlier; negative values keep detail longer.
+    pub bias: f32,
+    /// Relative band around each threshold in which the current level is
+    /// kept, so objects hovering at a threshold do not flicker.
+    pub hysteresis: f32,
+}
+
+impl LodSettings {
+    pub fn new() -> Self {
+        Self {
+            enabled: true,
+            bias: 0.0,
+            hysteresis: 0.1,
+        }
+    }
+}
+
+impl Default for LodSettings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Levels chosen in the previous frame, per node. Each view keeps its own
+/// so that views at different distances do not fight over the hysteresis.
+#[derive(Default)]
+pub struct LodState {
+    previous: HashMap<NodeId, usize>,
+    current: HashMap<NodeId, usize>,
+}
+
+impl LodState {
+    /// Starts a frame. Nodes not selected during it forget their level.
+    pub fn begin_frame(&mut self) {
+        std::mem::swap(&mut self.previous, &mut self.current);
+        self.current.clear();
+    }
+
+    /// Level of `mesh` to draw for node `id`: 0 for the mesh itself, `i` for
+    /// `mesh.lods[i - 1]`.
+    pub fn select(
+        &mut self,
+        id: NodeId,
+        sphere: &Sphere,
+        mesh: &Mesh,
+        camera: &Camera,
+        settings: &LodSettings,
+    ) -> usize {
+        if !settings.enabled || mesh.lods.is_empty() {
+            return 0;
+        }
+        let thresholds: Vec<f32> = mesh.lods.iter().map(|lod| lod.screen_coverage).collect();
+        let coverage = screen_coverage(sphere, camera);
+        let level = select_level(
+            coverage,
+            &thresholds,
+            self.previous.get(&id).copied(),
+            settings,
+        );
+        self.current.insert(id, level);
+        level
+    }
+}
+
+/// Fraction of the viewport height covered by `sphere`. Infinite when the
+/// camera is inside it.
+pub fn screen_coverage(sphere: &Sphere, camera: &Camera) -> f32 {
+    match camera.projection {
+        Projection::Perspective { fov_y } => {
+            let distance = sphere.center.distance(camera.position());
+            if distance <= sphere.radius {
+                return f32::INFINITY;
+            }
+            sphere.radius / (distance * (fov_y * 0.5).tan())
+        }
+        Projection::Orthographic { height } => 2.0 * sphere.radius / height.max(f32::EPSILON),
+    }
+}
+
+/// Picks the level for `coverage` given each coarser level's threshold,
+/// finest first. Level `i` covers `thresholds[i] <= coverage <
+/// thresholds[i - 1]`; the previous level is kept while the coverage stays
+/// within the hysteresis band around its range.
+pub fn select_level(
+    coverage: f32,
+    thresholds: &[f32],
+    previous: Option<usize>,
+    settings: &LodSettings,
+) -> usize {
+    let coverage = coverage * (-settings.bias).exp2();
+    let upper = |level: usize| {
+        level
+            .checked_sub(1)
+            .and_then(|i| thresholds.get(i))
+            .map_or(f32::INFINITY, |&t| t * (1.0 + settings.hysteresis))
+    };
+    let lower = |level: usize| {
+        thresholds
+            .get(level)
+            .map_or(0.0, |&t| t * (1.0 - settings.hysteresis))
+    };
+    if let Some(level) = previous.filter(|&level| level <= thresholds.len())
+        && coverage >= lower(level)
+        && coverage < upper(level)
+    {
+        return level;
+    }
+    thresholds.iter().filter(|&&t| coverage < t).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selects_levels_with_hysteresis_and_bias() {
+        let settings = LodSettings::new();
+        let thresholds = [0.4, 0.2, 0.1];
+        assert_eq!(select_level(1.0, &thresholds, None, &settings), 0);
+        assert_eq!(select_level(0.3, &thresholds, None, &settings), 1);
+        assert_eq!(select_level(0.05, &thresholds, None, &settings), 3);
+
+        // Just below the threshold the finer level stays, further down it
+        // switches; coming back, it stays coarse until clearly above.
+        assert_eq!(select_level(0.39, &thresholds, Some(0), &settings), 0);
+        assert_eq!(select_level(0.35, &thresholds, Some(0), &settings), 1);
+        assert_eq!(select_level(0.41, &thresholds, Some(1), &settings), 1);
+        assert_eq!(select_level(0.45, &thresholds, Some(1), &settings), 0);
+
+        let coarser = LodSettings {
+            bias: 1.0,
+            ..settings
+        };
+        assert_eq!(select_level(0.5, &thresholds, None, &coarser), 1);
+    }
+
+    #[test]
+    fn coverage_shrinks_with_distance() {
+        let mut camera = Camera::new();
+        camera.target = glam::Vec3::ZERO;
+        let sphere = Sphere::new(glam::Vec3::ZERO, 1.0);
+        camera.distance = 0.5;
+        assert_eq!(screen_coverage(&sphere, &camera), f32::INFINITY);
+        camera.distance = 10.0;
+        let near = screen_coverage(&sphere, &camera);
+        camera.distance = 20.0;
+        let far = screen_coverage(&sphere, &camera);
+        assert!((near / far - 2.0).abs() < 1e-4);
+
+        camera.projection = Projection::Orthographic { height: 8.0 };
+        assert_eq!(screen_coverage(&sphere, &camera), 0.25);
+    }
+}
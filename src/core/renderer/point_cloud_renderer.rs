@@ -0,0 +1,822 @@
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap},
+    mem::size_of,
+    sync::Arc,
+};
+
+use anyhow::Context;
+use glam::{Mat4, Vec4Swizzles};
+use glow::HasContext;
+use serde::{Deserialize, Serialize};
+use winit::dpi::PhysicalSize;
+
+use crate::core::{
+    asset_manager::{AssetId, AssetManager, Handle, PointCloud, Shader, point_cloud::PointOctree},
+    bounds::Frustum,
+    camera::{Camera, Projection},
+    renderer::{builtin_shader, gpu_mesh::attrib},
+    scene::Scene,
+};
+
+const POINTS_SHADER_PATH: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/src/core/renderer/shaders/points.glsl"
+);
+const EDL_SHADER_PATH: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/src/core/renderer/shaders/edl.glsl"
+);
+
+/// Interleaved chunk vertex: position, RGBA8 color, intensity.
+const VERTEX_SIZE: usize = 3 * size_of::<f32>() + 4 + size_of::<f32>();
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PointSizeMode {
+    /// `point_size` pixels regardless of distance.
+    Screen,
+    /// `world_point_size` scene units, shrinking with distance.
+    World,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PointColorMode {
+    Rgb,
+    Intensity,
+}
+
+#[derive(Clone, Debug)]
+pub struct PointCloudSettings {
+    pub size_mode: PointSizeMode,
+    pub point_size: f32,
+    pub world_point_size: f32,
+    pub color_mode: PointColorMode,
+    /// Draw points as discs instead of squares.
+    pub round_points: bool,
+    pub eye_dome_lighting: bool,
+    pub edl_strength: f32,
+    /// Distance in pixels of the neighbours sampled by eye-dome lighting.
+    pub edl_radius: f32,
+    /// Upper bound on points drawn per frame, across all clouds.
+    pub point_budget: usize,
+    /// Upper bound on GPU memory held by resident chunks.
+    pub gpu_budget: usize,
+    /// Bytes uploaded per frame; the rest stream in over later frames.
+    pub upload_budget: usize,
+    /// Nodes whose projected radius is smaller than this many pixels are
+    /// not refined further.
+    pub min_node_size: f32,
+}
+
+impl PointCloudSettings {
+    pub fn new() -> Self {
+        Self {
+            size_mode: PointSizeMode::Screen,
+            point_size: 2.0,
+            world_point_size: 0.01,
+            color_mode: PointColorMode::Rgb,
+            round_points: true,
+            eye_dome_lighting: true,
+            edl_strength: 1.0,
+            edl_radius: 1.5,
+            point_budget: 5_000_000,
+            gpu_budget: 512 << 20,
+            upload_budget: 16 << 20,
+            min_node_size: 64.0,
+        }
+    }
+}
+
+impl Default for PointCloudSettings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone, Copy, Default, Debug)]
+pub struct PointCloudStats {
+    /// Octree nodes chosen by the level-of-detail pass this frame.
+    pub selected_nodes: usize,
+    pub drawn_nodes: usize,
+    pub drawn_points: usize,
+    /// Selected nodes still waiting for an upload slot.
+    pub pending_nodes: usize,
+    pub resident_chunks: usize,
+    pub resident_bytes: usize,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+struct ChunkKey {
+    cloud: AssetId,
+    revision: u64,
+    node: usize,
+}
+
+/// GPU copy of one octree node's points.
+struct PointChunk {
+    gl: Arc<glow::Context>,
+    vertex_array: glow::NativeVertexArray,
+    buffer: glow::NativeBuffer,
+    count: i32,
+    bytes: usize,
+    last_used: u64,
+}
+
+impl PointChunk {
+    fn upload(
+        gl: Arc<glow::Context>,
+        cloud: &PointCloud,
+        start: usize,
+        count: usize,
+    ) -> anyhow::Result<Self> {
+        let mut vertices = Vec::with_capacity(count * VERTEX_SIZE);
+        for i in start..start + count {
+            for c in cloud.positions[i].to_array() {
+                vertices.extend(c.to_le_bytes());
+            }
+            let color = cloud.colors.get(i).map_or([255; 4], |c| {
+                c.to_array()
+                    .map(|v| (v.clamp(0.0, 1.0) * 255.0).round() as u8)
+            });
+            vertices.extend(color);
+            vertices.extend(
+                cloud
+                    .intensities
+                    .get(i)
+                    .copied()
+                    .unwrap_or(1.0)
+                    .to_le_bytes(),
+            );
+        }
+
+        unsafe {
+            let vertex_array = gl
+                .create_vertex_array()
+                .map_err(anyhow::Error::msg)
+                .context("failed to create vertex array")?;
+            let buffer = match gl.create_buffer() {
+                Ok(buffer) => buffer,
+                Err(err) => {
+                    gl.delete_vertex_array(vertex_array);
+                    return Err(anyhow::Error::msg(err).context("failed to create buffer"));
+                }
+            };
+            gl.bind_vertex_array(Some(vertex_array));
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(buffer));
+            gl.buffer_data_u8_slice(glow::ARRAY_BUFFER, &vertices, glow::STATIC_DRAW);
+            let stride = VERTEX_SIZE as i32;
+            gl.enable_vertex_attrib_array(attrib::POSITION);
+            gl.vertex_attrib_pointer_f32(attrib::POSITION, 3, glow::FLOAT, false, stride, 0);
+            gl.enable_vertex_attrib_array(attrib::COLOR);
+            gl.vertex_attrib_pointer_f32(attrib::COLOR, 4, glow::UNSIGNED_BYTE, true, stride, 12);
+            gl.enable_vertex_attrib_array(attrib::INTENSITY);
+            gl.vertex_attrib_pointer_f32(attrib::INTENSITY, 1, glow::FLOAT, false, stride, 16);
+            gl.bind_vertex_array(None);
+            gl.bind_buffer(glow::ARRAY_BUFFER, None);
+
+            Ok(Self {
+                gl,
+                vertex_array,
+                buffer,
+                count: count as i32,
+                bytes: vertices.len(),
+                last_used: 0,
+            })
+        }
+    }
+
+    fn draw(&self) {
+        unsafe {
+            self.gl.bind_vertex_array(Some(self.vertex_array));
+            self.gl.draw_arrays(glow::POINTS, 0, self.count);
+        }
+    }
+}
+
+impl Drop for PointChunk {
+    fn drop(&mut self) {
+        unsafe {
+            self.gl.delete_vertex_array(self.vertex_array);
+            self.gl.delete_buffer(self.buffer);
+        }
+    }
+}
+
+/// Offscreen color and depth the points are drawn into before the eye-dome
+/// lighting pass composites them. Both are textures so the pass can sample
+/// the depth.
+struct EdlTarget {
+    gl: Arc<glow::Context>,
+    framebuffer: glow::NativeFramebuffer,
+    color: glow::NativeTexture,
+    depth: glow::NativeTexture,
+    size: PhysicalSize<u32>,
+}
+
+impl EdlTarget {
+    fn new(gl: Arc<glow::Context>, size: PhysicalSize<u32>) -> anyhow::Result<Self> {
+        unsafe {
+            let framebuffer = gl
+                .create_framebuffer()
+                .map_err(anyhow::Error::msg)
+                .context("failed to create framebuffer")?;
+            let color = gl.create_texture().map_err(anyhow::Error::msg)?;
+            let depth = gl.create_texture().map_err(anyhow::Error::msg)?;
+            let this = Self {
+                gl,
+                framebuffer,
+                color,
+                depth,
+                size,
+            };
+            let gl = &this.gl;
+            let (width, height) = (size.width as i32, size.height as i32);
+            for (texture, internal, format, ty) in [
+                (color, glow::RGBA8, glow::RGBA, glow::UNSIGNED_BYTE),
+                (
+                    depth,
+                    glow::DEPTH_COMPONENT24,
+                    glow::DEPTH_COMPONENT,
+                    glow::UNSIGNED_INT,
+                ),
+            ] {
+                gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+                gl.tex_image_2d(
+                    glow::TEXTURE_2D,
+                    0,
+                    internal as i32,
+                    width,
+                    height,
+                    0,
+                    format,
+                    ty,
+                    glow::PixelUnpackData::Slice(None),
+                );
+                for parameter in [glow::TEXTURE_MIN_FILTER, glow::TEXTURE_MAG_FILTER] {
+                    gl.tex_parameter_i32(glow::TEXTURE_2D, parameter, glow::NEAREST as i32);
+                }
+                for parameter in [glow::TEXTURE_WRAP_S, glow::TEXTURE_WRAP_T] {
+                    gl.tex_parameter_i32(glow::TEXTURE_2D, parameter, glow::CLAMP_TO_EDGE as i32);
+                }
+            }
+            gl.bind_texture(glow::TEXTURE_2D, None);
+
+            let previous = gl.get_parameter_framebuffer(glow::FRAMEBUFFER_BINDING);
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(framebuffer));
+            gl.framebuffer_texture_2d(
+                glow::FRAMEBUFFER,
+                glow::COLOR_ATTACHMENT0,
+                glow::TEXTURE_2D,
+                Some(color),
+                0,
+            );
+            gl.framebuffer_texture_2d(
+                glow::FRAMEBUFFER,
+                glow::DEPTH_ATTACHMENT,
+                glow::TEXTURE_2D,
+                Some(depth),
+                0,
+            );
+            let status = gl.check_framebuffer_status(glow::FRAMEBUFFER);
+            gl.bind_framebuffer(glow::FRAMEBUFFER, previous);
+            if status != glow::FRAMEBUFFER_COMPLETE {
+                anyhow::bail!("eye-dome lighting framebuffer incomplete: {status:#x}");
+            }
+            Ok(this)
+        }
+    }
+}
+
+impl Drop for EdlTarget {
+    fn drop(&mut self) {
+        unsafe {
+            self.gl.delete_framebuffer(self.framebuffer);
+            self.gl.delete_texture(self.color);
+            self.gl.delete_texture(self.depth);
+        }
+    }
+}
+
+/// Draws scene point clouds. Each frame it picks the octree nodes worth
+/// drawing within the point budget, streams the missing ones to the GPU
+/// within the upload budget and evicts the least recently used chunks once
+/// the memory budget is exceeded.
+pub struct PointCloudRenderer {
+    gl: Arc<glow::Context>,
+    points_shader: Handle<Shader>,
+    edl_shader: Handle<Shader>,
+    settings: PointCloudSettings,
+    stats: PointCloudStats,
+    chunks: HashMap<ChunkKey, PointChunk>,
+    resident_bytes: usize,
+    frame: u64,
+    edl_target: Option<EdlTarget>,
+    /// Core profiles need a bound vertex array even for attribute-less draws.
+    empty_vertex_array: Option<glow::NativeVertexArray>,
+}
+
+impl PointCloudRenderer {
+    pub fn new(gl: Arc<glow::Context>, assets: &mut AssetManager) -> Self {
+        let empty_vertex_array = unsafe { gl.create_vertex_array() }
+            .inspect_err(|err| log::error!("failed to create vertex array: {err}"))
+            .ok();
+        Self {
+            gl,
+            points_shader: builtin_shader(
+                assets,
+                POINTS_SHADER_PATH,
+                include_str!("shaders/points.glsl"),
+            ),
+            edl_shader: builtin_shader(assets, EDL_SHADER_PATH, include_str!("shaders/edl.glsl")),
+            settings: PointCloudSettings::new(),
+            stats: PointCloudStats::default(),
+            chunks: HashMap::new(),
+            resident_bytes: 0,
+            frame: 0,
+            edl_target: None,
+            empty_vertex_array,
+        }
+    }
+
+    pub fn settings(&self) -> &PointCloudSettings {
+        &self.settings
+    }
+
+    pub fn settings_mut(&mut self) -> &mut PointCloudSettings {
+        &mut self.settings
+    }
+
+    pub fn stats(&self) -> PointCloudStats {
+        self.stats
+    }
+
+    /// Draws every visible node with a point cloud into the bound
+    /// framebuffer, depth-tested against what is already there.
+    pub fn render(
+        &mut self,
+        scene: &Scene,
+        assets: &AssetManager,
+        camera: &Camera,
+        size: PhysicalSize<u32>,
+    ) {
+        self.frame += 1;
+        self.drop_stale_chunks(assets);
+        self.stats = PointCloudStats::default();
+
+        let clouds: Vec<(AssetId, &PointCloud, Mat4)> = scene
+            .iter()
+            .filter(|(_, node)| node.visible)
+            .filter_map(|(_, node)| {
+                let handle = node.point_cloud.as_ref()?;
+                let cloud = assets.get(handle)?;
+                cloud.octree()?;
+                Some((handle.id(), cloud, node.world_matrix()))
+            })
+            .collect();
+        if !clouds.is_empty()
+            && let Some(program) = assets.get(&self.points_shader).and_then(Shader::program)
+        {
+            let aspect = size.width.max(1) as f32 / size.height.max(1) as f32;
+            let projection = camera.projection(aspect);
+            let view = camera.view();
+            let octrees: Vec<(Mat4, &PointOctree)> = clouds
+                .iter()
+                .filter_map(|&(_, cloud, model)| Some((model, cloud.octree()?)))
+                .collect();
+            let selection = select_nodes(
+                &octrees,
+                &projection,
+                &view,
+                size.height as f32,
+                &self.settings,
+            );
+            self.stats.selected_nodes = selection.len();
+
+            let draws = self.make_resident(&clouds, &selection);
+            let edl = self.settings.eye_dome_lighting
+                && assets
+                    .get(&self.edl_shader)
+                    .and_then(Shader::program)
+                    .is_some();
+            let previous_framebuffer = if edl {
+                self.bind_edl_target(size)
+            } else {
+                None
+            };
+
+            unsafe {
+                self.gl.enable(glow::DEPTH_TEST);
+                self.gl.depth_func(glow::LESS);
+                self.gl.enable(glow::PROGRAM_POINT_SIZE);
+            }
+            program.bind();
+            program.set_mat4("u_view_projection", &(projection * view));
+            program.set_i32(
+                "u_world_space",
+                (self.settings.size_mode == PointSizeMode::World) as i32,
+            );
+            program.set_f32(
+                "u_point_size",
+                match self.settings.size_mode {
+                    PointSizeMode::Screen => self.settings.point_size,
+                    PointSizeMode::World => self.settings.world_point_size,
+                },
+            );
+            program.set_f32(
+                "u_projection_scale",
+                projection.y_axis.y * size.height as f32 * 0.5,
+            );
+            program.set_i32(
+                "u_color_mode",
+                (self.settings.color_mode == PointColorMode::Intensity) as i32,
+            );
+            program.set_i32("u_round", self.settings.round_points as i32);
+
+            let mut current_cloud = None;
+            for (cloud, key) in draws {
+                if current_cloud != Some(cloud) {
+                    program.set_mat4("u_model", &clouds[cloud].2);
+                    current_cloud = Some(cloud);
+                }
+                let chunk = &self.chunks[&key];
+                chunk.draw();
+                self.stats.drawn_nodes += 1;
+                self.stats.drawn_points += chunk.count as usize;
+            }
+
+            unsafe {
+                self.gl.bind_vertex_array(None);
+                self.gl.disable(glow::PROGRAM_POINT_SIZE);
+            }
+            if edl {
+                self.composite_edl(assets, camera, size, previous_framebuffer);
+            }
+            unsafe {
+                self.gl.use_program(None);
+                self.gl.disable(glow::DEPTH_TEST);
+            }
+        }
+
+        self.stats.resident_chunks = self.chunks.len();
+        self.stats.resident_bytes = self.resident_bytes;
+    }
+
+    /// Releases chunks of clouds that were unloaded or rebuilt.
+    fn drop_stale_chunks(&mut self, assets: &AssetManager) {
+        let storage = assets.storage::<PointCloud>();
+        let mut freed = 0;
+        self.chunks.retain(|key, chunk| {
+            let current = storage
+                .get_by_id(key.cloud)
+                .is_some_and(|cloud| cloud.revision() == key.revision);
+            if !current {
+                freed += chunk.bytes;
+            }
+            current
+        });
+        self.resident_bytes -= freed;
+    }
+
+    /// Uploads what the budgets allow of the selection, most important first,
+    /// and returns the `(cloud, chunk)` pairs ready to draw.
+    fn make_resident(
+        &mut self,
+        clouds: &[(AssetId, &PointCloud, Mat4)],
+        selection: &[SelectedNode],
+    ) -> Vec<(usize, ChunkKey)> {
+        let mut uploaded = 0;
+        let mut draws = Vec::with_capacity(selection.len());
+        for selected in selection {
+            let (id, cloud, _) = clouds[selected.cloud];
+            let key = ChunkKey {
+                cloud: id,
+                revision: cloud.revision(),
+                node: selected.node,
+            };
+            if let Some(chunk) = self.chunks.get_mut(&key) {
+                chunk.last_used = self.frame;
+                draws.push((selected.cloud, key));
+                continue;
+            }
+
+            let Some(node) = cloud.octree().and_then(|o| o.nodes().get(selected.node)) else {
+                continue;
+            };
+            let bytes = node.count as usize * VERTEX_SIZE;
+            if uploaded + bytes > self.settings.upload_budget.max(VERTEX_SIZE)
+                || !self.make_room(bytes)
+            {
+                self.stats.pending_nodes += 1;
+                continue;
+            }
+            match PointChunk::upload(
+                self.gl.clone(),
+                cloud,
+                node.start as usize,
+                node.count as usize,
+            ) {
+                Ok(mut chunk) => {
+                    chunk.last_used = self.frame;
+                    uploaded += chunk.bytes;
+                    self.resident_bytes += chunk.bytes;
+                    self.chunks.insert(key, chunk);
+                    draws.push((selected.cloud, key));
+                }
+                Err(err) => log::error!("failed to upload point chunk: {err:#}"),
+            }
+        }
+        // Draw clouds together to avoid redundant model matrix changes.
+        draws.sort_by_key(|&(cloud, _)| cloud);
+        draws
+    }
+
+    /// Evicts chunks not drawn this frame, oldest first, until `bytes` more
+    /// fit into the GPU budget.
+    fn make_room(&mut self, bytes: usize) -> bool {
+        let budget = self.settings.gpu_budget;
+        if self.resident_bytes + bytes <= budget {
+            return true;
+        }
+        let mut evictable: Vec<(u64, ChunkKey)> = self
+            .chunks
+            .iter()
+            .filter(|(_, chunk)| chunk.last_used < self.frame)
+            .map(|(key, chunk)| (chunk.last_used, *key))
+            .collect();
+        evictable.sort_unstable_by_key(|&(last_used, _)| last_used);
+        for (_, key) in evictable {
+            if self.resident_bytes + bytes <= budget {
+                break;
+            }
+            if let Some(chunk) = self.chunks.remove(&key) {
+                self.resident_bytes -= chunk.bytes;
+            }
+        }
+        self.resident_bytes + bytes <= budget
+    }
+
+    /// Redirects point drawing into the offscreen target and returns the
+    /// framebuffer to composite into afterwards.
+    fn bind_edl_target(&mut self, size: PhysicalSize<u32>) -> Option<glow::NativeFramebuffer> {
+        if self.edl_target.as_ref().is_none_or(|t| t.size != size) {
+            self.edl_target = None;
+            self.edl_target = EdlTarget::new(self.gl.clone(), size)
+                .inspect_err(|err| log::error!("{err:#}"))
+                .ok();
+        }
+        let target = self.edl_target.as_ref()?;
+        unsafe {
+            let previous = self.gl.get_parameter_framebuffer(glow::FRAMEBUFFER_BINDING);
+            self.gl
+                .bind_framebuffer(glow::FRAMEBUFFER, Some(target.framebuffer));
+            self.gl.clear_color(0.0, 0.0, 0.0, 0.0);
+            self.gl.clear_depth_f32(1.0);
+            self.gl
+                .clear(glow::COLOR_BUFFER_BIT | glow::DEPTH_BUFFER_BIT);
+            previous
+        }
+    }
+
+    /// Shades the offscreen points by how much they stick out of their
+    /// neighbourhood in log depth and writes them, with their depth, into
+    /// the scene framebuffer.
+    fn composite_edl(
+        &self,
+        assets: &AssetManager,
+        camera: &Camera,
+        size: PhysicalSize<u32>,
+        previous_framebuffer: Option<glow::NativeFramebuffer>,
+    ) {
+        let (Some(target), Some(program)) = (
+            self.edl_target.as_ref(),
+            assets.get(&self.edl_shader).and_then(Shader::program),
+        ) else {
+            return;
+        };
+        unsafe {
+            self.gl
+                .bind_framebuffer(glow::FRAMEBUFFER, previous_framebuffer);
+            self.gl.active_texture(glow::TEXTURE0);
+            self.gl.bind_texture(glow::TEXTURE_2D, Some(target.color));
+            self.gl.active_texture(glow::TEXTURE1);
+            self.gl.bind_texture(glow::TEXTURE_2D, Some(target.depth));
+        }
+        program.bind();
+        program.set_i32("u_color", 0);
+        program.set_i32("u_depth", 1);
+        program.set_vec2(
+            "u_texel_size",
+            1.0 / size.width.max(1) as f32,
+            1.0 / size.height.max(1) as f32,
+        );
+        program.set_f32("u_near", camera.near);
+        program.set_f32("u_far", camera.far);
+        program.set_i32(
+            "u_orthographic",
+            matches!(camera.projection, Projection::Orthographic { .. }) as i32,
+        );
+        program.set_f32("u_strength", self.settings.edl_strength);
+        program.set_f32("u_radius", self.settings.edl_radius);
+        unsafe {
+            self.gl.bind_vertex_array(self.empty_vertex_array);
+            self.gl.draw_arrays(glow::TRIANGLES, 0, 3);
+            self.gl.bind_vertex_array(None);
+            self.gl.bind_texture(glow::TEXTURE_2D, None);
+            self.gl.active_texture(glow::TEXTURE0);
+            self.gl.bind_texture(glow::TEXTURE_2D, None);
+        }
+    }
+}
+
+impl Drop for PointCloudRenderer {
+    fn drop(&mut self) {
+        if let Some(vertex_array) = self.empty_vertex_array {
+            unsafe {
+                self.gl.delete_vertex_array(vertex_array);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct SelectedNode {
+    cloud: usize,
+    node: usize,
+    /// Projected bounding sphere radius in pixels.
+    screen_radius: f32,
+}
+
+impl Eq for SelectedNode {}
+
+impl Ord for SelectedNode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.screen_radius.total_cmp(&other.screen_radius)
+    }
+}
+
+impl PartialOrd for SelectedNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Walks all octrees at once, largest projected node first, and returns the
+/// visible nodes that fit into the point budget. Since inner nodes hold a
+/// subsample of their cell, the result is a coarse-to-fine mix that spends
+/// detail where it is visible.
+fn select_nodes(
+    octrees: &[(Mat4, &PointOctree)],
+    projection: &Mat4,
+    view: &Mat4,
+    viewport_height: f32,
+    settings: &PointCloudSettings,
+) -> Vec<SelectedNode> {
+    let view_projection = *projection * *view;
+    let projection_scale = projection.y_axis.y * viewport_height * 0.5;
+    let perspective = projection.w_axis.w == 0.0;
+    let frustums: Vec<Frustum> = octrees
+        .iter()
+        .map(|(model, _)| Frustum::from_matrix(&(view_projection * *model)))
+        .collect();
+
+    let screen_radius = |cloud: usize, node: usize| {
+        let (model, octree) = &octrees[cloud];
+        let bounds = &octree.nodes()[node].bounds;
+        let scale = model
+            .x_axis
+            .xyz()
+            .length()
+            .max(model.y_axis.xyz().length())
+            .max(model.z_axis.xyz().length());
+        let radius = bounds.radius() * scale;
+        if !perspective {
+            return radius * projection_scale;
+        }
+        let center = (*view * *model).transform_point3(bounds.center());
+        let distance = -center.z - radius;
+        if distance <= 0.0 {
+            // The camera is inside the node's bounding sphere.
+            f32::INFINITY
+        } else {
+            radius * projection_scale / distance
+        }
+    };
+
+    let mut heap = BinaryHeap::new();
+    for (cloud, (_, octree)) in octrees.iter().enumerate() {
+        if let Some(root) = octree.root()
+            && frustums[cloud].intersects_aabb(&root.bounds)
+        {
+            heap.push(SelectedNode {
+                cloud,
+                node: 0,
+                screen_radius: screen_radius(cloud, 0),
+            });
+        }
+    }
+
+    let mut selection = Vec::new();
+    let mut points = 0;
+    while let Some(selected) = heap.pop() {
+        let node = &octrees[selected.cloud].1.nodes()[selected.node];
+        if points + node.count as usize > settings.point_budget {
+            break;
+        }
+        points += node.count as usize;
+        selection.push(selected);
+        if selected.screen_radius < settings.min_node_size {
+            continue;
+        }
+        for child in node.children() {
+            if frustums[selected.cloud]
+                .intersects_aabb(&octrees[selected.cloud].1.nodes()[child].bounds)
+            {
+                heap.push(SelectedNode {
+                    cloud: selected.cloud,
+                    node: child,
+                    screen_radius: screen_radius(selected.cloud, child),
+                });
+            }
+        }
+    }
+    selection
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+
+    use super::*;
+
+    fn grid_octree(side: usize) -> PointOctree {
+        let positions: Vec<Vec3> = (0..side * side * side)
+            .map(|i| {
+                Vec3::new(
+                    (i % side) as f32,
+                    (i / side % side) as f32,
+                    (i / (side * side)) as f32,
+                ) / side as f32
+            })
+            .collect();
+        PointOctree::build(&positions).0
+    }
+
+    fn camera_at(z: f32) -> (Mat4, Mat4) {
+        let view = Mat4::look_at_rh(Vec3::new(0.5, 0.5, z), Vec3::splat(0.5), Vec3::Y);
+        let projection = Mat4::perspective_rh_gl(1.0, 1.0, 0.01, 1000.0);
+        (projection, view)
+    }
+
+    #[test]
+    fn selection_refines_up_close_and_respects_the_point_budget() {
+        let octree = grid_octree(64);
+        let octrees = [(Mat4::IDENTITY, &octree)];
+        let settings = PointCloudSettings::new();
+
+        let (projection, view) = camera_at(500.0);
+        let far = select_nodes(&octrees, &projection, &view, 1000.0, &settings);
+        assert_eq!(far.len(), 1, "a distant cloud draws only its root");
+
+        let (projection, view) = camera_at(2.0);
+        let unlimited = PointCloudSettings {
+            min_node_size: 0.0,
+            ..PointCloudSettings::new()
+        };
+        let near = select_nodes(&octrees, &projection, &view, 1000.0, &unlimited);
+        assert!(near.len() > 1);
+        let total: usize = near
+            .iter()
+            .map(|s| octree.nodes()[s.node].count as usize)
+            .sum();
+        assert_eq!(total, 64 * 64 * 64);
+
+        let budget = PointCloudSettings {
+            point_budget: 150_000,
+            ..PointCloudSettings::new()
+        };
+        let limited = select_nodes(&octrees, &projection, &view, 1000.0, &budget);
+        let total: usize = limited
+            .iter()
+            .map(|s| octree.nodes()[s.node].count as usize)
+            .sum();
+        assert!(total <= 150_000);
+        assert_eq!(limited[0].node, 0);
+    }
+
+    #[test]
+    fn clouds_behind_the_camera_are_culled() {
+        let octree = grid_octree(8);
+        let model = Mat4::from_translation(Vec3::new(0.0, 0.0, 10.0));
+        let (projection, view) = camera_at(2.0);
+        let selection = select_nodes(
+            &[(model, &octree)],
+            &projection,
+            &view,
+            1000.0,
+            &PointCloudSettings::new(),
+        );
+        assert!(selection.is_empty());
+    }
+}
@@ -0,0 +1,157 @@
+use std::sync::Arc;
+
+use anyhow::{Context, anyhow};
+use glam::{Mat3, Mat4, Vec3, Vec4};
+use glow::HasContext;
+
+const GLSL_VERSION: &str = "#version 330 core";
+
+/// A linked GL program built from a single GLSL file that holds both stages.
+/// The file is compiled twice, once with `VERTEX` and once with `FRAGMENT`
+/// defined; `#line 1` keeps compiler line numbers matching the file.
+pub struct ShaderProgram {
+    gl: Arc<glow::Context>,
+    program: glow::NativeProgram,
+}
+
+impl ShaderProgram {
+    pub fn compile(gl: Arc<glow::Context>, name: &str, source: &str) -> anyhow::Result<Self> {
+        Self::compile_with_defines(gl, name, source, &[])
+    }
+
+    pub fn compile_with_defines(
+        gl: Arc<glow::Context>,
+        name: &str,
+        source: &str,
+        defines: &[&str],
+    ) -> anyhow::Result<Self> {
+        let defines: String = defines.iter().map(|d| format!("#define {d}\n")).collect();
+        let stage_source =
+            |stage: &str| format!("{GLSL_VERSION}\n#define {stage}\n{defines}#line 1\n{source}");
+
+        unsafe {
+            let vertex = compile_stage(&gl, glow::VERTEX_SHADER, &stage_source("VERTEX"))
+                .with_context(|| format!("{name}: vertex stage"))?;
+            let fragment =
+                match compile_stage(&gl, glow::FRAGMENT_SHADER, &stage_source("FRAGMENT")) {
+                    Ok(fragment) => fragment,
+                    Err(err) => {
+                        gl.delete_shader(vertex);
+                        return Err(err).with_context(|| format!("{name}: fragment stage"));
+                    }
+                };
+
+            let program = gl.create_program().map_err(anyhow::Error::msg)?;
+            gl.attach_shader(program, vertex);
+            gl.attach_shader(program, fragment);
+            gl.link_program(program);
+            gl.detach_shader(program, vertex);
+            gl.detach_shader(program, fragment);
+            gl.delete_shader(vertex);
+            gl.delete_shader(fragment);
+
+            if !gl.get_program_link_status(program) {
+                let log = gl.get_program_info_log(program);
+                gl.delete_program(program);
+                return Err(anyhow!("{name}: link failed:\n{}", log.trim_end()));
+            }
+
+            Ok(Self { gl, program })
+        }
+    }
+
+    pub fn bind(&self) {
+        unsafe {
+            self.gl.use_program(Some(self.program));
+        }
+    }
+
+    fn location(&self, name: &str) -> Option<glow::NativeUniformLocation> {
+        unsafe { self.gl.get_uniform_location(self.program, name) }
+    }
+
+    pub fn set_i32(&self, name: &str, value: i32) {
+        unsafe {
+            self.gl.uniform_1_i32(self.location(name).as_ref(), value);
+        }
+    }
+
+    pub fn set_f32(&self, name: &str, value: f32) {
+        unsafe {
+            self.gl.uniform_1_f32(self.location(name).as_ref(), value);
+        }
+    }
+
+    pub fn set_f32_slice(&self, name: &str, values: &[f32]) {
+        unsafe {
+            self.gl
+                .uniform_1_f32_slice(self.location(name).as_ref(), values);
+        }
+    }
+
+    pub fn set_vec2(&self, name: &str, x: f32, y: f32) {
+        unsafe {
+            self.gl.uniform_2_f32(self.location(name).as_ref(), x, y);
+        }
+    }
+
+    pub fn set_vec3(&self, name: &str, value: Vec3) {
+        unsafe {
+            self.gl
+                .uniform_3_f32_slice(self.location(name).as_ref(), &value.to_array());
+        }
+    }
+
+    pub fn set_vec4(&self, name: &str, value: Vec4) {
+        unsafe {
+            self.gl
+                .uniform_4_f32_slice(self.location(name).as_ref(), &value.to_array());
+        }
+    }
+
+    pub fn set_mat3(&self, name: &str, value: &Mat3) {
+        unsafe {
+            self.gl.uniform_matrix_3_f32_slice(
+                self.location(name).as_ref(),
+                false,
+                &value.to_cols_array(),
+            );
+        }
+    }
+
+    pub fn set_mat4(&self, name: &str, value: &Mat4) {
+        unsafe {
+            self.gl.uniform_matrix_4_f32_slice(
+                self.location(name).as_ref(),
+                false,
+                &value.to_cols_array(),
+            );
+        }
+    }
+}
+
+impl Drop for ShaderProgram {
+    fn drop(&mut self) {
+        unsafe {
+            self.gl.delete_program(self.program);
+        }
+    }
+}
+
+unsafe fn compile_stage(
+    gl: &glow::Context,
+    stage: u32,
+    source: &str,
+) -> anyhow::Result<glow::NativeShader> {
+    unsafe {
+        let shader = gl.create_shader(stage).map_err(anyhow::Error::msg)?;
+        gl.shader_source(shader, source);
+        gl.compile_shader(shader);
+        if !gl.get_shader_compile_status(shader) {
+            let log = gl.get_shader_info_log(shader);
+            gl.delete_shader(shader);
+            return Err(anyhow!("compile failed:\n{}", log.trim_end()));
+        }
+        Ok(shader)
+    }
+}
@@ -0,0 +1,53 @@
+// Viewport background: a vertical gradient between two display colors, or
+// the environment map seen through each pixel, gamma-encoded like the mesh
+// pass.
+
+#ifdef VERTEX
+uniform mat4 u_inverse_view_projection;
+
+out vec2 v_uv;
+out vec3 v_direction;
+
+void main() {
+    // One triangle covering the viewport.
+    vec2 corner = vec2(float((gl_VertexID << 1) & 2), float(gl_VertexID & 2));
+    vec2 ndc = corner * 2.0 - 1.0;
+    vec4 near = u_inverse_view_projection * vec4(ndc, -1.0, 1.0);
+    vec4 far = u_inverse_view_projection * vec4(ndc, 1.0, 1.0);
+    v_uv = corner;
+    v_direction = far.xyz / far.w - near.xyz / near.w;
+    gl_Position = vec4(ndc, 1.0, 1.0);
+}
+#endif
+
+#ifdef FRAGMENT
+#define PI 3.14159265
+
+in vec2 v_uv;
+in vec3 v_direction;
+
+// 0 gradient, 1 environment map.
+uniform int u_mode;
+uniform vec3 u_gradient_top;
+uniform vec3 u_gradient_bottom;
+uniform sampler2D u_environment;
+uniform mat3 u_map_from_world;
+uniform float u_intensity;
+
+out vec4 frag_color;
+
+vec2 equirect_uv(vec3 d) {
+    return vec2(atan(d.x, -d.z) / (2.0 * PI) + 0.5, acos(clamp(d.y, -1.0, 1.0)) / PI);
+}
+
+void main() {
+    if (u_mode == 0) {
+        frag_color = vec4(mix(u_gradient_bottom, u_gradient_top, v_uv.y), 1.0);
+        return;
+    }
+    vec3 direction = normalize(u_map_from_world * v_direction);
+    // Level 0 explicitly: derivatives jump at the seam where u wraps.
+    vec3 radiance = textureLod(u_environment, equirect_uv(direction), 0.0).rgb * u_intensity;
+    frag_color = vec4(pow(radiance, vec3(1.0 / 2.2)), 1.0);
+}
+#endif
@@ -0,0 +1,63 @@
+// Split-sum lookup table for image-based specular: scale and bias applied
+// to F0, indexed by n·v along x and roughness along y.
+
+#ifdef VERTEX
+out vec2 v_uv;
+
+void main() {
+    vec2 corner = vec2(float((gl_VertexID << 1) & 2), float(gl_VertexID & 2));
+    v_uv = corner;
+    gl_Position = vec4(corner * 2.0 - 1.0, 0.0, 1.0);
+}
+#endif
+
+#ifdef FRAGMENT
+#define PI 3.14159265
+#define SAMPLE_COUNT 512u
+
+in vec2 v_uv;
+
+out vec4 frag_color;
+
+vec2 hammersley(uint i) {
+    uint bits = (i << 16u) | (i >> 16u);
+    bits = ((bits & 0x55555555u) << 1u) | ((bits & 0xAAAAAAAAu) >> 1u);
+    bits = ((bits & 0x33333333u) << 2u) | ((bits & 0xCCCCCCCCu) >> 2u);
+    bits = ((bits & 0x0F0F0F0Fu) << 4u) | ((bits & 0xF0F0F0F0u) >> 4u);
+    bits = ((bits & 0x00FF00FFu) << 8u) | ((bits & 0xFF00FF00u) >> 8u);
+    return vec2(float(i) / float(SAMPLE_COUNT), float(bits) * 2.3283064365386963e-10);
+}
+
+float geometry_schlick(float n_dot_x, float k) {
+    return n_dot_x / (n_dot_x * (1.0 - k) + k);
+}
+
+void main() {
+    float n_dot_v = max(v_uv.x, 1e-3);
+    float roughness = v_uv.y;
+    float alpha = roughness * roughness;
+    // Smith-Schlick with the image-based lighting remapping of k.
+    float k = alpha / 2.0;
+    vec3 v = vec3(sqrt(1.0 - n_dot_v * n_dot_v), 0.0, n_dot_v);
+
+    vec2 sum = vec2(0.0);
+    for (uint i = 0u; i < SAMPLE_COUNT; ++i) {
+        vec2 xi = hammersley(i);
+        float phi = 2.0 * PI * xi.x;
+        float cos_theta = sqrt((1.0 - xi.y) / (1.0 + (alpha * alpha - 1.0) * xi.y));
+        float sin_theta = sqrt(1.0 - cos_theta * cos_theta);
+        vec3 h = vec3(cos(phi) * sin_theta, sin(phi) * sin_theta, cos_theta);
+        vec3 l = normalize(2.0 * dot(v, h) * h - v);
+        float n_dot_l = max(l.z, 0.0);
+        float n_dot_h = max(h.z, 0.0);
+        float v_dot_h = max(dot(v, h), 0.0);
+        if (n_dot_l > 0.0) {
+            float g = geometry_schlick(n_dot_v, k) * geometry_schlick(n_dot_l, k);
+            float visibility = g * v_dot_h / (n_dot_h * n_dot_v);
+            float fresnel = pow(1.0 - v_dot_h, 5.0);
+            sum += vec2((1.0 - fresnel) * visibility, fresnel * visibility);
+        }
+    }
+    frag_color = vec4(sum / float(SAMPLE_COUNT), 0.0, 1.0);
+}
+#endif
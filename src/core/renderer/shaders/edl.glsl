@@ -0,0 +1,65 @@
+// Eye-dome lighting: darkens points that lie behind their screen-space
+// neighbours in log depth, which outlines silhouettes and brings out
+// surface relief without normals. Composites the offscreen point pass into
+// the scene, carrying its depth along so meshes still occlude correctly.
+
+#ifdef VERTEX
+out vec2 v_uv;
+
+void main() {
+    // One triangle covering the viewport.
+    vec2 corner = vec2(float((gl_VertexID << 1) & 2), float(gl_VertexID & 2));
+    v_uv = corner;
+    gl_Position = vec4(corner * 2.0 - 1.0, 0.0, 1.0);
+}
+#endif
+
+#ifdef FRAGMENT
+in vec2 v_uv;
+
+uniform sampler2D u_color;
+uniform sampler2D u_depth;
+uniform vec2 u_texel_size;
+uniform float u_near;
+uniform float u_far;
+uniform bool u_orthographic;
+uniform float u_strength;
+uniform float u_radius;
+
+out vec4 frag_color;
+
+float log_depth(float depth) {
+    float z = depth * 2.0 - 1.0;
+    float linear = u_orthographic
+        ? u_near + depth * (u_far - u_near)
+        : 2.0 * u_near * u_far / (u_far + u_near - z * (u_far - u_near));
+    return log2(max(linear, 1e-6));
+}
+
+void main() {
+    float depth = texture(u_depth, v_uv).r;
+    if (depth >= 1.0) {
+        discard;
+    }
+    float center = log_depth(depth);
+
+    const vec2 directions[8] = vec2[](
+        vec2(1.0, 0.0), vec2(-1.0, 0.0), vec2(0.0, 1.0), vec2(0.0, -1.0),
+        vec2(0.7071, 0.7071), vec2(-0.7071, 0.7071),
+        vec2(0.7071, -0.7071), vec2(-0.7071, -0.7071)
+    );
+    float response = 0.0;
+    for (int i = 0; i < 8; i++) {
+        vec2 uv = v_uv + directions[i] * u_radius * u_texel_size;
+        float neighbour_depth = texture(u_depth, uv).r;
+        // Empty pixels count as the far plane so silhouettes get outlined.
+        float neighbour = neighbour_depth >= 1.0 ? log2(u_far) : log_depth(neighbour_depth);
+        response += max(0.0, center - neighbour);
+    }
+    response /= 8.0;
+
+    float shade = exp(-response * 300.0 * u_strength);
+    frag_color = vec4(texture(u_color, v_uv).rgb * shade, 1.0);
+    gl_FragDepth = depth;
+}
+#endif
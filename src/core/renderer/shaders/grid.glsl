@@ -0,0 +1,96 @@
+// Infinite ground grid. Each pixel's view ray is intersected with the
+// plane through the origin perpendicular to the up axis; lines are
+// anti-aliased with screen-space derivatives and the hit's depth is
+// written so scene geometry occludes the grid. Output is gamma-encoded
+// like the mesh pass.
+
+#ifdef VERTEX
+uniform mat4 u_inverse_view_projection;
+
+out vec3 v_near;
+out vec3 v_far;
+
+vec3 unproject(vec2 ndc, float z) {
+    vec4 p = u_inverse_view_projection * vec4(ndc, z, 1.0);
+    return p.xyz / p.w;
+}
+
+void main() {
+    // One triangle covering the viewport.
+    vec2 corner = vec2(float((gl_VertexID << 1) & 2), float(gl_VertexID & 2));
+    vec2 ndc = corner * 2.0 - 1.0;
+    v_near = unproject(ndc, -1.0);
+    v_far = unproject(ndc, 1.0);
+    gl_Position = vec4(ndc, 0.0, 1.0);
+}
+#endif
+
+#ifdef FRAGMENT
+in vec3 v_near;
+in vec3 v_far;
+
+uniform mat4 u_view_projection;
+uniform vec3 u_camera_position;
+uniform bool u_z_up;
+// Cell size of the fine lines; every tenth line is a major line.
+uniform float u_spacing;
+// 0 shows the fine lines fully, 1 hides them as the view zooms out.
+uniform float u_fade;
+uniform float u_fade_distance;
+
+out vec4 frag_color;
+
+const vec3 LINE_COLOR = vec3(0.55);
+const vec3 X_AXIS_COLOR = vec3(0.9, 0.2, 0.2);
+const vec3 Y_AXIS_COLOR = vec3(0.3, 0.8, 0.2);
+const vec3 Z_AXIS_COLOR = vec3(0.2, 0.4, 0.95);
+
+// Coverage of lines one pixel wide every `spacing` units.
+float lines(vec2 coord, float spacing) {
+    vec2 c = coord / spacing;
+    vec2 width = fwidth(c);
+    vec2 distance = abs(fract(c - 0.5) - 0.5) / width;
+    return 1.0 - min(min(distance.x, distance.y), 1.0);
+}
+
+// Coverage of a line along `value == 0`, `pixels` wide.
+float axis(float value, float pixels) {
+    return 1.0 - min(abs(value) / (fwidth(value) * pixels), 1.0);
+}
+
+void main() {
+    float up_near = u_z_up ? v_near.z : v_near.y;
+    float up_far = u_z_up ? v_far.z : v_far.y;
+    float t = -up_near / (up_far - up_near);
+    if (!(t > 0.0 && t <= 1.0)) {
+        discard;
+    }
+    vec3 position = mix(v_near, v_far, t);
+    vec4 clip = u_view_projection * vec4(position, 1.0);
+    gl_FragDepth = clip.z / clip.w * 0.5 + 0.5;
+
+    // Plane coordinates: world X and the horizontal axis pointing away
+    // from the front view.
+    vec2 coord = u_z_up ? position.xy : position.xz;
+    float minor = lines(coord, u_spacing) * (1.0 - u_fade);
+    float major = lines(coord, u_spacing * 10.0);
+    vec4 color = vec4(LINE_COLOR, max(minor * 0.35, major * 0.7));
+
+    // The X axis runs where the other plane coordinate is zero.
+    float x_axis = axis(coord.y, 2.0);
+    float other_axis = axis(coord.x, 2.0);
+    color = mix(color, vec4(X_AXIS_COLOR, 1.0), x_axis);
+    color = mix(color, vec4(u_z_up ? Y_AXIS_COLOR : Z_AXIS_COLOR, 1.0), other_axis);
+
+    // Fade out towards the horizon and at grazing angles, where the lines
+    // would alias into noise.
+    vec3 to_camera = u_camera_position - position;
+    float grazing = abs(normalize(to_camera)[u_z_up ? 2 : 1]);
+    color.a *= 1.0 - smoothstep(0.5, 1.0, length(to_camera) / u_fade_distance);
+    color.a *= smoothstep(0.0, 0.15, grazing);
+    if (color.a <= 0.001) {
+        discard;
+    }
+    frag_color = vec4(pow(color.rgb, vec3(1.0 / 2.2)), color.a);
+}
+#endif
@@ -0,0 +1,26 @@
+// Debug overlay lines with a linear color per vertex. Output is
+// gamma-encoded like the mesh pass.
+
+#ifdef VERTEX
+layout(location = 0) in vec3 a_position;
+layout(location = 4) in vec4 a_color;
+
+uniform mat4 u_view_projection;
+
+out vec4 v_color;
+
+void main() {
+    gl_Position = u_view_projection * vec4(a_position, 1.0);
+    v_color = a_color;
+}
+#endif
+
+#ifdef FRAGMENT
+in vec4 v_color;
+
+out vec4 frag_color;
+
+void main() {
+    frag_color = vec4(pow(v_color.rgb, vec3(1.0 / 2.2)), v_color.a);
+}
+#endif
@@ -0,0 +1,262 @@
+// Instanced forward pass for scene meshes: one directional light plus a
+// flat ambient term or image-based lighting from the environment map, base
+// color and emissive from the material, or one of the debug views selected
+// by `u_view_mode`. Output is
+// gamma-encoded by hand because the render target is not an sRGB
+// framebuffer.
+
+#ifdef VERTEX
+layout(location = 0) in vec3 a_position;
+layout(location = 1) in vec3 a_normal;
+layout(location = 2) in vec2 a_tex_coord;
+layout(location = 3) in vec4 a_tangent;
+layout(location = 4) in vec4 a_color;
+// Per instance.
+layout(location = 6) in mat4 a_model;
+layout(location = 10) in uvec4 a_joints;
+layout(location = 11) in vec4 a_weights;
+
+uniform mat4 u_view_projection;
+uniform bool u_skinned;
+// One row of four texels (matrix columns) per joint.
+uniform sampler2D u_joint_matrices;
+// Position and normal delta per vertex, target after target, wrapped onto
+// rows of MORPH_TEXTURE_WIDTH texels.
+#define MORPH_TEXTURE_WIDTH 2048
+uniform sampler2D u_morph_targets;
+uniform int u_morph_target_count;
+uniform int u_vertex_count;
+uniform float u_morph_weights[64];
+
+out vec3 v_world_position;
+out vec3 v_normal;
+out vec2 v_tex_coord;
+out vec4 v_color;
+out vec3 v_tangent;
+// How much the model matrix scales areas, for the triangle density view.
+flat out float v_area_scale;
+
+vec3 morph_delta(int texel) {
+    ivec2 coord = ivec2(texel % MORPH_TEXTURE_WIDTH, texel / MORPH_TEXTURE_WIDTH);
+    return texelFetch(u_morph_targets, coord, 0).xyz;
+}
+
+mat4 joint_matrix(uint joint) {
+    int row = int(joint);
+    return mat4(
+        texelFetch(u_joint_matrices, ivec2(0, row), 0),
+        texelFetch(u_joint_matrices, ivec2(1, row), 0),
+        texelFetch(u_joint_matrices, ivec2(2, row), 0),
+        texelFetch(u_joint_matrices, ivec2(3, row), 0)
+    );
+}
+
+void main() {
+    vec3 position = a_position;
+    vec3 normal = a_normal;
+    for (int i = 0; i < u_morph_target_count; ++i) {
+        float weight = u_morph_weights[i];
+        if (weight != 0.0) {
+            int texel = (i * u_vertex_count + gl_VertexID) * 2;
+            position += weight * morph_delta(texel);
+            normal += weight * morph_delta(texel + 1);
+        }
+    }
+
+    mat4 model = a_model;
+    if (u_skinned) {
+        model = model * (a_weights.x * joint_matrix(a_joints.x)
+            + a_weights.y * joint_matrix(a_joints.y)
+            + a_weights.z * joint_matrix(a_joints.z)
+            + a_weights.w * joint_matrix(a_joints.w));
+    }
+    vec4 world = model * vec4(position, 1.0);
+    v_world_position = world.xyz;
+    v_normal = transpose(inverse(mat3(model))) * normal;
+    v_tex_coord = a_tex_coord;
+    v_color = a_color;
+    v_tangent = mat3(model) * a_tangent.xyz;
+    v_area_scale = pow(abs(determinant(mat3(model))), 2.0 / 3.0);
+    gl_Position = u_view_projection * world;
+}
+#endif
+
+#ifdef FRAGMENT
+in vec3 v_world_position;
+in vec3 v_normal;
+in vec2 v_tex_coord;
+in vec4 v_color;
+in vec3 v_tangent;
+flat in float v_area_scale;
+
+uniform vec4 u_base_color;
+uniform float u_metallic;
+uniform float u_roughness;
+uniform vec3 u_emissive;
+uniform sampler2D u_base_color_texture;
+uniform float u_alpha_cutoff;
+uniform vec3 u_light_direction;
+uniform vec3 u_camera_position;
+// The `ViewMode` discriminant, or 11 for the lines of the wireframe view.
+uniform int u_view_mode;
+uniform int u_lod_level;
+// Near and far view distance of the visible geometry, for the depth view.
+uniform vec2 u_depth_range;
+uniform vec3 u_material_color;
+// Average object-space triangle area of the mesh being drawn.
+uniform float u_triangle_area;
+// Image-based lighting: irradiance as spherical harmonics, the prefiltered
+// specular cubemap with one roughness step per mip and the split-sum
+// lookup table, all in the map's frame.
+uniform bool u_ibl;
+uniform vec3 u_irradiance[9];
+uniform samplerCube u_specular_map;
+uniform sampler2D u_brdf_lut;
+uniform mat3 u_map_from_world;
+uniform float u_environment_intensity;
+uniform float u_specular_max_lod;
+
+out vec4 frag_color;
+
+#define VIEW_UNLIT 1
+#define VIEW_NORMALS 3
+#define VIEW_UV_CHECKER 4
+#define VIEW_TANGENTS 5
+#define VIEW_DEPTH 6
+#define VIEW_OVERDRAW 7
+#define VIEW_MATERIAL_ID 8
+#define VIEW_TRIANGLE_DENSITY 9
+#define VIEW_LOD_LEVEL 10
+#define VIEW_WIREFRAME_LINES 11
+
+const vec3 LOD_COLORS[5] = vec3[](
+    vec3(0.2, 0.8, 0.3),
+    vec3(0.9, 0.85, 0.2),
+    vec3(0.95, 0.55, 0.15),
+    vec3(0.9, 0.2, 0.15),
+    vec3(0.8, 0.2, 0.8)
+);
+
+vec3 irradiance(vec3 n) {
+    return u_irradiance[0] * 0.282095
+        + u_irradiance[1] * 0.488603 * n.y
+        + u_irradiance[2] * 0.488603 * n.z
+        + u_irradiance[3] * 0.488603 * n.x
+        + u_irradiance[4] * 1.092548 * n.x * n.y
+        + u_irradiance[5] * 1.092548 * n.y * n.z
+        + u_irradiance[6] * 0.315392 * (3.0 * n.z * n.z - 1.0)
+        + u_irradiance[7] * 1.092548 * n.x * n.z
+        + u_irradiance[8] * 0.546274 * (n.x * n.x - n.y * n.y);
+}
+
+// Diffuse and specular light from the environment with the split-sum
+// approximation.
+vec3 image_based_light(vec3 base, vec3 n, vec3 to_camera) {
+    float roughness = clamp(u_roughness, 0.04, 1.0);
+    float metallic = clamp(u_metallic, 0.0, 1.0);
+    vec3 f0 = mix(vec3(0.04), base, metallic);
+    float n_dot_v = max(dot(n, to_camera), 1e-4);
+    vec2 brdf = texture(u_brdf_lut, vec2(n_dot_v, roughness)).rg;
+    vec3 reflected = u_map_from_world * reflect(-to_camera, n);
+    vec3 specular = textureLod(u_specular_map, reflected, roughness * u_specular_max_lod).rgb;
+    vec3 diffuse = max(irradiance(u_map_from_world * n), vec3(0.0)) * base * (1.0 - metallic);
+    return (diffuse + specular * (f0 * brdf.x + brdf.y)) * u_environment_intensity;
+}
+
+vec4 encode(vec3 color, float alpha) {
+    return vec4(pow(color, vec3(1.0 / 2.2)), alpha);
+}
+
+const vec3 DENSITY_COLORS[4] = vec3[](
+    vec3(0.9, 0.1, 0.1),
+    vec3(0.95, 0.85, 0.1),
+    vec3(0.2, 0.8, 0.2),
+    vec3(0.15, 0.3, 0.9)
+);
+
+// Red for triangles covering about a pixel, through yellow and green to
+// blue for triangles covering a thousand or more.
+vec3 density_color(float pixels_per_triangle) {
+    float x = clamp(log2(max(pixels_per_triangle, 1.0)) / 10.0, 0.0, 1.0) * 3.0;
+    int i = min(int(x), 2);
+    return mix(DENSITY_COLORS[i], DENSITY_COLORS[i + 1], x - float(i));
+}
+
+void main() {
+    vec4 base = u_base_color * v_color * texture(u_base_color_texture, v_tex_coord);
+    if (base.a < u_alpha_cutoff) {
+        discard;
+    }
+    if (u_view_mode == VIEW_WIREFRAME_LINES) {
+        frag_color = encode(vec3(0.05), 1.0);
+        return;
+    }
+    if (u_view_mode == VIEW_OVERDRAW) {
+        // Added up with additive blending, already in output space.
+        frag_color = vec4(0.12, 0.05, 0.02, 1.0);
+        return;
+    }
+    if (u_view_mode == VIEW_UNLIT) {
+        frag_color = encode(base.rgb, base.a);
+        return;
+    }
+    if (u_view_mode == VIEW_DEPTH) {
+        float distance = length(u_camera_position - v_world_position);
+        float t = clamp((distance - u_depth_range.x) / max(u_depth_range.y - u_depth_range.x, 1e-6), 0.0, 1.0);
+        frag_color = encode(vec3(1.0 - t), 1.0);
+        return;
+    }
+    if (u_view_mode == VIEW_UV_CHECKER) {
+        vec2 cell = floor(v_tex_coord * 8.0);
+        float checker = mod(cell.x + cell.y, 2.0) == 0.0 ? 0.9 : 0.25;
+        vec2 uv = fract(v_tex_coord);
+        frag_color = encode(checker * mix(vec3(1.0), vec3(uv, 1.0 - uv.x), 0.5), 1.0);
+        return;
+    }
+    if (u_view_mode == VIEW_TANGENTS) {
+        vec3 t = v_tangent;
+        frag_color = encode(dot(t, t) > 1e-8 ? normalize(t) * 0.5 + 0.5 : vec3(0.0), 1.0);
+        return;
+    }
+    if (u_view_mode == VIEW_TRIANGLE_DENSITY) {
+        vec3 dx = dFdx(v_world_position);
+        vec3 dy = dFdy(v_world_position);
+        float pixel_area = max(length(dx) * length(dy), 1e-12);
+        float triangle_area = u_triangle_area * v_area_scale;
+        frag_color = encode(density_color(triangle_area / pixel_area), 1.0);
+        return;
+    }
+
+    vec3 emissive = u_emissive;
+    if (u_view_mode == VIEW_LOD_LEVEL) {
+        base.rgb = LOD_COLORS[min(u_lod_level, 4)];
+        emissive = vec3(0.0);
+    } else if (u_view_mode == VIEW_MATERIAL_ID) {
+        base.rgb = u_material_color;
+        emissive = vec3(0.0);
+    }
+
+    // Meshes without normals get flat shading from screen-space derivatives,
+    // which always face the camera.
+    vec3 n = v_normal;
+    if (dot(n, n) < 0.25) {
+        n = cross(dFdx(v_world_position), dFdy(v_world_position));
+    } else if (!gl_FrontFacing) {
+        n = -n;
+    }
+    n = normalize(n);
+    if (u_view_mode == VIEW_NORMALS) {
+        frag_color = encode(n * 0.5 + 0.5, 1.0);
+        return;
+    }
+    vec3 to_camera = normalize(u_camera_position - v_world_position);
+
+    vec3 l = normalize(-u_light_direction);
+    float diffuse = max(dot(n, l), 0.0);
+    float specular = pow(max(dot(n, normalize(l + to_camera)), 0.0), 32.0) * 0.2;
+    vec3 ambient = u_ibl ? image_based_light(base.rgb, n, to_camera) : base.rgb * 0.15;
+    vec3 color = ambient + base.rgb * 0.85 * diffuse + vec3(specular) + emissive;
+
+    frag_color = encode(color, base.a);
+}
+#endif
@@ -0,0 +1,48 @@
+// Point cloud splats. Sizes are either fixed in pixels or given in scene
+// units and scaled by the projection; round splats discard the corners of
+// the point sprite. Output is gamma-encoded like the mesh pass.
+
+#ifdef VERTEX
+layout(location = 0) in vec3 a_position;
+layout(location = 4) in vec4 a_color;
+layout(location = 5) in float a_intensity;
+
+uniform mat4 u_model;
+uniform mat4 u_view_projection;
+uniform float u_point_size;
+uniform bool u_world_space;
+// Pixels per scene unit at distance 1: projection[1][1] * viewport height / 2.
+uniform float u_projection_scale;
+// 0: per-point color, 1: intensity as grayscale.
+uniform int u_color_mode;
+
+out vec3 v_color;
+
+void main() {
+    gl_Position = u_view_projection * u_model * vec4(a_position, 1.0);
+    if (u_world_space) {
+        gl_PointSize = clamp(u_point_size * u_projection_scale / gl_Position.w, 1.0, 64.0);
+    } else {
+        gl_PointSize = u_point_size;
+    }
+    v_color = u_color_mode == 1 ? vec3(a_intensity) : a_color.rgb;
+}
+#endif
+
+#ifdef FRAGMENT
+in vec3 v_color;
+
+uniform bool u_round;
+
+out vec4 frag_color;
+
+void main() {
+    if (u_round) {
+        vec2 offset = gl_PointCoord * 2.0 - 1.0;
+        if (dot(offset, offset) > 1.0) {
+            discard;
+        }
+    }
+    frag_color = vec4(pow(v_color, vec3(1.0 / 2.2)), 1.0);
+}
+#endif
@@ -0,0 +1,94 @@
+// One face of one mip level of the prefiltered specular cubemap: radiance
+// of the equirectangular map convolved with the GGX lobe of `u_roughness`,
+// importance sampled, reading coarser source mips for low-probability
+// samples to keep the result free of fireflies.
+
+#ifdef VERTEX
+out vec2 v_uv;
+
+void main() {
+    vec2 corner = vec2(float((gl_VertexID << 1) & 2), float(gl_VertexID & 2));
+    v_uv = corner;
+    gl_Position = vec4(corner * 2.0 - 1.0, 0.0, 1.0);
+}
+#endif
+
+#ifdef FRAGMENT
+#define PI 3.14159265
+#define SAMPLE_COUNT 256u
+
+in vec2 v_uv;
+
+uniform sampler2D u_environment;
+uniform vec2 u_source_size;
+uniform float u_roughness;
+// GL_TEXTURE_CUBE_MAP_POSITIVE_X + u_face is being rendered.
+uniform int u_face;
+
+out vec4 frag_color;
+
+vec3 face_direction(int face, vec2 uv) {
+    float s = uv.x * 2.0 - 1.0;
+    float t = uv.y * 2.0 - 1.0;
+    if (face == 0) return vec3(1.0, -t, -s);
+    if (face == 1) return vec3(-1.0, -t, s);
+    if (face == 2) return vec3(s, 1.0, t);
+    if (face == 3) return vec3(s, -1.0, -t);
+    if (face == 4) return vec3(s, -t, 1.0);
+    return vec3(-s, -t, -1.0);
+}
+
+vec2 equirect_uv(vec3 d) {
+    return vec2(atan(d.x, -d.z) / (2.0 * PI) + 0.5, acos(clamp(d.y, -1.0, 1.0)) / PI);
+}
+
+vec2 hammersley(uint i) {
+    uint bits = (i << 16u) | (i >> 16u);
+    bits = ((bits & 0x55555555u) << 1u) | ((bits & 0xAAAAAAAAu) >> 1u);
+    bits = ((bits & 0x33333333u) << 2u) | ((bits & 0xCCCCCCCCu) >> 2u);
+    bits = ((bits & 0x0F0F0F0Fu) << 4u) | ((bits & 0xF0F0F0F0u) >> 4u);
+    bits = ((bits & 0x00FF00FFu) << 8u) | ((bits & 0xFF00FF00u) >> 8u);
+    return vec2(float(i) / float(SAMPLE_COUNT), float(bits) * 2.3283064365386963e-10);
+}
+
+vec3 importance_sample_ggx(vec2 xi, vec3 n, float alpha) {
+    float phi = 2.0 * PI * xi.x;
+    float cos_theta = sqrt((1.0 - xi.y) / (1.0 + (alpha * alpha - 1.0) * xi.y));
+    float sin_theta = sqrt(1.0 - cos_theta * cos_theta);
+    vec3 h = vec3(cos(phi) * sin_theta, sin(phi) * sin_theta, cos_theta);
+    vec3 up = abs(n.z) < 0.999 ? vec3(0.0, 0.0, 1.0) : vec3(1.0, 0.0, 0.0);
+    vec3 tangent = normalize(cross(up, n));
+    vec3 bitangent = cross(n, tangent);
+    return normalize(tangent * h.x + bitangent * h.y + n * h.z);
+}
+
+void main() {
+    vec3 n = normalize(face_direction(u_face, v_uv));
+    if (u_roughness == 0.0) {
+        frag_color = vec4(textureLod(u_environment, equirect_uv(n), 0.0).rgb, 1.0);
+        return;
+    }
+    float alpha = u_roughness * u_roughness;
+    float texel_solid_angle = 4.0 * PI / (u_source_size.x * u_source_size.y);
+    vec3 sum = vec3(0.0);
+    float weight = 0.0;
+    for (uint i = 0u; i < SAMPLE_COUNT; ++i) {
+        vec3 h = importance_sample_ggx(hammersley(i), n, alpha);
+        vec3 l = normalize(2.0 * dot(n, h) * h - n);
+        float n_dot_l = dot(n, l);
+        if (n_dot_l > 0.0) {
+            // With the view along the normal, the pdf of l is D / 4.
+            float n_dot_h = max(dot(n, h), 0.0);
+            float a2 = alpha * alpha;
+            float denominator = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+            float d = a2 / (PI * denominator * denominator);
+            float pdf = d / 4.0 + 1e-4;
+            float sample_solid_angle = 1.0 / (float(SAMPLE_COUNT) * pdf);
+            float lod = max(0.5 * log2(sample_solid_angle / texel_solid_angle) + 1.0, 0.0);
+            sum += textureLod(u_environment, equirect_uv(l), lod).rgb * n_dot_l;
+            weight += n_dot_l;
+        }
+    }
+    frag_color = vec4(sum / max(weight, 1e-4), 1.0);
+}
+#endif
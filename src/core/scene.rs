@@ -1,20 +1,794 @@
+pub mod bvh;
+pub mod document;
+
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use glam::{Mat4, Vec3};
+
+use crate::core::animation::{Animator, Skin};
+use crate::core::asset_manager::{AssetManager, Handle, Material, Mesh, Model, PointCloud};
+use crate::core::bounds::{Aabb, Frustum, Ray, Sphere};
+use crate::core::time::Time;
+use crate::core::transform::Transform;
+
+pub use bvh::Bvh;
+pub use document::SceneDocument;
+
+/// Generational index of a node. Ids of removed nodes never alias new ones.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct NodeId {
+    index: u32,
+    generation: u32,
+}
+
+pub struct Node {
+    pub name: String,
+    pub transform: Transform,
+    pub visible: bool,
+    pub mesh: Option<Handle<Mesh>>,
+    pub material: Option<Handle<Material>>,
+    pub point_cloud: Option<Handle<PointCloud>>,
+    pub skin: Option<Skin>,
+    /// Morph target weights; empty uses the mesh defaults.
+    pub morph_weights: Vec<f32>,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    world: Mat4,
+    joint_matrices: Vec<Mat4>,
+    local_bounds: Aabb,
+    world_bounds: Aabb,
+    subtree_bounds: Aabb,
+}
+
+impl Node {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            transform: Transform::IDENTITY,
+            visible: true,
+            mesh: None,
+            material: None,
+            point_cloud: None,
+            skin: None,
+            morph_weights: Vec::new(),
+            parent: None,
+            children: Vec::new(),
+            world: Mat4::IDENTITY,
+            joint_matrices: Vec::new(),
+            local_bounds: Aabb::EMPTY,
+            world_bounds: Aabb::EMPTY,
+            subtree_bounds: Aabb::EMPTY,
+        }
+    }
+
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+
+    /// Local-to-world matrix as of the last `Scene::update`.
+    pub fn world_matrix(&self) -> Mat4 {
+        self.world
+    }
+
+    /// Per-joint skinning matrices relative to this node, as of the last
+    /// `Scene::update`; empty for nodes without a skin.
+    pub fn joint_matrices(&self) -> &[Mat4] {
+        &self.joint_matrices
+    }
+
+    /// Bounds of the node's own mesh or point cloud in object space; empty
+    /// for nodes without geometry or whose geometry is not loaded.
+    pub fn local_bounds(&self) -> Aabb {
+        self.local_bounds
+    }
+
+    /// World-space bounds of the node's own geometry.
+    pub fn world_bounds(&self) -> Aabb {
+        self.world_bounds
+    }
+
+    pub fn bounding_sphere(&self) -> Sphere {
+        self.local_bounds.bounding_sphere().transformed(&self.world)
+    }
+
+    /// World-space bounds of the node and all of its descendants.
+    pub fn subtree_bounds(&self) -> Aabb {
+        self.subtree_bounds
+    }
+}
+
+/// Result of `Scene::cull`.
+#[derive(Default)]
+pub struct CullResult {
+    pub visible: Vec<NodeId>,
+    /// Visible-flagged objects outside the frustum.
+    pub culled: usize,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct RayHit {
+    pub node: NodeId,
+    /// Distance along the ray, in units of its direction's length.
+    pub distance: f32,
+    pub point: Vec3,
+}
+
+struct Slot {
+    generation: u32,
+    node: Option<Node>,
+}
+
+/// Nodes taken out by `Scene::take_subtree`, in parent-before-child order,
+/// along with the animators that only drove them. Dropping a subtree that
+/// was not restored hands its reserved slots back to the scene.
+pub struct Subtree {
+    parent: Option<NodeId>,
+    /// Index among the parent's children or the roots.
+    position: usize,
+    nodes: Vec<(NodeId, Node)>,
+    animators: Vec<Animator>,
+    release_tx: Sender<u32>,
+}
+
+impl Subtree {
+    pub fn root(&self) -> NodeId {
+        self.nodes[0].0
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = &Node> {
+        self.nodes.iter().map(|(_, node)| node)
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+impl Drop for Subtree {
+    fn drop(&mut self) {
+        for (id, _) in &self.nodes {
+            // The scene may be gone already, and its slots with it.
+            let _ = self.release_tx.send(id.index);
+        }
+    }
+}
+
 pub struct Scene {
-    // fields go here
+    slots: Vec<Slot>,
+    free: Vec<u32>,
+    /// Vacant slots held for taken subtrees.
+    reserved: usize,
+    release_tx: Sender<u32>,
+    /// Slots of dropped subtrees, freed by `reclaim_slots`.
+    release_rx: Receiver<u32>,
+    roots: Vec<NodeId>,
+    bvh: Bvh,
+    animators: Vec<Animator>,
+    /// Models the nodes were spawned from, kept loaded while the scene
+    /// exists: saving traces node assets back to their model files, and
+    /// hot reload only watches loaded models.
+    models: Vec<Handle<Model>>,
 }
 
 impl Scene {
     pub fn new() -> Self {
-        // initialization code goes here
+        let (release_tx, release_rx) = mpsc::channel();
         Self {
-            // initialize fields
+            slots: Vec::new(),
+            free: Vec::new(),
+            reserved: 0,
+            release_tx,
+            release_rx,
+            roots: Vec::new(),
+            bvh: Bvh::new(),
+            animators: Vec::new(),
+            models: Vec::new(),
+        }
+    }
+
+    pub fn add_node(&mut self, mut node: Node, parent: Option<NodeId>) -> NodeId {
+        let parent = parent.filter(|p| self.contains(*p));
+        node.parent = parent;
+        node.children.clear();
+
+        self.reclaim_slots();
+        let id = match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index as usize];
+                slot.node = Some(node);
+                NodeId {
+                    index,
+                    generation: slot.generation,
+                }
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    node: Some(node),
+                });
+                NodeId {
+                    index: self.slots.len() as u32 - 1,
+                    generation: 0,
+                }
+            }
+        };
+
+        match parent {
+            Some(parent) => self.node_mut(parent).unwrap().children.push(id),
+            None => self.roots.push(id),
+        }
+        id
+    }
+
+    /// Removes the node and its whole subtree. Asset handles held by the
+    /// removed nodes are released.
+    pub fn remove_node(&mut self, id: NodeId) -> bool {
+        let Some(node) = self.node(id) else {
+            return false;
+        };
+        let parent = node.parent;
+        self.detach(id, parent);
+
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            let slot = &mut self.slots[id.index as usize];
+            if let Some(node) = slot.node.take() {
+                stack.extend(node.children);
+                slot.generation = slot.generation.wrapping_add(1);
+                self.free.push(id.index);
+            }
         }
+        true
+    }
+
+    /// Frees the slots of dropped subtrees with a new generation, so their
+    /// ids stay dead.
+    fn reclaim_slots(&mut self) {
+        while let Ok(index) = self.release_rx.try_recv() {
+            let slot = &mut self.slots[index as usize];
+            slot.generation = slot.generation.wrapping_add(1);
+            self.free.push(index);
+            self.reserved -= 1;
+        }
+    }
+
+    /// Removes the node and its subtree like `remove_node`, but keeps their
+    /// slots reserved so `restore_subtree` can bring them back under the
+    /// same ids. Dropping the subtree instead releases the slots.
+    pub fn take_subtree(&mut self, id: NodeId) -> Option<Subtree> {
+        let parent = self.node(id)?.parent;
+        let siblings = match parent {
+            Some(parent) => &self.node(parent).unwrap().children,
+            None => &self.roots,
+        };
+        let position = siblings.iter().position(|&child| child == id).unwrap_or(0);
+        self.detach(id, parent);
+
+        let mut nodes = Vec::new();
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            if let Some(node) = self.slots[id.index as usize].node.take() {
+                stack.extend(node.children.iter().rev());
+                nodes.push((id, node));
+            }
+        }
+        self.reserved += nodes.len();
+
+        let (dead, alive) = std::mem::take(&mut self.animators)
+            .into_iter()
+            .partition(|animator| !animator.is_alive(self));
+        self.animators = alive;
+        Some(Subtree {
+            parent,
+            position,
+            nodes,
+            animators: dead,
+            release_tx: self.release_tx.clone(),
+        })
+    }
+
+    /// Puts a taken subtree back where it was, or at the root level if its
+    /// parent is gone.
+    pub fn restore_subtree(&mut self, mut subtree: Subtree) {
+        let root = subtree.root();
+        let parent = subtree.parent.filter(|&p| self.contains(p));
+        let nodes = std::mem::take(&mut subtree.nodes);
+        self.reserved -= nodes.len();
+        for (id, mut node) in nodes {
+            if id == root {
+                node.parent = parent;
+            }
+            self.slots[id.index as usize].node = Some(node);
+        }
+        let siblings = match parent {
+            Some(parent) => &mut self.node_mut(parent).unwrap().children,
+            None => &mut self.roots,
+        };
+        siblings.insert(subtree.position.min(siblings.len()), root);
+        self.animators.append(&mut subtree.animators);
+    }
+
+    /// Moves `id` under `parent`, or to the root level for `None`. Fails if
+    /// that would make a node its own ancestor.
+    pub fn set_parent(&mut self, id: NodeId, parent: Option<NodeId>) -> bool {
+        let Some(node) = self.node(id) else {
+            return false;
+        };
+        if let Some(parent) = parent
+            && (!self.contains(parent) || self.is_ancestor(id, parent))
+        {
+            return false;
+        }
+        let old_parent = node.parent;
+        self.detach(id, old_parent);
+        self.node_mut(id).unwrap().parent = parent;
+        match parent {
+            Some(parent) => self.node_mut(parent).unwrap().children.push(id),
+            None => self.roots.push(id),
+        }
+        true
+    }
+
+    /// Whether `ancestor` is `node` itself or lies on its parent chain.
+    pub fn is_ancestor(&self, ancestor: NodeId, node: NodeId) -> bool {
+        let mut current = Some(node);
+        while let Some(id) = current {
+            if id == ancestor {
+                return true;
+            }
+            current = self.node(id).and_then(|n| n.parent);
+        }
+        false
+    }
+
+    fn detach(&mut self, id: NodeId, parent: Option<NodeId>) {
+        let siblings = match parent {
+            Some(parent) => &mut self.node_mut(parent).unwrap().children,
+            None => &mut self.roots,
+        };
+        siblings.retain(|&child| child != id);
     }
 
-    pub fn update(&mut self) {
-        // code to update the scene based on the elapsed time
+    pub fn contains(&self, id: NodeId) -> bool {
+        self.node(id).is_some()
     }
 
-    pub fn render(&self) {
-        // code to render the scene
+    pub fn node(&self, id: NodeId) -> Option<&Node> {
+        let slot = self.slots.get(id.index as usize)?;
+        if slot.generation != id.generation {
+            return None;
+        }
+        slot.node.as_ref()
+    }
+
+    pub fn node_mut(&mut self, id: NodeId) -> Option<&mut Node> {
+        let slot = self.slots.get_mut(id.index as usize)?;
+        if slot.generation != id.generation {
+            return None;
+        }
+        slot.node.as_mut()
+    }
+
+    pub fn roots(&self) -> &[NodeId] {
+        &self.roots
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (NodeId, &Node)> {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            let id = NodeId {
+                index: index as u32,
+                generation: slot.generation,
+            };
+            slot.node.as_ref().map(|node| (id, node))
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len() - self.free.len() - self.reserved
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Copies the model hierarchy into the scene and returns the ids of the
+    /// spawned top-level nodes. A model with animations also gets an
+    /// `Animator` playing its first clip.
+    pub fn spawn_model(&mut self, model: &Model, parent: Option<NodeId>) -> Vec<NodeId> {
+        let mut spawned: Vec<NodeId> = Vec::with_capacity(model.nodes.len());
+        let mut roots = Vec::new();
+        for model_node in &model.nodes {
+            let mut node = Node::new(model_node.name.clone());
+            node.transform = model_node.transform;
+            node.mesh = model.mesh(model_node).cloned();
+            node.material = model.material(model_node).cloned();
+            node.point_cloud = model.point_cloud(model_node).cloned();
+            node.morph_weights.clone_from(&model_node.morph_weights);
+
+            let node_parent = match model_node.parent {
+                Some(index) => Some(spawned[index]),
+                None => parent,
+            };
+            let id = self.add_node(node, node_parent);
+            if model_node.parent.is_none() {
+                roots.push(id);
+            }
+            spawned.push(id);
+        }
+
+        for (model_node, &id) in model.nodes.iter().zip(&spawned) {
+            if let Some(skin) = model.skin(model_node) {
+                self.node_mut(id).unwrap().skin = Some(skin.map_joints(|&j| spawned[j]));
+            }
+        }
+        if !model.animations.is_empty() {
+            let name = roots
+                .first()
+                .and_then(|&id| self.node(id))
+                .map(|node| node.name.clone())
+                .unwrap_or_default();
+            self.animators
+                .push(Animator::new(name, model.animations.clone(), spawned));
+        }
+        roots
+    }
+
+    /// Keeps `model` loaded for as long as the scene exists. Call it for
+    /// every model spawned with `spawn_model`, whose nodes only reference
+    /// its meshes and materials.
+    pub fn keep_model(&mut self, model: Handle<Model>) {
+        if !self.models.contains(&model) {
+            self.models.push(model);
+        }
+    }
+
+    pub fn models(&self) -> &[Handle<Model>] {
+        &self.models
+    }
+
+    pub fn animators(&self) -> &[Animator] {
+        &self.animators
+    }
+
+    pub fn animators_mut(&mut self) -> &mut [Animator] {
+        &mut self.animators
+    }
+
+    /// Whether any animator is playing, so frames keep changing.
+    pub fn is_animating(&self) -> bool {
+        self.animators
+            .iter()
+            .any(|animator| animator.player.playing)
+    }
+
+    /// Advances every animator by the frame's fixed steps, so playback does
+    /// not depend on the display rate, and poses its nodes. The pose is
+    /// sampled `alpha` of a step past the last fixed step, i.e. extrapolated
+    /// ahead of the simulated time rather than interpolated between the last
+    /// two steps. Animators whose nodes were all removed are dropped.
+    fn animate(&mut self, time: &Time) {
+        let mut animators = std::mem::take(&mut self.animators);
+        animators.retain(|animator| animator.is_alive(self));
+        let step = time.fixed_delta().as_secs_f32();
+        for animator in &mut animators {
+            for _ in 0..time.steps() {
+                animator.advance(step);
+            }
+            animator.apply(self, time.alpha() * step);
+        }
+        self.animators = animators;
+    }
+
+    /// Propagates world transforms and bounds down and up the hierarchy and
+    /// brings the BVH up to date: objects that moved are refit, a changed
+    /// set of objects or a degraded tree triggers a rebuild.
+    pub fn update(&mut self, time: &Time, assets: &AssetManager) {
+        self.animate(time);
+        let mut order = Vec::with_capacity(self.len());
+        let mut stack: Vec<(NodeId, Mat4)> =
+            self.roots.iter().map(|&id| (id, Mat4::IDENTITY)).collect();
+        while let Some((id, parent_world)) = stack.pop() {
+            let Some(node) = self.node_mut(id) else {
+                continue;
+            };
+            node.world = parent_world * node.transform.to_matrix();
+            let world = node.world;
+            stack.extend(node.children.iter().map(|&child| (child, world)));
+            order.push(id);
+        }
+
+        for &id in &order {
+            let node = self.node(id).unwrap();
+            let Some(skin) = &node.skin else {
+                continue;
+            };
+            // Joint world transforms relative to the skinned node, so the
+            // node's own transform still places the result.
+            let inverse_world = node.world.inverse();
+            let joint_matrices = skin
+                .joints
+                .iter()
+                .enumerate()
+                .map(|(i, &joint)| {
+                    self.node(joint).map_or(Mat4::IDENTITY, |joint| {
+                        inverse_world * joint.world * skin.inverse_bind_matrix(i)
+                    })
+                })
+                .collect();
+            self.node_mut(id).unwrap().joint_matrices = joint_matrices;
+        }
+
+        let mut rebuild = false;
+        let mut moved = Vec::new();
+        for &id in &order {
+            let node = self.node(id).unwrap();
+            let local_bounds = node
+                .mesh
+                .as_ref()
+                .and_then(|m| assets.get(m))
+                .map(Mesh::bounds)
+                .or_else(|| {
+                    node.point_cloud
+                        .as_ref()
+                        .and_then(|p| assets.get(p))
+                        .map(PointCloud::bounds)
+                })
+                .unwrap_or(Aabb::EMPTY);
+            let world_bounds = if node.joint_matrices.is_empty() {
+                local_bounds.transformed(&node.world)
+            } else {
+                // The bind-pose box carried along by every joint encloses
+                // any blend of them.
+                node.joint_matrices
+                    .iter()
+                    .map(|joint| local_bounds.transformed(&(node.world * *joint)))
+                    .fold(Aabb::EMPTY, |acc, b| acc.union(&b))
+            };
+            let was_in_bvh = self.bvh.contains(id);
+            if was_in_bvh == world_bounds.is_empty() {
+                rebuild = true;
+            } else if was_in_bvh && world_bounds != node.world_bounds {
+                moved.push((id, world_bounds));
+            }
+            let node = self.node_mut(id).unwrap();
+            node.local_bounds = local_bounds;
+            node.world_bounds = world_bounds;
+        }
+
+        // Children come after their parents in `order`, so walking it
+        // backwards finishes every subtree before its root.
+        for &id in order.iter().rev() {
+            let node = self.node(id).unwrap();
+            let subtree = node
+                .children
+                .iter()
+                .filter_map(|&child| self.node(child))
+                .fold(node.world_bounds, |acc, child| {
+                    acc.union(&child.subtree_bounds)
+                });
+            self.node_mut(id).unwrap().subtree_bounds = subtree;
+        }
+
+        // Removed nodes leave stale leaves behind.
+        rebuild |= self.bvh.len() != order.iter().filter(|&&id| self.bvh.contains(id)).count();
+        if !rebuild {
+            for (id, bounds) in moved {
+                self.bvh.refit(id, bounds);
+            }
+            rebuild = self.bvh.is_degraded();
+        }
+        if rebuild {
+            let items: Vec<(NodeId, Aabb)> = self
+                .iter()
+                .filter(|(_, node)| !node.world_bounds.is_empty())
+                .map(|(id, node)| (id, node.world_bounds))
+                .collect();
+            self.bvh.build(items);
+        }
+    }
+
+    pub fn bvh(&self) -> &Bvh {
+        &self.bvh
+    }
+
+    /// Visible-flagged nodes with geometry inside the frustum, as of the
+    /// last `update`.
+    pub fn cull(&self, frustum: &Frustum) -> CullResult {
+        let mut result = CullResult::default();
+        self.bvh.query_frustum(frustum, |id| {
+            if self.node(id).is_some_and(|node| node.visible) {
+                result.visible.push(id);
+            }
+        });
+        let candidates = self
+            .iter()
+            .filter(|(id, node)| node.visible && self.bvh.contains(*id))
+            .count();
+        result.culled = candidates - result.visible.len();
+        result
+    }
+
+    /// Nearest visible node hit by a world-space ray: exact triangles for
+    /// static meshes, bounding boxes for skinned meshes and point clouds.
+    pub fn raycast(&self, ray: &Ray, assets: &AssetManager) -> Option<RayHit> {
+        let mut best: Option<RayHit> = None;
+        self.bvh.query_ray(ray, |id, box_distance| {
+            let node = self.node(id).filter(|node| node.visible)?;
+            let mesh = node.mesh.as_ref().and_then(|m| assets.get(m));
+            let distance = match mesh.filter(|_| node.skin.is_none()) {
+                Some(mesh) => {
+                    let local = ray.transformed(&node.world.inverse());
+                    mesh.indices
+                        .chunks_exact(3)
+                        .filter_map(|t| {
+                            let [a, b, c] = [t[0], t[1], t[2]].map(|i| mesh.positions[i as usize]);
+                            local.intersect_triangle(a, b, c)
+                        })
+                        .min_by(f32::total_cmp)?
+                }
+                None => box_distance,
+            };
+            if best.is_none_or(|hit| distance < hit.distance) {
+                best = Some(RayHit {
+                    node: id,
+                    distance,
+                    point: ray.at(distance),
+                });
+            }
+            Some(distance)
+        });
+        best
+    }
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removing_node_removes_subtree() {
+        let mut scene = Scene::new();
+        let root = scene.add_node(Node::new("root"), None);
+        let child = scene.add_node(Node::new("child"), Some(root));
+        let grandchild = scene.add_node(Node::new("grandchild"), Some(child));
+
+        assert!(scene.remove_node(child));
+        assert!(!scene.contains(child));
+        assert!(!scene.contains(grandchild));
+        assert!(scene.node(root).unwrap().children().is_empty());
+
+        // A new node reusing the slot gets a fresh generation.
+        let reused = scene.add_node(Node::new("reused"), None);
+        assert!(!scene.contains(child));
+        assert!(scene.contains(reused));
+        assert_eq!(scene.len(), 2);
+    }
+
+    #[test]
+    fn taken_subtrees_restore_with_the_same_ids() {
+        let mut scene = Scene::new();
+        let a = scene.add_node(Node::new("a"), None);
+        let b = scene.add_node(Node::new("b"), None);
+        let child = scene.add_node(Node::new("child"), Some(a));
+
+        let subtree = scene.take_subtree(a).unwrap();
+        assert_eq!(subtree.len(), 2);
+        assert!(!scene.contains(a) && !scene.contains(child));
+        assert_eq!(scene.len(), 1);
+        // Reserved slots are not handed out again.
+        let c = scene.add_node(Node::new("c"), None);
+        assert_ne!(c, a);
+        assert_ne!(c, child);
+
+        scene.restore_subtree(subtree);
+        assert_eq!(scene.roots(), &[a, b, c]);
+        assert_eq!(scene.node(child).unwrap().parent(), Some(a));
+        assert_eq!(scene.len(), 4);
+    }
+
+    #[test]
+    fn dropped_subtrees_release_their_slots() {
+        let mut scene = Scene::new();
+        let a = scene.add_node(Node::new("a"), None);
+        let child = scene.add_node(Node::new("child"), Some(a));
+
+        drop(scene.take_subtree(a).unwrap());
+        assert_eq!(scene.len(), 0);
+        let b = scene.add_node(Node::new("b"), None);
+        let c = scene.add_node(Node::new("c"), None);
+        // Both slots are reused, under generations the old ids do not match.
+        assert_eq!(scene.slots.len(), 2);
+        assert!(!scene.contains(a) && !scene.contains(child));
+        assert!(scene.contains(b) && scene.contains(c));
+        assert_eq!(scene.len(), 2);
+    }
+
+    #[test]
+    fn set_parent_rejects_cycles() {
+        let mut scene = Scene::new();
+        let a = scene.add_node(Node::new("a"), None);
+        let b = scene.add_node(Node::new("b"), Some(a));
+        assert!(!scene.set_parent(a, Some(b)));
+        assert!(!scene.set_parent(a, Some(a)));
+        assert!(scene.set_parent(b, None));
+        assert_eq!(scene.roots(), &[a, b]);
+    }
+
+    #[test]
+    fn update_propagates_world_transforms() {
+        let mut scene = Scene::new();
+        let mut parent = Node::new("parent");
+        parent.transform = Transform::from_translation(Vec3::X);
+        let parent = scene.add_node(parent, None);
+        let mut child = Node::new("child");
+        child.transform = Transform::from_translation(Vec3::Y);
+        let child = scene.add_node(child, Some(parent));
+
+        scene.update(&Time::new(), &AssetManager::new());
+        let world = scene.node(child).unwrap().world_matrix();
+        assert!(
+            world
+                .w_axis
+                .truncate()
+                .abs_diff_eq(Vec3::new(1.0, 1.0, 0.0), 1e-6)
+        );
+    }
+
+    #[test]
+    fn moved_nodes_are_culled_and_picked_through_the_bvh() {
+        let mut assets = AssetManager::new();
+        let mut quad = Mesh::new("quad");
+        quad.positions = vec![
+            Vec3::new(-1.0, -1.0, 0.0),
+            Vec3::new(1.0, -1.0, 0.0),
+            Vec3::new(1.0, 1.0, 0.0),
+            Vec3::new(-1.0, 1.0, 0.0),
+        ];
+        quad.indices = vec![0, 1, 2, 0, 2, 3];
+        let quad = assets.add(quad);
+
+        let mut scene = Scene::new();
+        let parent = scene.add_node(Node::new("parent"), None);
+        let mut near = Node::new("near");
+        near.mesh = Some(quad.clone());
+        let near = scene.add_node(near, Some(parent));
+        let mut far = Node::new("far");
+        far.mesh = Some(quad);
+        far.transform = Transform::from_translation(Vec3::new(0.0, 0.0, -10.0));
+        let far = scene.add_node(far, Some(parent));
+        scene.update(&Time::new(), &assets);
+
+        let subtree = scene.node(parent).unwrap().subtree_bounds();
+        assert!(subtree.contains(Vec3::new(0.0, 0.0, -10.0)) && subtree.contains(Vec3::ZERO));
+
+        let ray = Ray::new(Vec3::new(0.5, 0.5, 5.0), -Vec3::Z);
+        let hit = scene.raycast(&ray, &assets).unwrap();
+        assert_eq!(hit.node, near);
+        assert!((hit.distance - 5.0).abs() < 1e-5);
+
+        // Move the near quad aside; the ray now reaches the far one.
+        scene.node_mut(near).unwrap().transform = Transform::from_translation(Vec3::X * 5.0);
+        scene.update(&Time::new(), &assets);
+        assert_eq!(scene.raycast(&ray, &assets).unwrap().node, far);
+
+        let view = Mat4::look_at_rh(Vec3::new(5.0, 0.0, 5.0), Vec3::new(5.0, 0.0, 0.0), Vec3::Y);
+        let projection = Mat4::perspective_rh_gl(0.5, 1.0, 0.1, 100.0);
+        let result = scene.cull(&Frustum::from_matrix(&(projection * view)));
+        assert_eq!(result.visible, vec![near]);
+        assert_eq!(result.culled, 1);
     }
 }
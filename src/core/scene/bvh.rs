@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+
+use crate::core::{
+    bounds::{Aabb, Frustum, Ray},
+    scene::NodeId,
+};
+
+const NONE: u32 = u32::MAX;
+
+/// Refitting lets the tree degrade as objects move apart; once its cost
+/// grows past this factor of the cost at build time it is rebuilt.
+const REBUILD_COST_RATIO: f32 = 2.0;
+
+#[derive(Clone, Debug)]
+struct BvhNode {
+    bounds: Aabb,
+    parent: u32,
+    /// Children for inner nodes, `NONE` for leaves.
+    left: u32,
+    right: u32,
+    leaf: Option<NodeId>,
+}
+
+/// Bounding volume hierarchy over the world-space bounds of scene nodes.
+/// Moving objects refit their leaf and its ancestors; adding or removing
+/// objects rebuilds the tree.
+#[derive(Default)]
+pub struct Bvh {
+    nodes: Vec<BvhNode>,
+    leaves: HashMap<NodeId, u32>,
+    built_cost: f32,
+}
+
+impl Bvh {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    pub fn contains(&self, id: NodeId) -> bool {
+        self.leaves.contains_key(&id)
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        self.nodes.first().map_or(Aabb::EMPTY, |n| n.bounds)
+    }
+
+    /// Builds top-down, splitting at the centroid median of the widest axis.
+    pub fn build(&mut self, items: impl IntoIterator<Item = (NodeId, Aabb)>) {
+        let mut items: Vec<(NodeId, Aabb)> = items.into_iter().collect();
+        self.nodes.clear();
+        self.leaves.clear();
+        if !items.is_empty() {
+            self.build_node(&mut items, NONE);
+        }
+        self.built_cost = self.cost();
+    }
+
+    fn build_node(&mut self, items: &mut [(NodeId, Aabb)], parent: u32) -> u32 {
+        let index = self.nodes.len() as u32;
+        let bounds = items.iter().fold(Aabb::EMPTY, |acc, (_, b)| acc.union(b));
+        self.nodes.push(BvhNode {
+            bounds,
+            parent,
+            left: NONE,
+            right: NONE,
+            leaf: None,
+        });
+
+        if let [(id, _)] = items {
+            self.nodes[index as usize].leaf = Some(*id);
+            self.leaves.insert(*id, index);
+            return index;
+        }
+
+        let centroids = Aabb::from_points(items.iter().map(|(_, b)| b.center()));
+        let axis = centroids.extents().max_position();
+        let mid = items.len() / 2;
+        items.select_nth_unstable_by(mid, |(_, a), (_, b)| {
+            a.center()[axis].total_cmp(&b.center()[axis])
+        });
+        let (left_items, right_items) = items.split_at_mut(mid);
+        let left = self.build_node(left_items, index);
+        let right = self.build_node(right_items, index);
+        let node = &mut self.nodes[index as usize];
+        node.left = left;
+        node.right = right;
+        index
+    }
+
+    /// Updates one object's bounds and its ancestors'. Returns `false` if
+    /// the object is not in the tree.
+    pub fn refit(&mut self, id: NodeId, bounds: Aabb) -> bool {
+        let Some(&leaf) = self.leaves.get(&id) else {
+            return false;
+        };
+        self.nodes[leaf as usize].bounds = bounds;
+        let mut current = self.nodes[leaf as usize].parent;
+        while current != NONE {
+            let node = &self.nodes[current as usize];
+            let (left, right, parent) = (node.left, node.right, node.parent);
+            let merged = self.nodes[left as usize]
+                .bounds
+                .union(&self.nodes[right as usize].bounds);
+            if merged == self.nodes[current as usize].bounds {
+                break;
+            }
+            self.nodes[current as usize].bounds = merged;
+            current = parent;
+        }
+        true
+    }
+
+    /// Whether refits have made the tree enough worse to warrant a rebuild.
+    pub fn is_degraded(&self) -> bool {
+        self.cost() > self.built_cost * REBUILD_COST_RATIO
+    }
+
+    /// Sum of inner node surface areas, proportional to the expected work
+    /// of a query.
+    fn cost(&self) -> f32 {
+        self.nodes
+            .iter()
+            .filter(|n| n.leaf.is_none())
+            .map(|n| n.bounds.half_area())
+            .sum()
+    }
+
+    /// Calls `visit` for every object whose bounds intersect the frustum.
+    pub fn query_frustum(&self, frustum: &Frustum, mut visit: impl FnMut(NodeId)) {
+        if self.nodes.is_empty() {
+            return;
+        }
+        let mut stack = vec![0u32];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index as usize];
+            if !frustum.intersects_sphere(&node.bounds.bounding_sphere())
+                || !frustum.intersects_aabb(&node.bounds)
+            {
+                continue;
+            }
+            match node.leaf {
+                Some(id) => visit(id),
+                None => stack.extend([node.left, node.right]),
+            }
+        }
+    }
+
+    /// Calls `visit` with each object whose bounds the ray enters, nearest
+    /// box first, together with the entry distance. `visit` returns the
+    /// distance of a confirmed hit, after which boxes further away are
+    /// skipped.
+    pub fn query_ray(&self, ray: &Ray, mut visit: impl FnMut(NodeId, f32) -> Option<f32>) {
+        if self.nodes.is_empty() {
+            return;
+        }
+        let mut closest = f32::INFINITY;
+        let mut stack = vec![0u32];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index as usize];
+            let Some(t) = ray.intersect_aabb(&node.bounds) else {
+                continue;
+            };
+            if t > closest {
+                continue;
+            }
+            if let Some(id) = node.leaf {
+                if let Some(hit) = visit(id, t) {
+                    closest = closest.min(hit);
+                }
+                continue;
+            }
+            // Push the farther child first so the nearer one is visited
+            // first and tightens `closest` early.
+            let entry = |child: u32| {
+                ray.intersect_aabb(&self.nodes[child as usize].bounds)
+                    .unwrap_or(f32::INFINITY)
+            };
+            let (near, far) = if entry(node.left) <= entry(node.right) {
+                (node.left, node.right)
+            } else {
+                (node.right, node.left)
+            };
+            stack.extend([far, near]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Mat4, Vec3};
+
+    use super::*;
+    use crate::core::scene::{Node, Scene};
+
+    fn boxes(count: usize) -> (Vec<NodeId>, Vec<Aabb>) {
+        let mut scene = Scene::new();
+        let ids = (0..count)
+            .map(|i| scene.add_node(Node::new(format!("{i}")), None))
+            .collect();
+        let bounds = (0..count)
+            .map(|i| {
+                let min = Vec3::new(i as f32 * 2.0, 0.0, 0.0);
+                Aabb::new(min, min + Vec3::ONE)
+            })
+            .collect();
+        (ids, bounds)
+    }
+
+    #[test]
+    fn frustum_query_matches_brute_force() {
+        let (ids, bounds) = boxes(100);
+        let mut bvh = Bvh::new();
+        bvh.build(ids.iter().copied().zip(bounds.iter().copied()));
+
+        let view = Mat4::look_at_rh(
+            Vec3::new(20.0, 0.5, 10.0),
+            Vec3::new(20.0, 0.5, 0.0),
+            Vec3::Y,
+        );
+        let projection = Mat4::perspective_rh_gl(0.5, 1.0, 0.1, 100.0);
+        let frustum = Frustum::from_matrix(&(projection * view));
+
+        let mut found = Vec::new();
+        bvh.query_frustum(&frustum, |id| found.push(id));
+        let expected: Vec<NodeId> = ids
+            .iter()
+            .zip(&bounds)
+            .filter(|(_, b)| frustum.intersects_aabb(b))
+            .map(|(id, _)| *id)
+            .collect();
+        assert!(!expected.is_empty() && expected.len() < ids.len());
+        found.sort_by_key(|id| expected.iter().position(|e| e == id));
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn refit_moves_objects_and_ray_finds_nearest() {
+        let (ids, bounds) = boxes(16);
+        let mut bvh = Bvh::new();
+        bvh.build(ids.iter().copied().zip(bounds.iter().copied()));
+
+        // Move the last box far up; a ray along +Y should now find it.
+        let moved = Aabb::new(Vec3::new(0.0, 50.0, 0.0), Vec3::new(1.0, 51.0, 1.0));
+        assert!(bvh.refit(ids[15], moved));
+        assert!(bvh.bounds().contains(Vec3::new(0.5, 50.5, 0.5)));
+
+        let ray = Ray::new(Vec3::new(0.5, -5.0, 0.5), Vec3::Y);
+        let mut visited = Vec::new();
+        bvh.query_ray(&ray, |id, t| {
+            visited.push(id);
+            Some(t)
+        });
+        // The box at the origin is hit first and hides the moved one.
+        assert_eq!(visited, vec![ids[0]]);
+        assert!(bvh.is_degraded());
+    }
+}
@@ -0,0 +1,918 @@
+//! Versioned scene files. A document holds the node hierarchy with
+//! transforms and component data, the animators, the camera with its
+//! bookmarks and the render settings. Meshes, materials, point clouds and
+//! skins are referenced by the VFS path of the model they came from and
+//! their index in it, so loading a scene loads those models again.
+//!
+//! The text form is TOML. The binary form is the same tree encoded as
+//! MessagePack after a magic number, so both go through one migration path.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{Context, bail};
+use glam::{Quat, Vec3};
+use serde::{Deserialize, Serialize};
+
+use crate::core::animation::Animator;
+use crate::core::asset_manager::{AssetId, AssetManager, AssetState, Model};
+use crate::core::camera::{Camera, Projection, UpAxis};
+use crate::core::renderer::{PointCloudSettings, PointColorMode, PointSizeMode};
+use crate::core::scene::{Node, NodeId, Scene};
+use crate::core::transform::Transform;
+
+/// Upgrades a document in place from one version to the next.
+type Migration = fn(&mut toml::Table) -> anyhow::Result<()>;
+
+/// `MIGRATIONS[n]` upgrades a version `n + 1` document to version `n + 2`.
+/// Changing the format means appending a step here; older files then load
+/// through every step up to the current version.
+const MIGRATIONS: &[Migration] = &[add_camera_up];
+
+/// Version written by this build.
+pub const VERSION: u32 = MIGRATIONS.len() as u32 + 1;
+
+const BINARY_MAGIC: &[u8; 4] = b"SCNB";
+
+/// Extension that selects the binary form when saving.
+pub const BINARY_EXTENSION: &str = "scnb";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SceneDocument {
+    pub version: u32,
+    /// Parents always come before their children.
+    #[serde(default)]
+    pub nodes: Vec<NodeEntry>,
+    #[serde(default)]
+    pub animators: Vec<AnimatorEntry>,
+    #[serde(default)]
+    pub camera: Option<CameraState>,
+    #[serde(default)]
+    pub bookmarks: Vec<CameraBookmark>,
+    #[serde(default)]
+    pub render: Option<RenderSettings>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct NodeEntry {
+    pub name: String,
+    /// Index of the parent in `nodes`.
+    #[serde(default)]
+    pub parent: Option<usize>,
+    #[serde(default)]
+    pub transform: TransformState,
+    #[serde(default = "default_visible")]
+    pub visible: bool,
+    #[serde(default)]
+    pub mesh: Option<AssetRef>,
+    #[serde(default)]
+    pub material: Option<AssetRef>,
+    #[serde(default)]
+    pub point_cloud: Option<AssetRef>,
+    #[serde(default)]
+    pub skin: Option<SkinRef>,
+    #[serde(default)]
+    pub morph_weights: Vec<f32>,
+}
+
+fn default_visible() -> bool {
+    true
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct TransformState {
+    #[serde(default)]
+    pub translation: [f32; 3],
+    /// Quaternion as `[x, y, z, w]`.
+    #[serde(default = "identity_rotation")]
+    pub rotation: [f32; 4],
+    #[serde(default = "unit_scale")]
+    pub scale: [f32; 3],
+}
+
+fn identity_rotation() -> [f32; 4] {
+    Quat::IDENTITY.to_array()
+}
+
+fn unit_scale() -> [f32; 3] {
+    [1.0; 3]
+}
+
+impl Default for TransformState {
+    fn default() -> Self {
+        Self::from(&Transform::IDENTITY)
+    }
+}
+
+impl From<&Transform> for TransformState {
+    fn from(transform: &Transform) -> Self {
+        Self {
+            translation: transform.translation.to_array(),
+            rotation: transform.rotation.to_array(),
+            scale: transform.scale.to_array(),
+        }
+    }
+}
+
+impl From<&TransformState> for Transform {
+    fn from(state: &TransformState) -> Self {
+        Self {
+            translation: Vec3::from_array(state.translation),
+            rotation: Quat::from_array(state.rotation).normalize(),
+            scale: Vec3::from_array(state.scale),
+        }
+    }
+}
+
+/// An asset owned by a model: the model's VFS path and the asset's index in
+/// the model's list of that kind.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct AssetRef {
+    pub model: PathBuf,
+    pub index: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SkinRef {
+    pub model: PathBuf,
+    pub index: usize,
+    /// Indices in `nodes` of the joints, in the skin's joint order.
+    pub joints: Vec<usize>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct AnimatorEntry {
+    pub name: String,
+    /// Model whose clips the animator plays.
+    pub model: PathBuf,
+    /// Pairs of a model node index, as addressed by the clip channels, and
+    /// the index in `nodes` it drives.
+    pub targets: Vec<[usize; 2]>,
+    #[serde(default)]
+    pub clip: usize,
+    #[serde(default)]
+    pub time: f32,
+    #[serde(default = "default_speed")]
+    pub speed: f32,
+    #[serde(default)]
+    pub playing: bool,
+    #[serde(default)]
+    pub looping: bool,
+}
+
+fn default_speed() -> f32 {
+    1.0
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CameraState {
+    pub target: [f32; 3],
+    pub distance: f32,
+    pub yaw_degrees: f32,
+    pub pitch_degrees: f32,
+    pub projection: ProjectionState,
+    pub near: f32,
+    pub far: f32,
+    #[serde(default)]
+    pub up: UpAxis,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ProjectionState {
+    Perspective { fov_y_degrees: f32 },
+    Orthographic { height: f32 },
+}
+
+impl From<&Camera> for CameraState {
+    fn from(camera: &Camera) -> Self {
+        Self {
+            target: camera.target.to_array(),
+            distance: camera.distance,
+            yaw_degrees: camera.yaw.to_degrees(),
+            pitch_degrees: camera.pitch.to_degrees(),
+            projection: match camera.projection {
+                Projection::Perspective { fov_y } => ProjectionState::Perspective {
+                    fov_y_degrees: fov_y.to_degrees(),
+                },
+                Projection::Orthographic { height } => ProjectionState::Orthographic { height },
+            },
+            near: camera.near,
+            far: camera.far,
+            up: camera.up,
+        }
+    }
+}
+
+impl From<&CameraState> for Camera {
+    fn from(state: &CameraState) -> Self {
+        Self {
+            target: Vec3::from_array(state.target),
+            distance: state.distance,
+            yaw: state.yaw_degrees.to_radians(),
+            pitch: state.pitch_degrees.to_radians(),
+            projection: match state.projection {
+                ProjectionState::Perspective { fov_y_degrees } => Projection::Perspective {
+                    fov_y: fov_y_degrees.to_radians(),
+                },
+                ProjectionState::Orthographic { height } => Projection::Orthographic { height },
+            },
+            near: state.near,
+            far: state.far,
+            up: state.up,
+        }
+    }
+}
+
+/// A named camera view the user can jump back to.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CameraBookmark {
+    pub name: String,
+    pub camera: CameraState,
+}
+
+/// How the scene looks rather than what it holds. Memory and upload
+/// budgets depend on the machine and stay with the app configuration.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct RenderSettings {
+    pub point_size_mode: PointSizeMode,
+    pub point_size: f32,
+    pub world_point_size: f32,
+    pub point_color_mode: PointColorMode,
+    pub round_points: bool,
+    pub eye_dome_lighting: bool,
+    pub edl_strength: f32,
+    pub edl_radius: f32,
+    pub point_budget: usize,
+}
+
+impl From<&PointCloudSettings> for RenderSettings {
+    fn from(settings: &PointCloudSettings) -> Self {
+        Self {
+            point_size_mode: settings.size_mode,
+            point_size: settings.point_size,
+            world_point_size: settings.world_point_size,
+            point_color_mode: settings.color_mode,
+            round_points: settings.round_points,
+            eye_dome_lighting: settings.eye_dome_lighting,
+            edl_strength: settings.edl_strength,
+            edl_radius: settings.edl_radius,
+            point_budget: settings.point_budget,
+        }
+    }
+}
+
+impl RenderSettings {
+    pub fn apply(&self, settings: &mut PointCloudSettings) {
+        settings.size_mode = self.point_size_mode;
+        settings.point_size = self.point_size;
+        settings.world_point_size = self.world_point_size;
+        settings.color_mode = self.point_color_mode;
+        settings.round_points = self.round_points;
+        settings.eye_dome_lighting = self.eye_dome_lighting;
+        settings.edl_strength = self.edl_strength;
+        settings.edl_radius = self.edl_radius;
+        settings.point_budget = self.point_budget;
+    }
+}
+
+/// Where each asset handed out by a loaded model came from.
+#[derive(Default)]
+struct AssetOrigins<'a> {
+    meshes: HashMap<AssetId, (&'a Path, usize)>,
+    materials: HashMap<AssetId, (&'a Path, usize)>,
+    point_clouds: HashMap<AssetId, (&'a Path, usize)>,
+    models: Vec<(&'a Path, &'a Model)>,
+}
+
+impl<'a> AssetOrigins<'a> {
+    fn new(assets: &'a AssetManager) -> Self {
+        let mut origins = Self::default();
+        let storage = assets.storage::<Model>();
+        for (id, model) in storage.iter() {
+            let Some(path) = storage.path(id) else {
+                continue;
+            };
+            for (index, handle) in model.meshes.iter().enumerate() {
+                origins.meshes.insert(handle.id(), (path, index));
+            }
+            for (index, handle) in model.materials.iter().enumerate() {
+                origins.materials.insert(handle.id(), (path, index));
+            }
+            for (index, handle) in model.point_clouds.iter().enumerate() {
+                origins.point_clouds.insert(handle.id(), (path, index));
+            }
+            origins.models.push((path, model));
+        }
+        origins
+    }
+
+    fn lookup(map: &HashMap<AssetId, (&Path, usize)>, id: AssetId) -> Option<AssetRef> {
+        map.get(&id).map(|&(path, index)| AssetRef {
+            model: path.to_path_buf(),
+            index,
+        })
+    }
+}
+
+impl SceneDocument {
+    pub fn new() -> Self {
+        Self {
+            version: VERSION,
+            nodes: Vec::new(),
+            animators: Vec::new(),
+            camera: None,
+            bookmarks: Vec::new(),
+            render: None,
+        }
+    }
+
+    /// Records the nodes and animators of `scene`. Components that did not
+    /// come from a model loaded by path cannot be referenced and are left
+    /// out with a warning; camera, bookmarks and render settings are up to
+    /// the caller.
+    pub fn capture(scene: &Scene, assets: &AssetManager) -> Self {
+        let origins = AssetOrigins::new(assets);
+        let mut document = Self::new();
+        let mut indices = HashMap::new();
+        let mut stack: Vec<(NodeId, Option<usize>)> =
+            scene.roots().iter().rev().map(|&id| (id, None)).collect();
+        while let Some((id, parent)) = stack.pop() {
+            let Some(node) = scene.node(id) else {
+                continue;
+            };
+            let index = document.nodes.len();
+            indices.insert(id, index);
+            document.nodes.push(NodeEntry {
+                name: node.name.clone(),
+                parent,
+                transform: TransformState::from(&node.transform),
+                visible: node.visible,
+                mesh: None,
+                material: None,
+                point_cloud: None,
+                skin: None,
+                morph_weights: node.morph_weights.clone(),
+            });
+            stack.extend(
+                node.children()
+                    .iter()
+                    .rev()
+                    .map(|&child| (child, Some(index))),
+            );
+        }
+
+        for (&id, &index) in &indices {
+            let node = scene.node(id).unwrap();
+            let entry = &mut document.nodes[index];
+            let missing = |kind: &str| {
+                log::warn!(
+                    "node \"{}\": {kind} is not from a saved model, leaving it out",
+                    node.name
+                );
+            };
+            if let Some(mesh) = &node.mesh {
+                entry.mesh = AssetOrigins::lookup(&origins.meshes, mesh.id());
+                if entry.mesh.is_none() {
+                    missing("mesh");
+                }
+            }
+            if let Some(material) = &node.material {
+                entry.material = AssetOrigins::lookup(&origins.materials, material.id());
+                if entry.material.is_none() {
+                    missing("material");
+                }
+            }
+            if let Some(point_cloud) = &node.point_cloud {
+                entry.point_cloud = AssetOrigins::lookup(&origins.point_clouds, point_cloud.id());
+                if entry.point_cloud.is_none() {
+                    missing("point cloud");
+                }
+            }
+            if let Some(skin) = &node.skin {
+                let joints: Option<Vec<usize>> = skin
+                    .joints
+                    .iter()
+                    .map(|joint| indices.get(joint).copied())
+                    .collect();
+                let origin = origins.models.iter().find_map(|&(path, model)| {
+                    let index = model.skins.iter().position(|candidate| {
+                        Arc::ptr_eq(
+                            &candidate.inverse_bind_matrices,
+                            &skin.inverse_bind_matrices,
+                        )
+                    })?;
+                    Some((path, index))
+                });
+                match (origin, joints) {
+                    (Some((path, index)), Some(joints)) => {
+                        entry.skin = Some(SkinRef {
+                            model: path.to_path_buf(),
+                            index,
+                            joints,
+                        });
+                    }
+                    _ => missing("skin"),
+                }
+            }
+        }
+
+        for animator in scene.animators() {
+            let Some(&(path, _)) = origins.models.iter().find(|(_, model)| {
+                std::ptr::eq(model.animations.as_ptr(), animator.clips().as_ptr())
+            }) else {
+                log::warn!(
+                    "animator \"{}\" is not from a saved model, leaving it out",
+                    animator.name
+                );
+                continue;
+            };
+            let targets: Vec<[usize; 2]> = animator
+                .targets()
+                .iter()
+                .enumerate()
+                .filter_map(|(model_node, id)| Some([model_node, *indices.get(id)?]))
+                .collect();
+            if targets.is_empty() {
+                continue;
+            }
+            let player = &animator.player;
+            document.animators.push(AnimatorEntry {
+                name: animator.name.clone(),
+                model: path.to_path_buf(),
+                targets,
+                clip: player.clip,
+                time: player.time,
+                speed: player.speed,
+                playing: player.playing,
+                looping: player.looping,
+            });
+        }
+        document
+    }
+
+    /// Distinct models the document references, to be loaded before
+    /// `instantiate`.
+    pub fn model_paths(&self) -> Vec<&Path> {
+        let nodes = self.nodes.iter().flat_map(|node| {
+            [&node.mesh, &node.material, &node.point_cloud]
+                .into_iter()
+                .flatten()
+                .map(|asset| asset.model.as_path())
+                .chain(node.skin.iter().map(|skin| skin.model.as_path()))
+        });
+        let animators = self
+            .animators
+            .iter()
+            .map(|animator| animator.model.as_path());
+        nodes
+            .chain(animators)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Adds the document's nodes and animators to `scene` and returns the
+    /// new root nodes. Every referenced model must already be loaded, and
+    /// the scene keeps it loaded; nothing is added if any reference cannot
+    /// be resolved.
+    pub fn instantiate(
+        &self,
+        scene: &mut Scene,
+        assets: &AssetManager,
+    ) -> anyhow::Result<Vec<NodeId>> {
+        self.validate()?;
+        let mut models = HashMap::new();
+        let mut handles = Vec::new();
+        for path in self.model_paths() {
+            let handle = assets.storage::<Model>().find_by_path(path);
+            let model = match handle
+                .as_ref()
+                .map(|handle| (assets.state(handle), assets.get(handle)))
+            {
+                Some((_, Some(model))) => model,
+                Some((AssetState::Failed(error), _)) => {
+                    bail!("model `{}` failed to load: {error}", path.display())
+                }
+                _ => bail!("model `{}` is not loaded", path.display()),
+            };
+            models.insert(path, model);
+            handles.extend(handle);
+        }
+
+        let mut nodes = Vec::with_capacity(self.nodes.len());
+        for (index, entry) in self.nodes.iter().enumerate() {
+            let context = || format!("nodes[{index}] (\"{}\")", entry.name);
+            let mut node = Node::new(entry.name.clone());
+            node.transform = Transform::from(&entry.transform);
+            node.visible = entry.visible;
+            node.morph_weights.clone_from(&entry.morph_weights);
+            if let Some(asset) = &entry.mesh {
+                let model = models[asset.model.as_path()];
+                node.mesh = Some(
+                    resolve(asset, &model.meshes, "meshes")
+                        .with_context(|| format!("{}.mesh", context()))?
+                        .clone(),
+                );
+            }
+            if let Some(asset) = &entry.material {
+                let model = models[asset.model.as_path()];
+                node.material = Some(
+                    resolve(asset, &model.materials, "materials")
+                        .with_context(|| format!("{}.material", context()))?
+                        .clone(),
+                );
+            }
+            if let Some(asset) = &entry.point_cloud {
+                let model = models[asset.model.as_path()];
+                node.point_cloud = Some(
+                    resolve(asset, &model.point_clouds, "point clouds")
+                        .with_context(|| format!("{}.point_cloud", context()))?
+                        .clone(),
+                );
+            }
+            if let Some(skin) = &entry.skin {
+                let model = models[skin.model.as_path()];
+                let asset = AssetRef {
+                    model: skin.model.clone(),
+                    index: skin.index,
+                };
+                let model_skin = resolve(&asset, &model.skins, "skins")
+                    .with_context(|| format!("{}.skin", context()))?;
+                if model_skin.joints.len() != skin.joints.len() {
+                    bail!(
+                        "{}.skin: skin {} of `{}` has {} joints, the document lists {}",
+                        context(),
+                        skin.index,
+                        skin.model.display(),
+                        model_skin.joints.len(),
+                        skin.joints.len()
+                    );
+                }
+            }
+            nodes.push(node);
+        }
+        for animator in &self.animators {
+            let model = models[animator.model.as_path()];
+            if let Some(&[model_node, _]) = animator
+                .targets
+                .iter()
+                .find(|[model_node, _]| *model_node >= model.nodes.len())
+            {
+                bail!(
+                    "animator \"{}\": `{}` has {} nodes, target {model_node} is out of range",
+                    animator.name,
+                    animator.model.display(),
+                    model.nodes.len()
+                );
+            }
+        }
+
+        let mut ids = Vec::with_capacity(nodes.len());
+        let mut roots = Vec::new();
+        for (entry, node) in self.nodes.iter().zip(nodes) {
+            let id = scene.add_node(node, entry.parent.map(|parent| ids[parent]));
+            if entry.parent.is_none() {
+                roots.push(id);
+            }
+            ids.push(id);
+        }
+        for (entry, &id) in self.nodes.iter().zip(&ids) {
+            if let Some(skin) = &entry.skin {
+                let model_skin = &models[skin.model.as_path()].skins[skin.index];
+                let mut joints = skin.joints.iter();
+                scene.node_mut(id).unwrap().skin =
+                    Some(model_skin.map_joints(|_| ids[*joints.next().unwrap()]));
+            }
+        }
+        for entry in &self.animators {
+            let model = models[entry.model.as_path()];
+            // Model nodes the document does not drive get an id that never
+            // resolves, like targets whose nodes were deleted.
+            let mut targets = vec![
+                NodeId {
+                    index: u32::MAX,
+                    generation: 0
+                };
+                model.nodes.len()
+            ];
+            for &[model_node, node] in &entry.targets {
+                targets[model_node] = ids[node];
+            }
+            let mut animator = Animator::new(entry.name.clone(), model.animations.clone(), targets);
+            let player = &mut animator.player;
+            player.clip = entry.clip;
+            player.time = entry.time;
+            player.speed = entry.speed;
+            player.playing = entry.playing;
+            player.looping = entry.looping;
+            scene.animators.push(animator);
+        }
+        for handle in handles {
+            scene.keep_model(handle);
+        }
+        Ok(roots)
+    }
+
+    /// Checks the references between entries, which serde cannot.
+    fn validate(&self) -> anyhow::Result<()> {
+        let count = self.nodes.len();
+        for (index, node) in self.nodes.iter().enumerate() {
+            let context = || format!("nodes[{index}] (\"{}\")", node.name);
+            if let Some(parent) = node.parent
+                && parent >= index
+            {
+                bail!("{}: parent {parent} must come before the node", context());
+            }
+            if let Some(skin) = &node.skin
+                && let Some(joint) = skin.joints.iter().find(|&&joint| joint >= count)
+            {
+                bail!(
+                    "{}.skin: joint {joint} is out of range for {count} nodes",
+                    context()
+                );
+            }
+        }
+        for animator in &self.animators {
+            if let Some([_, node]) = animator.targets.iter().find(|[_, node]| *node >= count) {
+                bail!(
+                    "animator \"{}\": target node {node} is out of range for {count} nodes",
+                    animator.name
+                );
+            }
+        }
+        Ok(())
+    }
+
+    pub fn to_toml(&self) -> anyhow::Result<String> {
+        toml::to_string_pretty(self).context("failed to serialize scene")
+    }
+
+    pub fn to_binary(&self) -> anyhow::Result<Vec<u8>> {
+        let table = toml::Table::try_from(self).context("failed to serialize scene")?;
+        let mut bytes = BINARY_MAGIC.to_vec();
+        rmp_serde::encode::write_named(&mut bytes, &table).context("failed to encode scene")?;
+        Ok(bytes)
+    }
+
+    /// Parses a TOML document, migrating it from older versions. Errors
+    /// point at the offending line and column when the file is current,
+    /// and at the field path otherwise.
+    pub fn from_toml(text: &str) -> anyhow::Result<Self> {
+        let mut table: toml::Table = text.parse()?;
+        let document = if migrate(&mut table, MIGRATIONS)? {
+            from_table(table)?
+        } else {
+            toml::from_str(text)?
+        };
+        document.validate()?;
+        Ok(document)
+    }
+
+    pub fn from_binary(bytes: &[u8]) -> anyhow::Result<Self> {
+        let Some(payload) = bytes.strip_prefix(BINARY_MAGIC) else {
+            bail!("not a binary scene file");
+        };
+        let mut table: toml::Table =
+            rmp_serde::from_slice(payload).context("corrupt binary scene")?;
+        migrate(&mut table, MIGRATIONS)?;
+        let document = from_table(table)?;
+        document.validate()?;
+        Ok(document)
+    }
+
+    /// Reads either form, telling them apart by the binary magic number.
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        if bytes.starts_with(BINARY_MAGIC) {
+            Self::from_binary(bytes)
+        } else {
+            let text = std::str::from_utf8(bytes)
+                .context("scene file is neither binary nor UTF-8 text")?;
+            Self::from_toml(text)
+        }
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let bytes =
+            std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+        Self::from_bytes(&bytes).with_context(|| format!("failed to load scene {}", path.display()))
+    }
+
+    /// Writes the binary form for `.scnb` files and TOML otherwise.
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let binary = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case(BINARY_EXTENSION));
+        let bytes = if binary {
+            self.to_binary()?
+        } else {
+            self.to_toml()?.into_bytes()
+        };
+        std::fs::write(path, bytes).with_context(|| format!("failed to write {}", path.display()))
+    }
+}
+
+impl Default for SceneDocument {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn resolve<'a, T>(asset: &AssetRef, list: &'a [T], kind: &str) -> anyhow::Result<&'a T> {
+    list.get(asset.index).with_context(|| {
+        format!(
+            "`{}` has {} {kind}, index {} is out of range",
+            asset.model.display(),
+            list.len(),
+            asset.index
+        )
+    })
+}
+
+fn from_table(table: toml::Table) -> anyhow::Result<SceneDocument> {
+    serde_path_to_error::deserialize(toml::Value::Table(table)).map_err(|err| {
+        let path = err.path().to_string();
+        anyhow::anyhow!("{path}: {}", err.into_inner().message())
+    })
+}
+
+/// Version 2 added `camera.up`. Older scenes were all Y-up, which is what
+/// the field defaults to, so their tables stay as they are.
+fn add_camera_up(_table: &mut toml::Table) -> anyhow::Result<()> {
+    Ok(())
+}
+
+/// Brings `table` up to `VERSION`, returning whether it was older.
+fn migrate(table: &mut toml::Table, migrations: &[Migration]) -> anyhow::Result<bool> {
+    let current = migrations.len() as i64 + 1;
+    let version = match table.get("version") {
+        Some(toml::Value::Integer(version)) => *version,
+        Some(other) => bail!("version: expected an integer, found {}", other.type_str()),
+        None => bail!("missing `version`"),
+    };
+    if version < 1 {
+        bail!("version: {version} is not a valid scene version");
+    }
+    if version > current {
+        bail!("version: scene version {version} is newer than this build supports ({current})");
+    }
+    for (from, migration) in (version..).zip(&migrations[version as usize - 1..]) {
+        migration(table).with_context(|| format!("failed to migrate scene from version {from}"))?;
+        table.insert("version".to_owned(), toml::Value::Integer(from + 1));
+    }
+    Ok(version < current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::asset_manager::{Handle, vfs::MemoryFiles};
+
+    fn assets_with_triangle() -> (AssetManager, Handle<Model>) {
+        let mut assets = AssetManager::new();
+        assets.vfs().mount(
+            "mem",
+            MemoryFiles::new().with_file("tri.obj", "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n"),
+        );
+        let model = assets.load_blocking("mem/tri.obj");
+        (assets, model)
+    }
+
+    #[test]
+    fn round_trips_scene_through_text_and_binary() {
+        let (assets, model) = assets_with_triangle();
+        let mut scene = Scene::new();
+        let roots = scene.spawn_model(assets.get(&model).unwrap(), None);
+        let child = scene.add_node(Node::new("child"), Some(roots[0]));
+        scene.node_mut(child).unwrap().transform =
+            Transform::from_translation(Vec3::new(1.0, 2.0, 3.0));
+        scene.node_mut(child).unwrap().visible = false;
+
+        let mut document = SceneDocument::capture(&scene, &assets);
+        document.camera = Some(CameraState::from(&Camera::new()));
+        document.bookmarks.push(CameraBookmark {
+            name: "front".to_owned(),
+            camera: CameraState::from(&Camera::new()),
+        });
+        document.render = Some(RenderSettings::from(&PointCloudSettings::new()));
+        assert_eq!(document.nodes.len(), scene.len());
+        assert_eq!(document.model_paths(), [Path::new("mem/tri.obj")]);
+        assert!(document.nodes.iter().any(|node| node.mesh.is_some()));
+
+        let text = document.to_toml().unwrap();
+        assert_eq!(SceneDocument::from_toml(&text).unwrap(), document);
+        let binary = document.to_binary().unwrap();
+        assert_eq!(SceneDocument::from_bytes(&binary).unwrap(), document);
+
+        let mut loaded = Scene::new();
+        document.instantiate(&mut loaded, &assets).unwrap();
+        assert_eq!(
+            SceneDocument::capture(&loaded, &assets).nodes,
+            document.nodes
+        );
+    }
+
+    #[test]
+    fn saving_traces_nodes_after_the_model_handle_is_dropped() {
+        let (mut assets, model) = assets_with_triangle();
+        let mut scene = Scene::new();
+        scene.spawn_model(assets.get(&model).unwrap(), None);
+        scene.keep_model(model.clone());
+        let expected = SceneDocument::capture(&scene, &assets);
+        assert!(expected.nodes.iter().any(|node| node.mesh.is_some()));
+        drop(model);
+        assets.process_completed();
+        assets.collect_unused();
+
+        let document = SceneDocument::capture(&scene, &assets);
+        assert_eq!(document, expected);
+
+        let mut loaded = Scene::new();
+        document.instantiate(&mut loaded, &assets).unwrap();
+        drop(scene);
+        assets.collect_unused();
+        assert_eq!(loaded.models().len(), 1);
+        assert_eq!(
+            SceneDocument::capture(&loaded, &assets).nodes,
+            document.nodes
+        );
+    }
+
+    #[test]
+    fn reports_precise_errors() {
+        let err = |text: &str| format!("{:#}", SceneDocument::from_toml(text).unwrap_err());
+
+        assert!(err("version = 2\n[[nodes]]\nname = \"a\"\ntranform = {}\n").contains("line 4"));
+        assert!(err("version = 2\n[[nodes]]\nname = 3\n").contains("line 3"));
+        assert!(err("[[nodes]]\nname = \"a\"\n").contains("missing `version`"));
+        assert!(err("version = 99\n").contains("newer than this build supports"));
+        assert!(
+            err("version = 2\n[[nodes]]\nname = \"a\"\nparent = 0\n")
+                .contains("nodes[0] (\"a\"): parent 0 must come before the node")
+        );
+
+        let (assets, _model) = assets_with_triangle();
+        let text =
+            "version = 1\n[[nodes]]\nname = \"a\"\nmesh = { model = \"mem/tri.obj\", index = 4 }\n";
+        let document = SceneDocument::from_toml(text).unwrap();
+        let error = document
+            .instantiate(&mut Scene::new(), &assets)
+            .unwrap_err();
+        assert_eq!(
+            format!("{error:#}"),
+            "nodes[0] (\"a\").mesh: `mem/tri.obj` has 1 meshes, index 4 is out of range"
+        );
+    }
+
+    #[test]
+    fn loads_version_1_scenes_without_a_camera_up_axis() {
+        let text = "version = 1\n\
+            [camera]\n\
+            target = [0.0, 0.0, 0.0]\n\
+            distance = 5.0\n\
+            yaw_degrees = 30.0\n\
+            pitch_degrees = -20.0\n\
+            projection = { type = \"perspective\", fov_y_degrees = 45.0 }\n\
+            near = 0.1\n\
+            far = 100.0\n";
+        let document = SceneDocument::from_toml(text).unwrap();
+        assert_eq!(VERSION, 2);
+        assert_eq!(document.version, VERSION);
+        assert_eq!(document.camera.unwrap().up, UpAxis::Y);
+
+        let saved = document.to_toml().unwrap();
+        let newer = saved.replace("version = 2", "version = 3");
+        let error = format!("{:#}", SceneDocument::from_toml(&newer).unwrap_err());
+        assert!(error.contains("scene version 3 is newer"), "{error}");
+    }
+
+    #[test]
+    fn migrates_older_versions_step_by_step() {
+        fn rename_title(table: &mut toml::Table) -> anyhow::Result<()> {
+            for node in table["nodes"].as_array_mut().unwrap() {
+                let node = node.as_table_mut().unwrap();
+                let title = node.remove("title").context("nodes: missing `title`")?;
+                node.insert("name".to_owned(), title);
+            }
+            Ok(())
+        }
+        let mut table: toml::Table = "version = 1\n[[nodes]]\ntitle = \"a\"\n".parse().unwrap();
+        assert!(migrate(&mut table, &[rename_title]).unwrap());
+        assert_eq!(table["version"].as_integer(), Some(2));
+        let document = from_table(table).unwrap();
+        assert_eq!(document.nodes[0].name, "a");
+
+        let mut table: toml::Table = "version = 2\n".parse().unwrap();
+        assert!(!migrate(&mut table, &[rename_title]).unwrap());
+    }
+}
@@ -1,18 +1,50 @@
 use std::time::{Duration, Instant};
 
+use crate::core::frame_stats::FrameStats;
+
+/// Frame clock plus a fixed-timestep accumulator. Each `update` measures the
+/// real frame delta and turns the scaled delta into a whole number of
+/// simulation `steps`; what is left over shows up as `alpha`, the fraction of
+/// a step rendering should interpolate by. Frame times also feed a rolling
+/// `FrameStats` history.
 pub struct Time {
     last_frame: Instant,
     delta_time: Duration,
     start_time: Instant,
+    fixed_delta: Duration,
+    max_steps: u32,
+    accumulator: Duration,
+    steps: u32,
+    simulated: Duration,
+    scale: f64,
+    paused: bool,
+    pending_steps: u32,
+    /// Set while the event loop sleeps; the next update is a wake-up, not
+    /// a frame.
+    idle: bool,
+    stats: FrameStats,
 }
 
 impl Time {
+    pub const DEFAULT_HZ: f64 = 60.0;
+    pub const DEFAULT_MAX_STEPS: u32 = 5;
+
     pub fn new() -> Self {
         let now = Instant::now();
         Self {
             last_frame: now,
             delta_time: Duration::ZERO,
             start_time: now,
+            fixed_delta: Duration::from_secs_f64(1.0 / Self::DEFAULT_HZ),
+            max_steps: Self::DEFAULT_MAX_STEPS,
+            accumulator: Duration::ZERO,
+            steps: 0,
+            simulated: Duration::ZERO,
+            scale: 1.0,
+            paused: false,
+            pending_steps: 0,
+            idle: false,
+            stats: FrameStats::default(),
         }
     }
 
@@ -20,8 +52,52 @@ impl Time {
         let now = Instant::now();
         self.delta_time = now.duration_since(self.last_frame);
         self.last_frame = now;
+        if std::mem::take(&mut self.idle) {
+            // The gap since the last frame was spent waiting for events; it
+            // is neither frame time nor time the simulation should catch up.
+            self.delta_time = Duration::ZERO;
+        } else {
+            self.stats.record_frame(self.delta_time);
+        }
+        self.advance(self.delta_time);
+    }
+
+    /// Called when the event loop goes to sleep until the next event, so
+    /// the frame that wakes it up does not count the idle period.
+    pub fn set_idle(&mut self) {
+        self.idle = true;
+    }
+
+    /// Feeds `delta` of real time into the accumulator.
+    fn advance(&mut self, delta: Duration) {
+        if self.paused {
+            self.steps = self.pending_steps.min(self.max_steps);
+            self.pending_steps -= self.steps;
+        } else {
+            self.accumulator += delta.mul_f64(self.scale);
+            let available = self.accumulator.as_nanos() / self.fixed_delta.as_nanos();
+            self.steps = available.min(self.max_steps as u128) as u32;
+            self.accumulator -= self.fixed_delta * self.steps;
+            // A frame that needed more than `max_steps` drops the backlog
+            // instead of falling further behind.
+            if self.accumulator >= self.fixed_delta {
+                self.accumulator = Duration::from_nanos(
+                    (self.accumulator.as_nanos() % self.fixed_delta.as_nanos()) as u64,
+                );
+            }
+        }
+        self.simulated += self.fixed_delta * self.steps;
     }
 
+    pub fn stats(&self) -> &FrameStats {
+        &self.stats
+    }
+
+    pub fn stats_mut(&mut self) -> &mut FrameStats {
+        &mut self.stats
+    }
+
+    /// Real time since the previous frame, unaffected by pause and scale.
     pub fn delta(&self) -> Duration {
         self.delta_time
     }
@@ -29,6 +105,75 @@ impl Time {
     pub fn elapsed(&self) -> Duration {
         self.start_time.elapsed()
     }
+
+    /// Length of one simulation step.
+    pub fn fixed_delta(&self) -> Duration {
+        self.fixed_delta
+    }
+
+    /// Simulation steps to run this frame.
+    pub fn steps(&self) -> u32 {
+        self.steps
+    }
+
+    /// Total simulated time.
+    pub fn simulated(&self) -> Duration {
+        self.simulated
+    }
+
+    /// How far between the last simulation step and the next one the
+    /// current frame lies, in `[0, 1)`.
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator.as_secs_f64() / self.fixed_delta.as_secs_f64()) as f32
+    }
+
+    pub fn hz(&self) -> f64 {
+        1.0 / self.fixed_delta.as_secs_f64()
+    }
+
+    pub fn set_hz(&mut self, hz: f64) {
+        self.fixed_delta = Duration::from_secs_f64(1.0 / hz.clamp(1.0, 1000.0));
+        self.accumulator = self.accumulator.min(self.fixed_delta);
+    }
+
+    pub fn max_steps(&self) -> u32 {
+        self.max_steps
+    }
+
+    /// Caps the catch-up after a long frame.
+    pub fn set_max_steps(&mut self, max_steps: u32) {
+        self.max_steps = max_steps.max(1);
+    }
+
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    pub fn set_scale(&mut self, scale: f64) {
+        self.scale = scale.max(0.0);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        self.pending_steps = 0;
+    }
+
+    /// Runs exactly one step on the next update while paused.
+    pub fn step(&mut self) {
+        if self.paused {
+            self.pending_steps += 1;
+        }
+    }
+}
+
+impl Default for Time {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[cfg(test)]
@@ -60,4 +205,59 @@ mod tests {
         assert!(elapsed_1 >= sleep_1);
         assert!(elapsed_2 >= sleep_1 + sleep_2);
     }
+
+    #[test]
+    fn waking_up_does_not_count_the_idle_period() {
+        let mut time = Time::new();
+        time.set_hz(100.0);
+        time.update();
+        time.set_idle();
+        sleep(Duration::from_millis(30));
+        time.update();
+        assert_eq!(time.delta(), Duration::ZERO);
+        assert_eq!(time.steps(), 0);
+        assert_eq!(time.stats().frame_times().count(), 1);
+
+        sleep(Duration::from_millis(15));
+        time.update();
+        assert!(time.delta() >= Duration::from_millis(15));
+        assert_eq!(time.stats().frame_times().count(), 2);
+    }
+
+    #[test]
+    fn accumulates_fixed_steps() {
+        let mut time = Time::new();
+        time.set_hz(100.0);
+        time.advance(Duration::from_millis(25));
+        assert_eq!(time.steps(), 2);
+        assert!((time.alpha() - 0.5).abs() < 1e-4);
+
+        time.advance(Duration::from_millis(5));
+        assert_eq!(time.steps(), 1);
+        assert!(time.alpha() < 1e-4);
+
+        time.set_scale(0.5);
+        time.advance(Duration::from_millis(40));
+        assert_eq!(time.steps(), 2);
+        assert_eq!(time.simulated(), Duration::from_millis(50));
+    }
+
+    #[test]
+    fn caps_catch_up_and_steps_while_paused() {
+        let mut time = Time::new();
+        time.set_hz(100.0);
+        time.set_max_steps(3);
+        time.advance(Duration::from_millis(1000));
+        assert_eq!(time.steps(), 3);
+        assert!(time.alpha() < 1.0);
+
+        time.set_paused(true);
+        time.advance(Duration::from_millis(100));
+        assert_eq!(time.steps(), 0);
+        time.step();
+        time.advance(Duration::from_millis(100));
+        assert_eq!(time.steps(), 1);
+        time.advance(Duration::from_millis(100));
+        assert_eq!(time.steps(), 0);
+    }
 }
@@ -1,7 +1,5 @@
-mod app;
-mod core;
-use app::SceneViewerAppFactory;
-use core::Application;
+use simple_3d_scene_viewer::app::{self, SceneViewerAppFactory};
+use simple_3d_scene_viewer::core::Application;
 use winit::event_loop::EventLoop;
 
 #[cfg(debug_assertions)]
@@ -16,6 +14,18 @@ fn init_logger() {
 
 fn main() {
     init_logger();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().is_some_and(|arg| arg == "convert") {
+        if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+            println!("{}", app::convert::USAGE);
+            return;
+        }
+        if let Err(err) = app::convert::run_from_args(&args[1..]) {
+            log::error!("{err:#}");
+            std::process::exit(1);
+        }
+        return;
+    }
     let event_loop = EventLoop::new().expect("Failed to create event loop.");
     let app_factory = SceneViewerAppFactory::new(app::load_default_config());
     let mut app = Application::new(Box::new(app_factory));